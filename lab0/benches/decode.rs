@@ -0,0 +1,60 @@
+//! Bytes per second decoded into `RustData` as the dump does, and only counted as
+//! `--stats-only` does, from memory and through a reader.
+//!
+//! Run with `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use legacy_system::{Layout, RustData};
+
+/// Records of the capture, 64 MiB.
+const RECORDS: usize = 1 << 20;
+
+fn report(name: &str, bytes: usize, elapsed: Duration) {
+    println!(
+        "{:16} {:>9} bytes in {:>9.2?}, {:>8.1} MiB/s",
+        name,
+        bytes,
+        elapsed,
+        bytes as f64 / elapsed.as_secs_f64() / (1 << 20) as f64
+    );
+}
+
+fn main() {
+    let layout = Layout::LP64;
+    let mut bytes = Vec::with_capacity(RECORDS * layout.size);
+    for i in 0..RECORDS {
+        let data = match i % 3 {
+            0 => RustData::Value {
+                val: i as f32,
+                timestamp: i as i64,
+            },
+            1 => RustData::MValue {
+                val: [i as f32; 10],
+                timestamp: i as i64,
+            },
+            _ => RustData::Message {
+                message: format!("message {}", i),
+            },
+        };
+        layout.encode(&data, &mut bytes).unwrap();
+    }
+
+    let start = Instant::now();
+    let decoded = layout
+        .records(bytes.as_slice())
+        .filter(Result::is_ok)
+        .count();
+    report("decode", bytes.len(), start.elapsed());
+    assert_eq!(decoded, RECORDS);
+
+    let start = Instant::now();
+    let summary = layout.summarize_reader(bytes.as_slice()).unwrap();
+    report("summarize reader", bytes.len(), start.elapsed());
+    assert_eq!(summary.records(), RECORDS as u64);
+
+    let start = Instant::now();
+    let summary = layout.summarize(&bytes);
+    report("summarize", bytes.len(), start.elapsed());
+    assert_eq!(summary.records(), RECORDS as u64);
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Arc,
+};
+
+use serde::Serialize;
+
+use crate::{Layout, ParseErrorKind, RustData, MESSAGE, VALUE};
+
+/// A field of an extension record.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Field {
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::Int(int) => write!(f, "{}", int),
+            Field::Float(float) => write!(f, "{}", float),
+            Field::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// The bytes of a record given to the decoder of an extension, read in the byte order
+/// of its layout. The offsets are from the start of the record, the one of the union
+/// is `layout().union`.
+#[derive(Debug, Clone, Copy)]
+pub struct RawRecord<'a> {
+    layout: &'a Layout,
+    bytes: &'a [u8],
+}
+
+impl RawRecord<'_> {
+    pub fn layout(&self) -> &Layout {
+        self.layout
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        self.bytes
+    }
+
+    pub fn i32_at(&self, offset: usize) -> i32 {
+        self.layout.i32_at(self.bytes, offset)
+    }
+
+    pub fn f32_at(&self, offset: usize) -> f32 {
+        self.layout.f32_at(self.bytes, offset)
+    }
+
+    /// A C `long` of the layout, as the timestamps.
+    pub fn long_at(&self, offset: usize) -> i64 {
+        self.layout.long_at(self.bytes, offset)
+    }
+
+    /// The text of the `len` bytes at `offset` up to the NUL, `None` if it's missing or not UTF-8.
+    pub fn text_at(&self, offset: usize, len: usize) -> Option<&str> {
+        let text = &self.bytes[offset..offset + len];
+        let len = text.iter().position(|&c| c == b'\0')?;
+        std::str::from_utf8(&text[..len]).ok()
+    }
+}
+
+type Decode = dyn Fn(RawRecord<'_>) -> Result<BTreeMap<String, Field>, String> + Send + Sync;
+
+/// Record types added to the ones of the C program, decoded to `RustData::Extension`.
+/// Given to the records with `Records::registry`.
+#[derive(Default)]
+pub struct Registry {
+    extensions: HashMap<i32, (String, Box<Decode>)>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// Decode the records tagged `tag`, in the record and in the union as the others,
+    /// with `decode`. Its error is reported as a `ParseErrorKind::Extension`.
+    ///
+    /// Panics if `tag` is one of the C program or already registered.
+    pub fn register<F>(mut self, tag: i32, name: impl Into<String>, decode: F) -> Self
+    where
+        F: Fn(RawRecord<'_>) -> Result<BTreeMap<String, Field>, String> + Send + Sync + 'static,
+    {
+        assert!(
+            !(VALUE..=MESSAGE).contains(&tag),
+            "tag {} is already a record of the C program",
+            tag
+        );
+        let name = name.into();
+        assert!(
+            !self.extensions.contains_key(&tag),
+            "tag {} is already registered",
+            tag
+        );
+        self.extensions.insert(tag, (name, Box::new(decode)));
+        self
+    }
+
+    /// Shared by the iterators of the records.
+    pub fn shared(self) -> Arc<Self> {
+        Arc::new(self)
+    }
+
+    pub fn contains(&self, tag: i32) -> bool {
+        self.extensions.contains_key(&tag)
+    }
+
+    /// Decode the record of a registered `tag`.
+    pub(crate) fn decode(
+        &self,
+        tag: i32,
+        layout: &Layout,
+        bytes: &[u8],
+    ) -> Result<RustData, ParseErrorKind> {
+        let (name, decode) = &self.extensions[&tag];
+        let fields = decode(RawRecord { layout, bytes })
+            .map_err(|message| ParseErrorKind::Extension { tag, message })?;
+        Ok(RustData::Extension {
+            tag,
+            name: name.clone(),
+            fields,
+        })
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.extensions.iter().map(|(tag, (name, _))| (tag, name)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use crate::{
+        extension::{Field, Registry},
+        CData, Endian, Layout, ParseError, ParseErrorKind, RustData,
+    };
+
+    const DATA: &[u8] = include_bytes!("../data");
+
+    /// A record of the C side tagged 4: `{ i32 data_type, i32 code, char unit[8] }` in the union.
+    fn registry() -> Registry {
+        Registry::new().register(4, "Reading", |record| {
+            let union = record.layout().union;
+            let code = record.i32_at(union + 4);
+            if code < 0 {
+                return Err(format!("negative code {}", code));
+            }
+            let unit = record.text_at(union + 8, 8).ok_or("invalid unit")?;
+            Ok(BTreeMap::from([
+                ("code".to_string(), Field::Int(code as i64)),
+                ("unit".to_string(), Field::Text(unit.to_string())),
+            ]))
+        })
+    }
+
+    fn reading(layout: &Layout, code: i32) -> Vec<u8> {
+        let mut bytes = vec![0; layout.size];
+        let int = |int: i32| match layout.endian {
+            Endian::Little => int.to_le_bytes(),
+            Endian::Big => int.to_be_bytes(),
+        };
+        bytes[..4].copy_from_slice(&int(4));
+        bytes[layout.union..layout.union + 4].copy_from_slice(&int(4));
+        bytes[layout.union + 4..layout.union + 8].copy_from_slice(&int(code));
+        bytes[layout.union + 8..layout.union + 10].copy_from_slice(b"mV");
+        bytes
+    }
+
+    #[test]
+    fn registry_test() {
+        let expected = RustData::Extension {
+            tag: 4,
+            name: "Reading".to_string(),
+            fields: BTreeMap::from([
+                ("code".to_string(), Field::Int(42)),
+                ("unit".to_string(), Field::Text("mV".to_string())),
+            ]),
+        };
+        let registry = registry().shared();
+
+        for layout in [Layout::LP64, Layout::ILP32.endian(Endian::Big)] {
+            let mut bytes = reading(&layout, 42);
+            bytes.extend(reading(&layout, -1));
+            let records = layout
+                .records(bytes.as_slice())
+                .registry(registry.clone())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                records,
+                [
+                    Ok(expected.clone()),
+                    Err(ParseError {
+                        offset: layout.size as u64,
+                        kind: ParseErrorKind::Extension {
+                            tag: 4,
+                            message: "negative code -1".to_string()
+                        }
+                    })
+                ]
+            );
+        }
+
+        // among the records of the C program, unknown without the registry
+        let mut bytes = DATA[..CData::SIZE].to_vec();
+        bytes.extend(reading(&Layout::LP64, 42));
+        let records = CData::iter_from_reader(bytes.as_slice())
+            .registry(registry)
+            .collect::<Vec<_>>();
+        assert_eq!(records[1], Ok(expected));
+        assert_eq!(
+            CData::iter_from_reader(bytes.as_slice()).nth(1),
+            Some(Err(ParseError {
+                offset: 64,
+                kind: ParseErrorKind::UnknownTag(4)
+            }))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "already a record")]
+    fn builtin_tag_test() {
+        let _ = Registry::new().register(2, "MValue", |_| Ok(BTreeMap::new()));
+    }
+}
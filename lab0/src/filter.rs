@@ -0,0 +1,204 @@
+use clap::{Args, ValueEnum};
+use legacy_system::{ParseError, RustData};
+
+/// Type of a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Kind {
+    Value,
+    #[value(name = "mvalue")]
+    MValue,
+    Message,
+    /// The types added by a `Registry`.
+    Extension,
+}
+
+/// Records printed by the dump, selected while they are decoded.
+#[derive(Args, Debug, Clone, Default)]
+pub struct Filter {
+    /// Only the records of this type
+    #[arg(long = "type", value_enum)]
+    pub kind: Option<Kind>,
+
+    /// Only the records at or after this timestamp, the messages have none
+    #[arg(long)]
+    pub since: Option<i64>,
+
+    /// Records left out before the first printed, after the other filters
+    #[arg(long, default_value_t = 0)]
+    pub skip: usize,
+
+    /// Records printed at most, the input isn't read after the last
+    #[arg(long)]
+    pub limit: Option<usize>,
+}
+
+impl Filter {
+    pub fn matches(&self, data: &RustData) -> bool {
+        let kind = match data {
+            RustData::Value { .. } => Kind::Value,
+            RustData::MValue { .. } => Kind::MValue,
+            RustData::Message { .. } => Kind::Message,
+            RustData::Extension { .. } => Kind::Extension,
+        };
+        self.kind.is_none_or(|k| k == kind)
+            && self
+                .since
+                .is_none_or(|since| data.timestamp().is_some_and(|t| t >= since))
+    }
+
+    /// The selected records of `records`, the errors are all kept.
+    pub fn apply<I>(self, records: I) -> Selected<I>
+    where
+        I: Iterator<Item = Result<RustData, ParseError>>,
+    {
+        Selected {
+            records,
+            filter: self,
+            skipped: 0,
+            taken: 0,
+        }
+    }
+}
+
+/// Records selected by a `Filter`, made by `Filter::apply`.
+pub struct Selected<I> {
+    records: I,
+    filter: Filter,
+    skipped: usize,
+    taken: usize,
+}
+
+impl<I> Iterator for Selected<I>
+where
+    I: Iterator<Item = Result<RustData, ParseError>>,
+{
+    type Item = Result<RustData, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.filter.limit == Some(self.taken) {
+                return None;
+            }
+            match self.records.next()? {
+                Ok(data) if !self.filter.matches(&data) => continue,
+                Ok(_) if self.skipped < self.filter.skip => self.skipped += 1,
+                Ok(data) => {
+                    self.taken += 1;
+                    return Some(Ok(data));
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use legacy_system::{ParseError, ParseErrorKind, RustData};
+
+    use crate::filter::{Filter, Kind};
+
+    fn records() -> Vec<Result<RustData, ParseError>> {
+        (0..9)
+            .map(|i| match i % 3 {
+                0 => Ok(RustData::Value {
+                    val: i as f32,
+                    timestamp: i,
+                }),
+                1 => Ok(RustData::MValue {
+                    val: [i as f32; 10],
+                    timestamp: i,
+                }),
+                _ => Ok(RustData::Message {
+                    message: i.to_string(),
+                }),
+            })
+            .collect()
+    }
+
+    fn selected(
+        filter: Filter,
+        records: Vec<Result<RustData, ParseError>>,
+    ) -> Vec<Result<RustData, ParseError>> {
+        filter.apply(records.into_iter()).collect()
+    }
+
+    #[test]
+    fn filter_test() {
+        let values = selected(
+            Filter {
+                kind: Some(Kind::Value),
+                ..Filter::default()
+            },
+            records(),
+        );
+        assert_eq!(
+            values,
+            [
+                records()[0].clone(),
+                records()[3].clone(),
+                records()[6].clone()
+            ]
+        );
+
+        let since = selected(
+            Filter {
+                since: Some(4),
+                ..Filter::default()
+            },
+            records(),
+        );
+        assert_eq!(
+            since,
+            [
+                records()[4].clone(),
+                records()[6].clone(),
+                records()[7].clone()
+            ]
+        );
+
+        // skip and limit count the records left by the other filters
+        let page = Filter {
+            kind: Some(Kind::Message),
+            skip: 1,
+            limit: Some(1),
+            ..Filter::default()
+        };
+        assert_eq!(selected(page, records()), [records()[5].clone()]);
+
+        assert_eq!(selected(Filter::default(), records()), records());
+    }
+
+    #[test]
+    fn errors_test() {
+        let error = ParseError {
+            offset: 64,
+            kind: ParseErrorKind::UnknownTag(7),
+        };
+        let mut records = records();
+        records.insert(1, Err(error.clone()));
+
+        let filter = Filter {
+            kind: Some(Kind::MValue),
+            ..Filter::default()
+        };
+        assert_eq!(
+            selected(filter, records.clone()),
+            [
+                Err(error),
+                records[2].clone(),
+                records[5].clone(),
+                records[8].clone()
+            ]
+        );
+
+        // the input isn't read after the last record
+        let mut input = records.into_iter();
+        let filter = Filter {
+            limit: Some(1),
+            ..Filter::default()
+        };
+        assert_eq!(filter.apply(input.by_ref()).count(), 1);
+        assert_eq!(input.len(), 9);
+    }
+}
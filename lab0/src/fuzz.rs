@@ -0,0 +1,55 @@
+//! Entry point for fuzzing the record decoder, built with `RUSTFLAGS="--cfg fuzzing"`
+//! for a fuzzer linking the library, and by the tests.
+
+use crate::{Endian, Layout};
+
+/// Bytes of the input decoded, the records after them add nothing new.
+pub const MAX_INPUT: usize = 4096;
+
+/// Decode `bytes` as the records of every layout, with and without `Records::resync`,
+/// and as a single record. Any input is decoded to records and errors, without panicking.
+pub fn fuzz_decode(bytes: &[u8]) {
+    let bytes = &bytes[..bytes.len().min(MAX_INPUT)];
+    for layout in [Layout::LP64, Layout::ILP32] {
+        for layout in [layout, layout.endian(Endian::Big)] {
+            for resync in [false, true] {
+                layout.records(bytes).resync(resync).for_each(drop);
+            }
+            let _ = layout.decode(bytes, 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fuzz::fuzz_decode;
+    use crate::{Layout, ParseError, ParseErrorKind};
+
+    const DATA: &[u8] = include_bytes!("../data");
+
+    #[test]
+    fn fuzz_decode_test() {
+        for len in 0..200 {
+            fuzz_decode(&DATA[..len]);
+        }
+        // every byte of the first records set to each value of the tags and of a NUL
+        for i in 0..200 {
+            for byte in [0x00, 0x01, 0x02, 0x03, 0x80, 0xff] {
+                let mut bytes = DATA[..200].to_vec();
+                bytes[i] = byte;
+                fuzz_decode(&bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn short_record_test() {
+        assert_eq!(
+            Layout::ILP32.decode(&DATA[..51], 64),
+            Err(ParseError {
+                offset: 64,
+                kind: ParseErrorKind::Truncated(51)
+            })
+        );
+    }
+}
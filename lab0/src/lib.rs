@@ -0,0 +1,867 @@
+use chrono::{DateTime, Utc};
+pub use extension::{Field, RawRecord, Registry};
+use serde::Serialize;
+pub use stats::{Series, Stats};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, Read, Write},
+    str::FromStr,
+    sync::Arc,
+};
+pub use summary::Summary;
+
+mod extension;
+#[cfg(any(fuzzing, test))]
+pub mod fuzz;
+mod stats;
+mod summary;
+
+/// Record of the C program, written as the `repr(C)` struct:
+///
+/// ```text
+/// 0   i32 data_type
+/// 8   union {
+///         Value   { i32 data_type, f32 val,       i64 timestamp (at 8) }
+///         MValue  { i32 data_type, f32 val[10],   i64 timestamp (at 48) }
+///         Message { i32 data_type, u8 message[21] }
+///     }
+/// ```
+///
+/// Every field is in little endian, the union is aligned to the 8 bytes of the timestamps.
+/// The records of other platforms are decoded through their `Layout`.
+#[derive(Clone, Copy)]
+pub struct CData([u8; CData::SIZE]);
+
+/// A decoded record, serialized tagged by its `type`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum RustData {
+    Value {
+        val: f32,
+        timestamp: i64,
+    },
+    MValue {
+        val: [f32; 10],
+        timestamp: i64,
+    },
+    Message {
+        message: String,
+    },
+    /// A record of a type added by a `Registry`.
+    Extension {
+        tag: i32,
+        name: String,
+        fields: BTreeMap<String, Field>,
+    },
+}
+
+impl RustData {
+    /// Timestamp of the values, a message has none.
+    pub fn timestamp(&self) -> Option<i64> {
+        match self {
+            RustData::Value { timestamp, .. } | RustData::MValue { timestamp, .. } => {
+                Some(*timestamp)
+            }
+            RustData::Message { .. } | RustData::Extension { .. } => None,
+        }
+    }
+
+    /// The timestamp as a date, the seconds from the Unix epoch in UTC.
+    /// `None` for a message or a timestamp out of the range of the dates.
+    pub fn datetime(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(self.timestamp()?, 0)
+    }
+
+    /// Write the record as the C program does, the inverse of `CData::to_rust`.
+    /// The padding is zeroed and the message cut to the 20 bytes that fit with its NUL.
+    pub fn encode(&self, writer: impl Write) -> io::Result<()> {
+        Layout::default().encode(self, writer)
+    }
+}
+
+/// Byte order of the fields of a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+impl FromStr for Endian {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "little" => Ok(Endian::Little),
+            "big" => Ok(Endian::Big),
+            _ => Err(format!("unknown byte order {}, expected little or big", s)),
+        }
+    }
+}
+
+/// Where the C compiler of a platform puts the fields of a record, offsets from its start.
+/// The offsets, and the `long` bytes after them, must fall within `size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub endian: Endian,
+    /// Bytes of a record.
+    pub size: usize,
+    /// Offset of the union, after the tag of the record.
+    pub union: usize,
+    /// Bytes of a `long`, the timestamps, 4 or 8.
+    pub long: usize,
+    pub value_val: usize,
+    pub value_timestamp: usize,
+    pub m_value_val: usize,
+    pub m_value_timestamp: usize,
+    pub message_text: usize,
+}
+
+impl Layout {
+    /// 64-bit Linux and macOS, the layout of `CData`: a `long` is 8 bytes and aligned to them.
+    pub const LP64: Layout = Layout {
+        endian: Endian::Little,
+        size: CData::SIZE,
+        union: 8,
+        long: 8,
+        value_val: 12,
+        value_timestamp: 16,
+        m_value_val: 12,
+        m_value_timestamp: 56,
+        message_text: 12,
+    };
+
+    /// 32-bit platforms and 64-bit Windows: a `long` is 4 bytes, nothing is padded.
+    pub const ILP32: Layout = Layout {
+        endian: Endian::Little,
+        size: 52,
+        union: 4,
+        long: 4,
+        value_val: 8,
+        value_timestamp: 12,
+        m_value_val: 8,
+        m_value_timestamp: 48,
+        message_text: 8,
+    };
+
+    /// The same layout with the fields in the `endian` byte order.
+    pub fn endian(self, endian: Endian) -> Self {
+        Layout { endian, ..self }
+    }
+
+    /// Decode the records of this layout as they are read, like `CData::iter_from_reader`.
+    pub fn records<R: Read>(self, reader: R) -> Records<R> {
+        Records {
+            reader,
+            layout: self,
+            buffer: vec![0; self.size],
+            offset: 0,
+            done: false,
+            resync: false,
+            registry: Arc::default(),
+            pending: None,
+        }
+    }
+
+    /// Decode the `size` bytes of a record read at `offset` from the start of the input,
+    /// the bytes after them are ignored.
+    pub fn decode(&self, bytes: &[u8], offset: u64) -> Result<RustData, ParseError> {
+        self.decode_with(bytes, offset, &Registry::default())
+    }
+
+    /// Decode a record like `decode`, the types of `registry` too.
+    pub fn decode_with(
+        &self,
+        bytes: &[u8],
+        offset: u64,
+        registry: &Registry,
+    ) -> Result<RustData, ParseError> {
+        let error = |kind| ParseError { offset, kind };
+        if bytes.len() < self.size {
+            return Err(error(ParseErrorKind::Truncated(bytes.len())));
+        }
+
+        let tag = self.i32_at(bytes, 0);
+        if registry.contains(tag) {
+            self.union_tag(bytes, tag).map_err(error)?;
+            return registry.decode(tag, self, bytes).map_err(error);
+        }
+
+        match self.tag(bytes).map_err(error)? {
+            VALUE => Ok(RustData::Value {
+                val: self.f32_at(bytes, self.value_val),
+                timestamp: self.long_at(bytes, self.value_timestamp),
+            }),
+            M_VALUE => Ok(RustData::MValue {
+                val: std::array::from_fn(|i| self.f32_at(bytes, self.m_value_val + 4 * i)),
+                timestamp: self.long_at(bytes, self.m_value_timestamp),
+            }),
+            _ => Ok(RustData::Message {
+                message: self.message(bytes).map_err(error)?.to_string(),
+            }),
+        }
+    }
+
+    /// Tag of the record, checked against the one of the union.
+    fn tag(&self, bytes: &[u8]) -> Result<i32, ParseErrorKind> {
+        let tag = self.i32_at(bytes, 0);
+        if !(VALUE..=MESSAGE).contains(&tag) {
+            return Err(ParseErrorKind::UnknownTag(tag));
+        }
+        self.union_tag(bytes, tag)?;
+        Ok(tag)
+    }
+
+    fn union_tag(&self, bytes: &[u8], tag: i32) -> Result<(), ParseErrorKind> {
+        let union_tag = self.i32_at(bytes, self.union);
+        if union_tag != tag {
+            return Err(ParseErrorKind::MismatchedTag {
+                record: tag,
+                union: union_tag,
+            });
+        }
+        Ok(())
+    }
+
+    /// Text of a message record, up to its NUL.
+    fn message<'a>(&self, bytes: &'a [u8]) -> Result<&'a str, ParseErrorKind> {
+        let text = &bytes[self.message_text..self.message_text + MESSAGE_LEN];
+        let len = text
+            .iter()
+            .position(|&c| c == b'\0')
+            .ok_or(ParseErrorKind::UnterminatedMessage)?;
+        std::str::from_utf8(&text[..len]).map_err(|_| ParseErrorKind::InvalidMessage)
+    }
+
+    /// Write `data` as a record of this layout, see `RustData::encode`.
+    /// A timestamp not fitting in a 4 bytes `long`, or an extension, is an `InvalidInput` error.
+    pub fn encode(&self, data: &RustData, mut writer: impl Write) -> io::Result<()> {
+        let mut bytes = vec![0; self.size];
+
+        let tag = match data {
+            RustData::Value { val, timestamp } => {
+                self.put(&mut bytes, self.value_val, val.to_le_bytes());
+                self.put_long(&mut bytes, self.value_timestamp, *timestamp)?;
+                VALUE
+            }
+            RustData::MValue { val, timestamp } => {
+                for (i, val) in val.iter().enumerate() {
+                    self.put(&mut bytes, self.m_value_val + 4 * i, val.to_le_bytes());
+                }
+                self.put_long(&mut bytes, self.m_value_timestamp, *timestamp)?;
+                M_VALUE
+            }
+            RustData::Message { message } => {
+                // the last byte is left for the NUL, a character is never split
+                let mut len = message.len().min(MESSAGE_LEN - 1);
+                while !message.is_char_boundary(len) {
+                    len -= 1;
+                }
+                bytes[self.message_text..self.message_text + len]
+                    .copy_from_slice(&message.as_bytes()[..len]);
+                MESSAGE
+            }
+            RustData::Extension { name, .. } => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} records can't be encoded", name),
+                ));
+            }
+        };
+        self.put(&mut bytes, 0, tag.to_le_bytes());
+        self.put(&mut bytes, self.union, tag.to_le_bytes());
+
+        writer.write_all(&bytes)
+    }
+
+    /// The `N` bytes of the field at `offset`, in little endian whatever the layout.
+    fn bytes<const N: usize>(&self, bytes: &[u8], offset: usize) -> [u8; N] {
+        let mut field: [u8; N] = bytes[offset..offset + N].try_into().unwrap();
+        if self.endian == Endian::Big {
+            field.reverse();
+        }
+        field
+    }
+
+    fn i32_at(&self, bytes: &[u8], offset: usize) -> i32 {
+        i32::from_le_bytes(self.bytes(bytes, offset))
+    }
+
+    fn f32_at(&self, bytes: &[u8], offset: usize) -> f32 {
+        f32::from_le_bytes(self.bytes(bytes, offset))
+    }
+
+    fn long_at(&self, bytes: &[u8], offset: usize) -> i64 {
+        match self.long {
+            4 => i32::from_le_bytes(self.bytes(bytes, offset)) as i64,
+            _ => i64::from_le_bytes(self.bytes(bytes, offset)),
+        }
+    }
+
+    /// Write the little endian `field` at `offset`, in the byte order of the layout.
+    fn put<const N: usize>(&self, bytes: &mut [u8], offset: usize, mut field: [u8; N]) {
+        if self.endian == Endian::Big {
+            field.reverse();
+        }
+        bytes[offset..offset + N].copy_from_slice(&field);
+    }
+
+    fn put_long(&self, bytes: &mut [u8], offset: usize, long: i64) -> io::Result<()> {
+        match self.long {
+            4 => {
+                let long = i32::try_from(long).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("timestamp {} overflows a 4 bytes long", long),
+                    )
+                })?;
+                self.put(bytes, offset, long.to_le_bytes());
+            }
+            _ => self.put(bytes, offset, long.to_le_bytes()),
+        }
+        Ok(())
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::LP64
+    }
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lp64" => Ok(Layout::LP64),
+            "ilp32" => Ok(Layout::ILP32),
+            _ => Err(format!("unknown layout {}, expected lp64 or ilp32", s)),
+        }
+    }
+}
+
+/// A record that can't be decoded, at `offset` bytes from the start of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub offset: u64,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The tag of the record is not one of the known types.
+    UnknownTag(i32),
+    /// The tag of the union doesn't match the one of the record.
+    MismatchedTag { record: i32, union: i32 },
+    /// The message fills its 21 bytes without a NUL.
+    UnterminatedMessage,
+    /// The message is not valid UTF-8.
+    InvalidMessage,
+    /// The input ends in the middle of the record, after these bytes.
+    Truncated(usize),
+    /// The input can't be read anymore.
+    Io(io::ErrorKind),
+    /// These bytes, from the offset, hold no valid record, see `Records::resync`.
+    Skipped(u64),
+    /// The decoder of the extension with the tag failed.
+    Extension { tag: i32, message: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "record at offset {}: ", self.offset)?;
+        match &self.kind {
+            ParseErrorKind::UnknownTag(tag) => write!(f, "unknown tag {}", tag),
+            ParseErrorKind::MismatchedTag { record, union } => {
+                write!(f, "the record is tagged {} but its data {}", record, union)
+            }
+            ParseErrorKind::UnterminatedMessage => write!(f, "the message is not NUL terminated"),
+            ParseErrorKind::InvalidMessage => write!(f, "the message is not valid UTF-8"),
+            ParseErrorKind::Truncated(len) => {
+                write!(f, "the input ends {} bytes into the record", len)
+            }
+            ParseErrorKind::Io(kind) => write!(f, "read failed: {}", kind),
+            ParseErrorKind::Skipped(len) => {
+                write!(f, "skipped {} bytes without a valid record", len)
+            }
+            ParseErrorKind::Extension { tag, message } => {
+                write!(f, "extension {}: {}", tag, message)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+const VALUE: i32 = 1;
+const M_VALUE: i32 = 2;
+const MESSAGE: i32 = 3;
+
+/// Bytes of the message, with its NUL.
+const MESSAGE_LEN: usize = 21;
+
+impl CData {
+    /// Bytes of a record.
+    pub const SIZE: usize = 64;
+
+    /// Decode every record of the file, the errors of a record don't stop the others.
+    pub fn from_file(file: &mut File) -> Vec<Result<RustData, ParseError>> {
+        CData::iter_from_reader(file).collect()
+    }
+
+    /// Decode the records as they are read, until the end of the input.
+    /// The iterator ends after a record cut short by the end of the input or a read error.
+    pub fn iter_from_reader<R: Read>(reader: R) -> Records<R> {
+        Layout::LP64.records(reader)
+    }
+
+    /// Decode the record read at `offset` from the start of the input.
+    pub fn to_rust(&self, offset: u64) -> Result<RustData, ParseError> {
+        Layout::LP64.decode(&self.0, offset)
+    }
+}
+
+/// Records decoded from a reader, made by `CData::iter_from_reader`.
+pub struct Records<R> {
+    reader: R,
+    layout: Layout,
+    buffer: Vec<u8>,
+    /// Bytes read so far.
+    offset: u64,
+    done: bool,
+    resync: bool,
+    registry: Arc<Registry>,
+    /// Record found by resynchronizing, after the bytes skipped are reported.
+    pending: Option<RustData>,
+}
+
+impl<R: Read> Records<R> {
+    /// Look for the next record after one with an invalid tag, instead of reading the one
+    /// after it. The input is scanned a byte at a time until a record decodes, a `Skipped`
+    /// error reports the bytes scanned past, then the record follows.
+    pub fn resync(mut self, resync: bool) -> Self {
+        self.resync = resync;
+        self
+    }
+
+    /// Decode the record types of `registry` too.
+    pub fn registry(mut self, registry: Arc<Registry>) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Fill the buffer from `filled` unless the input ends first, returning the bytes read.
+    fn fill(&mut self, filled: usize) -> io::Result<usize> {
+        read_full(&mut self.reader, &mut self.buffer[filled..])
+    }
+
+    /// Slide the record a byte at a time from the invalid one at `start`, until it decodes.
+    fn resynchronize(&mut self, start: u64) -> Result<RustData, ParseError> {
+        let size = self.layout.size;
+        loop {
+            self.buffer.copy_within(1.., 0);
+            match self.fill(size - 1) {
+                Ok(1) => self.offset += 1,
+                Ok(_) => {
+                    // the input ends, the bytes left are not a record either
+                    self.done = true;
+                    return Err(ParseError {
+                        offset: start,
+                        kind: ParseErrorKind::Skipped(self.offset - start),
+                    });
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Err(ParseError {
+                        offset: self.offset,
+                        kind: ParseErrorKind::Io(e.kind()),
+                    });
+                }
+            }
+
+            let offset = self.offset - size as u64;
+            if let Ok(data) = self
+                .layout
+                .decode_with(&self.buffer, offset, &self.registry)
+            {
+                self.pending = Some(data);
+                return Err(ParseError {
+                    offset: start,
+                    kind: ParseErrorKind::Skipped(offset - start),
+                });
+            }
+        }
+    }
+}
+
+/// Fill `buffer` unless the input ends first, returning the bytes read.
+fn read_full(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+impl<R: Read> Iterator for Records<R> {
+    type Item = Result<RustData, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(data) = self.pending.take() {
+            return Some(Ok(data));
+        }
+        if self.done {
+            return None;
+        }
+
+        let offset = self.offset;
+        let error = |kind| Some(Err(ParseError { offset, kind }));
+        let filled = match self.fill(0) {
+            Ok(filled) => filled,
+            Err(e) => {
+                self.done = true;
+                return error(ParseErrorKind::Io(e.kind()));
+            }
+        };
+        self.offset += filled as u64;
+
+        match filled {
+            0 => {
+                self.done = true;
+                None
+            }
+            len if len == self.layout.size => {
+                match self
+                    .layout
+                    .decode_with(&self.buffer, offset, &self.registry)
+                {
+                    Err(ParseError {
+                        kind: ParseErrorKind::UnknownTag(_) | ParseErrorKind::MismatchedTag { .. },
+                        ..
+                    }) if self.resync => Some(self.resynchronize(offset)),
+                    result => Some(result),
+                }
+            }
+            len => {
+                self.done = true;
+                error(ParseErrorKind::Truncated(len))
+            }
+        }
+    }
+}
+
+impl From<&RustData> for CData {
+    fn from(data: &RustData) -> Self {
+        let mut record = CData([0; CData::SIZE]);
+        // the timestamps fit in the 8 bytes of a `long`
+        Layout::LP64.encode(data, &mut record.0[..]).unwrap();
+        record
+    }
+}
+
+impl From<[u8; CData::SIZE]> for CData {
+    fn from(bytes: [u8; CData::SIZE]) -> Self {
+        CData(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, Read};
+
+    use crate::{CData, Endian, Layout, ParseError, ParseErrorKind, RustData};
+
+    const DATA: &[u8] = include_bytes!("../data");
+
+    fn record(index: usize) -> [u8; CData::SIZE] {
+        DATA[index * CData::SIZE..(index + 1) * CData::SIZE]
+            .try_into()
+            .unwrap()
+    }
+
+    #[test]
+    fn decode_test() {
+        assert_eq!(
+            CData::from(record(0)).to_rust(0),
+            Ok(RustData::Value {
+                val: 1.0,
+                timestamp: 1678656897
+            })
+        );
+        assert_eq!(
+            CData::from(record(1)).to_rust(64),
+            Ok(RustData::MValue {
+                val: [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0],
+                timestamp: 1678656897
+            })
+        );
+        assert_eq!(
+            CData::from(record(2)).to_rust(128),
+            Ok(RustData::Message {
+                message: "Bella".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_test() {
+        let error = |kind| Err(ParseError { offset: 640, kind });
+
+        let mut bytes = record(0);
+        bytes[0] = 7;
+        assert_eq!(
+            CData::from(bytes).to_rust(640),
+            error(ParseErrorKind::UnknownTag(7))
+        );
+
+        let mut bytes = record(0);
+        bytes[8] = 3;
+        assert_eq!(
+            CData::from(bytes).to_rust(640),
+            error(ParseErrorKind::MismatchedTag {
+                record: 1,
+                union: 3
+            })
+        );
+
+        let mut bytes = record(2);
+        bytes[12..33].fill(b'a');
+        assert_eq!(
+            CData::from(bytes).to_rust(640),
+            error(ParseErrorKind::UnterminatedMessage)
+        );
+
+        let mut bytes = record(2);
+        bytes[12] = 0xff;
+        assert_eq!(
+            CData::from(bytes).to_rust(640),
+            error(ParseErrorKind::InvalidMessage)
+        );
+    }
+
+    #[test]
+    fn reader_test() {
+        let records = CData::iter_from_reader(DATA).collect::<Vec<_>>();
+        assert_eq!(records.len(), 100);
+        assert!(records.iter().all(Result::is_ok));
+
+        // a partial record at the end is reported once
+        let mut records = CData::iter_from_reader(&DATA[..2 * CData::SIZE + 10]);
+        assert!(records.next().unwrap().is_ok());
+        assert!(records.next().unwrap().is_ok());
+        assert_eq!(
+            records.next(),
+            Some(Err(ParseError {
+                offset: 128,
+                kind: ParseErrorKind::Truncated(10)
+            }))
+        );
+        assert_eq!(records.next(), None);
+
+        assert_eq!(CData::iter_from_reader(io::empty()).count(), 0);
+    }
+
+    /// Reader returning a byte at a time, as a pipe may.
+    struct Slow<'a>(&'a [u8]);
+
+    impl Read for Slow<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((&first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            if buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn short_reads_test() {
+        let records = CData::iter_from_reader(Slow(&DATA[..3 * CData::SIZE])).collect::<Vec<_>>();
+        assert_eq!(
+            records,
+            CData::iter_from_reader(&DATA[..3 * CData::SIZE]).collect::<Vec<_>>()
+        );
+        assert_eq!(records.len(), 3);
+    }
+
+    #[test]
+    fn encode_test() {
+        // the padding of the C program is not zeroed, the fields are the same
+        for data in CData::iter_from_reader(DATA) {
+            let data = data.unwrap();
+            let mut bytes = Vec::new();
+            data.encode(&mut bytes).unwrap();
+
+            assert_eq!(bytes.len(), CData::SIZE);
+            assert_eq!(
+                CData::from(<[u8; CData::SIZE]>::try_from(bytes).unwrap()).to_rust(0),
+                Ok(data)
+            );
+        }
+
+        let message = |message: &str| {
+            let mut bytes = Vec::new();
+            RustData::Message {
+                message: message.to_string(),
+            }
+            .encode(&mut bytes)
+            .unwrap();
+            CData::iter_from_reader(bytes.as_slice()).next().unwrap()
+        };
+        assert_eq!(
+            message("Bella"),
+            Ok(RustData::Message {
+                message: "Bella".to_string()
+            })
+        );
+        assert_eq!(
+            message("E tutto il resto... e altro"),
+            Ok(RustData::Message {
+                message: "E tutto il resto... ".to_string()
+            })
+        );
+        // 19 bytes and a 2 bytes character
+        assert_eq!(
+            message("aaaaaaaaaaaaaaaaaaaè"),
+            Ok(RustData::Message {
+                message: "aaaaaaaaaaaaaaaaaaa".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn layout_test() {
+        let records = CData::iter_from_reader(DATA)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        for layout in [Layout::LP64, Layout::ILP32] {
+            for layout in [layout, layout.endian(Endian::Big)] {
+                let mut bytes = Vec::new();
+                for data in &records {
+                    layout.encode(data, &mut bytes).unwrap();
+                }
+                assert_eq!(bytes.len(), records.len() * layout.size);
+                assert_eq!(
+                    layout
+                        .records(bytes.as_slice())
+                        .map(Result::unwrap)
+                        .collect::<Vec<_>>(),
+                    records
+                );
+            }
+        }
+
+        let mut bytes = Vec::new();
+        let layout = Layout::ILP32.endian(Endian::Big);
+        layout.encode(&records[0], &mut bytes).unwrap();
+        assert_eq!(bytes[..12], [0, 0, 0, 1, 0, 0, 0, 1, 0x3f, 0x80, 0, 0]);
+        assert_eq!(bytes[12..16], 1678656897i32.to_be_bytes());
+        assert!(bytes[16..].iter().all(|&b| b == 0));
+
+        // the little endian tag read in big endian
+        assert_eq!(
+            Layout::LP64.endian(Endian::Big).records(DATA).next(),
+            Some(Err(ParseError {
+                offset: 0,
+                kind: ParseErrorKind::UnknownTag(1 << 24)
+            }))
+        );
+
+        let error = Layout::ILP32
+            .encode(
+                &RustData::Value {
+                    val: 1.0,
+                    timestamp: 1 << 40,
+                },
+                io::sink(),
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        assert_eq!("ilp32".parse(), Ok(Layout::ILP32));
+        assert_eq!("big".parse(), Ok(Endian::Big));
+        assert!("pdp".parse::<Endian>().is_err());
+    }
+
+    #[test]
+    fn resync_test() {
+        let skipped = |offset, len| {
+            Err(ParseError {
+                offset,
+                kind: ParseErrorKind::Skipped(len),
+            })
+        };
+        let records = CData::iter_from_reader(DATA).collect::<Vec<_>>();
+
+        // garbage between the records
+        let mut bytes = DATA[..2 * CData::SIZE].to_vec();
+        bytes.extend([0xff; 5]);
+        bytes.extend(&DATA[2 * CData::SIZE..]);
+        let resynced = CData::iter_from_reader(bytes.as_slice())
+            .resync(true)
+            .collect::<Vec<_>>();
+        assert_eq!(resynced[..2], records[..2]);
+        assert_eq!(resynced[2], skipped(128, 5));
+        assert_eq!(resynced[3..], records[2..]);
+
+        // without resynchronizing every record after is misaligned
+        let misaligned = CData::iter_from_reader(bytes.as_slice()).collect::<Vec<_>>();
+        assert!(misaligned[2..].iter().all(Result::is_err));
+
+        // a broken tag skips its record
+        let mut bytes = DATA[..3 * CData::SIZE].to_vec();
+        bytes[CData::SIZE] = 7;
+        let resynced = CData::iter_from_reader(bytes.as_slice())
+            .resync(true)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            resynced,
+            [records[0].clone(), skipped(64, 64), records[2].clone()]
+        );
+
+        // garbage up to the end of the input
+        let mut bytes = DATA[..CData::SIZE].to_vec();
+        bytes.extend([0xff; 100]);
+        let resynced = CData::iter_from_reader(bytes.as_slice())
+            .resync(true)
+            .collect::<Vec<_>>();
+        assert_eq!(resynced, [records[0].clone(), skipped(64, 100)]);
+    }
+
+    #[test]
+    fn datetime_test() {
+        let data = CData::from(record(0)).to_rust(0).unwrap();
+        assert_eq!(
+            data.datetime().unwrap().to_rfc3339(),
+            "2023-03-12T21:34:57+00:00"
+        );
+        assert_eq!(
+            RustData::Value {
+                val: 0.0,
+                timestamp: i64::MAX
+            }
+            .datetime(),
+            None
+        );
+        assert_eq!(
+            RustData::Message {
+                message: String::new()
+            }
+            .datetime(),
+            None
+        );
+    }
+}
@@ -1,134 +1,280 @@
-use clap::Parser;
-use std::ffi::CStr;
+use clap::{Args, Parser, Subcommand};
+use legacy_system::{Endian, Layout, RustData, Stats};
+use memmap2::Mmap;
 use std::fs::File;
-use std::io::{Read, self};
-use std::mem::{size_of, self};
+use std::io::{self, BufReader, BufWriter, Read, Write};
 use std::path::PathBuf;
-use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use filter::Filter;
+use output::Format;
+use split::Split;
+use timestamp::Timestamps;
 
+mod filter;
+mod output;
+mod split;
+mod timestamp;
+
+/// Dump, analyze or split the records of the legacy C sensor program, or generate them.
 #[derive(Parser, Debug)]
-#[command(version, long_about = None)]
-struct Args {
+#[command(version, long_about = None, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    args: DumpArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write synthetic records, the ones of the C program
+    Generate(GenerateArgs),
+    /// Analyze the records: counts, values, timestamps and messages
+    Stats(StatsArgs),
+    /// Write the records of every type in a file of their own, in one pass
+    Split(SplitArgs),
+}
 
-    /// Input file
+#[derive(Args, Debug)]
+struct DumpArgs {
+    /// Input file, stdin if missing or `-`
     #[arg(short, long)]
-    input: PathBuf,
-}
-
-
-#[derive(Clone, Copy)]
-#[repr(C)]
-struct Value {
-    data_type: i32,
-    val: f32,
-    timestamp: i64,
-}
-
-#[derive(Clone, Copy)]
-#[repr(C)]
-struct MValue {
-    data_type: i32,
-    val: [f32; 10],
-    timestamp: i64,
-}
-
-#[derive(Clone, Copy)]
-#[repr(C)]
-struct Message {
-    data_type: i32,
-    message: [u8; 21],
-}
-
-#[derive(Clone, Copy)]
-#[repr(C)]
-union DataUnion {
-    value: Value,
-    m_value: MValue,
-    message: Message,
-}
-
-#[derive(Clone, Copy)]
-#[repr(C)]
-struct CData {
-    data_type: i32,
-    data_union: DataUnion,
-}
-
-#[allow(dead_code)]
-#[derive(Debug)]
-enum RustData {
-    Value {
-        //data_type: i32,
-        val: f32,
-        timestamp: i64,
-    },
-    MValue {
-        //data_type: i32,
-        val: [f32; 10],
-        timestamp: i64,
-    },
-    Message {
-        //data_type: i32,
-        message: String,
+    input: Option<PathBuf>,
+
+    /// How the records are printed
+    #[arg(short, long, value_enum, default_value_t)]
+    output: Format,
+
+    /// After a record with an invalid tag, look for the next one a byte at a time
+    #[arg(long)]
+    resync: bool,
+
+    /// Only count the records, without printing them
+    #[arg(long, conflicts_with_all = ["output", "resync", "kind", "since", "skip", "limit", "tz", "format"])]
+    stats_only: bool,
+
+    #[command(flatten)]
+    filter: Filter,
+
+    #[command(flatten)]
+    timestamps: Timestamps,
+
+    #[command(flatten)]
+    layout: LayoutArgs,
+}
+
+/// Platform the records are written on.
+#[derive(Args, Debug)]
+struct LayoutArgs {
+    /// Byte order of the fields: little or big
+    #[arg(long, default_value = "little")]
+    endian: Endian,
+
+    /// Layout of the fields: lp64 (64-bit Unix) or ilp32 (32-bit, 64-bit Windows)
+    #[arg(long, default_value = "lp64")]
+    layout: Layout,
+}
+
+impl LayoutArgs {
+    fn layout(&self) -> Layout {
+        self.layout.endian(self.endian)
     }
 }
 
-impl CData {
-    fn from_file(file: &mut File) -> io::Result<Vec<RustData>> {
-        let mut data = Vec::<CData>::with_capacity(100);
-        let mut buffer = [0u8; size_of::<CData>()];
+#[derive(Args, Debug)]
+struct StatsArgs {
+    /// Input file, stdin if missing or `-`
+    input: Option<PathBuf>,
 
-        for _ in 0..100 {
-            file.read_exact(&mut buffer)?;
-            let c_data: CData = unsafe { mem::transmute(buffer) };
-            data.push(c_data);
-        }
+    /// Most frequent messages shown
+    #[arg(long, default_value_t = 5)]
+    top: usize,
+
+    /// After a record with an invalid tag, look for the next one a byte at a time
+    #[arg(long)]
+    resync: bool,
+
+    #[command(flatten)]
+    layout: LayoutArgs,
+}
+
+#[derive(Args, Debug)]
+struct SplitArgs {
+    /// Input file, stdin if missing or `-`
+    input: Option<PathBuf>,
+
+    /// Directory of values.csv, mvalues.csv and messages.txt, created if missing
+    #[arg(short, long)]
+    out_dir: PathBuf,
+
+    #[command(flatten)]
+    layout: LayoutArgs,
+}
+
+#[derive(Args, Debug)]
+struct GenerateArgs {
+    /// Number of records
+    #[arg(short = 'n', long, default_value_t = 100)]
+    count: usize,
+
+    /// Output file, stdout if missing or `-`
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Timestamp of the values, now if missing
+    #[arg(short, long)]
+    timestamp: Option<i64>,
+
+    #[command(flatten)]
+    layout: LayoutArgs,
+}
+
+const MESSAGES: [&str; 7] = [
+    "Bella",
+    "Test",
+    "Pippo",
+    "Pluto",
+    "42",
+    "AnswerToTheUniverse",
+    "E tutto il resto...",
+];
 
-        Ok(data.into_iter().map(|d| d.to_rust()).collect())
+/// The `index`-th record the C program writes: a value, ten values and a message in turn.
+fn synthetic(index: usize, timestamp: i64) -> RustData {
+    let round = index / 3;
+    match index % 3 {
+        0 => RustData::Value {
+            val: (round % 10 + 1) as f32,
+            timestamp,
+        },
+        1 => RustData::MValue {
+            val: std::array::from_fn(|i| (i + 1) as f32),
+            timestamp,
+        },
+        _ => RustData::Message {
+            message: MESSAGES[round % MESSAGES.len()].to_string(),
+        },
     }
+}
+
+fn generate(args: GenerateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let output: Box<dyn Write> = match args.output {
+        Some(path) if path.as_os_str() != "-" => Box::new(File::create(path)?),
+        _ => Box::new(io::stdout().lock()),
+    };
+    let mut output = BufWriter::new(output);
 
-    fn to_rust(self) -> RustData {
-        unsafe {
-            match self.data_type {
-                1 => RustData::Value {
-                    //data_type: self.data_union.value.data_type,
-                    val: self.data_union.value.val,
-                    timestamp: self.data_union.value.timestamp
-                },
-                2 => RustData::MValue { 
-                    //data_type: self.data_union.m_value.data_type,
-                    val: self.data_union.m_value.val, 
-                    timestamp: self.data_union.m_value.timestamp 
-                },
-                3 => {
-                    let c_message = self.data_union.message.message;
-                    let first_null = c_message.iter().position(|c| *c == b'\0').unwrap();
-
-                    // Generate CStr from raw bytes and then convert it to String
-                    let c_str = CStr::from_bytes_with_nul(&c_message[..=first_null]).expect("Cannot read string!");
-                    let message = String::from_str(c_str.to_str().unwrap()).unwrap();
-
-                    RustData::Message {
-                        //data_type: self.data_union.message.data_type, 
-                        message 
-                    }
-                }
-                _ => panic!("Unexpected value: {}!", self.data_type)
+    let timestamp = match args.timestamp {
+        Some(timestamp) => timestamp,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64,
+    };
+    let layout = args.layout.layout();
+    for index in 0..args.count {
+        layout.encode(&synthetic(index, timestamp), &mut output)?;
+    }
+    output.flush()?;
+
+    Ok(())
+}
+
+/// Input file, stdin if missing or `-`.
+fn open(input: Option<PathBuf>) -> io::Result<Box<dyn Read>> {
+    match input {
+        Some(path) if path.as_os_str() != "-" => Ok(Box::new(BufReader::new(File::open(path)?))),
+        _ => Ok(Box::new(io::stdin().lock())),
+    }
+}
+
+fn stats(args: StatsArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stats = Stats::default();
+    for data in args
+        .layout
+        .layout()
+        .records(open(args.input)?)
+        .resync(args.resync)
+    {
+        match data {
+            Ok(data) => stats.add(&data),
+            Err(e) => {
+                eprintln!("{}", e);
+                stats.invalid += 1;
             }
         }
     }
+    println!("{:.*}", args.top, stats);
+
+    Ok(())
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+/// Every line starts with the index of its record in the input, the broken ones counted.
+fn split(args: SplitArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut split = Split::create(&args.out_dir)?;
+    for (index, data) in args.layout.layout().records(open(args.input)?).enumerate() {
+        match data {
+            Ok(data) => split.write(index as u64, &data)?,
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    split.finish()?;
 
-    let mut file = File::open(args.input)?;
+    Ok(())
+}
 
-    let data = CData::from_file(&mut file)?;
+/// Count the records of the input, a file is mapped in memory instead of read.
+fn stats_only(args: DumpArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let layout = args.layout.layout();
+    let summary = match args.input {
+        Some(path) if path.as_os_str() != "-" => {
+            let file = File::open(path)?;
+            // SAFETY: the file isn't expected to change while it's counted, if it does
+            // the counts may be wrong but the bytes are only read
+            let map = unsafe { Mmap::map(&file)? };
+            layout.summarize(&map)
+        }
+        _ => layout.summarize_reader(io::stdin().lock())?,
+    };
+    println!("{}", summary);
+
+    Ok(())
+}
+
+fn dump(args: DumpArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if args.stats_only {
+        return stats_only(args);
+    }
+
+    let input = open(args.input)?;
+
+    if let Some(header) = args.output.header() {
+        println!("{}", header);
+    }
+
+    // a broken record is reported and the others still printed
+    let mut table = Vec::new();
+    args.filter
+        .apply(args.layout.layout().records(input).resync(args.resync))
+        .for_each(|d| match d {
+            Ok(d) if args.output == Format::Table => table.push(d),
+            Ok(d) => println!("{}", args.output.format(&d, &args.timestamps)),
+            Err(e) => eprintln!("{}", e),
+        });
 
-    data.iter()
-        .for_each(|d| println!("{:?}", d));
+    if args.output == Format::Table {
+        print!("{}", output::table(&table, &args.timestamps));
+    }
 
     Ok(())
 }
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Generate(args)) => generate(args),
+        Some(Command::Stats(args)) => stats(args),
+        Some(Command::Split(args)) => split(args),
+        None => dump(cli.args),
+    }
+}
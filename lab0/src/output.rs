@@ -0,0 +1,288 @@
+use clap::ValueEnum;
+use legacy_system::RustData;
+use serde::Serialize;
+
+use crate::timestamp::Timestamps;
+
+/// How the decoded records are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Format {
+    /// The `Debug` of a record per line.
+    #[default]
+    Debug,
+    /// An object per line, tagged by its `type`.
+    Json,
+    /// A line per record after a header, the values of `MValue` in a column each.
+    Csv,
+    /// Aligned columns, printed once the input ends.
+    Table,
+}
+
+const VALUES: usize = 10;
+
+impl Format {
+    /// Line printed before the records.
+    pub fn header(self) -> Option<String> {
+        match self {
+            Format::Csv => {
+                let values = (0..VALUES).map(|i| format!("val{}", i)).collect::<Vec<_>>();
+                Some(format!("type,timestamp,{},message", values.join(",")))
+            }
+            Format::Debug | Format::Json | Format::Table => None,
+        }
+    }
+
+    /// Line of a record, every format but `Table`. The dates of the timestamps replace them,
+    /// but in JSON where they are a `time` along them.
+    pub fn format(self, data: &RustData, timestamps: &Timestamps) -> String {
+        match self {
+            Format::Debug => match data {
+                RustData::Value { val, timestamp } if timestamps.dates() => {
+                    format!(
+                        "Value {{ val: {:?}, timestamp: {} }}",
+                        val,
+                        timestamps.render(*timestamp)
+                    )
+                }
+                RustData::MValue { val, timestamp } if timestamps.dates() => {
+                    format!(
+                        "MValue {{ val: {:?}, timestamp: {} }}",
+                        val,
+                        timestamps.render(*timestamp)
+                    )
+                }
+                _ => format!("{:?}", data),
+            },
+            Format::Json => {
+                let time = data
+                    .timestamp()
+                    .filter(|_| timestamps.dates())
+                    .map(|t| timestamps.render(t));
+                serde_json::to_string(&Json { data, time }).unwrap()
+            }
+            Format::Csv => {
+                let (kind, timestamp, values, message) = columns(data, timestamps);
+                let mut fields = vec![kind.to_string(), timestamp];
+                fields.extend(values.iter().map(f32::to_string));
+                fields.resize(2 + VALUES, String::new());
+                fields.push(csv_field(&message));
+                fields.join(",")
+            }
+            Format::Table => panic!("a table is printed with `table`"),
+        }
+    }
+}
+
+/// A record in JSON, with the date of its timestamp.
+#[derive(Serialize)]
+struct Json<'a> {
+    #[serde(flatten)]
+    data: &'a RustData,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time: Option<String>,
+}
+
+/// Name, timestamp, values and message of a record, the ones it has.
+/// The fields of an extension are its message, as `name=value`.
+fn columns<'a>(
+    data: &'a RustData,
+    timestamps: &Timestamps,
+) -> (&'a str, String, &'a [f32], String) {
+    match data {
+        RustData::Value { val, timestamp } => (
+            "Value",
+            timestamps.render(*timestamp),
+            std::slice::from_ref(val),
+            String::new(),
+        ),
+        RustData::MValue { val, timestamp } => {
+            ("MValue", timestamps.render(*timestamp), val, String::new())
+        }
+        RustData::Message { message } => ("Message", String::new(), &[], message.clone()),
+        RustData::Extension { name, fields, .. } => {
+            let fields = fields
+                .iter()
+                .map(|(name, field)| format!("{}={}", name, field));
+            (
+                name,
+                String::new(),
+                &[],
+                fields.collect::<Vec<_>>().join(" "),
+            )
+        }
+    }
+}
+
+/// The records in a table, its columns as wide as their longest cell.
+pub fn table(records: &[RustData], timestamps: &Timestamps) -> String {
+    let mut rows = vec![[
+        "type".to_string(),
+        "timestamp".to_string(),
+        "data".to_string(),
+    ]];
+    for data in records {
+        let (kind, timestamp, values, message) = columns(data, timestamps);
+        let data = match data {
+            RustData::Value { .. } | RustData::MValue { .. } => values
+                .iter()
+                .map(f32::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+            RustData::Message { .. } | RustData::Extension { .. } => message,
+        };
+        rows.push([kind.to_string(), timestamp, data]);
+    }
+
+    let widths: [usize; 3] =
+        std::array::from_fn(|i| rows.iter().map(|row| row[i].chars().count()).max().unwrap());
+    let line = |row: &[String; 3]| {
+        let cells = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!(" {:<width$} ", cell, width = width));
+        format!("|{}|\n", cells.collect::<Vec<_>>().join("|"))
+    };
+    let rule = format!(
+        "+{}+\n",
+        widths.map(|width| "-".repeat(width + 2)).join("+")
+    );
+
+    let mut table = rule.clone() + &line(&rows[0]) + &rule;
+    for row in &rows[1..] {
+        table += &line(row);
+    }
+    table + &rule
+}
+
+/// The field quoted if it contains a separator or a quote.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use legacy_system::{Field, RustData};
+
+    use crate::{
+        output::{table, Format},
+        timestamp::{Timestamps, Zone},
+    };
+
+    fn records() -> Vec<RustData> {
+        vec![
+            RustData::Value {
+                val: 1.5,
+                timestamp: 1678656897,
+            },
+            RustData::MValue {
+                val: [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0],
+                timestamp: 7,
+            },
+            RustData::Message {
+                message: "a, \"b\"".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn json_test() {
+        let lines = records()
+            .iter()
+            .map(|data| Format::Json.format(data, &Timestamps::default()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                r#"{"type":"Value","val":1.5,"timestamp":1678656897}"#,
+                r#"{"type":"MValue","val":[1.0,2.0,3.0,4.0,5.0,6.0,7.0,8.0,9.0,10.0],"timestamp":7}"#,
+                r#"{"type":"Message","message":"a, \"b\""}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_test() {
+        assert_eq!(
+            Format::Csv.header().unwrap(),
+            "type,timestamp,val0,val1,val2,val3,val4,val5,val6,val7,val8,val9,message"
+        );
+        let lines = records()
+            .iter()
+            .map(|data| Format::Csv.format(data, &Timestamps::default()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "Value,1678656897,1.5,,,,,,,,,,",
+                "MValue,7,1,2,3,4,5,6,7,8,9,10,",
+                "Message,,,,,,,,,,,,\"a, \"\"b\"\"\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn table_test() {
+        assert_eq!(
+            table(&records()[..2], &Timestamps::default()),
+            "\
++--------+------------+----------------------+
+| type   | timestamp  | data                 |
++--------+------------+----------------------+
+| Value  | 1678656897 | 1.5                  |
+| MValue | 7          | 1 2 3 4 5 6 7 8 9 10 |
++--------+------------+----------------------+
+"
+        );
+    }
+
+    #[test]
+    fn extension_test() {
+        let data = RustData::Extension {
+            tag: 4,
+            name: "Reading".to_string(),
+            fields: BTreeMap::from([
+                ("code".to_string(), Field::Int(42)),
+                ("unit".to_string(), Field::Text("mV".to_string())),
+            ]),
+        };
+        assert_eq!(
+            Format::Json.format(&data, &Timestamps::default()),
+            r#"{"type":"Extension","tag":4,"name":"Reading","fields":{"code":42,"unit":"mV"}}"#
+        );
+        assert_eq!(
+            Format::Csv.format(&data, &Timestamps::default()),
+            "Reading,,,,,,,,,,,,code=42 unit=mV"
+        );
+    }
+
+    #[test]
+    fn dates_test() {
+        let timestamps = Timestamps {
+            tz: Some(Zone::Utc),
+            format: Some("%Y-%m-%d %H:%M".to_string()),
+        };
+        let data = &records()[0];
+        assert_eq!(
+            Format::Debug.format(data, &timestamps),
+            "Value { val: 1.5, timestamp: 2023-03-12 21:34 }"
+        );
+        assert_eq!(
+            Format::Json.format(data, &timestamps),
+            r#"{"type":"Value","val":1.5,"timestamp":1678656897,"time":"2023-03-12 21:34"}"#
+        );
+        assert_eq!(
+            Format::Csv.format(data, &timestamps),
+            "Value,2023-03-12 21:34,1.5,,,,,,,,,,"
+        );
+        assert_eq!(
+            Format::Debug.format(&records()[2], &timestamps),
+            format!("{:?}", records()[2])
+        );
+    }
+}
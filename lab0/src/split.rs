@@ -0,0 +1,106 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use legacy_system::RustData;
+
+/// A file for every type of record, each line starting with the index of the record in the input.
+pub struct Split {
+    values: BufWriter<File>,
+    m_values: BufWriter<File>,
+    messages: BufWriter<File>,
+}
+
+impl Split {
+    /// Create `values.csv`, `mvalues.csv` and `messages.txt` in `dir`, and `dir` if missing.
+    pub fn create(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = |name| File::create(dir.join(name)).map(BufWriter::new);
+        let mut split = Split {
+            values: file("values.csv")?,
+            m_values: file("mvalues.csv")?,
+            messages: file("messages.txt")?,
+        };
+
+        writeln!(split.values, "index,timestamp,val")?;
+        let channels = (0..10).map(|i| format!("val{}", i)).collect::<Vec<_>>();
+        writeln!(split.m_values, "index,timestamp,{}", channels.join(","))?;
+        Ok(split)
+    }
+
+    /// Write the record at `index` in its file, the extensions are left out.
+    /// A message is a line of the index and its text, tab separated and escaped.
+    pub fn write(&mut self, index: u64, data: &RustData) -> io::Result<()> {
+        match data {
+            RustData::Value { val, timestamp } => {
+                writeln!(self.values, "{},{},{}", index, timestamp, val)
+            }
+            RustData::MValue { val, timestamp } => {
+                let val = val.iter().map(f32::to_string).collect::<Vec<_>>();
+                writeln!(self.m_values, "{},{},{}", index, timestamp, val.join(","))
+            }
+            RustData::Message { message } => {
+                writeln!(self.messages, "{}\t{}", index, message.escape_debug())
+            }
+            RustData::Extension { .. } => Ok(()),
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.values.flush()?;
+        self.m_values.flush()?;
+        self.messages.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use legacy_system::RustData;
+
+    use crate::split::Split;
+
+    #[test]
+    fn split_test() {
+        let dir = std::env::temp_dir().join(format!("lab0-split-{}", std::process::id()));
+        let records = [
+            RustData::Value {
+                val: 1.5,
+                timestamp: 10,
+            },
+            RustData::Message {
+                message: "a\tb".to_string(),
+            },
+            RustData::MValue {
+                val: [2.0; 10],
+                timestamp: 11,
+            },
+            RustData::Value {
+                val: 3.0,
+                timestamp: 12,
+            },
+        ];
+
+        let mut split = Split::create(&dir).unwrap();
+        for (index, data) in records.iter().enumerate() {
+            split.write(index as u64, data).unwrap();
+        }
+        split.finish().unwrap();
+
+        let read = |name| fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(
+            read("values.csv"),
+            "index,timestamp,val\n0,10,1.5\n3,12,3\n"
+        );
+        assert_eq!(
+            read("mvalues.csv"),
+            "index,timestamp,val0,val1,val2,val3,val4,val5,val6,val7,val8,val9\n2,11,2,2,2,2,2,2,2,2,2,2\n"
+        );
+        assert_eq!(read("messages.txt"), "1\ta\\tb\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
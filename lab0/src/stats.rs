@@ -0,0 +1,227 @@
+use std::{cmp::Reverse, collections::HashMap, fmt};
+
+use crate::RustData;
+
+/// Count, smallest, largest and mean of a series of values.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Series {
+    pub count: u64,
+    pub min: f32,
+    pub max: f32,
+    sum: f64,
+}
+
+impl Series {
+    pub fn add(&mut self, val: f32) {
+        if self.count == 0 {
+            (self.min, self.max) = (val, val);
+        } else {
+            self.min = self.min.min(val);
+            self.max = self.max.max(val);
+        }
+        self.count += 1;
+        self.sum += val as f64;
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+impl fmt::Display for Series {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mean() {
+            Some(mean) => write!(f, "min {} max {} mean {:.3}", self.min, self.max, mean),
+            None => write!(f, "none"),
+        }
+    }
+}
+
+/// Statistics of the decoded records, added one at a time in the order of the input.
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    pub messages: u64,
+    /// Records of the types of a `Registry`.
+    pub extensions: u64,
+    /// Records that don't decode.
+    pub invalid: u64,
+    /// The `val` of the values.
+    pub values: Series,
+    /// Every value of the `MValue`s, by their position.
+    pub channels: [Series; 10],
+    /// Smallest and largest timestamp.
+    pub timestamps: Option<(i64, i64)>,
+    /// The two timestamps one after the other, in the input, the furthest apart.
+    pub largest_gap: Option<(i64, i64)>,
+    /// Timestamps before the one of the record preceding them.
+    pub out_of_order: u64,
+    last_timestamp: Option<i64>,
+    message_counts: HashMap<String, u64>,
+}
+
+impl Stats {
+    pub fn add(&mut self, data: &RustData) {
+        match data {
+            RustData::Value { val, .. } => self.values.add(*val),
+            RustData::MValue { val, .. } => {
+                for (channel, &val) in self.channels.iter_mut().zip(val) {
+                    channel.add(val);
+                }
+            }
+            RustData::Message { message } => {
+                self.messages += 1;
+                *self.message_counts.entry(message.clone()).or_default() += 1;
+            }
+            RustData::Extension { .. } => self.extensions += 1,
+        }
+
+        let Some(timestamp) = data.timestamp() else {
+            return;
+        };
+        self.timestamps = Some(match self.timestamps {
+            Some((min, max)) => (min.min(timestamp), max.max(timestamp)),
+            None => (timestamp, timestamp),
+        });
+        if let Some(last) = self.last_timestamp {
+            if timestamp < last {
+                self.out_of_order += 1;
+            } else if self
+                .largest_gap
+                .is_none_or(|(from, to)| timestamp - last > to - from)
+            {
+                self.largest_gap = Some((last, timestamp));
+            }
+        }
+        self.last_timestamp = Some(timestamp);
+    }
+
+    /// Records decoded.
+    pub fn records(&self) -> u64 {
+        self.values.count + self.m_values() + self.messages + self.extensions
+    }
+
+    pub fn m_values(&self) -> u64 {
+        self.channels[0].count
+    }
+
+    /// The `n` messages found the most, with how many times, the most first.
+    pub fn top_messages(&self, n: usize) -> Vec<(&str, u64)> {
+        let mut messages = self
+            .message_counts
+            .iter()
+            .map(|(message, &count)| (message.as_str(), count))
+            .collect::<Vec<_>>();
+        messages.sort_by_key(|&(message, count)| (Reverse(count), message));
+        messages.truncate(n);
+        messages
+    }
+}
+
+impl fmt::Display for Stats {
+    /// The report of every statistic, with the five most frequent messages,
+    /// or as many as the precision: `{:.10}`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "records: {} ({} values, {} mvalues, {} messages, {} extensions, {} invalid)",
+            self.records(),
+            self.values.count,
+            self.m_values(),
+            self.messages,
+            self.extensions,
+            self.invalid
+        )?;
+        writeln!(f, "values: {}", self.values)?;
+        writeln!(f, "mvalues:")?;
+        for (i, channel) in self.channels.iter().enumerate() {
+            writeln!(f, "  [{}] {}", i, channel)?;
+        }
+
+        match self.timestamps {
+            Some((min, max)) => writeln!(f, "timestamps: {} to {}", min, max)?,
+            None => writeln!(f, "timestamps: none")?,
+        }
+        match self.largest_gap {
+            Some((from, to)) => {
+                writeln!(f, "largest gap: {} s, from {} to {}", to - from, from, to)?
+            }
+            None => writeln!(f, "largest gap: none")?,
+        }
+        writeln!(f, "out of order: {}", self.out_of_order)?;
+
+        write!(f, "messages:")?;
+        for (message, count) in self.top_messages(f.precision().unwrap_or(5)) {
+            write!(f, "\n  {:>6} {:?}", count, message)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        stats::{Series, Stats},
+        CData, RustData,
+    };
+
+    const DATA: &[u8] = include_bytes!("../data");
+
+    #[test]
+    fn series_test() {
+        let mut series = Series::default();
+        assert_eq!(series.mean(), None);
+        for val in [2.0, -1.0, 5.0] {
+            series.add(val);
+        }
+        assert_eq!(
+            (series.count, series.min, series.max, series.mean()),
+            (3, -1.0, 5.0, Some(2.0))
+        );
+    }
+
+    #[test]
+    fn stats_test() {
+        let mut stats = Stats::default();
+        CData::iter_from_reader(DATA).for_each(|data| stats.add(&data.unwrap()));
+
+        assert_eq!(
+            (
+                stats.records(),
+                stats.values.count,
+                stats.m_values(),
+                stats.messages
+            ),
+            (100, 34, 33, 33)
+        );
+        // the values go from 1 to 10, 1 to 4 once more
+        assert_eq!((stats.values.min, stats.values.max), (1.0, 10.0));
+        assert_eq!(stats.values.mean(), Some((55.0 * 3.0 + 10.0) / 34.0));
+        assert_eq!(stats.channels[9].mean(), Some(10.0));
+        assert_eq!(stats.timestamps, Some((1678656897, 1678656897)));
+        assert_eq!(stats.largest_gap, Some((1678656897, 1678656897)));
+        // 33 messages of 7 in turn, the first five found once more
+        assert_eq!(stats.top_messages(2), [("42", 5), ("Bella", 5)]);
+        assert_eq!(stats.top_messages(10).len(), 7);
+        assert_eq!(
+            stats.top_messages(7).last(),
+            Some(&("E tutto il resto...", 4))
+        );
+    }
+
+    #[test]
+    fn gaps_test() {
+        let mut stats = Stats::default();
+        for timestamp in [10, 12, 30, 25, 26] {
+            stats.add(&RustData::Value {
+                val: 0.0,
+                timestamp,
+            });
+            stats.add(&RustData::Message {
+                message: String::new(),
+            });
+        }
+        assert_eq!(stats.timestamps, Some((10, 30)));
+        assert_eq!(stats.largest_gap, Some((12, 30)));
+        assert_eq!(stats.out_of_order, 1);
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+use crate::{read_full, Layout, MESSAGE, M_VALUE, VALUE};
+
+/// Counts of the records of an input, checked as `Layout::decode` does but without
+/// making a `RustData`, so without allocating for every record.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub values: u64,
+    pub m_values: u64,
+    pub messages: u64,
+    /// Records that don't decode.
+    pub invalid: u64,
+    /// Bytes of a record cut short by the end of the input.
+    pub truncated: usize,
+    /// Smallest and largest timestamp of the values.
+    pub timestamps: Option<(i64, i64)>,
+}
+
+/// Records read at a time by `Layout::summarize_reader`.
+const CHUNK_RECORDS: usize = 1 << 14;
+
+impl Summary {
+    pub fn records(&self) -> u64 {
+        self.values + self.m_values + self.messages + self.invalid
+    }
+
+    /// Count the whole records of `bytes`, returning the bytes after the last.
+    fn add<'a>(&mut self, layout: &Layout, bytes: &'a [u8]) -> &'a [u8] {
+        let records = bytes.chunks_exact(layout.size);
+        let rest = records.remainder();
+
+        for record in records {
+            let timestamp = match layout.tag(record) {
+                Ok(VALUE) => {
+                    self.values += 1;
+                    layout.long_at(record, layout.value_timestamp)
+                }
+                Ok(M_VALUE) => {
+                    self.m_values += 1;
+                    layout.long_at(record, layout.m_value_timestamp)
+                }
+                Ok(MESSAGE) if layout.message(record).is_ok() => {
+                    self.messages += 1;
+                    continue;
+                }
+                _ => {
+                    self.invalid += 1;
+                    continue;
+                }
+            };
+
+            self.timestamps = Some(match self.timestamps {
+                Some((min, max)) => (min.min(timestamp), max.max(timestamp)),
+                None => (timestamp, timestamp),
+            });
+        }
+        rest
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "records: {} ({} values, {} mvalues, {} messages, {} invalid)",
+            self.records(),
+            self.values,
+            self.m_values,
+            self.messages,
+            self.invalid
+        )?;
+        match self.timestamps {
+            Some((min, max)) => writeln!(f, "timestamps: {} to {}", min, max)?,
+            None => writeln!(f, "timestamps: none")?,
+        }
+        write!(f, "truncated: {} bytes", self.truncated)
+    }
+}
+
+impl Layout {
+    /// Count the records of the whole input in memory, a mapped file.
+    pub fn summarize(&self, bytes: &[u8]) -> Summary {
+        let mut summary = Summary::default();
+        summary.truncated = summary.add(self, bytes).len();
+        summary
+    }
+
+    /// Count the records of `reader`, read in chunks of many records into a single buffer.
+    pub fn summarize_reader(&self, mut reader: impl Read) -> io::Result<Summary> {
+        let mut summary = Summary::default();
+        let mut buffer = vec![0; CHUNK_RECORDS * self.size];
+        loop {
+            let filled = read_full(&mut reader, &mut buffer)?;
+            let rest = summary.add(self, &buffer[..filled]);
+            if filled < buffer.len() {
+                summary.truncated = rest.len();
+                return Ok(summary);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{CData, Layout, Summary};
+
+    const DATA: &[u8] = include_bytes!("../data");
+
+    #[test]
+    fn summary_test() {
+        let summary = Layout::LP64.summarize(DATA);
+        assert_eq!(
+            summary,
+            Summary {
+                values: 34,
+                m_values: 33,
+                messages: 33,
+                invalid: 0,
+                truncated: 0,
+                timestamps: Some((1678656897, 1678656897)),
+            }
+        );
+        assert_eq!(
+            summary.records(),
+            CData::iter_from_reader(DATA).count() as u64
+        );
+
+        let mut bytes = DATA.to_vec();
+        bytes[CData::SIZE] = 7;
+        bytes[2 * CData::SIZE + 12..3 * CData::SIZE].fill(b'a');
+        bytes.extend([0; 10]);
+        let summary = Layout::LP64.summarize(&bytes);
+        assert_eq!(
+            (
+                summary.m_values,
+                summary.messages,
+                summary.invalid,
+                summary.truncated
+            ),
+            (32, 32, 2, 10)
+        );
+    }
+
+    #[test]
+    fn reader_test() {
+        // more than a chunk
+        let mut bytes = DATA.repeat(200);
+        bytes.extend([0; 10]);
+        let summary = Layout::LP64.summarize_reader(bytes.as_slice()).unwrap();
+        assert_eq!(summary, Layout::LP64.summarize(&bytes));
+        assert_eq!(summary.records(), 20000);
+        assert_eq!(summary.truncated, 10);
+    }
+}
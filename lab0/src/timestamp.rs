@@ -0,0 +1,141 @@
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, FixedOffset, Local, TimeZone,
+};
+use chrono_tz::Tz;
+use clap::Args;
+
+/// RFC 3339, with the offset of the zone.
+const DEFAULT_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%:z";
+
+/// Zone the timestamps are shown in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Utc,
+    /// The one of the system.
+    Local,
+    Fixed(FixedOffset),
+    Named(Tz),
+}
+
+impl FromStr for Zone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "utc" | "UTC" => Ok(Zone::Utc),
+            "local" => Ok(Zone::Local),
+            _ => s
+                .parse::<FixedOffset>()
+                .map(Zone::Fixed)
+                .or_else(|_| s.parse::<Tz>().map(Zone::Named))
+                .map_err(|_| format!("unknown time zone {}, expected utc, local, an offset as +02:00 or a name as Europe/Rome", s)),
+        }
+    }
+}
+
+/// A `strftime` format, checked when parsed since a wrong one can't be printed.
+fn parse_format(s: &str) -> Result<String, String> {
+    if StrftimeItems::new(s).any(|item| item == Item::Error) {
+        return Err(format!(
+            "invalid format {}, see the specifiers of strftime",
+            s
+        ));
+    }
+    Ok(s.to_string())
+}
+
+/// How the timestamps are printed: the seconds as they are read, or dates when
+/// a zone or a format is given.
+#[derive(Args, Debug, Clone, Default)]
+pub struct Timestamps {
+    /// Print the timestamps as dates in this zone: utc, local, an offset or a name as Europe/Rome
+    #[arg(long)]
+    pub tz: Option<Zone>,
+
+    /// Print the timestamps as dates with this strftime format, in UTC without --tz [default: RFC 3339]
+    #[arg(long, value_parser = parse_format)]
+    pub format: Option<String>,
+}
+
+impl Timestamps {
+    /// The timestamps are printed as dates.
+    pub fn dates(&self) -> bool {
+        self.tz.is_some() || self.format.is_some()
+    }
+
+    /// The timestamp as a date, or as it is out of the range of the dates.
+    pub fn render(&self, timestamp: i64) -> String {
+        let Some(datetime) = self
+            .dates()
+            .then(|| DateTime::from_timestamp(timestamp, 0))
+            .flatten()
+        else {
+            return timestamp.to_string();
+        };
+        let format = self.format.as_deref().unwrap_or(DEFAULT_FORMAT);
+        match self.tz.unwrap_or(Zone::Utc) {
+            Zone::Utc => render(datetime, format),
+            Zone::Local => render(datetime.with_timezone(&Local), format),
+            Zone::Fixed(offset) => render(datetime.with_timezone(&offset), format),
+            Zone::Named(tz) => render(datetime.with_timezone(&tz), format),
+        }
+    }
+}
+
+fn render<Z: TimeZone>(datetime: DateTime<Z>, format: &str) -> String
+where
+    Z::Offset: Display,
+{
+    datetime.format(format).to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::timestamp::{parse_format, Timestamps, Zone};
+
+    const TIMESTAMP: i64 = 1678656897;
+
+    fn timestamps(tz: Option<Zone>, format: Option<&str>) -> Timestamps {
+        Timestamps {
+            tz,
+            format: format.map(|format| parse_format(format).unwrap()),
+        }
+    }
+
+    #[test]
+    fn render_test() {
+        assert_eq!(Timestamps::default().render(TIMESTAMP), "1678656897");
+        assert_eq!(
+            timestamps(Some(Zone::Utc), None).render(TIMESTAMP),
+            "2023-03-12T21:34:57+00:00"
+        );
+
+        let rome = timestamps(Some("Europe/Rome".parse().unwrap()), None);
+        assert_eq!(rome.render(TIMESTAMP), "2023-03-12T22:34:57+01:00");
+        // summer time
+        assert_eq!(
+            rome.render(TIMESTAMP + 200 * 86400),
+            "2023-09-28T23:34:57+02:00"
+        );
+
+        let fixed = timestamps(Some("-05:00".parse().unwrap()), Some("%d/%m/%Y %H:%M"));
+        assert_eq!(fixed.render(TIMESTAMP), "12/03/2023 16:34");
+        assert_eq!(timestamps(None, Some("%s")).render(TIMESTAMP), "1678656897");
+
+        // out of the range of the dates
+        assert_eq!(
+            timestamps(Some(Zone::Utc), None).render(i64::MAX),
+            i64::MAX.to_string()
+        );
+    }
+
+    #[test]
+    fn parse_test() {
+        assert!("Mars/Olympus".parse::<Zone>().is_err());
+        assert_eq!("local".parse(), Ok(Zone::Local));
+        assert!(parse_format("%Y-%Q").is_err());
+    }
+}
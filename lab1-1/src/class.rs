@@ -0,0 +1,96 @@
+use std::{ops::RangeInclusive, str::FromStr};
+
+/// Characters kept in a slug, as ranges like `a-z` and single ones, written
+/// together as in a regular expression: `a-z0-9_.`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharClass {
+    ranges: Vec<RangeInclusive<char>>,
+}
+
+impl Default for CharClass {
+    /// The ASCII letters and digits.
+    fn default() -> Self {
+        CharClass::new()
+            .range('a', 'z')
+            .range('A', 'Z')
+            .range('0', '9')
+    }
+}
+
+impl CharClass {
+    /// A class without characters.
+    pub fn new() -> Self {
+        CharClass { ranges: Vec::new() }
+    }
+
+    /// Add the characters from `from` to `to`, both included.
+    pub fn range(mut self, from: char, to: char) -> Self {
+        self.ranges.push(from..=to);
+        self
+    }
+
+    /// Add every character of `chars`.
+    pub fn chars(mut self, chars: &str) -> Self {
+        self.ranges.extend(chars.chars().map(|char| char..=char));
+        self
+    }
+
+    /// Whether `char` is in the class.
+    pub fn contains(&self, char: char) -> bool {
+        self.ranges.iter().any(|range| range.contains(&char))
+    }
+}
+
+impl FromStr for CharClass {
+    type Err = String;
+
+    /// A `-` at the start or at the end is the character itself.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars = s.chars().collect::<Vec<_>>();
+        let mut class = CharClass::new();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i..] {
+                [from, '-', to, ..] => {
+                    if from > to {
+                        return Err(format!("range {}-{} out of order", from, to));
+                    }
+                    class = class.range(from, to);
+                    i += 3;
+                }
+                [char, ..] => {
+                    class = class.range(char, char);
+                    i += 1;
+                }
+                [] => unreachable!(),
+            }
+        }
+        if class.ranges.is_empty() {
+            return Err("the class has no characters".to_string());
+        }
+        Ok(class)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::CharClass;
+
+    #[test]
+    fn parse_test() {
+        let class = "a-f0-9_".parse::<CharClass>().unwrap();
+        assert_eq!(
+            class,
+            CharClass::new().range('a', 'f').range('0', '9').chars("_")
+        );
+        assert!(class.contains('c') && class.contains('_') && !class.contains('g'));
+
+        let class = "-.a-c-".parse::<CharClass>().unwrap();
+        assert_eq!(
+            class,
+            CharClass::new().chars("-.").range('a', 'c').chars("-")
+        );
+        assert!("z-a".parse::<CharClass>().is_err());
+        assert!("".parse::<CharClass>().is_err());
+    }
+}
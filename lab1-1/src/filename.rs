@@ -0,0 +1,172 @@
+/// Characters not allowed in a file name on Windows, the path separators among them.
+const RESERVED: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Names of devices on Windows, with any extension.
+const DEVICES: [&str; 4] = ["CON", "PRN", "AUX", "NUL"];
+
+/// How a string is turned into a file name valid on Unix and Windows, made with the builder
+/// methods from the defaults: the characters not allowed replaced with `_`, at most 255 bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilenameOptions {
+    replacement: char,
+    max_bytes: usize,
+}
+
+impl Default for FilenameOptions {
+    fn default() -> Self {
+        FilenameOptions {
+            replacement: '_',
+            max_bytes: 255,
+        }
+    }
+}
+
+impl FilenameOptions {
+    pub fn new() -> Self {
+        FilenameOptions::default()
+    }
+
+    /// Character replacing every run of the ones not allowed, a name that is a device
+    /// starts with it too. It must be allowed itself.
+    pub fn replacement(mut self, replacement: char) -> Self {
+        assert!(
+            !RESERVED.contains(&replacement) && !replacement.is_control(),
+            "{:?} isn't allowed in a file name",
+            replacement
+        );
+        self.replacement = replacement;
+        self
+    }
+
+    /// Bytes of the name at most, its extension is kept if it fits.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        assert!(max_bytes > 0, "a file name has a byte at least");
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// The file name of `name`, the replacement alone if nothing is left of it.
+    pub fn sanitize(&self, name: &str) -> String {
+        let mut sanitized = String::new();
+        for char in name.chars() {
+            let char = if RESERVED.contains(&char) || char.is_control() {
+                self.replacement
+            } else {
+                char
+            };
+            if char == self.replacement && sanitized.ends_with(self.replacement) {
+                continue;
+            }
+            sanitized.push(char);
+        }
+        // Windows drops the dots and spaces at the end, `.` and `..` are the directories
+        let mut sanitized = sanitized
+            .trim_start()
+            .trim_end_matches([' ', '.'])
+            .to_string();
+
+        if is_device(&sanitized) {
+            sanitized.insert(0, self.replacement);
+        }
+        if sanitized.len() > self.max_bytes {
+            sanitized = self.truncate(&sanitized);
+        }
+        if sanitized.is_empty() {
+            sanitized.push(self.replacement);
+        }
+        sanitized
+    }
+
+    /// The start of the stem of `name` and its extension in `max_bytes`, the start
+    /// of `name` if the extension alone doesn't fit.
+    fn truncate(&self, name: &str) -> String {
+        let extension = match name.rfind('.') {
+            Some(dot) if dot > 0 && name.len() - dot < self.max_bytes => &name[dot..],
+            _ => "",
+        };
+        let stem = &name[..name.len() - extension.len()];
+        let stem = floor(stem, self.max_bytes - extension.len()).trim_end_matches([' ', '.']);
+        if stem.is_empty() {
+            return floor(name, self.max_bytes)
+                .trim_end_matches([' ', '.'])
+                .to_string();
+        }
+        format!("{}{}", stem, extension)
+    }
+}
+
+/// Whether the part of `name` before its first dot is a device: `CON.txt` is `CON` too.
+fn is_device(name: &str) -> bool {
+    let device = name.split('.').next().unwrap().trim_end().to_uppercase();
+    match device.as_bytes() {
+        [b'C', b'O', b'M', b'1'..=b'9'] | [b'L', b'P', b'T', b'1'..=b'9'] => true,
+        _ => DEVICES.contains(&device.as_str()),
+    }
+}
+
+/// The longest start of `s` of `max_bytes` at most.
+fn floor(s: &str, max_bytes: usize) -> &str {
+    let mut end = max_bytes.min(s.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// The file name of `name` with the default options.
+pub fn sanitize_filename(name: &str) -> String {
+    FilenameOptions::default().sanitize(name)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{sanitize_filename, FilenameOptions};
+
+    #[test]
+    fn sanitize_test() {
+        assert_eq!(sanitize_filename("report.pdf"), "report.pdf");
+        assert_eq!(
+            sanitize_filename("Perché è così?.txt"),
+            "Perché è così_.txt"
+        );
+        assert_eq!(sanitize_filename("../../etc/passwd"), ".._.._etc_passwd");
+        assert_eq!(
+            sanitize_filename("C:\\Users\\me\\a|b.txt"),
+            "C_Users_me_a_b.txt"
+        );
+        assert_eq!(sanitize_filename(" notes. . "), "notes");
+        assert_eq!(sanitize_filename(".bashrc"), ".bashrc");
+        assert_eq!(sanitize_filename(".."), "_");
+        assert_eq!(sanitize_filename("tab\there"), "tab_here");
+    }
+
+    #[test]
+    fn devices_test() {
+        assert_eq!(sanitize_filename("CON"), "_CON");
+        assert_eq!(sanitize_filename("con.txt"), "_con.txt");
+        assert_eq!(sanitize_filename("Lpt9 .tar.gz"), "_Lpt9 .tar.gz");
+        assert_eq!(sanitize_filename("COM10"), "COM10");
+        assert_eq!(sanitize_filename("console.log"), "console.log");
+        assert_eq!(
+            FilenameOptions::new().replacement('-').sanitize("nul"),
+            "-nul"
+        );
+    }
+
+    #[test]
+    fn max_bytes_test() {
+        let options = FilenameOptions::new().max_bytes(10);
+        assert_eq!(options.sanitize("a very long name.txt"), "a very.txt");
+        assert_eq!(options.sanitize("perchèèèèè.md"), "perchè.md");
+        // the two bytes of `è` don't fit, the name is cut before it
+        assert_eq!(
+            FilenameOptions::new()
+                .max_bytes(9)
+                .sanitize("perchèèèèè.md"),
+            "perch.md"
+        );
+        assert_eq!(options.sanitize("archive.verylongextension"), "archive.ve");
+        assert_eq!(options.sanitize(".hidden-and-long"), ".hidden-an");
+        assert_eq!(sanitize_filename(&"x".repeat(300)).len(), 255);
+    }
+}
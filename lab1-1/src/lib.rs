@@ -0,0 +1,477 @@
+use std::{error::Error, fmt};
+
+pub use class::CharClass;
+pub use filename::{sanitize_filename, FilenameOptions};
+pub use set::SlugSet;
+pub use stop_words::Language;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+mod class;
+mod filename;
+mod set;
+mod stop_words;
+
+/// Letters that are not a base letter with its marks once decomposed.
+const SPECIAL: [(char, &str); 20] = [
+    ('ß', "ss"),
+    ('ẞ', "SS"),
+    ('æ', "ae"),
+    ('Æ', "AE"),
+    ('œ', "oe"),
+    ('Œ', "OE"),
+    ('ø', "o"),
+    ('Ø', "O"),
+    ('đ', "d"),
+    ('Đ', "D"),
+    ('ð', "d"),
+    ('Ð', "D"),
+    ('ł', "l"),
+    ('Ł', "L"),
+    ('ı', "i"),
+    ('þ', "th"),
+    ('Þ', "TH"),
+    ('ħ', "h"),
+    ('Ħ', "H"),
+    ('ŀ', "l"),
+];
+
+/// The text without diacritics: every character decomposed in compatibility form
+/// (NFKD), without its combining marks, but the `SPECIAL` ones. The letters of
+/// the other scripts left are written in Latin ones if `transliterate`.
+fn remove_diacritics(text: &str, transliterate: bool) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for char in text.chars() {
+        if let Some((_, replacement)) = SPECIAL.iter().find(|(special, _)| *special == char) {
+            folded.push_str(replacement);
+            continue;
+        }
+        for char in char.nfkd().filter(|&c| !is_combining_mark(c)) {
+            match transliteration(char).filter(|_| transliterate && !char.is_ascii()) {
+                Some(latin) => folded.push_str(latin),
+                None => folded.push(char),
+            }
+        }
+    }
+    folded
+}
+
+#[cfg(feature = "transliterate")]
+fn transliteration(char: char) -> Option<&'static str> {
+    Some(unidecode::unidecode_char(char))
+}
+
+#[cfg(not(feature = "transliterate"))]
+fn transliteration(_: char) -> Option<&'static str> {
+    None
+}
+
+/// A slug without letters or digits, of a string without any to keep.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmptySlug;
+
+impl fmt::Display for EmptySlug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the slug has no letters or digits")
+    }
+}
+
+impl Error for EmptySlug {}
+
+/// How a string is turned into a slug, made with the builder methods from the defaults:
+/// of ASCII letters and digits `-` separated, of any length, lowercase, without separators
+/// at the ends nor two in a row, the other scripts transliterated with the `transliterate` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlugifyOptions {
+    separator: char,
+    class: CharClass,
+    max_length: Option<usize>,
+    lowercase: bool,
+    trim_separators: bool,
+    transliterate: bool,
+    fallback: Option<String>,
+    replacements: Vec<(String, String)>,
+    stop_words: Vec<String>,
+}
+
+impl Default for SlugifyOptions {
+    fn default() -> Self {
+        SlugifyOptions {
+            separator: '-',
+            class: CharClass::default(),
+            max_length: None,
+            lowercase: true,
+            trim_separators: true,
+            transliterate: cfg!(feature = "transliterate"),
+            fallback: None,
+            replacements: Vec::new(),
+            stop_words: Vec::new(),
+        }
+    }
+}
+
+impl SlugifyOptions {
+    pub fn new() -> Self {
+        SlugifyOptions::default()
+    }
+
+    /// Character replacing every run of the ones not allowed.
+    pub fn separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Allow the characters of `chars` too, like `_` and `.` of identifiers and file names.
+    pub fn allow(mut self, chars: &str) -> Self {
+        self.class = self.class.chars(chars);
+        self
+    }
+
+    /// Allow the characters of `class` only, instead of the letters and digits, matched
+    /// once the accents are removed and the letters lowercased.
+    pub fn char_class(mut self, class: CharClass) -> Self {
+        self.class = class;
+        self
+    }
+
+    /// Characters of the slug at most, it's cut at the last separator that fits,
+    /// or after `max_length` characters if its first word is longer.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Lowercase the letters, otherwise their case is kept.
+    pub fn lowercase(mut self, lowercase: bool) -> Self {
+        self.lowercase = lowercase;
+        self
+    }
+
+    /// Remove the separators at the start and at the end of the slug, the default.
+    pub fn trim_separators(mut self, trim_separators: bool) -> Self {
+        self.trim_separators = trim_separators;
+        self
+    }
+
+    /// Write the letters of Cyrillic, Greek, CJK (Mandarin pinyin) and the other scripts
+    /// in Latin ones, instead of replacing them with separators.
+    #[cfg(feature = "transliterate")]
+    pub fn transliterate(mut self, transliterate: bool) -> Self {
+        self.transliterate = transliterate;
+        self
+    }
+
+    /// Replace every `from` of the string with its `to` before its characters are filtered,
+    /// the first one matching where more do. They're added to the ones given before.
+    pub fn with_replacements(mut self, replacements: &[(&str, &str)]) -> Self {
+        self.replacements.extend(
+            replacements
+                .iter()
+                .filter(|(from, _)| !from.is_empty())
+                .map(|(from, to)| (from.to_string(), to.to_string())),
+        );
+        self
+    }
+
+    /// Leave out the stop words of `language`, added to the ones given before.
+    pub fn stop_words(self, language: Language) -> Self {
+        self.with_stop_words(language.stop_words())
+    }
+
+    /// Leave out the words of the string among `stop_words`, matched without their case
+    /// and accents, added to the ones given before. A string of stop words only keeps them.
+    pub fn with_stop_words(mut self, stop_words: &[&str]) -> Self {
+        self.stop_words.extend(
+            stop_words
+                .iter()
+                .map(|word| remove_diacritics(word, false).to_lowercase()),
+        );
+        self
+    }
+
+    /// Slug of a string without letters or digits, instead of an `EmptySlug`.
+    pub fn fallback(mut self, fallback: impl Into<String>) -> Self {
+        self.fallback = Some(fallback.into());
+        self
+    }
+
+    /// The slug of `slug`, the fallback or an empty one if there's nothing to keep.
+    pub fn slugify(&self, slug: &str) -> String {
+        self.try_slugify(slug).unwrap_or_default()
+    }
+
+    /// The slug of `slug`, the fallback or an error if there's nothing to keep.
+    pub fn try_slugify(&self, slug: &str) -> Result<String, EmptySlug> {
+        let slugified = self.slug(slug);
+        if slugified.chars().any(|char| char != self.separator) {
+            return Ok(slugified);
+        }
+        self.fallback.clone().ok_or(EmptySlug)
+    }
+
+    fn slug(&self, slug: &str) -> String {
+        let mut slugified = String::new();
+
+        let mut slug = remove_diacritics(&self.replace(slug), self.transliterate);
+        if self.lowercase {
+            slug = slug.to_lowercase();
+        }
+
+        for char in slug.chars() {
+            let next_char = if self.class.contains(char) {
+                char
+            } else {
+                self.separator
+            };
+
+            if next_char == self.separator && slugified.ends_with(self.separator) {
+                continue;
+            }
+
+            slugified.push(next_char);
+        }
+
+        if !self.stop_words.is_empty() {
+            slugified = self.remove_stop_words(&slugified);
+        }
+        if self.trim_separators {
+            slugified = slugified.trim_matches(self.separator).to_string();
+        }
+        if let Some(max_length) = self.max_length {
+            slugified = self.truncate(&slugified, max_length);
+        }
+
+        slugified
+    }
+
+    /// The words of `slug` but the stop words, all of them if they're all stop words.
+    fn remove_stop_words(&self, slug: &str) -> String {
+        let is_stop_word = |word: &str| {
+            self.stop_words
+                .iter()
+                .any(|stop_word| *stop_word == word.to_lowercase())
+        };
+        let words = slug.split(self.separator).collect::<Vec<_>>();
+        if words
+            .iter()
+            .all(|word| word.is_empty() || is_stop_word(word))
+        {
+            return slug.to_string();
+        }
+
+        // the empty words are the separators at the ends, kept for the trimming
+        let words = words
+            .into_iter()
+            .filter(|word| word.is_empty() || !is_stop_word(word));
+        words.collect::<Vec<_>>().join(&self.separator.to_string())
+    }
+
+    /// `slug` with the replacements, in one pass: a replacement isn't replaced again.
+    fn replace(&self, slug: &str) -> String {
+        if self.replacements.is_empty() {
+            return slug.to_string();
+        }
+
+        let mut replaced = String::new();
+        let mut rest = slug;
+        while let Some(char) = rest.chars().next() {
+            match self
+                .replacements
+                .iter()
+                .find(|(from, _)| rest.starts_with(from.as_str()))
+            {
+                Some((from, to)) => {
+                    replaced.push_str(to);
+                    rest = &rest[from.len()..];
+                }
+                None => {
+                    replaced.push(char);
+                    rest = &rest[char.len_utf8()..];
+                }
+            }
+        }
+        replaced
+    }
+
+    /// The words of `slug` that fit in `max_length` characters.
+    fn truncate(&self, slug: &str, max_length: usize) -> String {
+        let chars = slug.chars().collect::<Vec<_>>();
+        if chars.len() <= max_length {
+            return slug.to_string();
+        }
+
+        let end = if chars[max_length] == self.separator {
+            max_length
+        } else {
+            match chars[..max_length]
+                .iter()
+                .rposition(|&char| char == self.separator)
+            {
+                Some(end) if end > 0 => end,
+                _ => max_length,
+            }
+        };
+        let mut truncated = chars[..end].iter().collect::<String>();
+        if self.trim_separators {
+            truncated.truncate(truncated.trim_end_matches(self.separator).len());
+        }
+        truncated
+    }
+}
+
+/// The slug of `slug` with the default options, empty if there's nothing to keep.
+pub fn slugify(slug: &str) -> String {
+    SlugifyOptions::default().slugify(slug)
+}
+
+/// The slug of `slug` with the default options, an error if there's nothing to keep.
+pub fn try_slugify(slug: &str) -> Result<String, EmptySlug> {
+    SlugifyOptions::default().try_slugify(slug)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        remove_diacritics, slugify, try_slugify, CharClass, EmptySlug, Language, SlugifyOptions,
+    };
+
+    #[test]
+    fn slugify_test() {
+        assert_eq!(slugify("Hello, World"), "hello-world");
+        assert_eq!(slugify("Perché è così?"), "perche-e-cosi");
+        assert_eq!(slugify("TEST123&&"), "test123");
+    }
+
+    #[test]
+    fn diacritics_test() {
+        assert_eq!(
+            remove_diacritics(
+                "àáâäæãåāăąçćčđďèéêëēėęěğǵḧîïíīįìıİłḿñńǹňôöòóœøōõőṕŕřßśšşșťțûüùúūǘůűųẃẍÿýžźż",
+                false
+            ),
+            "aaaaaeaaaaacccddeeeeeeeegghiiiiiiiIlmnnnnoooooeooooprrssssssttuuuuuuuuuwxyyzzz"
+        );
+        // missing from the old table
+        assert_eq!(
+            slugify("Ångström Ŵales ǈ ﬁne ²"),
+            "angstrom-wales-lj-fine-2"
+        );
+        assert_eq!(slugify("Straße ŒUVRE"), "strasse-oeuvre");
+        assert_eq!(
+            SlugifyOptions::new().lowercase(false).slugify("Ærø Łódź"),
+            "AEro-Lodz"
+        );
+    }
+
+    #[test]
+    fn options_test() {
+        let options = SlugifyOptions::new().separator('_');
+        assert_eq!(options.slugify("  Hello,  World!  "), "hello_world");
+        let options = SlugifyOptions::new().trim_separators(false);
+        assert_eq!(options.slugify("  Hello,  World!  "), "-hello-world-");
+
+        let options = SlugifyOptions::new().lowercase(false);
+        assert_eq!(options.slugify("Ciao À Tutti"), "Ciao-A-Tutti");
+
+        let options = SlugifyOptions::new().max_length(7);
+        assert_eq!(options.slugify("hello world"), "hello");
+        assert_eq!(options.max_length(6).slugify("hello world"), "hello");
+    }
+
+    #[test]
+    fn replacements_test() {
+        let options = SlugifyOptions::new().with_replacements(&[("&", " and "), ("€", "eur")]);
+        assert_eq!(options.slugify("Fish & Chips"), "fish-and-chips");
+        assert_eq!(options.slugify("10€ only"), "10eur-only");
+
+        // the first one given wins, and a replacement isn't replaced again
+        let options = SlugifyOptions::new()
+            .with_replacements(&[("c++", "cpp"), ("+", "plus")])
+            .with_replacements(&[("cpp", "c"), ("", "x")]);
+        assert_eq!(options.slugify("c++ + rust"), "cpp-plus-rust");
+        assert_eq!(options.slugify("cpp"), "c");
+    }
+
+    #[test]
+    fn stop_words_test() {
+        let options = SlugifyOptions::new().stop_words(Language::English);
+        assert_eq!(options.slugify("The Lord of the Rings"), "lord-rings");
+        assert_eq!(options.slugify("Of Mice and Men"), "mice-men");
+        // a title of stop words only is kept
+        assert_eq!(options.slugify("The And"), "the-and");
+        assert_eq!(
+            options.clone().lowercase(false).slugify("THE Hobbit"),
+            "Hobbit"
+        );
+        assert_eq!(options.trim_separators(false).slugify(" the end "), "-end-");
+
+        let options = SlugifyOptions::new()
+            .stop_words(Language::Italian)
+            .with_stop_words(&["Perché"]);
+        assert_eq!(options.slugify("Il nome della rosa"), "nome-rosa");
+        assert_eq!(options.slugify("L'amore è perché"), "amore");
+        assert_eq!("it".parse(), Ok(Language::Italian));
+        assert!("klingon".parse::<Language>().is_err());
+    }
+
+    #[test]
+    fn class_test() {
+        let options = SlugifyOptions::new().separator('_').lowercase(false);
+        assert_eq!(options.slugify("parse HTTP-Request!"), "parse_HTTP_Request");
+        assert_eq!(
+            options.clone().allow(".").slugify("Report 2024.final.pdf"),
+            "Report_2024.final.pdf"
+        );
+        // a separator allowed is collapsed too
+        assert_eq!(options.allow("_").slugify("__init__ file"), "init_file");
+
+        let hex = SlugifyOptions::new().char_class("a-f0-9".parse().unwrap());
+        assert_eq!(hex.slugify("Deadbeef 42 xyz"), "deadbeef-42");
+        let options = SlugifyOptions::new().char_class(CharClass::new().range('a', 'z'));
+        assert_eq!(options.slugify("Àbc 123 dé"), "abc-de");
+    }
+
+    #[test]
+    fn max_length_test() {
+        let rings = "The Lord of the Rings";
+        let options = |max_length| SlugifyOptions::new().max_length(max_length);
+        assert_eq!(options(10).slugify(rings), "the-lord");
+        assert_eq!(options(11).slugify(rings), "the-lord-of");
+        assert_eq!(options(100).slugify(rings), "the-lord-of-the-rings");
+        // a first word too long is cut
+        assert_eq!(options(4).slugify("Supercalifragilistic day"), "supe");
+        assert_eq!(options(0).slugify(rings), "");
+        assert_eq!(
+            options(5).trim_separators(false).slugify(" the end "),
+            "-the"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "transliterate")]
+    fn transliterate_test() {
+        assert_eq!(slugify("Привет мир"), "privet-mir");
+        assert_eq!(slugify("Καλημέρα κόσμε"), "kalemera-kosme");
+        assert_eq!(slugify("北京 欢迎你"), "bei-jing-huan-ying-ni");
+        assert_eq!(slugify("Café Ελλάδα"), "cafe-ellada");
+
+        let options = SlugifyOptions::new().transliterate(false);
+        assert_eq!(options.slugify("Привет, world"), "world");
+    }
+
+    #[test]
+    fn empty_test() {
+        assert_eq!(slugify("hello!!!"), "hello");
+        assert_eq!(slugify("!!!"), "");
+        assert_eq!(try_slugify("!?! ..."), Err(EmptySlug));
+        assert_eq!(try_slugify("a!b"), Ok("a-b".to_string()));
+
+        let options = SlugifyOptions::new().fallback("n-a");
+        assert_eq!(options.try_slugify("&&"), Ok("n-a".to_string()));
+        assert_eq!(options.trim_separators(false).slugify("&&"), "n-a");
+        // a separator that is a letter too
+        assert_eq!(
+            SlugifyOptions::new().separator('x').slugify("a xx b"),
+            "axb"
+        );
+    }
+}
@@ -1,56 +1,194 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::thread;
+
 use clap::Parser;
+use lab1_1::{CharClass, EmptySlug, Language, SlugifyOptions};
 
 #[derive(Debug, Parser)]
-struct Args  {
-    #[arg()]
-    input: String,
-}
+struct Args {
+    /// Text of the slug, a slug per line of the input if missing
+    #[arg(conflicts_with_all = ["in_file", "out_file", "jobs"])]
+    input: Option<String>,
+
+    /// File of the lines, stdin if missing or `-`
+    #[arg(long = "in", value_name = "FILE")]
+    in_file: Option<PathBuf>,
+
+    /// File of the slugs, stdout if missing or `-`
+    #[arg(long = "out", value_name = "FILE")]
+    out_file: Option<PathBuf>,
+
+    /// Threads the lines are split among
+    #[arg(short, long, default_value = "1")]
+    jobs: NonZeroUsize,
+
+    /// Character between the words
+    #[arg(short, long, default_value_t = '-')]
+    separator: char,
+
+    /// Characters of the slug at most, cut between words
+    #[arg(short, long)]
+    max_length: Option<usize>,
+
+    /// Keep the uppercase letters
+    #[arg(short, long)]
+    keep_case: bool,
 
+    /// Allow these characters too, like `_.`
+    #[arg(short, long, value_name = "CHARS")]
+    allow: Option<String>,
 
-const SUBS_I : &str =
-"àáâäæãåāăąçćčđďèéêëēėęěğǵḧîïíīįìıİłḿñńǹňôöòóœøōõőṕŕřßśšşșťțûüùúūǘůűųẃẍÿýžźż";
-const SUBS_O: &str =
-"aaaaaaaaaacccddeeeeeeeegghiiiiiiiilmnnnnoooooooooprrsssssttuuuuuuuuuwxyyzzz";
+    /// Allow only the characters of the class instead of letters and digits, like `a-z0-9_`
+    #[arg(long, value_name = "CLASS", conflicts_with = "allow")]
+    class: Option<CharClass>,
 
+    /// Keep the separators at the ends
+    #[arg(long)]
+    no_trim: bool,
 
-fn remove_diacritic(char: char) -> char {
-    match SUBS_I.chars().position(|c| c == char) {
-        Some(val) => SUBS_O.chars().take(val + 1).last().unwrap(),
-        None => char,
+    /// Slug of an input without letters or digits, an error otherwise
+    #[arg(short, long)]
+    fallback: Option<String>,
+
+    /// Text replaced before the characters are filtered, as FROM=TO, the first given wins
+    #[arg(short, long = "replace", value_name = "FROM=TO", value_parser = replacement)]
+    replacements: Vec<(String, String)>,
+
+    /// Leave out the articles, prepositions and conjunctions: english or italian
+    #[arg(long, value_name = "LANGUAGE")]
+    stop_words: Option<Language>,
+
+    /// Replace the letters of the other scripts than Latin with separators
+    #[cfg(feature = "transliterate")]
+    #[arg(long)]
+    no_transliterate: bool,
+}
+
+fn replacement(replacement: &str) -> Result<(String, String), String> {
+    let (from, to) = replacement.split_once('=').ok_or("expected FROM=TO")?;
+    if from.is_empty() {
+        return Err("the text replaced is empty".to_string());
     }
+    Ok((from.to_string(), to.to_string()))
 }
 
-fn slugify(slug: String) -> String {
-    let mut slugified = String::new();
+/// Lines read for every thread before their slugs are written.
+const BATCH: usize = 4096;
+
+/// The slugs of `lines`, in order, made by `jobs` threads a slice each.
+fn slugify_all(
+    options: &SlugifyOptions,
+    lines: &[String],
+    jobs: usize,
+) -> Vec<Result<String, EmptySlug>> {
+    if jobs == 1 {
+        return lines.iter().map(|line| options.try_slugify(line)).collect();
+    }
 
-    for mut char in slug.to_lowercase().chars() {
-        
-        char = remove_diacritic(char);
+    thread::scope(|scope| {
+        let handles = lines
+            .chunks(lines.len().div_ceil(jobs))
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|line| options.try_slugify(line))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
 
-        let next_char = match char {
-            'a' ..= 'z' => char,
-            '0' ..=  '9' => char,
-            _ => '-',
-        };
+/// A slug per line, an empty one for the lines without letters or digits.
+fn batch(options: &SlugifyOptions, args: &Args) -> io::Result<()> {
+    let input: Box<dyn BufRead> = match &args.in_file {
+        Some(path) if path.as_os_str() != "-" => Box::new(BufReader::new(File::open(path)?)),
+        _ => Box::new(io::stdin().lock()),
+    };
+    let output: Box<dyn Write> = match &args.out_file {
+        Some(path) if path.as_os_str() != "-" => Box::new(File::create(path)?),
+        _ => Box::new(io::stdout().lock()),
+    };
+    let mut output = BufWriter::new(output);
 
-        match slugified.chars().last() {
-            Some(val) => if val == '-' && next_char == '-' {
-                continue;
-            },
-            None => (),
+    let jobs = args.jobs.get();
+    let mut lines = input.lines();
+    let mut number = 0;
+    loop {
+        let batch = lines
+            .by_ref()
+            .take(BATCH * jobs)
+            .collect::<io::Result<Vec<_>>>()?;
+        if batch.is_empty() {
+            break;
         }
 
-        slugified.push(next_char);
+        for (line, slug) in batch.iter().zip(slugify_all(options, &batch, jobs)) {
+            number += 1;
+            match slug {
+                Ok(slug) => writeln!(output, "{}", slug)?,
+                Err(e) => {
+                    eprintln!("line {}: {}: {:?}", number, e, line);
+                    writeln!(output)?;
+                }
+            }
+        }
     }
-
-    slugified
+    output.flush()
 }
 
-
 fn main() {
     let args = Args::parse();
-    let mut s = SUBS_I.to_string();
-    s.push_str("TEST123&&");
-    s.push_str(&args.input);
-    println!("{}", slugify(s));
+
+    let mut options = SlugifyOptions::new()
+        .separator(args.separator)
+        .lowercase(!args.keep_case)
+        .trim_separators(!args.no_trim);
+    if let Some(max_length) = args.max_length {
+        options = options.max_length(max_length);
+    }
+    if let Some(class) = &args.class {
+        options = options.char_class(class.clone());
+    }
+    if let Some(allow) = &args.allow {
+        options = options.allow(allow);
+    }
+    if let Some(fallback) = &args.fallback {
+        options = options.fallback(fallback.as_str());
+    }
+    let replacements = args
+        .replacements
+        .iter()
+        .map(|(from, to)| (from.as_str(), to.as_str()));
+    options = options.with_replacements(&replacements.collect::<Vec<_>>());
+    if let Some(language) = args.stop_words {
+        options = options.stop_words(language);
+    }
+    #[cfg(feature = "transliterate")]
+    {
+        options = options.transliterate(!args.no_transliterate);
+    }
+
+    let Some(input) = &args.input else {
+        if let Err(e) = batch(&options, &args) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    };
+    match options.try_slugify(input) {
+        Ok(slug) => println!("{}", slug),
+        Err(e) => {
+            eprintln!("{}: {:?}", e, input);
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+
+use crate::SlugifyOptions;
+
+/// Slugs issued once each, a repeated one gets the first free `-2`, `-3`, … suffix.
+#[derive(Debug, Clone, Default)]
+pub struct SlugSet {
+    options: SlugifyOptions,
+    issued: HashSet<String>,
+}
+
+impl SlugSet {
+    /// An empty set of the slugs made with `options`.
+    pub fn new(options: SlugifyOptions) -> Self {
+        SlugSet {
+            options,
+            issued: HashSet::new(),
+        }
+    }
+
+    /// A slug of `slug` not issued before, within the `max_length` of the options.
+    pub fn slugify(&mut self, slug: &str) -> String {
+        let slug = self.options.slugify(slug);
+        let unique = (1..)
+            .map(|n| self.suffixed(&slug, n))
+            .find(|unique| !self.issued.contains(unique))
+            .unwrap();
+        self.issued.insert(unique.clone());
+        unique
+    }
+
+    /// Whether `slug` was issued.
+    pub fn contains(&self, slug: &str) -> bool {
+        self.issued.contains(slug)
+    }
+
+    /// Number of slugs issued.
+    pub fn len(&self) -> usize {
+        self.issued.len()
+    }
+
+    /// Whether no slug was issued.
+    pub fn is_empty(&self) -> bool {
+        self.issued.is_empty()
+    }
+
+    /// `slug` with its `n`-th suffix, the words that don't fit with it cut.
+    fn suffixed(&self, slug: &str, n: usize) -> String {
+        if n == 1 {
+            return slug.to_string();
+        }
+        if slug.is_empty() {
+            return n.to_string();
+        }
+
+        let suffix = format!("{}{}", self.options.separator, n);
+        let slug = match self.options.max_length {
+            Some(max_length) => {
+                let slug = self
+                    .options
+                    .truncate(slug, max_length.saturating_sub(suffix.chars().count()));
+                slug.trim_end_matches(self.options.separator).to_string()
+            }
+            None => slug.to_string(),
+        };
+        slug + &suffix
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{SlugSet, SlugifyOptions};
+
+    #[test]
+    fn set_test() {
+        let mut set = SlugSet::default();
+        assert_eq!(set.slugify("My Title"), "my-title");
+        assert_eq!(set.slugify("my title!"), "my-title-2");
+        assert_eq!(set.slugify("My Title"), "my-title-3");
+        // a suffix already issued is skipped
+        assert_eq!(set.slugify("My Title 4"), "my-title-4");
+        assert_eq!(set.slugify("My Title"), "my-title-5");
+        assert_eq!(set.len(), 5);
+        assert!(set.contains("my-title-2"));
+
+        assert_eq!(set.slugify("!!"), "");
+        assert_eq!(set.slugify("??"), "2");
+    }
+
+    #[test]
+    fn max_length_test() {
+        let mut set = SlugSet::new(SlugifyOptions::new().separator('_').max_length(12));
+        assert_eq!(set.slugify("Hello big world"), "hello_big");
+        assert_eq!(set.slugify("Hello big world"), "hello_big_2");
+        assert_eq!(set.slugify("Hello big world"), "hello_big_3");
+
+        let mut set = SlugSet::new(SlugifyOptions::new().max_length(5));
+        assert_eq!(set.slugify("hello"), "hello");
+        assert_eq!(set.slugify("hello"), "hel-2");
+    }
+}
@@ -0,0 +1,47 @@
+use std::str::FromStr;
+
+/// Language of a list of stop words, the articles, prepositions and conjunctions
+/// left out of a slug.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    Italian,
+}
+
+const ENGLISH: [&str; 18] = [
+    "a", "an", "and", "as", "at", "but", "by", "for", "from", "in", "into", "of", "on", "or",
+    "the", "to", "with", "without",
+];
+
+/// Without their accents, the elided ones (`l'`, `dell'`) without the apostrophe.
+const ITALIAN: [&str; 46] = [
+    "a", "ad", "al", "all", "alla", "alle", "agli", "ai", "col", "con", "d", "da", "dal", "dalla",
+    "dei", "del", "dell", "della", "delle", "degli", "di", "e", "ed", "fra", "gli", "i", "il",
+    "in", "l", "la", "le", "lo", "nel", "nell", "nella", "nelle", "o", "per", "sul", "sull",
+    "sulla", "su", "tra", "un", "una", "uno",
+];
+
+impl Language {
+    /// The stop words, lowercase.
+    pub fn stop_words(self) -> &'static [&'static str] {
+        match self {
+            Language::English => &ENGLISH,
+            Language::Italian => &ITALIAN,
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "en" | "english" => Ok(Language::English),
+            "it" | "italian" => Ok(Language::Italian),
+            _ => Err(format!(
+                "unknown language {:?}, expected english or italian",
+                s
+            )),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Records written and read per second by the file-IO and the memory-mapped buffers.
+//!
+//! Run with `cargo bench`.
+
+use std::error::Error;
+use std::time::{Duration, Instant};
+use std::{env, fs, process};
+
+use lab2_1::shared::{FileReader, FullPolicy, MmapBuffer, SensorData};
+use labs_testkit::SensorStream;
+
+const RECORDS: u32 = 20_000;
+const CAPACITY: u32 = 1000;
+
+fn report(name: &str, elapsed: Duration) {
+    println!(
+        "{:8} {:>8} records in {:>8.2?}, {:>10.0} records/s",
+        name,
+        RECORDS,
+        elapsed,
+        RECORDS as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let file_path = env::temp_dir().join(format!("lab2-1-bench-file-{}", process::id()));
+    let mmap_path = env::temp_dir().join(format!("lab2-1-bench-mmap-{}", process::id()));
+
+    // readings of 10 sensors sampled at 100 Hz
+    let records = SensorStream::synthetic(100, 1).take(RECORDS as usize).collect::<Vec<_>>();
+
+    let mut file = FileReader::with_options(&file_path, CAPACITY);
+    file.set_full_policy(FullPolicy::OverwriteOldest);
+    let start = Instant::now();
+    for &data in &records {
+        file.write_data(data)?;
+    }
+    report("file", start.elapsed());
+
+    let start = Instant::now();
+    for chunk in records.chunks(CAPACITY as usize) {
+        file.write_batch(chunk)?;
+    }
+    report("batch", start.elapsed());
+
+    let mut mmap = MmapBuffer::<SensorData>::open(&mmap_path, CAPACITY)?;
+    mmap.set_full_policy(FullPolicy::OverwriteOldest);
+    let start = Instant::now();
+    for &data in &records {
+        mmap.write_data(data)?;
+    }
+    report("mmap", start.elapsed());
+
+    assert_eq!(file.read_data()?.len(), mmap.read_data()?.len());
+
+    fs::remove_file(file_path)?;
+    fs::remove_file(mmap_path)?;
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use std::env;
+use std::error::Error;
+use std::process;
+use std::time::SystemTime;
+
+use lab2_1::shared::{FileReader, Slot};
+
+const USAGE: &str = "usage: bufctl <dump|stats|reset|verify> <file> [capacity]";
+
+fn print_time(name: &str, time: Option<SystemTime>) {
+    match time.map(|time| time.elapsed().unwrap_or_default()) {
+        Some(elapsed) => println!("{:14} {:.1?} ago", name, elapsed),
+        None => println!("{:14} never", name),
+    }
+}
+
+fn dump(buffer: &FileReader) -> Result<(), Box<dyn Error>> {
+    let stats = buffer.stats()?;
+    let unread = |index: u32| (index + stats.capacity - stats.index) % stats.capacity < stats.len;
+
+    for (index, slot) in buffer.slots()?.iter().enumerate() {
+        // the records not read yet are marked
+        let mark = if unread(index as u32) { '*' } else { ' ' };
+        match slot {
+            Slot::Committed(data) => println!("{}{:4} {:?}", mark, index, data),
+            Slot::Reserved(since) => println!("{}{:4} reserved at {}", mark, index, since),
+            Slot::Corrupted => println!("{}{:4} corrupted or never written", mark, index),
+        }
+    }
+    Ok(())
+}
+
+fn stats(buffer: &FileReader) -> Result<(), Box<dyn Error>> {
+    let stats = buffer.stats()?;
+    println!("{:14} {}", "capacity", stats.capacity);
+    println!("{:14} {} from slot {}", "unread", stats.len, stats.index);
+    println!("{:14} {}", "total written", stats.total_written);
+    println!("{:14} {}", "dropped", stats.dropped);
+    print_time("last write", stats.last_write);
+    print_time("last read", stats.last_read);
+    Ok(())
+}
+
+fn verify(buffer: &FileReader) -> Result<(), Box<dyn Error>> {
+    let problems = buffer.verify()?;
+    if problems.is_empty() {
+        println!("ok");
+        return Ok(());
+    }
+
+    for problem in problems {
+        println!("{}", problem);
+    }
+    process::exit(1);
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let (command, path) = match &args[..] {
+        [command, path, ..] => (command.as_str(), path),
+        _ => return Err(USAGE.into()),
+    };
+
+    // the capacity is needed to reset a file whose header can't be read
+    let mut buffer = match args.get(2) {
+        Some(capacity) => FileReader::with_options(path, capacity.parse()?),
+        None => FileReader::from_file(path)?,
+    };
+
+    match command {
+        "dump" => dump(&buffer),
+        "stats" => stats(&buffer),
+        "reset" => Ok(buffer.reset()?),
+        "verify" => verify(&buffer),
+        _ => Err(USAGE.into()),
+    }
+}
@@ -1,29 +1,38 @@
 use std::error::Error;
-use std::thread;
 use std::time::Duration;
 
-mod shared;
+use lab2_1::shared;
+use labs_common::{sampling_jitter, SensorData, SensorStats, SENSORS};
 
 
-fn print_sensor(data: &Vec<shared::SensorData>) {
-    for i in 0..10 {
-        println!("sensor {:2}: max {}; min {}; avg {};",
-            i,
-            data.into_iter().map(|d| d.values[i]).reduce(f32::max).unwrap(),
-            data.into_iter().map(|d| d.values[i]).reduce(f32::min).unwrap(),
-            data.into_iter().map(|d| d.values[i]).sum::<f32>() / data.len() as f32
+fn print_sensor(data: &[SensorData]) {
+    if let Some((mean, jitter)) = sampling_jitter(data) {
+        println!("sampling: every {:.1} ms; jitter {:.1} ms;", mean, jitter);
+    }
+    for i in 0..SENSORS {
+        let Some(stats) = SensorStats::of(data, i) else { return };
+        println!("sensor {:2}: max {}; min {}; avg {}; stddev {:.2};",
+            i, stats.max, stats.min, stats.mean, stats.stddev
         );
     }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut file = shared::FileReader::new();
+    // the corrupted and the abandoned slots are reported as warnings
+    labs_common::init_tracing("warn")?;
+    let mut file = shared::FileReader::from_args()?.into_reader();
 
     loop {
-        thread::sleep(Duration::from_secs(10));
+        // wake up as soon as the producer writes
+        if !file.wait_for_data(Duration::from_secs(10))? {
+            continue;
+        }
+
+        let (data, gaps) = file.read_data_checked()?;
+        for gap in gaps {
+            eprintln!("lost {} records between {} and {}", gap.lost(), gap.after, gap.before);
+        }
 
-        let data = file.read_data()?;
-        
         println!("{:#?}", data);
         print_sensor(&data);
 
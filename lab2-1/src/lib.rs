@@ -0,0 +1,4 @@
+//! Circular buffer of `SensorData` in a file, written by the producer and read by the
+//! consumer and `bufctl`.
+
+pub mod shared;
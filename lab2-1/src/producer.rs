@@ -1,13 +1,82 @@
 use std::error::Error;
-use std::{thread, mem};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::{env, thread, mem};
 use std::time::Duration;
 
-use crate::shared::SensorData;
+use lab2_1::shared::{self, Producer, SensorData};
+use labs_common::write_frame;
 
-mod shared;
+/// A consumer not reading for this long is disconnected instead of stopping the producer.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Where the readings are written.
+enum Output {
+    File(Producer<SensorData>),
+    /// The consumers connected with TCP, each one receives every reading.
+    Tcp(Arc<Mutex<Vec<TcpStream>>>),
+}
+
+impl Output {
+    /// `--listen <addr>` to stream the readings over TCP, the buffer file arguments otherwise.
+    fn from_args() -> Result<Self, Box<dyn Error>> {
+        let mut args = env::args().skip(1);
+        if args.next().as_deref() != Some("--listen") {
+            return Ok(Self::File(shared::FileReader::from_args()?.into_writer()));
+        }
+
+        let addr = args.next().ok_or("usage: producer --listen <addr>")?;
+        let listener = TcpListener::bind(addr)?;
+        println!("listening on {}", listener.local_addr()?);
+
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let accepted = clients.clone();
+        thread::spawn(move || accept(listener, accepted));
+        Ok(Self::Tcp(clients))
+    }
+
+    fn write(&mut self, data: SensorData) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::File(file) => Ok(file.write_data(data)?),
+            Self::Tcp(clients) => {
+                // the readings are not kept for the consumers that aren't connected
+                clients.lock().unwrap().retain_mut(|stream| match write_frame(stream, &data) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        eprintln!("disconnected {}: {}", peer(stream), e);
+                        false
+                    }
+                });
+                Ok(())
+            }
+        }
+    }
+}
+
+fn peer(stream: &TcpStream) -> String {
+    stream.peer_addr().map_or("?".to_string(), |addr| addr.to_string())
+}
+
+/// Add the consumers connecting to `listener` to `clients`.
+fn accept(listener: TcpListener, clients: Arc<Mutex<Vec<TcpStream>>>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("accept: {}", e);
+                continue;
+            }
+        };
+        // best effort, without them a slow consumer only delays the others more
+        let _ = stream.set_write_timeout(Some(CLIENT_TIMEOUT));
+        let _ = stream.set_nodelay(true);
+        println!("connected {}", peer(&stream));
+        clients.lock().unwrap().push(stream);
+    }
+}
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut file = shared::FileReader::new();
+    let mut output = Output::from_args()?;
 
     let mut seq = 1..;
     let mut values =  [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0];
@@ -16,9 +85,9 @@ fn main() -> Result<(), Box<dyn Error>> {
         let data = SensorData {
             seq: seq.next().unwrap(),
             values,
-            timestamp: 0,
+            timestamp: labs_common::unix_millis(),
         };
-        file.write_data(data)?;
+        output.write(data)?;
         for n in values.iter_mut() { *n += 10.0; }
 
         println!("wrote: {:?}", data);
@@ -1,170 +1,1405 @@
-use std::error::Error;
+use std::env;
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
-use std::time::Duration;
-use std::{mem, thread};
-use std::os::unix::prelude::FileExt;
+use std::marker::PhantomData;
+use std::ops::RangeBounds;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
 
-use fcntl::FcntlLockType;
+use archive::Archive;
+use lock::{FileLock, PlatformLock};
+use positional::PositionalIo;
+use tracing::{debug, warn};
+use watch::FileWatch;
 
-#[repr(C)]
+pub use labs_common::{Clock, SensorData, SystemClock};
+pub use labs_error::FileBufferError;
+
+#[cfg(feature = "async")]
+pub use async_buffer::AsyncFileBuffer;
+pub use handle::{BReader, BWriter, BufferHandle, BufferMode, Consumer, Producer};
+#[cfg(target_endian = "little")]
+pub use mmap::MmapBuffer;
+
+#[cfg(feature = "async")]
+mod async_buffer;
+mod archive;
+mod handle;
+mod lock;
+#[cfg(target_endian = "little")]
+mod mmap;
+mod positional;
+mod watch;
+
+/// File used by the producer and the consumer when no path is given.
+pub const DEFAULT_FILE: &str = "cicular";
+/// Number of `SensorData` stored in the default buffer.
+pub const DEFAULT_CAPACITY: u32 = 10;
+
+/// First bytes of every buffer file.
+const MAGIC: [u8; 4] = *b"CBUF";
+/// Version of the file layout, changed on every incompatible change.
+const FORMAT_VERSION: u32 = 4;
+
+/// Size of the file header: the magic, 5 `u32` and the 4 `u64` of the statistics.
+const HEAD_SIZE: usize = 56;
+/// Every slot starts with the commit flag and the CRC32 of the record.
+const SLOT_HEADER_SIZE: usize = 8;
+/// Commit flag of a slot whose record was completely written.
+const COMMITTED: u32 = 0x434d4954;
+/// Commit flag of a slot being written.
+const UNCOMMITTED: u32 = 0;
+/// Commit flag of a slot reserved by a producer, which writes it without holding the lock.
+/// The CRC field holds the reservation time until the record is written.
+const RESERVED: u32 = 0x52535644;
+/// Reserved slots older than this were abandoned by a crashed producer.
+const RESERVATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Content of a slot, as seen by a consumer.
 #[derive(Debug, Clone, Copy)]
-pub struct SensorData {
-    pub seq: u32, // sequenza letture
-    pub values: [f32; 10],
-    pub timestamp: u32,
+pub enum Slot<T> {
+    Committed(T),
+    /// Still being written, reserved at the given time in seconds since the epoch.
+    Reserved(u32),
+    /// Never written, torn write or wrong CRC.
+    Corrupted,
+}
+
+/// Fixed size element stored in a `FileBuffer`.
+pub trait Record: Copy {
+    /// Number of bytes written by `serialize`.
+    const SIZE: usize;
+
+    fn serialize(&self) -> Vec<u8>;
+
+    /// `bytes` is exactly `SIZE` bytes long.
+    fn deserialize(bytes: &[u8]) -> Self;
+
+    /// Sequence number given by the producer, used to detect the lost records.
+    fn seq(&self) -> Option<u32> {
+        None
+    }
+}
+
+/// Records lost between two records read one after the other,
+/// the sequence numbers from `after + 1` to `before - 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeqGap {
+    pub after: u32,
+    pub before: u32,
+}
+
+impl SeqGap {
+    pub fn lost(&self) -> u32 {
+        self.before - self.after - 1
+    }
 }
 
-#[repr(C)]
+/// What `write_data` does when the buffer is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FullPolicy {
+    /// The new record is dropped.
+    #[default]
+    Reject,
+    /// The oldest record is replaced, the buffer keeps the latest `capacity` records.
+    /// The new record is dropped while another producer is still writing the oldest one.
+    OverwriteOldest,
+}
+
+impl FromStr for FullPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(FullPolicy::Reject),
+            "overwrite" => Ok(FullPolicy::OverwriteOldest),
+            _ => Err(format!("unknown policy `{}`, expected `reject` or `overwrite`", s)),
+        }
+    }
+}
+
+/// Header at the start of the file, every field is stored in little endian.
 struct CircularBuffer {
+    magic: [u8; 4],
+    version: u32,
     len: u32,
     index: u32,
     capacity: u32,
+    elem_size: u32,
+    total_written: u64,
+    dropped: u64,
+    /// Milliseconds since the epoch, 0 if it never happened.
+    last_write: u64,
+    last_read: u64,
+}
+
+/// State of a buffer, see `FileBuffer::stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferStats {
+    pub capacity: u32,
+    /// Records not read yet, starting from the slot `index`.
+    pub len: u32,
+    pub index: u32,
+    /// Records ever written, the dropped ones are not counted.
+    pub total_written: u64,
+    /// Records lost because the buffer was full: the rejected new ones,
+    /// or the overwritten old ones with `FullPolicy::OverwriteOldest`.
+    pub dropped: u64,
+    pub last_write: Option<SystemTime>,
+    /// Time of the last read, a consumer that stopped reading is behind this.
+    pub last_read: Option<SystemTime>,
 }
 
-pub struct FileReader {
+/// Circular buffer of `T` stored in a file, shared between processes.
+#[derive(Clone)]
+pub struct FileBuffer<T: Record> {
     file: PathBuf,
+    capacity: u32,
+    policy: FullPolicy,
+    archive: Option<Archive>,
+    clock: Arc<dyn Clock>,
+    _record: PhantomData<T>,
 }
 
-impl SensorData {
-    pub fn default() -> Self {
-        Self {
-            seq: 0,
-            values: [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0],
-            timestamp: 0,
-        }
+/// Buffer used by the producer and the consumer, each one takes its own end
+/// with `into_writer` and `into_reader`.
+pub type FileReader = FileBuffer<SensorData>;
+
+impl Record for SensorData {
+    /// The bytes of `SensorData::to_bytes`, the same of every lab.
+    const SIZE: usize = SensorData::SIZE;
+
+    fn serialize(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
     }
 
-    fn serialize(self) -> [u8; mem::size_of::<Self>()] {
-        unsafe { mem::transmute::<Self, [u8; mem::size_of::<Self>()]>(self) }
+    fn deserialize(bytes: &[u8]) -> Self {
+        SensorData::from_bytes(bytes).unwrap()
     }
 
-    fn deserialize(bytes: [u8; mem::size_of::<Self>()]) -> Self {
-        unsafe { mem::transmute::<[u8; mem::size_of::<Self>()], Self>(bytes) }
+    fn seq(&self) -> Option<u32> {
+        Some(self.seq)
     }
 }
 
 impl CircularBuffer {
-    fn default() -> Self {
+    fn new(capacity: u32, elem_size: u32) -> Self {
         Self {
+            magic: MAGIC,
+            version: FORMAT_VERSION,
             len: 0,
             index: 0,
-            capacity: 10,
+            capacity,
+            elem_size,
+            total_written: 0,
+            dropped: 0,
+            last_write: 0,
+            last_read: 0,
         }
     }
 
-    fn serialize(self) -> [u8; mem::size_of::<Self>()] {
-        unsafe { mem::transmute::<Self, [u8; mem::size_of::<Self>()]>(self) }
+    fn serialize(&self) -> [u8; HEAD_SIZE] {
+        let fields = [self.version, self.len, self.index, self.capacity, self.elem_size];
+        let counters = [self.total_written, self.dropped, self.last_write, self.last_read];
+
+        let mut bytes = [0u8; HEAD_SIZE];
+        bytes[..4].copy_from_slice(&self.magic);
+        for (chunk, field) in bytes[4..24].chunks_exact_mut(4).zip(fields) {
+            chunk.copy_from_slice(&field.to_le_bytes());
+        }
+        for (chunk, counter) in bytes[24..].chunks_exact_mut(8).zip(counters) {
+            chunk.copy_from_slice(&counter.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn deserialize(bytes: [u8; HEAD_SIZE]) -> Self {
+        let field = |i: usize| u32::from_le_bytes(bytes[i * 4..(i + 1) * 4].try_into().unwrap());
+        let counter =
+            |i: usize| u64::from_le_bytes(bytes[24 + i * 8..24 + (i + 1) * 8].try_into().unwrap());
+
+        Self {
+            magic: bytes[..4].try_into().unwrap(),
+            version: field(1),
+            len: field(2),
+            index: field(3),
+            capacity: field(4),
+            elem_size: field(5),
+            total_written: counter(0),
+            dropped: counter(1),
+            last_write: counter(2),
+            last_read: counter(3),
+        }
     }
 
-    fn deserialize(bytes: [u8; mem::size_of::<Self>()]) -> Self {
-        unsafe { mem::transmute::<[u8; mem::size_of::<Self>()], Self>(bytes) }
+    fn stats(&self) -> BufferStats {
+        let time = |millis: u64| match millis {
+            0 => None,
+            millis => Some(UNIX_EPOCH + Duration::from_millis(millis)),
+        };
+
+        BufferStats {
+            capacity: self.capacity,
+            len: self.len,
+            index: self.index,
+            total_written: self.total_written,
+            dropped: self.dropped,
+            last_write: time(self.last_write),
+            last_read: time(self.last_read),
+        }
     }
 }
 
 impl FileReader {
     pub fn new() -> Self {
+        Self::with_options(DEFAULT_FILE, DEFAULT_CAPACITY)
+    }
+}
+
+impl Default for FileReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Record> FileBuffer<T> {
+    /// Buffer of `capacity` elements stored in `path`, the file is created on the first access.
+    /// An existing file must have been created with the same capacity and element size.
+    pub fn with_options<P: AsRef<Path>>(path: P, capacity: u32) -> Self {
         Self {
-            file: "cicular".into(),
+            file: path.as_ref().to_path_buf(),
+            capacity,
+            policy: FullPolicy::default(),
+            archive: None,
+            clock: Arc::new(SystemClock),
+            _record: PhantomData,
         }
     }
 
-    fn init_file(file: &Path) -> Result<(), Box<dyn Error>> {
-        let mut output = File::create(file)?;
+    /// Buffer given on the command line as `[path] [capacity] [reject|overwrite] [archive]`,
+    /// defaults for the missing ones.
+    pub fn from_args() -> Result<Self, FileBufferError> {
+        let mut args = env::args().skip(1);
+        let path = args.next().unwrap_or_else(|| DEFAULT_FILE.to_string());
+        let capacity = match args.next() {
+            Some(capacity) => capacity
+                .parse()
+                .map_err(|e| FileBufferError::InvalidArgument(format!("capacity `{}`: {}", capacity, e)))?,
+            None => DEFAULT_CAPACITY,
+        };
 
-        let head = CircularBuffer::default().serialize();
-        output.write_all(&head)?;
+        let mut buffer = Self::with_options(path, capacity);
+        if let Some(policy) = args.next() {
+            buffer.set_full_policy(policy.parse().map_err(FileBufferError::InvalidArgument)?);
+        }
+        if let Some(archive) = args.next() {
+            buffer.set_archive(archive);
+        }
+        Ok(buffer)
+    }
 
-        // wirte capcity * size byte of SensorData
-        for _ in 0..CircularBuffer::default().capacity {
-            output.write_all(&[0u8; mem::size_of::<SensorData>()])?;
+    /// Buffer stored in `path` with the capacity written in its header,
+    /// to inspect a file without knowing how it was created.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, FileBufferError> {
+        let mut head_bytes = [0u8; HEAD_SIZE];
+        File::open(path.as_ref())?.read_exact(&mut head_bytes)?;
+
+        let head = CircularBuffer::deserialize(head_bytes);
+        let buffer = Self::with_options(path, head.capacity);
+        if head.magic != MAGIC {
+            return Err(buffer.invalid("not a circular buffer file".to_string()));
         }
+        Ok(buffer)
+    }
+
+    pub fn set_full_policy(&mut self, policy: FullPolicy) {
+        self.policy = policy;
+    }
+
+    /// Take the times of the writes, of the reads and of the archived records from `clock`.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Also append every record written to the append-only log in `path`,
+    /// where it can be found with `replay_archive` after the ring overwrote it.
+    pub fn set_archive<P: AsRef<Path>>(&mut self, path: P) {
+        self.archive = Some(Archive::new(path.as_ref().to_path_buf()));
+    }
+
+    /// Archived records written in `range`, with the time they were written, from the oldest.
+    pub fn replay_archive<R: RangeBounds<SystemTime>>(
+        &self,
+        range: R,
+    ) -> Result<Vec<(SystemTime, T)>, FileBufferError> {
+        match &self.archive {
+            Some(archive) => archive.replay(range),
+            None => Err(FileBufferError::NoArchive(self.file.display().to_string())),
+        }
+    }
+
+    fn init_file(&self) -> Result<(), FileBufferError> {
+        let mut output = File::create(&self.file)?;
+        output.write_all(&self.initial_content())?;
 
         Ok(())
     }
 
-    pub fn write_data(&mut self, data: SensorData) -> Result<(), Box<dyn Error>> {
-        let file_exists = Path::new(&self.file).try_exists()?;
-        if !file_exists {
-            println!("write_data: file created");
-            FileReader::init_file(&self.file)?;
+    /// Content of a new file: the header and the empty slots.
+    fn initial_content(&self) -> Vec<u8> {
+        let mut content = CircularBuffer::new(self.capacity, T::SIZE as u32).serialize().to_vec();
+
+        // wirte capcity * size byte of the slots
+        content.resize(Self::slot_position(self.capacity) as usize, 0);
+        content
+    }
+
+    fn invalid(&self, reason: String) -> FileBufferError {
+        FileBufferError::Invalid { path: self.file.display().to_string(), reason }
+    }
+
+    /// Read the header, checking that the file holds a buffer of the expected capacity
+    /// and element size, and that it is not truncated.
+    fn read_head(&self, file: &mut File) -> Result<CircularBuffer, FileBufferError> {
+        let file_len = file.metadata()?.len();
+        let head_size = HEAD_SIZE as u64;
+        if file_len < head_size {
+            return Err(self.invalid(format!(
+                "truncated header, {} bytes instead of {}",
+                file_len, head_size
+            )));
         }
 
-        let mut output = OpenOptions::new().read(true).write(true).open(&self.file)?;
-        while !fcntl::lock_file(&output, None, Some(FcntlLockType::Write))? {
-            thread::sleep(Duration::from_millis(100));
+        let mut head_bytes = [0u8; HEAD_SIZE];
+        file.read_exact(&mut head_bytes)?;
+
+        let head = CircularBuffer::deserialize(head_bytes);
+        if head.magic != MAGIC {
+            return Err(self.invalid("not a circular buffer file".to_string()));
+        }
+        if head.version != FORMAT_VERSION {
+            return Err(self.invalid(format!(
+                "unsupported format version {}, expected {}",
+                head.version, FORMAT_VERSION
+            )));
+        }
+        if head.capacity != self.capacity {
+            return Err(self.invalid(format!(
+                "buffer capacity is {}, expected {}",
+                head.capacity, self.capacity
+            )));
+        }
+        if head.elem_size as usize != T::SIZE {
+            return Err(self.invalid(format!(
+                "element size is {}, expected {}",
+                head.elem_size,
+                T::SIZE
+            )));
+        }
+        if head.len > head.capacity || head.index >= head.capacity {
+            return Err(self.invalid(format!(
+                "corrupted header, {} elements from {} with capacity {}",
+                head.len, head.index, head.capacity
+            )));
         }
 
-        let mut head_bytes = [0u8; mem::size_of::<CircularBuffer>()];
-        output.read_exact(&mut head_bytes)?;
+        let expected_len = Self::slot_position(head.capacity);
+        if file_len < expected_len {
+            return Err(self.invalid(format!(
+                "truncated file, {} bytes instead of {}",
+                file_len, expected_len
+            )));
+        }
 
-        let mut head = CircularBuffer::deserialize(head_bytes);
+        Ok(head)
+    }
 
-        // if buffer is full don't write anything.
-        if head.len != head.capacity {
-            let head_size = mem::size_of::<CircularBuffer>();
-            let write_position = ((head.index + head.len) % head.capacity) as usize
-                * mem::size_of::<SensorData>()
-                + head_size;
+    fn slot_size() -> usize {
+        SLOT_HEADER_SIZE + T::SIZE
+    }
+
+    /// Position in the file of the slot `index`.
+    fn slot_position(index: u32) -> u64 {
+        (HEAD_SIZE + index as usize * Self::slot_size()) as u64
+    }
 
-            output.write_at(&data.serialize(), write_position as u64)?;
+    /// Reserve the slot of the next record, so that the record can be written without
+    /// holding the lock while other producers reserve the following slots.
+    /// Returns the file and the position of the slot, `None` if the buffer is full.
+    fn reserve_slot(&self) -> Result<Option<(File, u64)>, FileBufferError> {
+        let (file, mut head) = self.open_locked(true)?;
+        let position = Self::slot_position((head.index + head.len) % head.capacity);
+        let now = self.clock.unix_millis();
 
-            // update head
-            head.len = head.len + 1;
-            output.write_at(&head.serialize(), 0)?;
+        // if buffer is full don't write anything, unless the oldest record can be replaced:
+        // it can't while another producer is still writing it.
+        let full = head.len == head.capacity;
+        if full
+            && (self.policy == FullPolicy::Reject
+                || Self::being_written(&Self::read_slot(&file, position)?, now))
+        {
+            head.dropped += 1;
+            file.write_all_at(&head.serialize(), 0)?;
+            PlatformLock::unlock(&file)?;
+            return Ok(None);
         }
 
-        if !fcntl::unlock_file(&output, None)? {
-            return Err("Could not unlock file!".into());
+        let mut reservation = RESERVED.to_le_bytes().to_vec();
+        reservation.extend_from_slice(&unix_secs(now).to_le_bytes());
+        file.write_all_at(&reservation, position)?;
+
+        // update head
+        if head.len == head.capacity {
+            head.index = (head.index + 1) % head.capacity;
+            head.dropped += 1;
+        } else {
+            head.len += 1;
         }
+        head.total_written += 1;
+        head.last_write = now;
+        file.write_all_at(&head.serialize(), 0)?;
 
-        Ok(())
+        PlatformLock::unlock(&file)?;
+        Ok(Some((file, position)))
+    }
+
+    /// Whether `slot` is reserved by a producer that didn't abandon it at `now`,
+    /// in milliseconds since the epoch.
+    fn being_written(slot: &Slot<T>, now: u64) -> bool {
+        matches!(slot, Slot::Reserved(since) if !abandoned(*since, now))
+    }
+
+    /// Slot content after the commit flag: the CRC and the record.
+    fn slot_body(data: &T) -> Vec<u8> {
+        let record = data.serialize();
+
+        let mut body = crc32fast::hash(&record).to_le_bytes().to_vec();
+        body.extend_from_slice(&record);
+        body
+    }
+
+    fn read_slot(file: &File, position: u64) -> Result<Slot<T>, FileBufferError> {
+        let mut slot = vec![0u8; Self::slot_size()];
+        file.read_exact_at(&mut slot, position)?;
+
+        Ok(Self::decode_slot(&slot))
+    }
+
+    fn decode_slot(slot: &[u8]) -> Slot<T> {
+        let flag = u32::from_le_bytes(slot[0..4].try_into().unwrap());
+        let crc = u32::from_le_bytes(slot[4..8].try_into().unwrap());
+        let record = &slot[SLOT_HEADER_SIZE..];
+        match flag {
+            COMMITTED if crc == crc32fast::hash(record) => Slot::Committed(T::deserialize(record)),
+            RESERVED => Slot::Reserved(crc),
+            _ => Slot::Corrupted,
+        }
+    }
+
+    /// Add the record of the slot `index` to `data`, the corrupted and the abandoned slots
+    /// are skipped with a warning.
+    /// Returns `false` if the slot is still being written, the following records
+    /// must not be read before it.
+    fn collect_slot(slot: Slot<T>, index: u32, data: &mut Vec<T>, now: u64) -> bool {
+        match slot {
+            Slot::Committed(record) => data.push(record),
+            Slot::Reserved(since) if !abandoned(since, now) => return false,
+            Slot::Reserved(_) => warn!(slot = index, "abandoned slot skipped"),
+            Slot::Corrupted => warn!(slot = index, "corrupted slot skipped"),
+        }
+        true
     }
 
-    pub fn read_data(&mut self) -> Result<Vec<SensorData>, Box<dyn Error>> {
+    /// Open the buffer file, creating it if needed, and lock it.
+    /// The lock is exclusive if the file is going to be modified.
+    fn open_locked(&self, exclusive: bool) -> Result<(File, CircularBuffer), FileBufferError> {
         let file_exists = Path::new(&self.file).try_exists()?;
         if !file_exists {
-            FileReader::init_file(&self.file)?;
+            debug!(file = %self.file.display(), "created");
+            self.init_file()?;
         }
 
-        let mut data = Vec::new();
+        let mut file = OpenOptions::new().read(true).write(true).open(&self.file)?;
+        PlatformLock::lock(&file, exclusive)?;
+
+        let head = self.read_head(&mut file)?;
+        Ok((file, head))
+    }
+
+    fn unlock(file: File) -> Result<(), FileBufferError> {
+        PlatformLock::unlock(&file)?;
+        Ok(())
+    }
+
+    /// Append as many records of `data` as fit, taking the lock once and writing the header
+    /// once. Returns the number of records accepted, all of them with `FullPolicy::OverwriteOldest`
+    /// unless the oldest slot is still being written by another producer.
+    pub fn write_batch(&mut self, data: &[T]) -> Result<usize, FileBufferError> {
+        let (output, mut head) = self.open_locked(true)?;
+        let accepted = self.append_locked(&output, &mut head, data)?;
+
+        Self::unlock(output)?;
+        Ok(accepted)
+    }
+
+    /// Body of `write_batch`, `output` is already locked and `head` read from it.
+    fn append_locked(
+        &self,
+        output: &File,
+        head: &mut CircularBuffer,
+        data: &[T],
+    ) -> Result<usize, FileBufferError> {
+        let now = self.clock.unix_millis();
+        let mut accepted = 0;
+        for record in data {
+            let position = Self::slot_position((head.index + head.len) % head.capacity);
 
-        let mut input = OpenOptions::new().read(true).write(true).open(&self.file)?;
+            // the oldest slot can't be replaced while another producer is writing it,
+            // the following records would replace it too
+            let full = head.len == head.capacity;
+            if full
+                && (self.policy == FullPolicy::Reject
+                    || Self::being_written(&Self::read_slot(output, position)?, now))
+            {
+                break;
+            }
+            accepted += 1;
 
-        while !fcntl::lock_file(&input, None, Some(FcntlLockType::Write))? {
-            thread::sleep(Duration::from_millis(100));
+            // written under the lock, the commit flag is still set last
+            output.write_all_at(&UNCOMMITTED.to_le_bytes(), position)?;
+            output.write_all_at(&Self::slot_body(record), position + 4)?;
+            output.write_all_at(&COMMITTED.to_le_bytes(), position)?;
+
+            if head.len == head.capacity {
+                head.index = (head.index + 1) % head.capacity;
+                head.dropped += 1;
+            } else {
+                head.len += 1;
+            }
         }
 
+        // update head
+        head.total_written += accepted as u64;
+        head.dropped += (data.len() - accepted) as u64;
+        if accepted > 0 {
+            head.last_write = now;
+        }
+        output.write_all_at(&head.serialize(), 0)?;
 
-        let mut head_bytes = [0u8; mem::size_of::<CircularBuffer>()];
-        input.read_exact(&mut head_bytes)?;
+        if let Some(archive) = &self.archive {
+            archive.append(&data[..accepted], now)?;
+        }
+        Ok(accepted)
+    }
 
-        let mut head = CircularBuffer::deserialize(head_bytes);
+    /// Read at most `count` records starting from the slot `index`, stopping at the
+    /// first slot still being written at `now`. Returns the records and the number of slots read.
+    fn read_slots(
+        file: &File,
+        head: &CircularBuffer,
+        index: u32,
+        count: u32,
+        now: u64,
+    ) -> Result<(Vec<T>, u32), FileBufferError> {
+        let mut data = Vec::new();
+        for i in 0..count {
+            let slot = (index + i) % head.capacity;
+            let state = Self::read_slot(file, Self::slot_position(slot))?;
+            if !Self::collect_slot(state, slot, &mut data, now) {
+                return Ok((data, i));
+            }
+        }
+        Ok((data, count))
+    }
 
-        let mut data_bytes = [0u8; mem::size_of::<SensorData>()];
-        for _ in 0..head.len {
-            let head_size = mem::size_of::<CircularBuffer>();
-            let read_position = (head.index % head.capacity) as usize
-                * mem::size_of::<SensorData>()
-                + head_size;
+    /// Append `data`, many producers can write at the same time: each one reserves
+    /// a slot under the lock and then writes its record without holding the lock.
+    pub fn write_data(&mut self, data: T) -> Result<(), FileBufferError> {
+        let (output, position) = match self.reserve_slot()? {
+            Some(reserved) => reserved,
+            None => return Ok(()),
+        };
 
-            input.read_at(&mut data_bytes, read_position as u64)?;
-            data.push(SensorData::deserialize(data_bytes));
+        // the commit flag is set last, a crash in between leaves the slot reserved
+        output.write_all_at(&Self::slot_body(&data), position + 4)?;
+        output.write_all_at(&COMMITTED.to_le_bytes(), position)?;
 
-            head.index = (head.index + 1) % head.capacity;
-            head.len -= 1;
+        if let Some(archive) = &self.archive {
+            archive.append(&[data], self.clock.unix_millis())?;
         }
+        Ok(())
+    }
+
+    /// Remove and return every record, from the oldest.
+    pub fn read_data(&mut self) -> Result<Vec<T>, FileBufferError> {
+        self.read_up_to(usize::MAX)
+    }
+
+    /// Remove and return at most `n` records, from the oldest.
+    pub fn read_up_to(&mut self, n: usize) -> Result<Vec<T>, FileBufferError> {
+        let (input, mut head) = self.open_locked(true)?;
+        let data = Self::take_locked(&input, &mut head, n, self.clock.unix_millis())?;
+
+        Self::unlock(input)?;
+        Ok(data)
+    }
+
+    /// Body of `read_up_to`, `input` is already locked and `head` read from it.
+    /// `now` is the time of the read, in milliseconds since the epoch.
+    fn take_locked(
+        input: &File,
+        head: &mut CircularBuffer,
+        n: usize,
+        now: u64,
+    ) -> Result<Vec<T>, FileBufferError> {
+        let count = head.len.min(n.try_into().unwrap_or(u32::MAX));
+        let (data, count) = Self::read_slots(input, head, head.index, count, now)?;
 
         // update header
-        input.write_at(&CircularBuffer::default().serialize(), 0)?;
+        head.index = (head.index + count) % head.capacity;
+        head.len -= count;
+        head.last_read = now;
+        input.write_all_at(&head.serialize(), 0)?;
 
-        if !fcntl::unlock_file(&input, None)? {
-            return Err("Could not unlock file!".into());
-        }
+        Ok(data)
+    }
+
+    /// Return the latest `n` records, from the oldest of them, without removing them.
+    pub fn peek_latest(&self, n: usize) -> Result<Vec<T>, FileBufferError> {
+        let (input, head) = self.open_locked(false)?;
+
+        let count = head.len.min(n.try_into().unwrap_or(u32::MAX));
+        let index = head.index + head.len - count;
+        let (data, _) = Self::read_slots(&input, &head, index, count, self.clock.unix_millis())?;
 
+        Self::unlock(input)?;
         Ok(data)
     }
 
+    /// Wait until there is at least a record to read, or until `timeout` elapses.
+    /// Returns `false` if the buffer is still empty.
+    pub fn wait_for_data(&self, timeout: Duration) -> Result<bool, FileBufferError> {
+        let deadline = Instant::now() + timeout;
+
+        // the file must exist to be watched, the watch must be set before
+        // checking the length to not miss a write in between
+        self.len()?;
+        let mut watch = FileWatch::new(&self.file)?;
+
+        loop {
+            if !self.is_empty()? {
+                return Ok(true);
+            }
+            if !watch.wait(deadline)? {
+                return Ok(!self.is_empty()?);
+            }
+        }
+    }
+
+    /// Number of records not read yet.
+    pub fn len(&self) -> Result<usize, FileBufferError> {
+        let (input, head) = self.open_locked(false)?;
+        Self::unlock(input)?;
+        Ok(head.len as usize)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, FileBufferError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Rewrite the buffer with `new_capacity` slots, keeping the unread records in order.
+    /// The other processes must open the buffer again with the new capacity.
+    pub fn resize(&mut self, new_capacity: u32) -> Result<(), FileBufferError> {
+        let (file, mut head) = self.open_locked(true)?;
+
+        if new_capacity == 0 || new_capacity < head.len {
+            Self::unlock(file)?;
+            return Err(FileBufferError::Resize {
+                path: self.file.display().to_string(),
+                capacity: new_capacity,
+                unread: head.len,
+            });
+        }
+
+        let slot_size = Self::slot_size();
+        let now = self.clock.unix_millis();
+        let mut slots = vec![0u8; new_capacity as usize * slot_size];
+        for (i, slot) in slots.chunks_exact_mut(slot_size).take(head.len as usize).enumerate() {
+            let index = (head.index + i as u32) % head.capacity;
+            file.read_exact_at(slot, Self::slot_position(index))?;
+            // the producer would write its record in the old position
+            if Self::being_written(&Self::decode_slot(slot), now) {
+                Self::unlock(file)?;
+                return Err(FileBufferError::Busy(self.file.display().to_string()));
+            }
+        }
+
+        head.index = 0;
+        head.capacity = new_capacity;
+        let mut content = head.serialize().to_vec();
+        content.extend_from_slice(&slots);
+        file.write_all_at(&content, 0)?;
+        file.set_len(content.len() as u64)?;
+
+        self.capacity = new_capacity;
+        Self::unlock(file)
+    }
+
+    /// Every slot of the ring, read or not, from the first one of the file.
+    pub fn slots(&self) -> Result<Vec<Slot<T>>, FileBufferError> {
+        let (input, head) = self.open_locked(false)?;
+
+        let mut slots = Vec::new();
+        for index in 0..head.capacity {
+            slots.push(Self::read_slot(&input, Self::slot_position(index))?);
+        }
+
+        Self::unlock(input)?;
+        Ok(slots)
+    }
+
+    /// Check the header and the records not read yet,
+    /// returns the problems found, none if the buffer is healthy.
+    pub fn verify(&self) -> Result<Vec<String>, FileBufferError> {
+        let (input, head) = match self.open_locked(false) {
+            Ok(opened) => opened,
+            Err(e) => return Ok(vec![e.to_string()]),
+        };
+
+        let now = self.clock.unix_millis();
+        let mut problems = Vec::new();
+        for i in 0..head.len {
+            let index = (head.index + i) % head.capacity;
+            match Self::read_slot(&input, Self::slot_position(index))? {
+                Slot::Committed(_) => {}
+                Slot::Reserved(since) if !abandoned(since, now) => {}
+                Slot::Reserved(_) => problems.push(format!("slot {} was abandoned", index)),
+                Slot::Corrupted => problems.push(format!("slot {} is corrupted", index)),
+            }
+        }
+
+        Self::unlock(input)?;
+        Ok(problems)
+    }
+
+    /// Empty the buffer and clear its statistics. The header is not read,
+    /// so that a file too damaged to be opened can still be reused.
+    pub fn reset(&mut self) -> Result<(), FileBufferError> {
+        let file = OpenOptions::new().write(true).create(true).truncate(false).open(&self.file)?;
+        PlatformLock::lock(&file, true)?;
+
+        let content = self.initial_content();
+        file.write_all_at(&content, 0)?;
+        file.set_len(content.len() as u64)?;
+
+        Self::unlock(file)
+    }
+
+    /// Counters and timestamps kept in the header, to tell if the producers and the consumer
+    /// are keeping up: a consumer that stopped reading leaves `last_read` behind.
+    pub fn stats(&self) -> Result<BufferStats, FileBufferError> {
+        let (input, head) = self.open_locked(false)?;
+        Self::unlock(input)?;
+        Ok(head.stats())
+    }
+}
+
+/// Seconds since the epoch of `millis`, the time of the reservations.
+fn unix_secs(millis: u64) -> u32 {
+    (millis / 1000) as u32
+}
+
+/// Whether a slot reserved at `since` was left by a crashed producer, at `now`
+/// in milliseconds since the epoch.
+fn abandoned(since: u32, now: u64) -> bool {
+    unix_secs(now).saturating_sub(since) >= RESERVATION_TIMEOUT.as_secs() as u32
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::ops::Deref;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+    use std::{env, fs, process, thread};
+
+    use labs_common::ManualClock;
+
+    use crate::shared::positional::PositionalIo;
+    use crate::shared::{
+        FileBufferError, FileReader, FullPolicy, MmapBuffer, Producer, Record, SensorData, SeqGap,
+        Slot, COMMITTED, RESERVATION_TIMEOUT,
+    };
+
+    /// File of the test `name` in the temporary directory, removed when dropped,
+    /// also if the test fails.
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("lab2-1-{}-{}", name, process::id()));
+            let _ = fs::remove_file(&path);
+            Self(path)
+        }
+    }
+
+    impl Deref for TempPath {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl AsRef<Path> for TempPath {
+        fn as_ref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn sensor_data_format_test() {
+        let data = SensorData {
+            seq: 0x01020304,
+            values: [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, -0.5],
+            timestamp: 0x0a0b0c0d_00000007,
+        };
+        #[rustfmt::skip]
+        let golden: [u8; 52] = [
+            0x04, 0x03, 0x02, 0x01,
+            0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x80, 0x3f,
+            0x00, 0x00, 0x00, 0x40,
+            0x00, 0x00, 0x40, 0x40,
+            0x00, 0x00, 0x80, 0x40,
+            0x00, 0x00, 0xa0, 0x40,
+            0x00, 0x00, 0xc0, 0x40,
+            0x00, 0x00, 0xe0, 0x40,
+            0x00, 0x00, 0x00, 0x41,
+            0x00, 0x00, 0x00, 0xbf,
+            0x07, 0x00, 0x00, 0x00,
+            0x0d, 0x0c, 0x0b, 0x0a,
+        ];
+
+        assert_eq!(data.serialize(), golden);
+        let decoded = SensorData::deserialize(&golden);
+        assert_eq!(decoded.seq, data.seq);
+        assert_eq!(decoded.values, data.values);
+        assert_eq!(decoded.timestamp, data.timestamp);
+    }
+
+    #[test]
+    fn read_head_test() {
+        let path = TempPath::new("read-head");
+
+        let mut buffer = FileReader::with_options(&path, 4);
+        buffer.write_data(SensorData::default()).unwrap();
+        assert_eq!(buffer.read_data().unwrap().len(), 1);
+
+        let err = FileReader::with_options(&path, 5).read_data().unwrap_err();
+        assert!(err.to_string().contains("capacity is 4, expected 5"));
+
+        let len = fs::metadata(&path).unwrap().len();
+        fs::File::options().write(true).open(&path).unwrap().set_len(len - 1).unwrap();
+        let err = buffer.read_data().unwrap_err();
+        assert!(err.to_string().contains("truncated file"));
+
+        fs::write(&path, b"garbage").unwrap();
+        let err = buffer.read_data().unwrap_err();
+        assert!(err.to_string().contains("truncated header"));
+
+        fs::write(&path, [1u8; 64]).unwrap();
+        let err = buffer.read_data().unwrap_err();
+        assert!(err.to_string().contains("not a circular buffer file"));
+    }
+
+    #[test]
+    fn full_policy_test() {
+        let path = TempPath::new("full-policy");
+
+        let mut buffer = FileReader::with_options(&path, 3);
+        let write = |buffer: &mut FileReader, seqs| {
+            for seq in seqs {
+                let data = SensorData { seq, ..SensorData::default() };
+                buffer.write_data(data).unwrap();
+            }
+        };
+        let read = |buffer: &mut FileReader| {
+            buffer.read_data().unwrap().iter().map(|d| d.seq).collect::<Vec<_>>()
+        };
+
+        write(&mut buffer, 1..=5);
+        assert_eq!(read(&mut buffer), [1, 2, 3]);
+
+        buffer.set_full_policy(FullPolicy::OverwriteOldest);
+        write(&mut buffer, 1..=5);
+        assert_eq!(read(&mut buffer), [3, 4, 5]);
+    }
+
+    #[test]
+    fn partial_read_test() {
+        let path = TempPath::new("partial-read");
+
+        let mut buffer = FileReader::with_options(&path, 4);
+        for seq in 1..=4 {
+            let data = SensorData { seq, ..SensorData::default() };
+            buffer.write_data(data).unwrap();
+        }
+        let seqs = |data: Vec<SensorData>| data.iter().map(|d| d.seq).collect::<Vec<_>>();
+
+        assert_eq!(seqs(buffer.peek_latest(2).unwrap()), [3, 4]);
+        assert_eq!(buffer.len().unwrap(), 4);
+        assert_eq!(seqs(buffer.read_up_to(3).unwrap()), [1, 2, 3]);
+        assert_eq!(buffer.len().unwrap(), 1);
+
+        buffer.write_data(SensorData { seq: 5, ..SensorData::default() }).unwrap();
+        assert_eq!(seqs(buffer.peek_latest(10).unwrap()), [4, 5]);
+        assert_eq!(seqs(buffer.read_data().unwrap()), [4, 5]);
+        assert_eq!(buffer.len().unwrap(), 0);
+    }
+
+    #[test]
+    fn wait_for_data_test() {
+        let path = TempPath::new("wait-for-data");
+
+        let buffer = FileReader::with_options(&path, 4);
+        assert!(!buffer.wait_for_data(Duration::from_millis(50)).unwrap());
+
+        let start = Instant::now();
+        thread::scope(|s| {
+            s.spawn(|| {
+                thread::sleep(Duration::from_millis(100));
+                FileReader::with_options(&path, 4).write_data(SensorData::default()).unwrap();
+            });
+            assert!(buffer.wait_for_data(Duration::from_secs(5)).unwrap());
+        });
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn mmap_buffer_test() {
+        let path = TempPath::new("mmap-buffer");
+
+        let mut mmap = MmapBuffer::<SensorData>::open(&path, 3).unwrap();
+        let mut file = FileReader::with_options(&path, 3);
+        mmap.set_full_policy(FullPolicy::OverwriteOldest);
+        for seq in 1..=4 {
+            mmap.write_data(SensorData { seq, ..SensorData::default() }).unwrap();
+        }
+        assert_eq!(mmap.len(), 3);
+
+        // both the backends share the same file format
+        let seqs = file.read_up_to(2).unwrap().iter().map(|d| d.seq).collect::<Vec<_>>();
+        assert_eq!(seqs, [2, 3]);
+        assert_eq!(mmap.len(), 1);
+        file.write_data(SensorData { seq: 5, ..SensorData::default() }).unwrap();
+        let seqs = mmap.read_data().unwrap().iter().map(|d| d.seq).collect::<Vec<_>>();
+        assert_eq!(seqs, [4, 5]);
+    }
+
+    #[test]
+    fn mmap_shared_file_test() {
+        let path = TempPath::new("mmap-shared-file");
+        let archive = TempPath::new("mmap-shared-file-log");
+
+        let mut mmap = MmapBuffer::<SensorData>::open(&path, 2).unwrap();
+        mmap.set_full_policy(FullPolicy::OverwriteOldest);
+        mmap.set_archive(&archive);
+        let mut file = FileReader::with_options(&path, 2);
+        let (reserved, position) = file.reserve_slot().unwrap().unwrap();
+        file.write_data(SensorData { seq: 2, ..SensorData::default() }).unwrap();
+
+        // the oldest slot is still being written by the file-IO producer
+        mmap.write_data(SensorData { seq: 3, ..SensorData::default() }).unwrap();
+        assert_eq!(file.stats().unwrap().dropped, 1);
+
+        let data = SensorData { seq: 1, ..SensorData::default() };
+        reserved.write_all_at(&FileReader::slot_body(&data), position + 4).unwrap();
+        reserved.write_all_at(&COMMITTED.to_le_bytes(), position).unwrap();
+        mmap.write_data(SensorData { seq: 4, ..SensorData::default() }).unwrap();
+        let archived = mmap.replay_archive(..).unwrap().iter().map(|(_, d)| d.seq).collect::<Vec<_>>();
+        assert_eq!(archived, [4]);
+
+        // the mapping follows the resizes of the other handles
+        file.resize(4).unwrap();
+        mmap.write_data(SensorData { seq: 5, ..SensorData::default() }).unwrap();
+        assert_eq!(file.len().unwrap(), 3);
+        file.read_up_to(2).unwrap();
+        file.resize(1).unwrap();
+        let seqs = mmap.read_data().unwrap().iter().map(|d| d.seq).collect::<Vec<_>>();
+        assert_eq!(seqs, [5]);
+    }
+
+    #[test]
+    fn corrupted_slot_test() {
+        let path = TempPath::new("corrupted-slot");
+
+        let mut buffer = FileReader::with_options(&path, 4);
+        for seq in 1..=3 {
+            let data = SensorData { seq, ..SensorData::default() };
+            buffer.write_data(data).unwrap();
+        }
+
+        // flip a byte of the second record
+        let file = fs::File::options().read(true).write(true).open(&path).unwrap();
+        let position = FileReader::slot_position(1) + 20;
+        let mut byte = [0u8];
+        file.read_exact_at(&mut byte, position).unwrap();
+        file.write_all_at(&[!byte[0]], position).unwrap();
+
+        let seqs = buffer.read_data().unwrap().iter().map(|d| d.seq).collect::<Vec<_>>();
+        assert_eq!(seqs, [1, 3]);
+    }
+
+    #[test]
+    fn reserved_slot_test() {
+        let path = TempPath::new("reserved-slot");
+
+        let mut buffer = FileReader::with_options(&path, 4);
+        let (file, position) = buffer.reserve_slot().unwrap().unwrap();
+        buffer.write_data(SensorData { seq: 2, ..SensorData::default() }).unwrap();
+
+        // the record after the reserved slot is committed, but it can't be read before it
+        assert_eq!(buffer.len().unwrap(), 2);
+        assert!(buffer.read_data().unwrap().is_empty());
+        assert_eq!(buffer.len().unwrap(), 2);
+
+        let data = SensorData { seq: 1, ..SensorData::default() };
+        file.write_all_at(&FileReader::slot_body(&data), position + 4).unwrap();
+        file.write_all_at(&COMMITTED.to_le_bytes(), position).unwrap();
+
+        let seqs = buffer.read_data().unwrap().iter().map(|d| d.seq).collect::<Vec<_>>();
+        assert_eq!(seqs, [1, 2]);
+    }
 
+    #[test]
+    fn overwrite_reserved_slot_test() {
+        let path = TempPath::new("overwrite-reserved");
+
+        let mut buffer = FileReader::with_options(&path, 2);
+        buffer.set_full_policy(FullPolicy::OverwriteOldest);
+        let (file, position) = buffer.reserve_slot().unwrap().unwrap();
+        buffer.write_data(SensorData { seq: 2, ..SensorData::default() }).unwrap();
+
+        // the oldest slot is still being written, the new record is dropped instead
+        buffer.write_data(SensorData { seq: 3, ..SensorData::default() }).unwrap();
+        assert_eq!(buffer.stats().unwrap().dropped, 1);
+
+        let data = SensorData { seq: 1, ..SensorData::default() };
+        file.write_all_at(&FileReader::slot_body(&data), position + 4).unwrap();
+        file.write_all_at(&COMMITTED.to_le_bytes(), position).unwrap();
+        buffer.write_data(SensorData { seq: 4, ..SensorData::default() }).unwrap();
+
+        let seqs = buffer.read_data().unwrap().iter().map(|d| d.seq).collect::<Vec<_>>();
+        assert_eq!(seqs, [2, 4]);
+    }
+
+    #[test]
+    fn overwrite_reserved_slot_batch_test() {
+        let path = TempPath::new("overwrite-reserved-batch");
+
+        let mut buffer = FileReader::with_options(&path, 2);
+        buffer.set_full_policy(FullPolicy::OverwriteOldest);
+        let (file, position) = buffer.reserve_slot().unwrap().unwrap();
+        buffer.write_data(SensorData { seq: 2, ..SensorData::default() }).unwrap();
+
+        // the oldest slot is still being written, the whole batch is dropped
+        let batch = (3..=4).map(|seq| SensorData { seq, ..SensorData::default() }).collect::<Vec<_>>();
+        assert_eq!(buffer.write_batch(&batch).unwrap(), 0);
+        assert_eq!(buffer.stats().unwrap().dropped, 2);
+
+        let data = SensorData { seq: 1, ..SensorData::default() };
+        file.write_all_at(&FileReader::slot_body(&data), position + 4).unwrap();
+        file.write_all_at(&COMMITTED.to_le_bytes(), position).unwrap();
+        assert_eq!(buffer.write_batch(&batch[1..]).unwrap(), 1);
+
+        let seqs = buffer.read_data().unwrap().iter().map(|d| d.seq).collect::<Vec<_>>();
+        assert_eq!(seqs, [2, 4]);
+    }
+
+    #[test]
+    fn stats_test() {
+        let path = TempPath::new("stats");
+
+        let mut buffer = FileReader::with_options(&path, 3);
+        let stats = buffer.stats().unwrap();
+        assert_eq!((stats.capacity, stats.len, stats.total_written, stats.dropped), (3, 0, 0, 0));
+        assert_eq!((stats.last_write, stats.last_read), (None, None));
+
+        for seq in 1..=5 {
+            buffer.write_data(SensorData { seq, ..SensorData::default() }).unwrap();
+        }
+        let stats = buffer.stats().unwrap();
+        assert_eq!((stats.len, stats.total_written, stats.dropped), (3, 3, 2));
+        assert!(stats.last_write.is_some() && stats.last_read.is_none());
+
+        buffer.read_up_to(2).unwrap();
+        let stats = buffer.stats().unwrap();
+        assert_eq!((stats.len, stats.total_written), (1, 3));
+        assert!(stats.last_read >= stats.last_write);
+    }
+
+    #[test]
+    fn resize_test() {
+        let path = TempPath::new("resize");
+
+        let mut buffer = FileReader::with_options(&path, 4);
+        let write = |buffer: &mut FileReader, seqs| {
+            for seq in seqs {
+                buffer.write_data(SensorData { seq, ..SensorData::default() }).unwrap();
+            }
+        };
+        let read = |buffer: &mut FileReader| {
+            buffer.read_data().unwrap().iter().map(|d| d.seq).collect::<Vec<_>>()
+        };
+
+        // the unread records wrap around the end of the ring
+        write(&mut buffer, 1..=3);
+        buffer.read_up_to(2).unwrap();
+        write(&mut buffer, 4..=6);
+
+        buffer.resize(6).unwrap();
+        write(&mut buffer, 7..=8);
+        assert_eq!(buffer.len().unwrap(), 6);
+        assert!(matches!(buffer.resize(5), Err(FileBufferError::Resize { capacity: 5, unread: 6, .. })));
+        assert_eq!(read(&mut buffer), [3, 4, 5, 6, 7, 8]);
+
+        write(&mut buffer, 9..=10);
+        buffer.resize(2).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), FileReader::slot_position(2));
+        assert_eq!(read(&mut buffer), [9, 10]);
+
+        // the other handles see the old capacity
+        assert!(matches!(FileReader::with_options(&path, 4).len(), Err(FileBufferError::Invalid { .. })));
+    }
+
+    #[test]
+    fn handle_test() {
+        let path = TempPath::new("handle");
+
+        let mut producer = FileReader::open_writer(&path);
+        let mut consumer = FileReader::open_reader(&path);
+        for seq in 1..=3 {
+            producer.write_data(SensorData { seq, ..SensorData::default() }).unwrap();
+        }
+
+        assert_eq!(consumer.len().unwrap(), 3);
+        assert_eq!(consumer.peek_latest(1).unwrap()[0].seq, 3);
+        let seqs = consumer.read_data().unwrap().iter().map(|d| d.seq).collect::<Vec<_>>();
+        assert_eq!(seqs, [1, 2, 3]);
+        assert_eq!(producer.stats().unwrap().len, 0);
+    }
+
+    #[test]
+    fn write_batch_test() {
+        let path = TempPath::new("write-batch");
+
+        let mut buffer = FileReader::with_options(&path, 4);
+        let batch = (1..=6).map(|seq| SensorData { seq, ..SensorData::default() }).collect::<Vec<_>>();
+        let read = |buffer: &mut FileReader| {
+            buffer.read_data().unwrap().iter().map(|d| d.seq).collect::<Vec<_>>()
+        };
+
+        buffer.write_data(batch[0]).unwrap();
+        assert_eq!(buffer.write_batch(&batch[1..]).unwrap(), 3);
+        assert_eq!(buffer.stats().unwrap().dropped, 2);
+        assert_eq!(read(&mut buffer), [1, 2, 3, 4]);
+
+        buffer.set_full_policy(FullPolicy::OverwriteOldest);
+        assert_eq!(buffer.write_batch(&batch).unwrap(), 6);
+        assert_eq!(read(&mut buffer), [3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn read_data_checked_test() {
+        let path = TempPath::new("read-checked");
+
+        let mut producer = FileReader::with_options(&path, 3).into_writer();
+        let mut consumer = FileReader::with_options(&path, 3).into_reader();
+        let write = |producer: &mut Producer<SensorData>, seqs: &[u32]| {
+            for &seq in seqs {
+                producer.write_data(SensorData { seq, ..SensorData::default() }).unwrap();
+            }
+        };
+
+        // 4 and 5 are dropped because the buffer is full
+        write(&mut producer, &[1, 2, 3, 4, 5]);
+        let (data, gaps) = consumer.read_data_checked().unwrap();
+        assert_eq!((data.len(), gaps), (3, vec![]));
+
+        write(&mut producer, &[6, 7, 9]);
+        let (_, gaps) = consumer.read_data_checked().unwrap();
+        assert_eq!(gaps, [SeqGap { after: 3, before: 6 }, SeqGap { after: 7, before: 9 }]);
+        assert_eq!(gaps.iter().map(SeqGap::lost).sum::<u32>(), 3);
+
+        // a restarted producer starts again from 1, nothing was lost
+        write(&mut producer, &[1]);
+        consumer.read_up_to(1).unwrap();
+        write(&mut producer, &[2]);
+        assert_eq!(consumer.read_data_checked().unwrap().1, []);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_buffer_test() {
+        use crate::shared::AsyncFileBuffer;
+
+        let path = TempPath::new("async-buffer");
+
+        let mut buffer = AsyncFileBuffer::new(FileReader::with_options(&path, 4));
+        for seq in 1..=5 {
+            buffer.write_data(SensorData { seq, ..SensorData::default() }).await.unwrap();
+        }
+
+        // the same file is shared with the blocking buffer
+        let mut sync_buffer = FileReader::with_options(&path, 4);
+        assert_eq!(sync_buffer.stats().unwrap().dropped, 1);
+        sync_buffer.write_data(SensorData::default()).unwrap();
+        sync_buffer.read_up_to(1).unwrap();
+
+        let seqs = buffer.read_data().await.unwrap().iter().map(|d| d.seq).collect::<Vec<_>>();
+        assert_eq!(seqs, [2, 3, 4]);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_overwrite_reserved_slot_test() {
+        use crate::shared::AsyncFileBuffer;
+
+        let path = TempPath::new("async-overwrite-reserved");
+
+        // a blocking producer is still writing the oldest slot
+        let mut sync_buffer = FileReader::with_options(&path, 2);
+        let (file, position) = sync_buffer.reserve_slot().unwrap().unwrap();
+        sync_buffer.write_data(SensorData { seq: 2, ..SensorData::default() }).unwrap();
+
+        let mut buffer = AsyncFileBuffer::new(FileReader::with_options(&path, 2));
+        buffer.set_full_policy(FullPolicy::OverwriteOldest);
+        buffer.write_data(SensorData { seq: 3, ..SensorData::default() }).await.unwrap();
+        assert_eq!(sync_buffer.stats().unwrap().dropped, 1);
+
+        let data = SensorData { seq: 1, ..SensorData::default() };
+        file.write_all_at(&FileReader::slot_body(&data), position + 4).unwrap();
+        file.write_all_at(&COMMITTED.to_le_bytes(), position).unwrap();
+        buffer.write_data(SensorData { seq: 4, ..SensorData::default() }).await.unwrap();
+
+        let seqs = buffer.read_data().await.unwrap().iter().map(|d| d.seq).collect::<Vec<_>>();
+        assert_eq!(seqs, [2, 4]);
+    }
+
+    #[test]
+    fn archive_test() {
+        let path = TempPath::new("archive");
+        let archive = TempPath::new("archive-log");
+
+        let mut buffer = FileReader::with_options(&path, 2);
+        buffer.set_full_policy(FullPolicy::OverwriteOldest);
+        assert!(matches!(buffer.replay_archive(..), Err(FileBufferError::NoArchive(_))));
+        buffer.set_archive(&archive);
+        assert!(buffer.replay_archive(..).unwrap().is_empty());
+
+        buffer.write_data(SensorData { seq: 1, ..SensorData::default() }).unwrap();
+        thread::sleep(Duration::from_millis(5));
+        let after_first = SystemTime::now();
+        // the times are stored in milliseconds
+        thread::sleep(Duration::from_millis(5));
+        let batch = (2..=5).map(|seq| SensorData { seq, ..SensorData::default() }).collect::<Vec<_>>();
+        buffer.write_batch(&batch).unwrap();
+
+        let seqs = |records: Vec<(SystemTime, SensorData)>| {
+            records.iter().map(|(_, d)| d.seq).collect::<Vec<_>>()
+        };
+        // the ring only keeps the last 2 records
+        assert_eq!(buffer.read_data().unwrap().len(), 2);
+        assert_eq!(seqs(buffer.replay_archive(..).unwrap()), [1, 2, 3, 4, 5]);
+        assert_eq!(seqs(buffer.replay_archive(after_first..).unwrap()), [2, 3, 4, 5]);
+        assert_eq!(seqs(buffer.replay_archive(..after_first).unwrap()), [1]);
+    }
+
+    #[test]
+    fn clock_test() {
+        let path = TempPath::new("clock");
+        let archive = TempPath::new("clock-log");
+
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        let clock = Arc::new(ManualClock::new(start));
+        let mut buffer = FileReader::with_options(&path, 4);
+        buffer.set_clock(clock.clone());
+        buffer.set_archive(&archive);
+
+        buffer.write_data(SensorData { seq: 1, ..SensorData::default() }).unwrap();
+        clock.advance(Duration::from_secs(1));
+        buffer.write_batch(&[SensorData { seq: 2, ..SensorData::default() }]).unwrap();
+        clock.advance(Duration::from_secs(1));
+        buffer.read_data().unwrap();
+
+        let stats = buffer.stats().unwrap();
+        assert_eq!(stats.last_write, Some(start + Duration::from_secs(1)));
+        assert_eq!(stats.last_read, Some(start + Duration::from_secs(2)));
+
+        let times = buffer.replay_archive(..).unwrap().into_iter().map(|(time, _)| time).collect::<Vec<_>>();
+        assert_eq!(times, [start, start + Duration::from_secs(1)]);
+        assert_eq!(buffer.replay_archive(start + Duration::from_millis(1)..).unwrap().len(), 1);
+
+        let mmap_path = TempPath::new("clock-mmap");
+        let mut mmap = MmapBuffer::<SensorData>::open(&mmap_path, 2).unwrap();
+        mmap.set_clock(clock.clone());
+        mmap.write_data(SensorData::default()).unwrap();
+        let stats = FileReader::with_options(&mmap_path, 2).stats().unwrap();
+        assert_eq!(stats.last_write, Some(start + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn abandoned_slot_test() {
+        let path = TempPath::new("abandoned-slot");
+
+        let clock = Arc::new(ManualClock::new(UNIX_EPOCH + Duration::from_secs(1_000)));
+        let mut buffer = FileReader::with_options(&path, 4);
+        buffer.set_clock(clock.clone());
+        buffer.reserve_slot().unwrap().unwrap();
+        buffer.write_data(SensorData { seq: 2, ..SensorData::default() }).unwrap();
+
+        clock.advance(RESERVATION_TIMEOUT - Duration::from_secs(1));
+        assert!(buffer.read_data().unwrap().is_empty());
+        assert!(buffer.verify().unwrap().is_empty());
+
+        // the producer never committed its slot, it is skipped once the timeout is over
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(buffer.verify().unwrap().len(), 1);
+        let seqs = buffer.read_data().unwrap().iter().map(|d| d.seq).collect::<Vec<_>>();
+        assert_eq!(seqs, [2]);
+    }
+
+    #[test]
+    fn verify_test() {
+        let path = TempPath::new("verify");
+
+        let mut buffer = FileReader::with_options(&path, 3);
+        for seq in 1..=2 {
+            buffer.write_data(SensorData { seq, ..SensorData::default() }).unwrap();
+        }
+        assert!(buffer.verify().unwrap().is_empty());
+
+        let file = fs::File::options().read(true).write(true).open(&path).unwrap();
+        file.write_all_at(&[0xff], FileReader::slot_position(1) + 20).unwrap();
+        assert_eq!(buffer.verify().unwrap(), ["slot 1 is corrupted"]);
+        assert!(matches!(buffer.slots().unwrap()[..], [Slot::Committed(_), Slot::Corrupted, _]));
+
+        // a damaged header makes the whole file unusable, until it is reset
+        file.write_all_at(&[0xff; 4], 8).unwrap();
+        assert_eq!(buffer.verify().unwrap().len(), 1);
+        let mut detected = FileReader::from_file(&path).unwrap();
+        detected.reset().unwrap();
+        assert!(buffer.verify().unwrap().is_empty());
+        assert_eq!(buffer.stats().unwrap().total_written, 0);
+    }
 }
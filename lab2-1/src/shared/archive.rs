@@ -0,0 +1,94 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::ops::RangeBounds;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::lock::{FileLock, PlatformLock};
+use super::{FileBufferError, Record};
+
+/// First bytes of every archive file.
+const ARCHIVE_MAGIC: [u8; 4] = *b"CARC";
+/// Size of the archive header: the magic and the element size.
+const ARCHIVE_HEAD_SIZE: usize = 8;
+/// Every entry starts with the time it was archived, in milliseconds since the epoch.
+const ENTRY_HEADER_SIZE: usize = 8;
+
+/// Append-only log of every record written to a buffer, kept after the ring overwrites them.
+#[derive(Clone)]
+pub struct Archive {
+    path: PathBuf,
+}
+
+impl Archive {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn invalid(&self, reason: String) -> FileBufferError {
+        FileBufferError::Invalid { path: self.path.display().to_string(), reason }
+    }
+
+    /// Append `data` written at `now`, in milliseconds since the epoch,
+    /// the file is created if needed.
+    pub fn append<T: Record>(&self, data: &[T], now: u64) -> Result<(), FileBufferError> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        PlatformLock::lock(&file, true)?;
+
+        let entry_size = ENTRY_HEADER_SIZE + T::SIZE;
+        let mut entries = Vec::with_capacity(ARCHIVE_HEAD_SIZE + data.len() * entry_size);
+        if file.metadata()?.len() == 0 {
+            entries.extend_from_slice(&ARCHIVE_MAGIC);
+            entries.extend_from_slice(&(T::SIZE as u32).to_le_bytes());
+        }
+        for record in data {
+            entries.extend_from_slice(&now.to_le_bytes());
+            entries.extend_from_slice(&record.serialize());
+        }
+        file.write_all(&entries)?;
+
+        PlatformLock::unlock(&file)?;
+        Ok(())
+    }
+
+    /// Every archived record with the time it was written, from the oldest,
+    /// whose time is in `range`. A missing archive is empty.
+    pub fn replay<T: Record, R: RangeBounds<SystemTime>>(
+        &self,
+        range: R,
+    ) -> Result<Vec<(SystemTime, T)>, FileBufferError> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        PlatformLock::lock(&file, false)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        PlatformLock::unlock(&file)?;
+
+        if bytes.len() < ARCHIVE_HEAD_SIZE || bytes[..4] != ARCHIVE_MAGIC {
+            return Err(self.invalid("not an archive file".to_string()));
+        }
+        let elem_size = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        if elem_size as usize != T::SIZE {
+            return Err(self.invalid(format!(
+                "element size is {}, expected {}",
+                elem_size,
+                T::SIZE
+            )));
+        }
+
+        // an entry cut by a crashed producer is at the end, `chunks_exact` leaves it out
+        let entries = bytes[ARCHIVE_HEAD_SIZE..].chunks_exact(ENTRY_HEADER_SIZE + T::SIZE);
+        let records = entries
+            .map(|entry| {
+                let millis = u64::from_le_bytes(entry[..ENTRY_HEADER_SIZE].try_into().unwrap());
+                let time = UNIX_EPOCH + Duration::from_millis(millis);
+                (time, T::deserialize(&entry[ENTRY_HEADER_SIZE..]))
+            })
+            .filter(|(time, _)| range.contains(time))
+            .collect();
+        Ok(records)
+    }
+}
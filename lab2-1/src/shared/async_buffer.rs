@@ -0,0 +1,85 @@
+use std::fs::File;
+use std::panic;
+use std::time::Duration;
+
+use tracing::debug;
+
+use super::lock::{FileLock, PlatformLock};
+use super::{FileBuffer, FileBufferError, FullPolicy, Record};
+
+/// Time between two attempts to take a busy lock.
+const LOCK_RETRY: Duration = Duration::from_millis(5);
+
+/// `FileBuffer` for the async services: waiting for the lock does not block
+/// the runtime thread, the reads and the writes run on the blocking pool.
+pub struct AsyncFileBuffer<T: Record> {
+    buffer: FileBuffer<T>,
+}
+
+impl<T: Record + Send + 'static> AsyncFileBuffer<T> {
+    pub fn new(buffer: FileBuffer<T>) -> Self {
+        Self { buffer }
+    }
+
+    pub fn set_full_policy(&mut self, policy: FullPolicy) {
+        self.buffer.set_full_policy(policy);
+    }
+
+    /// Open the file and poll the lock until it is acquired, the file is created if needed.
+    async fn open_locked(&self, exclusive: bool) -> Result<File, FileBufferError> {
+        let path = &self.buffer.file;
+        if !tokio::fs::try_exists(path).await? {
+            debug!(file = %path.display(), "created");
+            tokio::fs::write(path, self.buffer.initial_content()).await?;
+        }
+
+        let file = tokio::fs::OpenOptions::new().read(true).write(true).open(path).await?;
+        let file = file.into_std().await;
+        while !PlatformLock::try_lock(&file, exclusive)? {
+            tokio::time::sleep(LOCK_RETRY).await;
+        }
+
+        Ok(file)
+    }
+
+    /// Append `data`, it is dropped if the buffer is full and the policy is `Reject`,
+    /// or if the oldest slot is still being written by a blocking producer.
+    pub async fn write_data(&mut self, data: T) -> Result<(), FileBufferError> {
+        let mut output = self.open_locked(true).await?;
+        let buffer = self.buffer.clone();
+
+        blocking(move || {
+            let mut head = buffer.read_head(&mut output)?;
+            buffer.append_locked(&output, &mut head, &[data])?;
+            FileBuffer::<T>::unlock(output)
+        })
+        .await
+    }
+
+    /// Remove and return every record, from the oldest.
+    pub async fn read_data(&mut self) -> Result<Vec<T>, FileBufferError> {
+        let mut input = self.open_locked(true).await?;
+        let buffer = self.buffer.clone();
+
+        blocking(move || {
+            let mut head = buffer.read_head(&mut input)?;
+            let now = buffer.clock.unix_millis();
+            let data = FileBuffer::<T>::take_locked(&input, &mut head, usize::MAX, now)?;
+            FileBuffer::<T>::unlock(input)?;
+            Ok(data)
+        })
+        .await
+    }
+}
+
+/// Run `f` on the blocking pool, a panic of `f` is resumed in the caller.
+async fn blocking<R, F>(f: F) -> Result<R, FileBufferError>
+where
+    R: Send + 'static,
+    F: FnOnce() -> Result<R, FileBufferError> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(e) => panic::resume_unwind(e.into_panic()),
+    }
+}
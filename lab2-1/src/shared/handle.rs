@@ -0,0 +1,126 @@
+use std::marker::PhantomData;
+use std::path::Path;
+use std::ops::RangeBounds;
+use std::time::{Duration, SystemTime};
+
+use super::{BufferStats, FileBuffer, FileBufferError, FullPolicy, Record, SeqGap, DEFAULT_CAPACITY};
+
+pub struct BReader {}
+pub struct BWriter {}
+pub trait BufferMode {}
+impl BufferMode for BReader {}
+impl BufferMode for BWriter {}
+
+/// `FileBuffer` that can only be read or only be written, depending on `Mode`.
+pub struct BufferHandle<T: Record, Mode: BufferMode> {
+    buffer: FileBuffer<T>,
+    /// Sequence number of the last record read.
+    last_seq: Option<u32>,
+    mode: PhantomData<Mode>,
+}
+
+pub type Producer<T> = BufferHandle<T, BWriter>;
+pub type Consumer<T> = BufferHandle<T, BReader>;
+
+impl<T: Record> FileBuffer<T> {
+    /// Writing end of the buffer of `DEFAULT_CAPACITY` elements stored in `path`.
+    pub fn open_writer<P: AsRef<Path>>(path: P) -> Producer<T> {
+        Self::with_options(path, DEFAULT_CAPACITY).into_writer()
+    }
+
+    /// Reading end of the buffer of `DEFAULT_CAPACITY` elements stored in `path`.
+    pub fn open_reader<P: AsRef<Path>>(path: P) -> Consumer<T> {
+        Self::with_options(path, DEFAULT_CAPACITY).into_reader()
+    }
+
+    pub fn into_writer(self) -> Producer<T> {
+        BufferHandle { buffer: self, last_seq: None, mode: PhantomData::<BWriter> }
+    }
+
+    pub fn into_reader(self) -> Consumer<T> {
+        BufferHandle { buffer: self, last_seq: None, mode: PhantomData::<BReader> }
+    }
+}
+
+impl<T: Record, Mode: BufferMode> BufferHandle<T, Mode> {
+    /// Number of records not read yet.
+    pub fn len(&self) -> Result<usize, FileBufferError> {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> Result<bool, FileBufferError> {
+        self.buffer.is_empty()
+    }
+
+    pub fn stats(&self) -> Result<BufferStats, FileBufferError> {
+        self.buffer.stats()
+    }
+}
+
+impl<T: Record> BufferHandle<T, BWriter> {
+    pub fn set_full_policy(&mut self, policy: FullPolicy) {
+        self.buffer.set_full_policy(policy);
+    }
+
+    pub fn write_data(&mut self, data: T) -> Result<(), FileBufferError> {
+        self.buffer.write_data(data)
+    }
+
+    /// Append as many records of `data` as fit, returns the number accepted.
+    pub fn write_batch(&mut self, data: &[T]) -> Result<usize, FileBufferError> {
+        self.buffer.write_batch(data)
+    }
+}
+
+impl<T: Record> BufferHandle<T, BReader> {
+    /// Remove and return every record, from the oldest.
+    pub fn read_data(&mut self) -> Result<Vec<T>, FileBufferError> {
+        self.read_data_checked().map(|(data, _)| data)
+    }
+
+    /// Like `read_data`, also returning the gaps in the sequence numbers: the records
+    /// dropped because the buffer was full or lost, since the previous read.
+    pub fn read_data_checked(&mut self) -> Result<(Vec<T>, Vec<SeqGap>), FileBufferError> {
+        let data = self.buffer.read_data()?;
+        let gaps = self.find_gaps(&data);
+        Ok((data, gaps))
+    }
+
+    /// Remove and return at most `n` records, from the oldest.
+    pub fn read_up_to(&mut self, n: usize) -> Result<Vec<T>, FileBufferError> {
+        let data = self.buffer.read_up_to(n)?;
+        self.find_gaps(&data);
+        Ok(data)
+    }
+
+    fn find_gaps(&mut self, data: &[T]) -> Vec<SeqGap> {
+        let mut gaps = Vec::new();
+        for seq in data.iter().filter_map(Record::seq) {
+            // a smaller sequence number is a restarted producer, not a gap
+            match self.last_seq {
+                Some(last) if seq.saturating_sub(last) > 1 => gaps.push(SeqGap { after: last, before: seq }),
+                _ => {}
+            }
+            self.last_seq = Some(seq);
+        }
+        gaps
+    }
+
+    /// Return the latest `n` records without removing them.
+    pub fn peek_latest(&self, n: usize) -> Result<Vec<T>, FileBufferError> {
+        self.buffer.peek_latest(n)
+    }
+
+    /// Archived records written in `range`, see `FileBuffer::replay_archive`.
+    pub fn replay_archive<R: RangeBounds<SystemTime>>(
+        &self,
+        range: R,
+    ) -> Result<Vec<(SystemTime, T)>, FileBufferError> {
+        self.buffer.replay_archive(range)
+    }
+
+    /// Wait until there is at least a record to read, returns `false` on timeout.
+    pub fn wait_for_data(&self, timeout: Duration) -> Result<bool, FileBufferError> {
+        self.buffer.wait_for_data(timeout)
+    }
+}
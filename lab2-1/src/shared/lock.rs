@@ -0,0 +1,129 @@
+use std::fs::File;
+use std::io;
+
+/// Advisory lock on a whole file, used to serialize the accesses of the
+/// producers and the consumers to the buffer.
+pub trait FileLock {
+    /// Block until the lock is acquired. A shared lock can be held by many
+    /// processes at once, an exclusive one only if no other lock is held.
+    fn lock(file: &File, exclusive: bool) -> io::Result<()>;
+
+    /// Acquire the lock only if it can be done without waiting, returns `false` otherwise.
+    #[cfg(feature = "async")]
+    fn try_lock(file: &File, exclusive: bool) -> io::Result<bool>;
+
+    fn unlock(file: &File) -> io::Result<()>;
+}
+
+/// Locking implementation of the platform the buffer is built for.
+#[cfg(unix)]
+pub type PlatformLock = Fcntl;
+#[cfg(windows)]
+pub type PlatformLock = LockFileEx;
+
+/// POSIX record locks, set with `fcntl(F_SETLKW)`.
+#[cfg(unix)]
+pub struct Fcntl;
+
+#[cfg(unix)]
+impl Fcntl {
+    fn set_lock(file: &File, lock_type: libc::c_int, cmd: libc::c_int) -> io::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        // zero length, the lock covers the whole file whatever its size
+        let mut flock = unsafe { std::mem::zeroed::<libc::flock>() };
+        flock.l_type = lock_type as _;
+        flock.l_whence = libc::SEEK_SET as _;
+
+        loop {
+            if unsafe { libc::fcntl(file.as_raw_fd(), cmd, &flock) } == 0 {
+                return Ok(());
+            }
+            let err = io::Error::last_os_error();
+            // a signal woke the waiting process up, keep waiting
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl FileLock for Fcntl {
+    fn lock(file: &File, exclusive: bool) -> io::Result<()> {
+        let lock_type = if exclusive { libc::F_WRLCK } else { libc::F_RDLCK };
+        Self::set_lock(file, lock_type as _, libc::F_SETLKW)
+    }
+
+    #[cfg(feature = "async")]
+    fn try_lock(file: &File, exclusive: bool) -> io::Result<bool> {
+        let lock_type = if exclusive { libc::F_WRLCK } else { libc::F_RDLCK };
+        match Self::set_lock(file, lock_type as _, libc::F_SETLK) {
+            Ok(()) => Ok(true),
+            // another process holds a conflicting lock
+            Err(err) if matches!(err.raw_os_error(), Some(libc::EAGAIN | libc::EACCES)) => {
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn unlock(file: &File) -> io::Result<()> {
+        Self::set_lock(file, libc::F_UNLCK as _, libc::F_SETLK)
+    }
+}
+
+/// Windows byte-range locks, covering all the possible file lengths.
+#[cfg(windows)]
+pub struct LockFileEx;
+
+#[cfg(windows)]
+impl LockFileEx {
+    fn lock_file(file: &File, exclusive: bool, flags: u32) -> io::Result<()> {
+        use std::os::windows::io::AsRawHandle;
+        use windows_sys::Win32::Storage::FileSystem::{LockFileEx, LOCKFILE_EXCLUSIVE_LOCK};
+
+        let flags = if exclusive { flags | LOCKFILE_EXCLUSIVE_LOCK } else { flags };
+        let mut overlapped = unsafe { std::mem::zeroed() };
+        let locked = unsafe {
+            LockFileEx(file.as_raw_handle(), flags, 0, u32::MAX, u32::MAX, &mut overlapped)
+        };
+        if locked == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+impl FileLock for LockFileEx {
+    fn lock(file: &File, exclusive: bool) -> io::Result<()> {
+        Self::lock_file(file, exclusive, 0)
+    }
+
+    #[cfg(feature = "async")]
+    fn try_lock(file: &File, exclusive: bool) -> io::Result<bool> {
+        use windows_sys::Win32::Foundation::ERROR_LOCK_VIOLATION;
+        use windows_sys::Win32::Storage::FileSystem::LOCKFILE_FAIL_IMMEDIATELY;
+
+        match Self::lock_file(file, exclusive, LOCKFILE_FAIL_IMMEDIATELY) {
+            Ok(()) => Ok(true),
+            // another process holds a conflicting lock
+            Err(err) if err.raw_os_error() == Some(ERROR_LOCK_VIOLATION as i32) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn unlock(file: &File) -> io::Result<()> {
+        use std::os::windows::io::AsRawHandle;
+        use windows_sys::Win32::Storage::FileSystem::UnlockFileEx;
+
+        let mut overlapped = unsafe { std::mem::zeroed() };
+        let unlocked =
+            unsafe { UnlockFileEx(file.as_raw_handle(), 0, u32::MAX, u32::MAX, &mut overlapped) };
+        if unlocked == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use std::fs::File;
+use std::io::Seek;
+use std::ops::RangeBounds;
+use std::path::Path;
+use std::slice;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use memmap2::MmapMut;
+
+use super::lock::{FileLock, PlatformLock};
+use super::{Clock, FileBuffer, FileBufferError, FullPolicy, Record, COMMITTED, HEAD_SIZE, UNCOMMITTED};
+
+/// Offsets of the header fields changed by the reads and the writes.
+const LEN_OFFSET: usize = 8;
+const INDEX_OFFSET: usize = 12;
+/// Changed by `FileBuffer::resize`, the file is mapped again then.
+const CAPACITY_OFFSET: usize = 16;
+const TOTAL_WRITTEN_OFFSET: usize = 24;
+const DROPPED_OFFSET: usize = 32;
+const LAST_WRITE_OFFSET: usize = 40;
+const LAST_READ_OFFSET: usize = 48;
+
+/// Same buffer of `FileBuffer`, with the file mapped in memory: reads and writes
+/// do not pay the positional read and write system calls, only the file lock is taken.
+///
+/// The header fields are accessed in place as atomics, which is only possible
+/// because the file is in little endian like the target.
+pub struct MmapBuffer<T: Record> {
+    file: File,
+    map: MmapMut,
+    /// Path, capacity, policy, clock and archive of the buffer, the capacity is the one
+    /// of the mapping.
+    buffer: FileBuffer<T>,
+}
+
+impl<T: Record> MmapBuffer<T> {
+    /// Map the buffer of `capacity` elements stored in `path`, the file is created if needed.
+    pub fn open<P: AsRef<Path>>(path: P, capacity: u32) -> Result<Self, FileBufferError> {
+        // the header is validated the same way of the file-IO buffer
+        let buffer = FileBuffer::<T>::with_options(path, capacity);
+        let (file, _) = buffer.open_locked(false)?;
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        PlatformLock::unlock(&file)?;
+
+        Ok(Self { file, map, buffer })
+    }
+
+    pub fn set_full_policy(&mut self, policy: FullPolicy) {
+        self.buffer.set_full_policy(policy);
+    }
+
+    /// Take the times of the writes, of the reads and of the archived records from `clock`.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.buffer.set_clock(clock);
+    }
+
+    /// Also append every record written to the archive in `path`, see `FileBuffer::set_archive`.
+    pub fn set_archive<P: AsRef<Path>>(&mut self, path: P) {
+        self.buffer.set_archive(path);
+    }
+
+    /// Archived records written in `range`, with the time they were written, from the oldest.
+    pub fn replay_archive<R: RangeBounds<SystemTime>>(
+        &self,
+        range: R,
+    ) -> Result<Vec<(SystemTime, T)>, FileBufferError> {
+        self.buffer.replay_archive(range)
+    }
+
+    /// Take the lock, mapping the file again if another handle resized it: the slots past
+    /// the end of a shrunk file can't be touched.
+    fn lock(&mut self) -> Result<(), FileBufferError> {
+        PlatformLock::lock(&self.file, true)?;
+        if let Err(e) = self.remap() {
+            PlatformLock::unlock(&self.file)?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn remap(&mut self) -> Result<(), FileBufferError> {
+        // the header is in the first page, mapped whatever the length of the file
+        let capacity = self.field(CAPACITY_OFFSET).load(Ordering::Acquire);
+        let file_len = self.file.metadata()?.len();
+        if capacity == self.buffer.capacity && file_len == self.map.len() as u64 {
+            return Ok(());
+        }
+
+        // the header of the resized buffer is validated like the one of a new handle
+        self.buffer.capacity = capacity;
+        self.file.rewind()?;
+        self.buffer.read_head(&mut self.file)?;
+        self.map = unsafe { MmapMut::map_mut(&self.file)? };
+        Ok(())
+    }
+
+    fn field(&self, offset: usize) -> &AtomicU32 {
+        // the mapping is page aligned and the header fields are 4 bytes aligned
+        unsafe { &*(self.map.as_ptr().add(offset) as *const AtomicU32) }
+    }
+
+    fn counter(&self, offset: usize) -> &AtomicU64 {
+        // the statistics are 8 bytes aligned
+        unsafe { &*(self.map.as_ptr().add(offset) as *const AtomicU64) }
+    }
+
+    fn slot(&mut self, index: u32) -> &mut [u8] {
+        let size = FileBuffer::<T>::slot_size();
+        let start = HEAD_SIZE + index as usize * size;
+        &mut self.map[start..start + size]
+    }
+
+    /// Number of records not read yet, read without taking the lock.
+    pub fn len(&self) -> usize {
+        self.field(LEN_OFFSET).load(Ordering::Acquire) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append `data`, the whole write is done under the lock, it is a copy in memory.
+    /// It is dropped if the buffer is full and the policy is `Reject`, or if the oldest slot
+    /// is still being written by a `FileBuffer` producer.
+    pub fn write_data(&mut self, data: T) -> Result<(), FileBufferError> {
+        self.lock()?;
+
+        let capacity = self.buffer.capacity;
+        let len = self.field(LEN_OFFSET).load(Ordering::Acquire);
+        let index = self.field(INDEX_OFFSET).load(Ordering::Acquire);
+        let position = (index + len) % capacity;
+        let now = self.buffer.clock.unix_millis();
+
+        // if buffer is full don't write anything, unless the oldest record can be replaced:
+        // it can't while another producer is still writing it.
+        let full = len == capacity;
+        let written = !full
+            || (self.buffer.policy == FullPolicy::OverwriteOldest
+                && !FileBuffer::being_written(&FileBuffer::<T>::decode_slot(self.slot(position)), now));
+        if written {
+            let body = FileBuffer::<T>::slot_body(&data);
+            let slot = self.slot(position);
+            slot[..4].copy_from_slice(&UNCOMMITTED.to_le_bytes());
+            slot[4..].copy_from_slice(&body);
+            slot[..4].copy_from_slice(&COMMITTED.to_le_bytes());
+
+            if full {
+                self.field(INDEX_OFFSET).store((index + 1) % capacity, Ordering::Release);
+                self.counter(DROPPED_OFFSET).fetch_add(1, Ordering::Relaxed);
+            } else {
+                self.field(LEN_OFFSET).store(len + 1, Ordering::Release);
+            }
+            self.counter(TOTAL_WRITTEN_OFFSET).fetch_add(1, Ordering::Relaxed);
+            self.counter(LAST_WRITE_OFFSET).store(now, Ordering::Relaxed);
+        } else {
+            self.counter(DROPPED_OFFSET).fetch_add(1, Ordering::Relaxed);
+        }
+
+        PlatformLock::unlock(&self.file)?;
+        if let (true, Some(archive)) = (written, &self.buffer.archive) {
+            archive.append(slice::from_ref(&data), now)?;
+        }
+        Ok(())
+    }
+
+    /// Remove and return every record, from the oldest.
+    pub fn read_data(&mut self) -> Result<Vec<T>, FileBufferError> {
+        self.read_up_to(usize::MAX)
+    }
+
+    /// Remove and return at most `n` records, from the oldest.
+    pub fn read_up_to(&mut self, n: usize) -> Result<Vec<T>, FileBufferError> {
+        self.lock()?;
+
+        let capacity = self.buffer.capacity;
+        let len = self.field(LEN_OFFSET).load(Ordering::Acquire);
+        let index = self.field(INDEX_OFFSET).load(Ordering::Acquire);
+        let mut count = len.min(n.try_into().unwrap_or(u32::MAX));
+
+        let now = self.buffer.clock.unix_millis();
+        let mut data = Vec::new();
+        for i in 0..count {
+            let slot = (index + i) % capacity;
+            let state = FileBuffer::<T>::decode_slot(self.slot(slot));
+            if !FileBuffer::<T>::collect_slot(state, slot, &mut data, now) {
+                count = i;
+                break;
+            }
+        }
+
+        self.field(INDEX_OFFSET).store((index + count) % capacity, Ordering::Release);
+        self.field(LEN_OFFSET).store(len - count, Ordering::Release);
+        self.counter(LAST_READ_OFFSET).store(now, Ordering::Relaxed);
+
+        PlatformLock::unlock(&self.file)?;
+        Ok(data)
+    }
+}
@@ -0,0 +1,68 @@
+use std::io;
+use std::path::Path;
+use std::time::Instant;
+
+/// Interval between two checks of the buffer when the file changes cannot be watched.
+#[cfg(not(target_os = "linux"))]
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Changes of a file, used to wake up as soon as a producer writes to the buffer.
+#[cfg(target_os = "linux")]
+pub struct FileWatch {
+    inotify: inotify::Inotify,
+}
+
+#[cfg(target_os = "linux")]
+impl FileWatch {
+    pub fn new(path: &Path) -> io::Result<Self> {
+        use inotify::{Inotify, WatchMask};
+
+        let inotify = Inotify::init()?;
+        inotify.watches().add(path, WatchMask::MODIFY)?;
+        Ok(Self { inotify })
+    }
+
+    /// Wait for the file to be modified until `deadline`, `false` if it was not.
+    pub fn wait(&mut self, deadline: Instant) -> io::Result<bool> {
+        use std::os::fd::AsRawFd;
+
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let mut fds = libc::pollfd {
+            fd: self.inotify.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = timeout.as_millis().try_into().unwrap_or(i32::MAX);
+        let ready = unsafe { libc::poll(&mut fds, 1, timeout) };
+        if ready < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // the events themselves do not matter, drain them so that the next poll waits again
+        let mut buffer = [0u8; 1024];
+        while self
+            .inotify
+            .read_events(&mut buffer)
+            .is_ok_and(|mut events| events.next().is_some())
+        {}
+
+        Ok(ready > 0)
+    }
+}
+
+/// Fallback for the platforms without inotify, the buffer is checked periodically.
+#[cfg(not(target_os = "linux"))]
+pub struct FileWatch;
+
+#[cfg(not(target_os = "linux"))]
+impl FileWatch {
+    pub fn new(_path: &Path) -> io::Result<Self> {
+        Ok(Self)
+    }
+
+    pub fn wait(&mut self, deadline: Instant) -> io::Result<bool> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        std::thread::sleep(timeout.min(POLL_INTERVAL));
+        Ok(Instant::now() < deadline)
+    }
+}
@@ -0,0 +1,48 @@
+//! Expressions evaluated per second generating the combinations of operations for every
+//! permutation of the numbers, as the search used to, and generating them once.
+//!
+//! Run with `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use itertools::Itertools;
+use lab3_1::{calculate, operation_combinations, permutations_with_replacement, OPERATIONS};
+
+const NUMBERS: [i32; 7] = [1, 2, 3, 4, 5, 6, 7];
+
+fn report(name: &str, expressions: usize, elapsed: Duration) {
+    println!(
+        "{:12} {:>9} expressions in {:>9.2?}, {:>11.0} expressions/s",
+        name,
+        expressions,
+        elapsed,
+        expressions as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    let permutations = NUMBERS.into_iter().permutations(NUMBERS.len()).collect::<Vec<_>>();
+    let k = NUMBERS.len() - 1;
+
+    let start = Instant::now();
+    let mut expressions = 0;
+    for numbers in &permutations {
+        for ops in permutations_with_replacement(&OPERATIONS, k) {
+            let ops = ops.into_iter().copied().collect::<Vec<_>>();
+            calculate(numbers, &ops);
+            expressions += 1;
+        }
+    }
+    report("per numbers", expressions, start.elapsed());
+
+    let start = Instant::now();
+    let mut expressions = 0;
+    let combinations = operation_combinations(&OPERATIONS, k);
+    for numbers in &permutations {
+        for ops in &combinations {
+            calculate(numbers, ops);
+            expressions += 1;
+        }
+    }
+    report("hoisted", expressions, start.elapsed());
+}
@@ -0,0 +1,213 @@
+use std::{fmt, time::{Duration, Instant}};
+
+use lab3_1::Solver;
+
+use crate::output::Format;
+
+/// How the permutations of the numbers are split between the jobs of the thread pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// One block of permutations per thread.
+    Blocks,
+    /// Jobs of the given number of permutations, taken by the first idle thread.
+    Chunks(usize),
+}
+
+impl Strategy {
+    fn name(self) -> &'static str {
+        match self {
+            Strategy::Blocks => "blocks",
+            Strategy::Chunks(_) => "chunks",
+        }
+    }
+
+    fn chunk_size(self) -> Option<usize> {
+        match self {
+            Strategy::Blocks => None,
+            Strategy::Chunks(chunk_size) => Some(chunk_size),
+        }
+    }
+
+    fn apply(self, solver: Solver) -> Solver {
+        match self {
+            Strategy::Blocks => solver,
+            Strategy::Chunks(chunk_size) => solver.chunk_size(chunk_size),
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strategy::Blocks => write!(f, "blocks"),
+            Strategy::Chunks(chunk_size) => write!(f, "chunks of {}", chunk_size),
+        }
+    }
+}
+
+/// Times of the repeated searches with a strategy and a number of threads.
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    pub strategy: Strategy,
+    pub threads: usize,
+    pub times: Vec<Duration>,
+    /// Solutions found by the last search.
+    pub solutions: usize,
+}
+
+impl Measurement {
+    /// Time every search of `solver` after `warmup` untimed ones.
+    pub fn run(
+        solver: &Solver,
+        strategy: Strategy,
+        threads: usize,
+        warmup: usize,
+        repetitions: usize,
+    ) -> Self {
+        let solver = strategy.apply(solver.clone().threads(threads));
+        for _ in 0..warmup {
+            solver.solve();
+        }
+
+        let mut solutions = 0;
+        let times = (0..repetitions)
+            .map(|_| {
+                let start = Instant::now();
+                solutions = solver.solve().len();
+                start.elapsed()
+            })
+            .collect();
+
+        Self { strategy, threads, times, solutions }
+    }
+
+    pub fn median(&self) -> Duration {
+        let mut times = self.times.clone();
+        times.sort();
+        let middle = times.len() / 2;
+        if times.len().is_multiple_of(2) {
+            (times[middle - 1] + times[middle]) / 2
+        } else {
+            times[middle]
+        }
+    }
+
+    pub fn mean(&self) -> Duration {
+        self.times.iter().sum::<Duration>() / self.times.len() as u32
+    }
+
+    /// Sample standard deviation of the times, zero with a single repetition.
+    pub fn stddev(&self) -> Duration {
+        if self.times.len() < 2 {
+            return Duration::ZERO;
+        }
+        let mean = self.mean().as_secs_f64();
+        let variance = self
+            .times
+            .iter()
+            .map(|time| (time.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / (self.times.len() - 1) as f64;
+        Duration::from_secs_f64(variance.sqrt())
+    }
+}
+
+/// Line printed before the measurements.
+pub fn header(format: Format) -> Option<&'static str> {
+    match format {
+        Format::Csv => Some("strategy,chunk_size,threads,repetitions,median_ms,mean_ms,stddev_ms,solutions"),
+        Format::Text | Format::Json => None,
+    }
+}
+
+pub fn format(format: Format, measurement: &Measurement) -> String {
+    let ms = |time: Duration| time.as_secs_f64() * 1000.0;
+    let strategy = measurement.strategy;
+
+    match format {
+        Format::Text => format!(
+            "{:14} {:>3} threads: median {:>10.2?}, stddev {:>10.2?}, {} solutions",
+            strategy.to_string(),
+            measurement.threads,
+            measurement.median(),
+            measurement.stddev(),
+            measurement.solutions
+        ),
+        Format::Json => format!(
+            "{{\"strategy\":\"{}\",\"chunk_size\":{},\"threads\":{},\"repetitions\":{},\"median_ms\":{:.3},\"mean_ms\":{:.3},\"stddev_ms\":{:.3},\"solutions\":{}}}",
+            strategy.name(),
+            strategy.chunk_size().map_or("null".to_string(), |size| size.to_string()),
+            measurement.threads,
+            measurement.times.len(),
+            ms(measurement.median()),
+            ms(measurement.mean()),
+            ms(measurement.stddev()),
+            measurement.solutions
+        ),
+        Format::Csv => format!(
+            "{},{},{},{},{:.3},{:.3},{:.3},{}",
+            strategy.name(),
+            strategy.chunk_size().map_or(String::new(), |size| size.to_string()),
+            measurement.threads,
+            measurement.times.len(),
+            ms(measurement.median()),
+            ms(measurement.mean()),
+            ms(measurement.stddev()),
+            measurement.solutions
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use lab3_1::Solver;
+
+    use crate::{
+        bench::{format, Measurement, Strategy},
+        output::Format,
+    };
+
+    fn measurement(times: &[u64]) -> Measurement {
+        Measurement {
+            strategy: Strategy::Chunks(4),
+            threads: 2,
+            times: times.iter().map(|&ms| Duration::from_millis(ms)).collect(),
+            solutions: 7,
+        }
+    }
+
+    #[test]
+    fn statistics_test() {
+        let odd = measurement(&[30, 10, 20]);
+        assert_eq!(odd.median(), Duration::from_millis(20));
+        assert_eq!(odd.mean(), Duration::from_millis(20));
+        assert_eq!(odd.stddev(), Duration::from_millis(10));
+
+        let even = measurement(&[40, 10, 20, 30]);
+        assert_eq!(even.median(), Duration::from_millis(25));
+
+        assert_eq!(measurement(&[10]).stddev(), Duration::ZERO);
+    }
+
+    #[test]
+    fn format_test() {
+        let measurement = measurement(&[30, 10, 20]);
+
+        assert_eq!(
+            format(Format::Json, &measurement),
+            r#"{"strategy":"chunks","chunk_size":4,"threads":2,"repetitions":3,"median_ms":20.000,"mean_ms":20.000,"stddev_ms":10.000,"solutions":7}"#
+        );
+        assert_eq!(format(Format::Csv, &measurement), "chunks,4,2,3,20.000,20.000,10.000,7");
+    }
+
+    #[test]
+    fn run_test() {
+        let solver = Solver::new(vec![2, 3, 5]);
+        let measurement = Measurement::run(&solver, Strategy::Blocks, 2, 1, 3);
+
+        assert_eq!(measurement.times.len(), 3);
+        assert_eq!(measurement.solutions, solver.solve().len());
+    }
+}
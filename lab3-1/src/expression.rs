@@ -0,0 +1,529 @@
+use std::{fmt, iter::Peekable, str::Chars, str::FromStr};
+
+use num_rational::Rational64;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub};
+
+/// Values are exact fractions, `5 / 2 * 4` is 10.
+pub type Value = Rational64;
+
+/// Value of a number of the game.
+pub fn value(num: i32) -> Value {
+    Value::from_integer(num.into())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Operation {
+    Sum,
+    Sub,
+    Div,
+    Mul,
+    /// Power with an integer exponent, `2 ^ -1` is 1/2.
+    Pow,
+    /// Remainder of the division of two integers, with the sign of `lhs`.
+    Mod,
+    /// Digits of two non-negative integers one after the other, `2 || 4` is 24.
+    Concat,
+}
+
+/// Operations tried between every pair of numbers by default.
+pub const OPERATIONS: [Operation; 4] = [
+    Operation::Sum,
+    Operation::Sub,
+    Operation::Div,
+    Operation::Mul,
+];
+
+impl Operation {
+    /// Result of `lhs op rhs`, `None` on a division by zero, an overflow
+    /// or operands the operation is not defined for, like a fractional exponent.
+    pub fn apply(self, lhs: Value, rhs: Value) -> Option<Value> {
+        match self {
+            Operation::Div => lhs.checked_div(&rhs),
+            Operation::Mul => lhs.checked_mul(&rhs),
+            Operation::Sub => lhs.checked_sub(&rhs),
+            Operation::Sum => lhs.checked_add(&rhs),
+            Operation::Pow => checked_pow(lhs, i32::try_from(integer(rhs)?).ok()?),
+            Operation::Mod => integer(lhs)?.checked_rem(integer(rhs)?).map(Value::from_integer),
+            Operation::Concat => {
+                let (lhs, rhs) = (integer(lhs)?, integer(rhs)?);
+                if lhs < 0 || rhs < 0 {
+                    return None;
+                }
+                let digits = rhs.checked_ilog10().map_or(1, |log| log + 1);
+                lhs.checked_mul(10i64.checked_pow(digits)?)?
+                    .checked_add(rhs)
+                    .map(Value::from_integer)
+            }
+        }
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            Operation::Sum | Operation::Sub => 1,
+            Operation::Mul | Operation::Div | Operation::Mod => 2,
+            Operation::Pow => 3,
+            Operation::Concat => 4,
+        }
+    }
+
+    /// The operation and its inverse, `+` and `-` or `*` and `/`,
+    /// `None` for the operations that can't be reordered.
+    fn group(self) -> Option<(Operation, Operation)> {
+        match self {
+            Operation::Sum | Operation::Sub => Some((Operation::Sum, Operation::Sub)),
+            Operation::Mul | Operation::Div => Some((Operation::Mul, Operation::Div)),
+            Operation::Pow | Operation::Mod | Operation::Concat => None,
+        }
+    }
+
+    fn is_inverse(self) -> bool {
+        matches!(self, Operation::Sub | Operation::Div)
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Operation::Sum => "+",
+            Operation::Sub => "-",
+            Operation::Div => "/",
+            Operation::Mul => "*",
+            Operation::Pow => "^",
+            Operation::Mod => "%",
+            Operation::Concat => "||",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+fn integer(value: Value) -> Option<i64> {
+    value.is_integer().then(|| value.to_integer())
+}
+
+/// `base` multiplied by itself `exp` times, by squaring so that big exponents
+/// of 0, 1 and -1 don't take long.
+fn checked_pow(base: Value, exp: i32) -> Option<Value> {
+    let mut base = if exp < 0 { value(1).checked_div(&base)? } else { base };
+    let mut exp = exp.unsigned_abs();
+    let mut result = value(1);
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.checked_mul(&base)?;
+        }
+        exp >>= 1;
+        // the last square is not needed and could overflow
+        if exp > 0 {
+            base = base.checked_mul(&base)?;
+        }
+    }
+    Some(result)
+}
+
+/// Operation of an expression evaluated, with its operands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub lhs: Value,
+    pub op: Operation,
+    pub rhs: Value,
+    pub result: Value,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} = {}", self.lhs, self.op, self.rhs, self.result)
+    }
+}
+
+/// Expression tree, the numbers are the leaves.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Expression {
+    Number(i32),
+    Binary(Box<Expression>, Operation, Box<Expression>),
+}
+
+impl Expression {
+    pub fn binary(lhs: Expression, op: Operation, rhs: Expression) -> Self {
+        Expression::Binary(Box::new(lhs), op, Box::new(rhs))
+    }
+
+    /// `nums` with `ops` between them, evaluated from left to right.
+    pub fn chain(nums: &[i32], ops: &[Operation]) -> Self {
+        let mut nums = nums.iter();
+        let first = Expression::Number(*nums.next().unwrap());
+
+        nums.zip(ops)
+            .fold(first, |lhs, (num, op)| Expression::binary(lhs, *op, Expression::Number(*num)))
+    }
+
+    /// `None` if an operation can't be applied, like a division by zero.
+    pub fn value(&self) -> Option<Value> {
+        match self {
+            Expression::Number(num) => Some(value(*num)),
+            Expression::Binary(lhs, op, rhs) => op.apply(lhs.value()?, rhs.value()?),
+        }
+    }
+
+    /// The operations in the order they are evaluated, `None` if one can't be applied.
+    pub fn steps(&self) -> Option<Vec<Step>> {
+        let mut steps = Vec::new();
+        self.evaluate(&mut steps)?;
+        Some(steps)
+    }
+
+    fn evaluate(&self, steps: &mut Vec<Step>) -> Option<Value> {
+        let (lhs, op, rhs) = match self {
+            Expression::Number(num) => return Some(value(*num)),
+            Expression::Binary(lhs, op, rhs) => (lhs.evaluate(steps)?, *op, rhs.evaluate(steps)?),
+        };
+
+        let result = op.apply(lhs, rhs)?;
+        steps.push(Step { lhs, op, rhs, result });
+        Some(result)
+    }
+
+    /// Equivalent expression with the operands of every chain of sums and subtractions,
+    /// or of products and divisions, sorted: `3 - 1 + 2` and `2 + (3 - 1)` are both `2 + 3 - 1`.
+    /// The operands of the other operations keep their order.
+    pub fn canonical(&self) -> Expression {
+        let (direct, inverse) = match self {
+            Expression::Number(_) => return self.clone(),
+            Expression::Binary(lhs, op, rhs) => match op.group() {
+                Some(group) => group,
+                None => return Expression::binary(lhs.canonical(), *op, rhs.canonical()),
+            },
+        };
+
+        let (mut terms, mut inverse_terms) = (Vec::new(), Vec::new());
+        self.collect_terms(direct, false, &mut terms, &mut inverse_terms);
+        terms.sort();
+        inverse_terms.sort();
+
+        // the leftmost operand is never inverted
+        let mut terms = terms.into_iter();
+        let first = terms.next().unwrap();
+        let expression = terms.fold(first, |lhs, rhs| Expression::binary(lhs, direct, rhs));
+        inverse_terms
+            .into_iter()
+            .fold(expression, |lhs, rhs| Expression::binary(lhs, inverse, rhs))
+    }
+
+    /// Canonical operands of the chain of operations of the same group as `direct`,
+    /// split by whether they are applied with the inverse operation.
+    fn collect_terms(
+        &self,
+        direct: Operation,
+        inverted: bool,
+        terms: &mut Vec<Expression>,
+        inverse_terms: &mut Vec<Expression>,
+    ) {
+        match self {
+            Expression::Binary(lhs, op, rhs) if op.group().is_some_and(|(op, _)| op == direct) => {
+                lhs.collect_terms(direct, inverted, terms, inverse_terms);
+                rhs.collect_terms(direct, inverted ^ op.is_inverse(), terms, inverse_terms);
+            }
+            _ if inverted => inverse_terms.push(self.canonical()),
+            _ => terms.push(self.canonical()),
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expression::Number(_) => u8::MAX,
+            Expression::Binary(_, op, _) => op.precedence(),
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    /// Only the parentheses needed by the usual precedence are written,
+    /// an operation on the right is always grouped, as a power on the left.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (lhs, op, rhs) = match self {
+            Expression::Number(num) => return write!(f, "{}", num),
+            Expression::Binary(lhs, op, rhs) => (lhs, op, rhs),
+        };
+
+        // `2 ^ 3 ^ 2` would be read as `2 ^ (3 ^ 2)`
+        let right_associative = *op == Operation::Pow;
+        if lhs.precedence() < op.precedence()
+            || (right_associative && lhs.precedence() == op.precedence())
+        {
+            write!(f, "({})", lhs)?;
+        } else {
+            write!(f, "{}", lhs)?;
+        }
+        write!(f, " {} ", op)?;
+        if rhs.precedence() <= op.precedence() {
+            write!(f, "({})", rhs)
+        } else {
+            write!(f, "{}", rhs)
+        }
+    }
+}
+
+impl FromStr for Expression {
+    type Err = String;
+
+    /// Parse an expression written as it is displayed, a power groups from the right
+    /// and the other operations from the left.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { tokens: tokenize(s)?, pos: 0 };
+        let expression = parser.expression(0)?;
+        match parser.next() {
+            None => Ok(expression),
+            Some(token) => Err(format!("unexpected `{}`", token)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Number(i32),
+    Op(Operation),
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(num) => write!(f, "{}", num),
+            Token::Op(op) => write!(f, "{}", op),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '0'..='9' => Token::Number(number(c, &mut chars)?),
+            // a minus where an operand is expected is the sign of a number
+            '-' if matches!(tokens.last(), None | Some(Token::Op(_) | Token::Open))
+                && chars.peek().is_some_and(char::is_ascii_digit) =>
+            {
+                let digit = chars.next().unwrap();
+                Token::Number(-number(digit, &mut chars)?)
+            }
+            '+' => Token::Op(Operation::Sum),
+            '-' => Token::Op(Operation::Sub),
+            '*' => Token::Op(Operation::Mul),
+            '/' => Token::Op(Operation::Div),
+            '^' => Token::Op(Operation::Pow),
+            '%' => Token::Op(Operation::Mod),
+            '|' if chars.next_if_eq(&'|').is_some() => Token::Op(Operation::Concat),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c => return Err(format!("unexpected `{}`", c)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// The number starting with the digit `first`.
+fn number(first: char, chars: &mut Peekable<Chars>) -> Result<i32, String> {
+    let mut digits = first.to_string();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        digits.push(digit);
+    }
+    digits.parse().map_err(|_| format!("{} is too big", digits))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token
+    }
+
+    /// Operations with a precedence higher than `min`, with their operands.
+    fn expression(&mut self, min: u8) -> Result<Expression, String> {
+        let mut lhs = self.operand()?;
+
+        while let Some(&Token::Op(op)) = self.tokens.get(self.pos) {
+            if op.precedence() <= min {
+                break;
+            }
+            self.pos += 1;
+
+            // the operand on the right of a power can be another power
+            let min = if op == Operation::Pow { op.precedence() - 1 } else { op.precedence() };
+            lhs = Expression::binary(lhs, op, self.expression(min)?);
+        }
+        Ok(lhs)
+    }
+
+    fn operand(&mut self) -> Result<Expression, String> {
+        match self.next() {
+            Some(Token::Number(num)) => Ok(Expression::Number(num)),
+            Some(Token::Open) => {
+                let expression = self.expression(0)?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expression),
+                    _ => Err("missing `)`".to_string()),
+                }
+            }
+            Some(token) => Err(format!("unexpected `{}`", token)),
+            None => Err("unexpected end of the expression".to_string()),
+        }
+    }
+}
+
+/// Every expression tree with `nums` as leaves in this order and `operations` between them,
+/// with its value. The expressions with an operation that can't be applied are left out.
+pub fn expression_trees(nums: &[i32], operations: &[Operation]) -> Vec<(Expression, Value)> {
+    if let [num] = nums {
+        return vec![(Expression::Number(*num), value(*num))];
+    }
+
+    let mut trees = Vec::new();
+    for split in 1..nums.len() {
+        let lhs = expression_trees(&nums[..split], operations);
+        let rhs = expression_trees(&nums[split..], operations);
+
+        for (lhs, lhs_value) in &lhs {
+            for (rhs, rhs_value) in &rhs {
+                for &op in operations {
+                    if let Some(value) = op.apply(*lhs_value, *rhs_value) {
+                        trees.push((Expression::binary(lhs.clone(), op, rhs.clone()), value));
+                    }
+                }
+            }
+        }
+    }
+    trees
+}
+
+#[cfg(test)]
+mod test {
+    use crate::expression::{expression_trees, value, Expression, Operation, Value, OPERATIONS};
+
+    const ALL_OPERATIONS: [Operation; 7] = [
+        Operation::Sum,
+        Operation::Sub,
+        Operation::Div,
+        Operation::Mul,
+        Operation::Pow,
+        Operation::Mod,
+        Operation::Concat,
+    ];
+
+    #[test]
+    fn display_test() {
+        let sum = Expression::chain(&[3, 2], &[Operation::Sum]);
+        let expr = Expression::binary(sum.clone(), Operation::Mul, Expression::Number(2));
+        assert_eq!(expr.to_string(), "(3 + 2) * 2");
+        assert_eq!(expr.value(), Some(value(10)));
+
+        let expr = Expression::binary(Expression::Number(8), Operation::Sub, sum);
+        assert_eq!(expr.to_string(), "8 - (3 + 2)");
+        assert_eq!(Expression::chain(&[2, 3, 4], &[Operation::Mul, Operation::Sum]).to_string(), "2 * 3 + 4");
+
+        let pow = Expression::chain(&[2, 3, 2], &[Operation::Pow, Operation::Pow]);
+        assert_eq!(pow.to_string(), "(2 ^ 3) ^ 2");
+        assert_eq!(pow.value(), Some(value(64)));
+        assert_eq!(Expression::chain(&[1, 2, 3], &[Operation::Sum, Operation::Concat]).to_string(), "(1 + 2) || 3");
+    }
+
+    #[test]
+    fn apply_test() {
+        let apply = |lhs: i32, op: Operation, rhs: i32| op.apply(value(lhs), value(rhs));
+
+        assert_eq!(apply(2, Operation::Pow, 10), Some(value(1024)));
+        assert_eq!(apply(2, Operation::Pow, -2), Some(Value::new(1, 4)));
+        assert_eq!(apply(1, Operation::Pow, i32::MAX), Some(value(1)));
+        assert_eq!(apply(0, Operation::Pow, -1), None);
+        assert_eq!(apply(10, Operation::Pow, 100), None);
+        assert_eq!(Operation::Pow.apply(value(4), Value::new(1, 2)), None);
+
+        assert_eq!(apply(7, Operation::Mod, 3), Some(value(1)));
+        assert_eq!(apply(-7, Operation::Mod, 3), Some(value(-1)));
+        assert_eq!(apply(7, Operation::Mod, 0), None);
+        assert_eq!(Operation::Mod.apply(Value::new(7, 2), value(2)), None);
+
+        assert_eq!(apply(2, Operation::Concat, 4), Some(value(24)));
+        assert_eq!(apply(12, Operation::Concat, 0), Some(value(120)));
+        assert_eq!(apply(12, Operation::Concat, 305), Some(value(12305)));
+        assert_eq!(apply(-1, Operation::Concat, 2), None);
+        assert_eq!(Operation::Concat.apply(Value::from_integer(i64::MAX), value(1)), None);
+    }
+
+    #[test]
+    fn canonical_test() {
+        let canonical = |nums: &[i32], ops: &[Operation]| Expression::chain(nums, ops).canonical().to_string();
+
+        assert_eq!(canonical(&[3, 2, 5], &[Operation::Sum, Operation::Sum]), "2 + 3 + 5");
+        assert_eq!(canonical(&[3, 1, 2], &[Operation::Sub, Operation::Sum]), "2 + 3 - 1");
+        assert_eq!(canonical(&[3, 4, 2], &[Operation::Div, Operation::Mul]), "2 * 3 / 4");
+        // the operands of a different group are sorted on their own
+        assert_eq!(canonical(&[3, 2, 5], &[Operation::Sum, Operation::Mul]), "5 * (2 + 3)");
+
+        let nested = Expression::binary(
+            Expression::Number(2),
+            Operation::Sub,
+            Expression::chain(&[3, 1], &[Operation::Sub]),
+        );
+        assert_eq!(nested.canonical().to_string(), "1 + 2 - 3");
+        assert_eq!(nested.canonical().value(), nested.value());
+
+        // the operands of a power are not reordered
+        assert_eq!(canonical(&[3, 2, 1], &[Operation::Pow, Operation::Sum]), "1 + 3 ^ 2");
+        assert_eq!(canonical(&[3, 2], &[Operation::Concat]), "3 || 2");
+    }
+
+    #[test]
+    fn steps_test() {
+        let expr = Expression::binary(
+            Expression::Number(5),
+            Operation::Mul,
+            Expression::chain(&[5, 1, 5], &[Operation::Sub, Operation::Div]),
+        );
+        let steps = expr.steps().unwrap().iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(steps, ["5 - 1 = 4", "4 / 5 = 4/5", "5 * 4/5 = 4"]);
+        assert_eq!(Expression::chain(&[1, 0], &[Operation::Div]).steps(), None);
+    }
+
+    #[test]
+    fn parse_test() {
+        let expr = "(3 + 2) * 2".parse::<Expression>().unwrap();
+        assert_eq!(expr, Expression::binary(Expression::chain(&[3, 2], &[Operation::Sum]), Operation::Mul, Expression::Number(2)));
+        assert_eq!("2^3^2".parse::<Expression>().unwrap().value(), Some(value(512)));
+        assert_eq!("10 - -3 - 2".parse::<Expression>().unwrap().value(), Some(value(11)));
+        assert_eq!("1 || 2 * 3".parse::<Expression>().unwrap().value(), Some(value(36)));
+
+        assert_eq!("2 +".parse::<Expression>(), Err("unexpected end of the expression".to_string()));
+        assert_eq!("(2 + 3".parse::<Expression>(), Err("missing `)`".to_string()));
+        assert_eq!("2 3".parse::<Expression>(), Err("unexpected `3`".to_string()));
+        assert_eq!("2 & 3".parse::<Expression>(), Err("unexpected `&`".to_string()));
+        assert!("99999999999".parse::<Expression>().is_err());
+
+        // what is displayed is parsed back to the same tree
+        for (expr, _) in expression_trees(&[1, -2, 3, 4], &ALL_OPERATIONS) {
+            assert_eq!(expr.to_string().parse(), Ok(expr));
+        }
+    }
+
+    #[test]
+    fn trees_test() {
+        // 2 shapes with 4 operations each in both positions
+        assert_eq!(expression_trees(&[1, 2, 3], &OPERATIONS).len(), 2 * 4 * 4);
+        // the division by zero is left out
+        assert_eq!(expression_trees(&[1, 0], &OPERATIONS).len(), 3);
+        assert_eq!(expression_trees(&[1, 0], &[Operation::Mod, Operation::Concat]).len(), 1);
+        assert!(expression_trees(&[1, 2, 3, 4], &OPERATIONS)
+            .iter()
+            .all(|(expr, value)| expr.value() == Some(*value)));
+    }
+}
@@ -0,0 +1,733 @@
+use std::{
+    cmp::Ordering as Order,
+    collections::BTreeSet,
+    fmt, mem,
+    ops::{ControlFlow, RangeInclusive},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use itertools::Itertools;
+use lab5_1::{current_worker, ThreadPool};
+
+mod expression;
+mod memo;
+mod progress;
+
+pub use expression::{expression_trees, value, Expression, Operation, Step, Value, OPERATIONS};
+pub use memo::CacheStats;
+pub use progress::Progress;
+
+use memo::Memo;
+
+/// Solution sent by `Solver::solve_streaming` as soon as it is found.
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub expression: Expression,
+    /// Name of the worker of the thread pool that found it.
+    pub thread: Option<String>,
+    /// Time from the start of the search.
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+/// Expressions whose value is the nearest to the target, found by `Solver::closest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Closest {
+    /// Difference between their value and the target, zero if they are solutions.
+    pub distance: Value,
+    /// Sorted, every one once.
+    pub expressions: Vec<Expression>,
+}
+
+/// Search of the expressions combining all the `numbers`, or some of them with `subsets`,
+/// that evaluate to the target.
+#[derive(Debug, Clone)]
+pub struct Solver {
+    numbers: Vec<i32>,
+    target: i32,
+    threads: usize,
+    /// Permutations of the numbers searched by every job of the pool.
+    chunk_size: Option<usize>,
+    /// Whether every expression tree is tried, not only the ones evaluated from left to right.
+    parentheses: bool,
+    /// Whether the solutions equivalent by commutativity are reported once.
+    canonical: bool,
+    /// Solutions after which the search is stopped.
+    limit: Option<usize>,
+    /// Operations tried between every pair of numbers.
+    operations: Vec<Operation>,
+    /// Whether the expressions may leave some of the numbers out.
+    subsets: bool,
+    /// Whether the values of every sub-multiset of the numbers are computed once.
+    memoize: bool,
+    progress: Option<Arc<Progress>>,
+    cache_stats: Option<Arc<CacheStats>>,
+}
+
+/// Job of the pool searching a chunk of permutations.
+type Job = Box<dyn FnOnce() + Send>;
+
+impl Solver {
+    /// Solver of the game with `numbers`, looking for 10 on a single thread.
+    pub fn new(numbers: Vec<i32>) -> Self {
+        Self {
+            numbers,
+            target: 10,
+            threads: 1,
+            chunk_size: None,
+            parentheses: false,
+            canonical: true,
+            limit: None,
+            operations: OPERATIONS.to_vec(),
+            subsets: false,
+            memoize: false,
+            progress: None,
+            cache_stats: None,
+        }
+    }
+
+    pub fn target(mut self, target: i32) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "at least one thread is needed");
+        self.threads = threads;
+        self
+    }
+
+    /// Permutations of the numbers searched by every job of the thread pool,
+    /// by default they are split in one block per thread.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "the chunks can't be empty");
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    pub fn parentheses(mut self, parentheses: bool) -> Self {
+        self.parentheses = parentheses;
+        self
+    }
+
+    /// Whether the solutions equivalent by commutativity, like `2 + 3` and `3 + 2`,
+    /// are reported once in their `Expression::canonical` form, the default.
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Stop the search once `limit` solutions are found, which ones depends on the threads.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Stop the search at the first solution.
+    pub fn first(self) -> Self {
+        self.limit(1)
+    }
+
+    /// Operations tried between every pair of numbers, `OPERATIONS` by default.
+    /// The powers, remainders and concatenations grow the search quickly.
+    pub fn operations(mut self, operations: &[Operation]) -> Self {
+        self.operations = operations.to_vec();
+        self
+    }
+
+    /// Whether the expressions may use only some of the numbers, as in Countdown,
+    /// by default they use all of them.
+    pub fn subsets(mut self, subsets: bool) -> Self {
+        self.subsets = subsets;
+        self
+    }
+
+    /// Whether every expression tree is searched through the values of every sub-multiset
+    /// of the numbers, computed once and cached, instead of tree by tree: much faster with
+    /// many numbers. The expressions are the ones of `parentheses`, without progress.
+    pub fn memoize(mut self, memoize: bool) -> Self {
+        self.memoize = memoize;
+        self
+    }
+
+    /// Count the permutations searched in `progress`, reset at the start of every search.
+    pub fn progress(mut self, progress: Arc<Progress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Count the use of the cache of `memoize` in `stats`.
+    pub fn cache_stats(mut self, stats: Arc<CacheStats>) -> Self {
+        self.cache_stats = Some(stats);
+        self
+    }
+
+    /// Every solution once, sorted.
+    pub fn solve(&self) -> Vec<Expression> {
+        let results = Arc::new(Mutex::new(BTreeSet::<Expression>::new()));
+
+        let target = value(self.target);
+        let (canonical, limit) = (self.canonical, self.limit);
+        if self.memoize {
+            let mut results = BTreeSet::new();
+            let _ = self.memo(false).expressions(target, &mut |expression| {
+                results.insert(solution(&|| expression.clone(), canonical));
+                if limit_reached(limit, results.len()) {
+                    return ControlFlow::Break(());
+                }
+                ControlFlow::Continue(())
+            });
+            return results.into_iter().collect();
+        }
+
+        self.search({
+            let results = results.clone();
+            move |value, expression| {
+                if value != target {
+                    return ControlFlow::Continue(());
+                }
+                let expression = solution(expression, canonical);
+                let mut results = results.lock().unwrap();
+                // another thread may have reached the limit before this one stopped
+                if !limit_reached(limit, results.len()) {
+                    results.insert(expression);
+                }
+                if limit_reached(limit, results.len()) {
+                    return ControlFlow::Break(());
+                }
+                ControlFlow::Continue(())
+            }
+        });
+
+        let results = mem::take(&mut *results.lock().unwrap());
+        results.into_iter().collect()
+    }
+
+    /// Every solution once, sent as soon as it is found by the search running
+    /// in the background. The channel is closed when the search is over.
+    pub fn solve_streaming(&self) -> Receiver<Solution> {
+        let (sender, receiver) = mpsc::channel();
+        let solver = self.clone();
+        let start = Instant::now();
+
+        thread::spawn(move || {
+            let target = value(solver.target);
+            let (canonical, limit) = (solver.canonical, solver.limit);
+            if solver.memoize {
+                let mut found = BTreeSet::new();
+                let _ = solver.memo(false).expressions(target, &mut |expression| {
+                    let expression = solution(&|| expression.clone(), canonical);
+                    let solution = Solution {
+                        expression: expression.clone(),
+                        thread: thread::current().name().map(String::from),
+                        elapsed: start.elapsed(),
+                    };
+                    if found.insert(expression) && sender.send(solution).is_err() {
+                        return ControlFlow::Break(());
+                    }
+                    if limit_reached(limit, found.len()) {
+                        return ControlFlow::Break(());
+                    }
+                    ControlFlow::Continue(())
+                });
+                return;
+            }
+
+            let found = Mutex::new(BTreeSet::<Expression>::new());
+
+            solver.search(move |value, expression| {
+                if value != target {
+                    return ControlFlow::Continue(());
+                }
+                let expression = solution(expression, canonical);
+                let solution = Solution {
+                    expression: expression.clone(),
+                    thread: thread::current().name().map(String::from),
+                    elapsed: start.elapsed(),
+                };
+
+                let mut found = found.lock().unwrap();
+                if limit_reached(limit, found.len()) {
+                    return ControlFlow::Break(());
+                }
+                if found.insert(expression) && sender.send(solution).is_err() {
+                    // nobody is listening anymore
+                    return ControlFlow::Break(());
+                }
+                if limit_reached(limit, found.len()) {
+                    return ControlFlow::Break(());
+                }
+                ControlFlow::Continue(())
+            });
+        });
+
+        receiver
+    }
+
+    /// The expressions nearest to the target, the solutions if there are any.
+    /// Every worker keeps its own best and they are merged at the end of the search,
+    /// `None` if no expression can be evaluated.
+    pub fn closest(&self) -> Option<Closest> {
+        let target = value(self.target);
+        let canonical = self.canonical;
+        if self.memoize {
+            let memo = self.memo(true);
+            let values = memo.values();
+            let nearest = values.iter().filter_map(|&value| distance(value, target)).min()?;
+
+            let mut expressions = BTreeSet::new();
+            for value in values {
+                if distance(value, target) == Some(nearest) {
+                    let _ = memo.expressions(value, &mut |expression| {
+                        expressions.insert(solution(&|| expression.clone(), canonical));
+                        ControlFlow::Continue(())
+                    });
+                }
+            }
+            return Some(Closest { distance: nearest, expressions: expressions.into_iter().collect() });
+        }
+
+        type Best = Option<(Value, BTreeSet<Expression>)>;
+        let bests = Arc::new((0..self.threads).map(|_| Mutex::new(Best::None)).collect::<Vec<_>>());
+        self.search({
+            let bests = bests.clone();
+            move |value, expression| {
+                let Some(distance) = distance(value, target) else {
+                    return ControlFlow::Continue(());
+                };
+                let worker = current_worker().unwrap_or_default() as usize;
+                let mut best = bests[worker % bests.len()].lock().unwrap();
+
+                match best.as_mut() {
+                    Some((nearest, _)) if distance > *nearest => {}
+                    Some((nearest, expressions)) if distance == *nearest => {
+                        expressions.insert(solution(expression, canonical));
+                    }
+                    _ => *best = Some((distance, BTreeSet::from([solution(expression, canonical)]))),
+                }
+                ControlFlow::Continue(())
+            }
+        });
+
+        let (distance, expressions) = bests
+            .iter()
+            .filter_map(|best| best.lock().unwrap().take())
+            .reduce(|lhs, mut rhs| match lhs.0.cmp(&rhs.0) {
+                Order::Less => lhs,
+                Order::Greater => rhs,
+                Order::Equal => {
+                    rhs.1.extend(lhs.1);
+                    rhs
+                }
+            })?;
+        Some(Closest { distance, expressions: expressions.into_iter().collect() })
+    }
+
+    /// The values in `targets` that have at least one solution, sorted.
+    pub fn reachable(&self, targets: RangeInclusive<i32>) -> Vec<i32> {
+        if self.memoize {
+            return self
+                .memo(true)
+                .values()
+                .into_iter()
+                .filter(|value| value.is_integer())
+                .filter_map(|value| i32::try_from(value.to_integer()).ok())
+                .filter(|target| targets.contains(target))
+                .collect();
+        }
+
+        let results = Arc::new(Mutex::new(BTreeSet::<i32>::new()));
+
+        self.search({
+            let results = results.clone();
+            move |value, _| {
+                if let Ok(target) = i32::try_from(value.to_integer()) {
+                    if value.is_integer() && targets.contains(&target) {
+                        results.lock().unwrap().insert(target);
+                    }
+                }
+                ControlFlow::Continue(())
+            }
+        });
+
+        let results = mem::take(&mut *results.lock().unwrap());
+        results.into_iter().collect()
+    }
+
+    /// Values of every sub-multiset of the numbers, with `all_values` of all of them.
+    fn memo(&self, all_values: bool) -> Memo {
+        let stats = self.cache_stats.clone().unwrap_or_default();
+        Memo::new(&self.numbers, &self.operations, self.subsets, all_values, self.threads, stats)
+    }
+
+    /// Evaluate every expression, calling `found` with its value and a function building it,
+    /// so that the expressions are only allocated when needed. The permutations of the numbers
+    /// are searched in chunks by a thread pool, every job stops as soon as `found` breaks.
+    fn search<F>(&self, found: F)
+    where
+        F: Fn(Value, &dyn Fn() -> Expression) -> ControlFlow<()> + Send + Sync + 'static,
+    {
+        let lengths = if self.subsets { 1..=self.numbers.len() } else { self.numbers.len()..=self.numbers.len() };
+        let number_permutations = Arc::new(
+            lengths
+                .flat_map(|len| self.numbers.clone().into_iter().permutations(len))
+                .collect::<Vec<_>>(),
+        );
+        let chunk_size = self
+            .chunk_size
+            .unwrap_or_else(|| number_permutations.len().div_ceil(self.threads))
+            .max(1);
+
+        // the same operations are tried between the numbers of every permutation of a length
+        let combinations = Arc::new(if self.parentheses {
+            Vec::new()
+        } else {
+            (0..self.numbers.len().max(1))
+                .map(|k| operation_combinations(&self.operations, k))
+                .collect()
+        });
+        let operations = Arc::new(self.operations.clone());
+
+        let found = Arc::new(found);
+        let stop = Arc::new(AtomicBool::new(false));
+        let pool = ThreadPool::<Job>::new(self.threads as u32);
+        if let Some(progress) = &self.progress {
+            progress.start(number_permutations.len());
+        }
+
+        for start in (0..number_permutations.len()).step_by(chunk_size) {
+            let number_permutations = number_permutations.clone();
+            let combinations = combinations.clone();
+            let operations = operations.clone();
+            let found = found.clone();
+            let stop = stop.clone();
+            let progress = self.progress.clone();
+            let parentheses = self.parentheses;
+
+            pool.execute(Box::new(move || {
+                let end = (start + chunk_size).min(number_permutations.len());
+                let chunk = &number_permutations[start..end];
+                let operations = if parentheses {
+                    Operations::Trees(&operations)
+                } else {
+                    Operations::Chains(&combinations)
+                };
+                search_chunk(chunk, operations, &stop, progress.as_deref(), &*found);
+            }))
+            .unwrap();
+        }
+
+        // the pool waits for every chunk when dropped
+        drop(pool);
+    }
+}
+
+fn solution(expression: &dyn Fn() -> Expression, canonical: bool) -> Expression {
+    if canonical {
+        expression().canonical()
+    } else {
+        expression()
+    }
+}
+
+/// `|value - target|`, `None` on an overflow.
+pub fn distance(value: Value, target: Value) -> Option<Value> {
+    let difference = Operation::Sub.apply(value, target)?;
+    if difference < Value::default() {
+        Operation::Sub.apply(Value::default(), difference)
+    } else {
+        Some(difference)
+    }
+}
+
+fn limit_reached(limit: Option<usize>, found: usize) -> bool {
+    limit.is_some_and(|limit| found >= limit)
+}
+
+/// Operations tried between the numbers of every permutation.
+#[derive(Debug, Clone, Copy)]
+enum Operations<'a> {
+    /// Every expression tree with these operations.
+    Trees(&'a [Operation]),
+    /// Each one of the combinations of as many operations as needed, from left to right,
+    /// indexed by their length.
+    Chains(&'a [Vec<Vec<Operation>>]),
+}
+
+/// Evaluate the expressions of every permutation in `chunk`, until `stop` is set.
+fn search_chunk<F>(
+    chunk: &[Vec<i32>],
+    operations: Operations,
+    stop: &AtomicBool,
+    progress: Option<&Progress>,
+    found: &F,
+) where
+    F: Fn(Value, &dyn Fn() -> Expression) -> ControlFlow<()>,
+{
+    let found = |value, expression: &dyn Fn() -> Expression| {
+        if found(value, expression).is_break() {
+            stop.store(true, Ordering::Relaxed);
+        }
+    };
+
+    let worker = current_worker().unwrap_or_default() as usize;
+
+    for numbers in chunk {
+        if let Some(progress) = progress {
+            progress.add(worker);
+        }
+        let combinations = match operations {
+            Operations::Chains(combinations) => combinations,
+            Operations::Trees(operations) => {
+                for (expression, value) in expression_trees(numbers, operations) {
+                    if stop.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    found(value, &|| expression.clone());
+                }
+                continue;
+            }
+        };
+
+        for ops in &combinations[numbers.len().saturating_sub(1)] {
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            if let Some(value) = calculate(numbers, ops) {
+                found(value, &|| Expression::chain(numbers, ops));
+            }
+        }
+    }
+}
+
+/// Every sequence of `k` elements of `items`, with repetitions.
+pub fn permutations_with_replacement<T>(
+    items: &[T],
+    k: usize,
+) -> impl Iterator<Item = Vec<&T>> {
+    std::iter::repeat(items.iter())
+        .take(k)
+        .multi_cartesian_product()
+}
+
+/// Every sequence of `k` of the `operations`, to be put between `k + 1` numbers.
+pub fn operation_combinations(operations: &[Operation], k: usize) -> Vec<Vec<Operation>> {
+    // the product of no iterators is empty, a single number needs no operation
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    permutations_with_replacement(operations, k)
+        .map(|ops| ops.into_iter().copied().collect())
+        .collect()
+}
+
+/// Value of `nums` with `ops` between them from left to right,
+/// `None` if an operation can't be applied, like a division by zero.
+pub fn calculate(nums: &[i32], ops: &[Operation]) -> Option<Value> {
+    let mut nums = nums.iter();
+    let mut partial = value(*nums.next()?);
+
+    for (num, op) in nums.zip(ops.iter()) {
+        partial = op.apply(partial, value(*num))?;
+    }
+
+    return Some(partial);
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::{calculate, operation_combinations, value, CacheStats, Operation, Progress, Solver, OPERATIONS};
+
+    #[test]
+    fn fraction_test() {
+        // 7 / 2 * 3 is 10 only truncating
+        let solutions = Solver::new(vec![7, 2, 3]).solve();
+        assert!(solutions.iter().all(|solution| solution.to_string() != "7 / 2 * 3"));
+        // 1 / 5 is only exact as a fraction
+        let solutions = Solver::new(vec![1, 5, 5, 5]).target(24).parentheses(true).solve();
+        assert!(solutions.iter().any(|solution| solution.to_string() == "5 * (5 - 1 / 5)"));
+    }
+
+    #[test]
+    fn calculate_test() {
+        assert_eq!(calculate(&[2, 3, 4], &[Operation::Sum, Operation::Mul]), Some(value(20)));
+        assert_eq!(calculate(&[2, 0], &[Operation::Div]), None);
+        // no truncation of the division
+        assert_eq!(calculate(&[5, 2, 4], &[Operation::Div, Operation::Mul]), Some(value(10)));
+        assert_eq!(calculate(&[7, 2, 2], &[Operation::Div, Operation::Mul]), Some(value(7)));
+    }
+
+    #[test]
+    fn operation_combinations_test() {
+        let combinations = operation_combinations(&OPERATIONS, 3);
+        assert_eq!(combinations.len(), 4 * 4 * 4);
+        assert!(combinations.iter().all(|ops| ops.len() == 3));
+        assert_eq!(combinations[1], [Operation::Sum, Operation::Sum, Operation::Sub]);
+        assert_eq!(operation_combinations(&[Operation::Pow, Operation::Mod], 2).len(), 2 * 2);
+        assert_eq!(operation_combinations(&OPERATIONS, 0), [Vec::<Operation>::new()]);
+    }
+
+    #[test]
+    fn solve_test() {
+        let solutions = Solver::new(vec![2, 3, 5]).target(10).canonical(false).solve();
+        let solutions = solutions.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(solutions, ["2 + 3 + 5", "2 + 5 + 3", "3 + 2 + 5", "3 + 5 + 2", "5 + 2 + 3", "5 + 3 + 2"]);
+
+        let solutions = Solver::new(vec![2, 3, 5]).target(10).solve();
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].to_string(), "2 + 3 + 5");
+
+        let chunks = Solver::new(vec![2, 3, 5, 7])
+            .threads(3)
+            .chunk_size(1)
+            .solve();
+        assert_eq!(chunks, Solver::new(vec![2, 3, 5, 7]).solve());
+        assert!(chunks.iter().all(|solution| solution.value() == Some(value(10))));
+    }
+
+    #[test]
+    fn parentheses_test() {
+        let solutions = Solver::new(vec![2, 3, 2]).parentheses(true).canonical(false).solve();
+        let solutions = solutions.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert!(solutions.contains(&"2 * (3 + 2)".to_string()));
+        assert!(!Solver::new(vec![2, 3, 2]).canonical(false).solve().iter().any(|solution| solution.to_string() == "2 * (3 + 2)"));
+        // the two 2s give the same expressions
+        let unique = solutions.iter().collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(unique.len(), solutions.len());
+    }
+
+    #[test]
+    fn streaming_test() {
+        let solver = Solver::new(vec![2, 3, 5, 7]).threads(4).parentheses(true);
+        let mut streamed = solver.solve_streaming().iter().map(|solution| solution.to_string()).collect::<Vec<_>>();
+        streamed.sort();
+
+        let mut solutions = solver.solve().iter().map(ToString::to_string).collect::<Vec<_>>();
+        solutions.sort();
+        assert_eq!(streamed, solutions);
+    }
+
+    #[test]
+    fn operations_test() {
+        let operations = [OPERATIONS.as_slice(), &[Operation::Pow, Operation::Mod, Operation::Concat]].concat();
+
+        let solutions = Solver::new(vec![2, 4]).target(24).operations(&operations).solve();
+        let solutions = solutions.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(solutions, ["2 || 4"]);
+        assert!(Solver::new(vec![2, 4]).target(24).solve().is_empty());
+
+        // the overflows are left out instead of panicking
+        let solver = Solver::new(vec![9, 99, 999, 9999]).threads(2).parentheses(true).operations(&operations);
+        for target in solver.reachable(0..=20) {
+            assert!(!solver.clone().target(target).first().solve().is_empty());
+        }
+    }
+
+    #[test]
+    fn subsets_test() {
+        assert!(Solver::new(vec![2, 8, 7]).solve().is_empty());
+        let solutions = Solver::new(vec![2, 8, 7]).subsets(true).threads(2).solve();
+        let solutions = solutions.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(solutions, ["2 + 8"]);
+
+        let solver = Solver::new(vec![2, 8, 7]).subsets(true).parentheses(true);
+        assert!(solver.solve().iter().any(|solution| solution.to_string() == "2 + 8"));
+        assert_eq!(Solver::new(vec![10, 3]).subsets(true).solve()[0].to_string(), "10");
+    }
+
+    #[test]
+    fn memoize_test() {
+        let operations = [OPERATIONS.as_slice(), &[Operation::Pow, Operation::Concat]].concat();
+        for (numbers, target) in [(vec![2, 3, 5, 7], 10), (vec![2, 2, 3, 3], 12), (vec![1, 5, 5, 5], 24)] {
+            let solver = Solver::new(numbers).target(target).threads(2).parentheses(true);
+            let memoized = solver.clone().memoize(true);
+            assert_eq!(memoized.solve(), solver.solve());
+            assert_eq!(memoized.clone().canonical(false).solve(), solver.clone().canonical(false).solve());
+            assert_eq!(memoized.reachable(-50..=50), solver.reachable(-50..=50));
+            assert_eq!(memoized.clone().target(1000).closest(), solver.clone().target(1000).closest());
+
+            let solver = solver.operations(&operations).subsets(true);
+            assert_eq!(solver.clone().memoize(true).solve(), solver.solve());
+        }
+
+        let solver = Solver::new(vec![1, 2, 3, 4, 5]).memoize(true);
+        assert_eq!(solver.clone().first().solve().len(), 1);
+        let mut streamed = solver.solve_streaming().iter().map(|solution| solution.expression).collect::<Vec<_>>();
+        streamed.sort();
+        assert_eq!(streamed, solver.solve());
+
+        let stats = Arc::new(CacheStats::default());
+        let solver = Solver::new(vec![2, 2, 3]).memoize(true).cache_stats(stats.clone());
+        solver.reachable(0..=10);
+        assert_eq!(stats.misses(), 5);
+        // the values of all the numbers are not needed for a target
+        solver.solve();
+        assert_eq!(stats.misses(), 5 + 4);
+        assert!(stats.hits() > 0);
+    }
+
+    #[test]
+    fn limit_test() {
+        let solver = Solver::new(vec![1, 2, 3, 4, 5]).threads(4).parentheses(true);
+        let first = solver.clone().first().solve();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].value(), Some(value(10)));
+
+        assert_eq!(solver.clone().limit(5).solve().len(), 5);
+        assert_eq!(solver.limit(5).solve_streaming().iter().count(), 5);
+        assert!(Solver::new(vec![1, 1]).first().solve().is_empty());
+    }
+
+    #[test]
+    fn progress_test() {
+        let progress = Arc::new(Progress::new(3));
+        Solver::new(vec![1, 2, 3, 4]).threads(3).chunk_size(5).progress(progress.clone()).solve();
+        assert_eq!(progress.total(), 24);
+        assert_eq!(progress.done(), 24);
+    }
+
+    #[test]
+    fn closest_test() {
+        let closest = Solver::new(vec![1, 1]).threads(2).closest().unwrap();
+        assert_eq!(closest.distance, value(8));
+        assert_eq!(closest.expressions.len(), 1);
+        assert_eq!(closest.expressions[0].to_string(), "1 + 1");
+
+        // 9 * 3 + 1 is 28, one more than 27
+        let solver = Solver::new(vec![1, 3, 9]).target(29).threads(3).chunk_size(1);
+        let closest = solver.closest().unwrap();
+        assert_eq!(closest.distance, value(1));
+        assert!(closest
+            .expressions
+            .iter()
+            .all(|expression| [value(28), value(30)].contains(&expression.value().unwrap())));
+        assert_eq!(closest, solver.threads(1).closest().unwrap());
+
+        let solver = Solver::new(vec![2, 3, 5]).threads(2);
+        let closest = solver.closest().unwrap();
+        assert_eq!(closest.distance, value(0));
+        assert_eq!(closest.expressions, solver.solve());
+    }
+
+    #[test]
+    fn reachable_test() {
+        let solver = Solver::new(vec![1, 2, 3, 4]);
+        assert_eq!(solver.reachable(20..=30), [20, 21, 22, 23, 24, 25, 26, 27, 28, 30]);
+        for target in solver.reachable(-5..=5) {
+            assert!(!solver.clone().target(target).solve().is_empty());
+        }
+    }
+}
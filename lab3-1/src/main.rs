@@ -1,171 +1,399 @@
 use std::{
-    collections::BTreeSet,
-    sync::{Arc, Mutex},
-    time::Instant,
-    vec,
+    io::{self, Write},
+    ops::RangeInclusive,
+    process,
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
-use clap::Parser;
-use itertools::Itertools;
+use bench::{Measurement, Strategy};
+use clap::{Parser, Subcommand};
+use lab3_1::{distance, value, CacheStats, Expression, Operation, Progress, Solver, Value, OPERATIONS};
+use output::Format;
 
-#[derive(Debug, Clone, Copy)]
-enum Operation {
-    Sum,
-    Sub,
-    Div,
-    Mul,
+mod bench;
+mod output;
+mod play;
+
+/// Time between two progress reports.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    args: Args,
 }
 
-impl ToString for Operation {
-    fn to_string(&self) -> String {
-        match self {
-            Operation::Sum => "+".into(),
-            Operation::Sub => "-".into(),
-            Operation::Div => "/".into(),
-            Operation::Mul => "*".into(),
-        }
-    }
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Time the search splitting the permutations in blocks and in chunks.
+    Bench(BenchArgs),
+    /// Draw numbers and a target as in Countdown, score an answer and reveal the best ones.
+    Play(PlayArgs),
 }
 
-#[derive(Debug, Parser)]
+#[derive(Debug, clap::Args)]
 struct Args {
-    #[arg()]
+    /// Numbers to combine, all of them are used in every expression.
+    #[arg(required = true)]
     input: Vec<i32>,
+    /// Value the expressions must evaluate to.
+    #[arg(long, default_value_t = 10)]
+    target: i32,
+    /// Try every way of grouping the numbers, not only from left to right.
+    #[arg(long)]
+    parentheses: bool,
+    /// Report the solutions equivalent by commutativity, like `2 + 3` and `3 + 2`, separately.
+    #[arg(long)]
+    all_forms: bool,
+    /// Also try raising to an integer power, `2 ^ 3`.
+    #[arg(long)]
+    pow: bool,
+    /// Also try the remainder of the division of two integers, `7 % 3`.
+    #[arg(long = "mod")]
+    modulo: bool,
+    /// Also try writing two numbers one after the other, `2 || 4` is 24.
+    #[arg(long)]
+    concat: bool,
+    /// Stop at the first solution.
+    #[arg(long, conflicts_with = "limit")]
+    first: bool,
+    /// Stop once `limit` solutions are found.
+    #[arg(long)]
+    limit: Option<usize>,
+    /// Workers of the thread pool, all the available cores by default.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    threads: Option<u32>,
+    /// Permutations of the numbers searched by every job of the pool,
+    /// by default one block per thread.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    chunk_size: Option<u32>,
+    /// Search the values of every sub-multiset of the numbers once, much faster with many numbers.
+    #[arg(long, requires = "parentheses", conflicts_with = "progress")]
+    memoize: bool,
+    /// Report the progress of the search on stderr.
+    #[arg(long)]
+    progress: bool,
+    /// Print the time of the search, and the use of the cache with `--memoize`, on stderr.
+    #[arg(short, long)]
+    verbose: bool,
+    /// When the target can't be reached, print the expressions nearest to it.
+    #[arg(long, conflicts_with_all = ["first", "limit"])]
+    closest: bool,
+    /// Print the solutions as soon as they are found instead of sorted at the end.
+    #[arg(long, conflicts_with_all = ["all_targets", "closest"])]
+    stream: bool,
+    /// How the solutions are printed with `--stream`.
+    #[arg(long, value_enum, default_value_t, requires = "stream")]
+    format: Format,
+    /// Report which targets in `lo..hi` (or `lo..=hi`) are reachable instead of the solutions.
+    #[arg(long, value_parser = parse_range, conflicts_with = "closest")]
+    all_targets: Option<RangeInclusive<i32>>,
 }
 
-fn main() {
-    let args = Args::parse();
-    let len = args.input.len();
-
-    let max_threads = 32;
-
-    for nthread in 1..=max_threads {
-        let nums = args.input.clone();
-        let ops = &vec![
-            Operation::Sum,
-            Operation::Sub,
-            Operation::Div,
-            Operation::Mul,
-        ];
-
-        let number_permutations = Arc::new(nums.into_iter().permutations(len).collect::<Vec<_>>());
-
-        let results = Arc::new(Mutex::new(BTreeSet::<String>::new()));
-
-        // Start block calculation
-        let time = Instant::now();
-
-        std::thread::scope(|s| {
-            let range = number_permutations.len() / nthread;
-
-            for thread in 0..nthread {
-                let number_permutations = number_permutations.clone();
-                let results = results.clone();
-
-                s.spawn(move || {
-                    let thead_range = (range * thread)..if thread + 1 == nthread {
-                        number_permutations.len()
-                    } else {
-                        range * (thread + 1)
-                    };
-
-                    for numbers in &number_permutations.as_slice()[thead_range] {
-                        let operation_comb = permutations_with_replacement(ops, len - 1);
-
-                        for ops in operation_comb {
-                            if let Some(10) = calculate(numbers, &ops) {
-                                let string = convert_combination(numbers, &ops);
-                                results.lock().unwrap().insert(string);
-                            }
-                        }
-                    }
-                });
-            }
-        });
+#[derive(Debug, clap::Args)]
+struct BenchArgs {
+    /// Numbers to combine, all of them are used in every expression.
+    #[arg(required = true)]
+    input: Vec<i32>,
+    /// Value the expressions must evaluate to.
+    #[arg(long, default_value_t = 10)]
+    target: i32,
+    /// Try every way of grouping the numbers, not only from left to right.
+    #[arg(long)]
+    parentheses: bool,
+    /// Most threads tried, starting from 1, all the available cores by default.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    threads: Option<u32>,
+    /// Permutations of the numbers searched by every job with the chunked strategy.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    chunk_size: u32,
+    /// Untimed searches before the timed ones.
+    #[arg(long, default_value_t = 1)]
+    warmup: usize,
+    /// Timed searches for every strategy and number of threads.
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+    repetitions: u32,
+    /// How the measurements are printed.
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+}
+
+#[derive(Debug, clap::Args)]
+struct PlayArgs {
+    /// Numbers drawn.
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(1..))]
+    count: u32,
+    /// Numbers drawn from 25, 50, 75 and 100, the others are small.
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u32).range(0..=4))]
+    large: u32,
+    /// Small numbers in `lo..hi` (or `lo..=hi`), every one can be drawn twice.
+    #[arg(long, default_value = "1..=10", value_parser = parse_range)]
+    small: RangeInclusive<i32>,
+    /// Targets in `lo..hi` (or `lo..=hi`).
+    #[arg(long, default_value = "101..=999", value_parser = parse_range)]
+    targets: RangeInclusive<i32>,
+    /// Seed of the draw, to play a round again.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Best answers revealed at the end of the round.
+    #[arg(long, default_value_t = 5)]
+    reveal: usize,
+    /// Workers of the thread pool looking for the best answers, all the available cores by default.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    threads: Option<u32>,
+}
+
+/// The given threads, or all the available cores.
+fn threads(threads: Option<u32>) -> usize {
+    threads.map_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()), |n| n as usize)
+}
+
+/// Parse `lo..hi` excluding `hi`, or `lo..=hi` including it.
+fn parse_range(range: &str) -> Result<RangeInclusive<i32>, String> {
+    let (lo, hi) = range
+        .split_once("..")
+        .ok_or_else(|| format!("expected lo..hi, got {}", range))?;
+    let (hi, inclusive) = match hi.strip_prefix('=') {
+        Some(hi) => (hi, true),
+        None => (hi, false),
+    };
+
+    let lo = lo.parse::<i32>().map_err(|e| format!("{}: {}", lo, e))?;
+    let hi = hi.parse::<i32>().map_err(|e| format!("{}: {}", hi, e))?;
+    Ok(if inclusive { lo..=hi } else { lo..=hi - 1 })
+}
 
-        println!(
-            "nthreads with blocks:\t\t {}, t: {:?}, size: {}",
-            nthread,
-            time.elapsed(),
-            results.lock().unwrap().len()
-        );
+fn print_reachable(solver: Solver, targets: RangeInclusive<i32>) {
+    let reachable = solver.reachable(targets.clone());
+    let unreachable = targets
+        .filter(|target| reachable.binary_search(target).is_err())
+        .collect::<Vec<_>>();
 
-        {
-            results.lock().unwrap().clear();
+    println!("reachable:   {:?}", reachable);
+    println!("unreachable: {:?}", unreachable);
+}
+
+fn print_closest(solver: Solver) {
+    let Some(closest) = solver.closest() else {
+        println!("no expression can be evaluated");
+        return;
+    };
+
+    if closest.distance == Value::default() {
+        for expression in closest.expressions {
+            println!("{}", expression);
         }
+        return;
+    }
+
+    println!("no solution, the nearest expressions are {} away:", closest.distance);
+    for expression in closest.expressions {
+        println!("{} = {}", expression, expression.value().unwrap());
+    }
+}
+
+/// Thread printing the progress of the search on stderr until it is dropped.
+struct ProgressReporter {
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
 
-        // Start interleaved
-        let time = Instant::now();
-
-        std::thread::scope(|s| {
-            for thread in 0..nthread {
-                let number_permutations = number_permutations.clone();
-                let results = results.clone();
-
-                s.spawn(move || {
-                    let thread_range = (0 + thread..number_permutations.len()).step_by(nthread);
-
-                    let numbers = number_permutations.as_slice();
-                    for index in thread_range {
-                        let operation_comb = permutations_with_replacement(&ops, len - 1);
-
-                        for ops in operation_comb {
-                            if let Some(10) = calculate(&numbers[index], &ops) {
-                                let string = convert_combination(&numbers[index], &ops);
-                                results.lock().unwrap().insert(string);
-                            }
-                        }
-                    }
-                });
+impl ProgressReporter {
+    fn spawn(progress: Arc<Progress>) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(PROGRESS_INTERVAL) {
+                eprint!("\r{}", progress.report());
             }
+            eprintln!("\r{}", progress.report());
         });
 
-        println!(
-            "nthreads with interleaved:\t {}, t: {:?}, size: {}",
-            nthread,
-            time.elapsed(),
-            results.lock().unwrap().len()
-        );
+        Self { stop: Some(stop), handle: Some(handle) }
     }
 }
 
-fn convert_combination(nums: &Vec<i32>, ops: &Vec<&Operation>) -> String {
-    let mut nums = nums.iter();
-    let ops = ops.iter();
-    let mut result = nums.next().unwrap().to_string();
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap();
+        }
+    }
+}
 
-    nums.zip(ops)
-        .for_each(|(num, op)| result += &format!(" {} {}", op.to_string(), num));
+fn bench(args: BenchArgs) {
+    let solver = Solver::new(args.input)
+        .target(args.target)
+        .parentheses(args.parentheses);
+    let strategies = [Strategy::Blocks, Strategy::Chunks(args.chunk_size as usize)];
 
-    result
+    if let Some(header) = bench::header(args.format) {
+        println!("{}", header);
+    }
+    for threads in 1..=threads(args.threads) {
+        for strategy in strategies {
+            let measurement =
+                Measurement::run(&solver, strategy, threads, args.warmup, args.repetitions as usize);
+            println!("{}", bench::format(args.format, &measurement));
+        }
+    }
 }
 
-fn permutations_with_replacement<T: Copy>(
-    items: &Vec<T>,
-    k: usize,
-) -> impl Iterator<Item = Vec<&T>> {
-    std::iter::repeat(items.iter())
-        .take(k)
-        .multi_cartesian_product()
+/// Prompt until the answer is valid, `None` at the end of the input or on an empty line.
+fn read_answer(numbers: &[i32]) -> Option<(Expression, Value)> {
+    let mut lines = io::stdin().lines();
+    loop {
+        print!("your answer: ");
+        io::stdout().flush().unwrap();
+
+        let Some(line) = lines.next() else {
+            println!();
+            return None;
+        };
+        let line = line.unwrap();
+        if line.trim().is_empty() {
+            return None;
+        }
+        match line.parse::<Expression>().and_then(|answer| Ok((play::check(&answer, numbers)?, answer))) {
+            Ok((value, answer)) => return Some((answer, value)),
+            Err(e) => println!("{}, try again", e),
+        }
+    }
 }
 
-fn calculate(nums: &Vec<i32>, ops: &Vec<&Operation>) -> Option<i32> {
-    let mut nums = nums.iter();
-    let mut partial = *nums.next()?;
+fn play(args: PlayArgs) {
+    let mut rng = play::Rng::new(args.seed);
+    let round = play::draw(&mut rng, args.count as usize, args.large as usize, args.small, args.targets)
+        .unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            process::exit(2);
+        });
 
-    for (num, op) in nums.zip(ops.iter()) {
-        match op {
-            Operation::Div => {
-                if *num == 0 {
-                    return None;
-                }
-                partial = partial / (*num);
-            }
-            Operation::Mul => partial = partial * (*num),
-            Operation::Sub => partial = partial - (*num),
-            Operation::Sum => partial = partial + (*num),
+    println!("numbers: {}", round.numbers.iter().map(ToString::to_string).collect::<Vec<_>>().join(" "));
+    println!("target:  {}", round.target);
+
+    // the best answers are searched while the player thinks
+    let solver = Solver::new(round.numbers.clone())
+        .target(round.target)
+        .threads(threads(args.threads))
+        .parentheses(true)
+        .subsets(true)
+        .memoize(true);
+    let search = thread::spawn(move || solver.closest());
+
+    match read_answer(&round.numbers) {
+        Some((answer, answer_value)) => {
+            let points = distance(answer_value, value(round.target)).map_or(0, play::score);
+            println!("{} = {}: {} points", answer, answer_value, points);
+        }
+        None => println!("no answer: 0 points"),
+    }
+
+    println!("looking for the best answers...");
+    let Some(closest) = search.join().unwrap() else {
+        return;
+    };
+    if closest.distance == Value::default() {
+        println!("{} answers reach the target, like:", closest.expressions.len());
+    } else {
+        println!("the best answers are {} away, like:", closest.distance);
+    }
+    for expression in closest.expressions.iter().take(args.reveal) {
+        println!("  {} = {}", expression, expression.value().unwrap());
+    }
+}
+
+/// Print what the options ask for.
+fn search(solver: Solver, args: &Args) {
+    if let Some(targets) = args.all_targets.clone() {
+        print_reachable(solver, targets);
+        return;
+    }
+
+    if args.closest {
+        print_closest(solver);
+        return;
+    }
+
+    if args.stream {
+        if let Some(header) = args.format.header() {
+            println!("{}", header);
         }
+        for solution in solver.solve_streaming() {
+            println!("{}", args.format.format(&solution));
+        }
+        return;
     }
 
-    return Some(partial);
+    for expression in solver.solve() {
+        println!("{}", expression);
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let args = match cli.command {
+        Some(Command::Bench(args)) => return bench(args),
+        Some(Command::Play(args)) => return play(args),
+        None => cli.args,
+    };
+
+    let operations = OPERATIONS
+        .into_iter()
+        .chain(args.pow.then_some(Operation::Pow))
+        .chain(args.modulo.then_some(Operation::Mod))
+        .chain(args.concat.then_some(Operation::Concat))
+        .collect::<Vec<_>>();
+
+    let mut solver = Solver::new(args.input.clone())
+        .target(args.target)
+        .threads(threads(args.threads))
+        .parentheses(args.parentheses)
+        .canonical(!args.all_forms)
+        .operations(&operations)
+        .memoize(args.memoize);
+    if let Some(chunk_size) = args.chunk_size {
+        solver = solver.chunk_size(chunk_size as usize);
+    }
+    if let Some(limit) = args.limit {
+        solver = solver.limit(limit);
+    }
+    if args.first {
+        solver = solver.first();
+    }
+
+    let cache_stats = args.memoize.then(|| Arc::new(CacheStats::default()));
+    if let Some(stats) = &cache_stats {
+        solver = solver.cache_stats(stats.clone());
+    }
+
+    // stops reporting when main returns
+    let _reporter = args.progress.then(|| {
+        let progress = Arc::new(Progress::new(threads(args.threads)));
+        solver = solver.clone().progress(progress.clone());
+        ProgressReporter::spawn(progress)
+    });
+
+    let start = Instant::now();
+    search(solver, &args);
+
+    if args.verbose {
+        eprintln!("search took {:.2?}", start.elapsed());
+        if let Some(stats) = cache_stats {
+            eprintln!(
+                "cache: {} hits, {} misses, {} values",
+                stats.hits(),
+                stats.misses(),
+                stats.values()
+            );
+        }
+    }
 }
@@ -0,0 +1,261 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+};
+
+use itertools::Itertools;
+use lab5_1::ThreadPool;
+
+use crate::{value, Expression, Operation, Value};
+
+/// Counters of the cache of the values of every sub-multiset of the numbers,
+/// shared with a `Solver` searching with `memoize`.
+#[derive(Debug, Default)]
+pub struct CacheStats {
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    values: AtomicUsize,
+}
+
+impl CacheStats {
+    /// Value sets taken from the cache instead of being evaluated again.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Value sets evaluated, one for every distinct sub-multiset of the numbers.
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Values stored in the cache.
+    pub fn values(&self) -> usize {
+        self.values.load(Ordering::Relaxed)
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Values of the expressions of every sub-multiset of the numbers, from which
+/// the expressions with a value are rebuilt.
+pub(crate) struct Memo {
+    operations: Vec<Operation>,
+    /// Sorted values of every sorted sub-multiset.
+    cache: HashMap<Vec<i32>, Vec<Value>>,
+    /// Multisets at the root of the expressions searched.
+    roots: Vec<Vec<i32>>,
+    stats: Arc<CacheStats>,
+}
+
+impl Memo {
+    /// Evaluate the sub-multisets from the smallest, the splits of the ones
+    /// of the same size are evaluated by a thread pool. The values of all the numbers
+    /// together, the most, are only evaluated if `values` is going to be called.
+    pub(crate) fn new(
+        numbers: &[i32],
+        operations: &[Operation],
+        subsets: bool,
+        all_values: bool,
+        threads: usize,
+        stats: Arc<CacheStats>,
+    ) -> Self {
+        let numbers = numbers.iter().copied().sorted().collect::<Vec<_>>();
+        let mut cache = Arc::new(HashMap::new());
+        let shared_operations = Arc::new(operations.to_vec());
+
+        let largest = if all_values { numbers.len() } else { numbers.len().saturating_sub(1).max(1) };
+        for len in 1..=largest.min(numbers.len()) {
+            let multisets = sub_multisets(&numbers, len);
+            // the subsets of the positions with the same numbers are evaluated once
+            let positions = numbers.iter().combinations(len).count();
+            stats.hits.fetch_add(positions - multisets.len(), Ordering::Relaxed);
+
+            let (sender, receiver) = mpsc::channel::<(Vec<i32>, HashSet<Value>)>();
+            let pool = ThreadPool::<Job>::new(threads as u32);
+            for multiset in multisets {
+                if let [num] = multiset[..] {
+                    sender.send((multiset, HashSet::from([value(num)]))).unwrap();
+                    continue;
+                }
+
+                for (lhs, rhs) in splits(&multiset) {
+                    let multiset = multiset.clone();
+                    let cache = cache.clone();
+                    let operations = shared_operations.clone();
+                    let sender = sender.clone();
+                    let stats = stats.clone();
+
+                    pool.execute(Box::new(move || {
+                        let (lhs, rhs): (&Vec<Value>, &Vec<Value>) = (&cache[&lhs], &cache[&rhs]);
+                        stats.hits.fetch_add(2, Ordering::Relaxed);
+
+                        let mut values = HashSet::new();
+                        for (&lhs, &op, &rhs) in itertools::iproduct!(lhs, operations.iter(), rhs) {
+                            values.extend(op.apply(lhs, rhs));
+                        }
+                        sender.send((multiset, values)).unwrap();
+                    }))
+                    .unwrap();
+                }
+            }
+            drop(sender);
+
+            let mut level = HashMap::<Vec<i32>, HashSet<Value>>::new();
+            for (multiset, values) in receiver {
+                level.entry(multiset).or_default().extend(values);
+            }
+            // the jobs, with their references to the cache, are over
+            drop(pool);
+
+            let cache = Arc::get_mut(&mut cache).unwrap();
+            for (multiset, values) in level {
+                stats.misses.fetch_add(1, Ordering::Relaxed);
+                stats.values.fetch_add(values.len(), Ordering::Relaxed);
+                cache.insert(multiset, values.into_iter().sorted().collect());
+            }
+        }
+
+        let roots = if subsets {
+            (1..=numbers.len()).flat_map(|len| sub_multisets(&numbers, len)).collect()
+        } else {
+            vec![numbers]
+        };
+
+        Self {
+            operations: operations.to_vec(),
+            cache: Arc::try_unwrap(cache).unwrap(),
+            roots,
+            stats,
+        }
+    }
+
+    fn lookup(&self, multiset: &[i32]) -> Option<&[Value]> {
+        let values = self.cache.get(multiset)?;
+        self.stats.hits.fetch_add(1, Ordering::Relaxed);
+        Some(values)
+    }
+
+    /// Value of every expression, sorted. The values of all the numbers must have been evaluated.
+    pub(crate) fn values(&self) -> Vec<Value> {
+        self.roots
+            .iter()
+            .flat_map(|root| self.lookup(root).expect("values of all the numbers not evaluated"))
+            .copied()
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// Call `visit` with every expression evaluating to `target`, until it breaks.
+    pub(crate) fn expressions(
+        &self,
+        target: Value,
+        visit: &mut dyn FnMut(Expression) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        for root in &self.roots {
+            self.expressions_of(root, target, visit)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn expressions_of(
+        &self,
+        multiset: &[i32],
+        target: Value,
+        visit: &mut dyn FnMut(Expression) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        // the values of all the numbers may not be evaluated, they are then tried all
+        if self.lookup(multiset).is_some_and(|values| values.binary_search(&target).is_err()) {
+            return ControlFlow::Continue(());
+        }
+        if let [num] = multiset {
+            return visit(Expression::Number(*num));
+        }
+
+        for (lhs, rhs) in splits(multiset) {
+            let (lhs_values, rhs_values) = (self.lookup(&lhs).unwrap(), self.lookup(&rhs).unwrap());
+            for (&lhs_value, &op, &rhs_value) in itertools::iproduct!(lhs_values, &self.operations, rhs_values) {
+                if op.apply(lhs_value, rhs_value) != Some(target) {
+                    continue;
+                }
+                self.expressions_of(&lhs, lhs_value, &mut |lhs| {
+                    self.expressions_of(&rhs, rhs_value, &mut |rhs| {
+                        visit(Expression::binary(lhs.clone(), op, rhs))
+                    })
+                })?;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Every distinct sorted sub-multiset of `len` of the sorted `numbers`.
+fn sub_multisets(numbers: &[i32], len: usize) -> Vec<Vec<i32>> {
+    numbers.iter().copied().combinations(len).unique().collect()
+}
+
+/// Every distinct way of splitting `multiset` in two non-empty sides, in both orders.
+fn splits(multiset: &[i32]) -> Vec<(Vec<i32>, Vec<i32>)> {
+    let full = (1usize << multiset.len()) - 1;
+    (1..full)
+        .map(|mask| {
+            let (lhs, rhs): (Vec<_>, Vec<_>) = multiset
+                .iter()
+                .enumerate()
+                .partition(|(i, _)| mask & (1 << i) != 0);
+            let side = |side: Vec<(usize, &i32)>| side.into_iter().map(|(_, &num)| num).collect::<Vec<_>>();
+            (side(lhs), side(rhs))
+        })
+        .unique()
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{ops::ControlFlow, sync::Arc};
+
+    use crate::{
+        memo::{splits, CacheStats, Memo},
+        value, OPERATIONS,
+    };
+
+    #[test]
+    fn splits_test() {
+        assert_eq!(splits(&[1, 2]), [(vec![1], vec![2]), (vec![2], vec![1])]);
+        // the two 2s give the same splits
+        assert_eq!(splits(&[2, 2, 3]).len(), 4);
+    }
+
+    #[test]
+    fn memo_test() {
+        let stats = Arc::new(CacheStats::default());
+        let memo = Memo::new(&[2, 3, 2], &OPERATIONS, false, true, 2, stats.clone());
+        // {2}, {3}, {2, 2}, {2, 3}, {2, 2, 3}
+        assert_eq!(stats.misses(), 5);
+        assert!(stats.hits() > 0);
+        assert_eq!(stats.values(), memo.cache.values().map(Vec::len).sum::<usize>());
+
+        assert!(memo.values().contains(&value(10)));
+        let mut expressions = Vec::new();
+        let _ = memo.expressions(value(10), &mut |expression| {
+            expressions.push(expression.to_string());
+            ControlFlow::Continue(())
+        });
+        expressions.sort();
+        assert_eq!(expressions, ["(2 + 3) * 2", "(3 + 2) * 2", "2 * (2 + 3)", "2 * (3 + 2)"]);
+
+        // without the values of all the numbers the same expressions are found
+        let memo = Memo::new(&[2, 3, 2], &OPERATIONS, false, false, 2, Arc::default());
+        let mut found = Vec::new();
+        let _ = memo.expressions(value(10), &mut |expression| {
+            found.push(expression.to_string());
+            ControlFlow::Continue(())
+        });
+        found.sort();
+        assert_eq!(found, expressions);
+    }
+}
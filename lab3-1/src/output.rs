@@ -0,0 +1,110 @@
+use clap::ValueEnum;
+use lab3_1::Solution;
+
+/// How the solutions, or the measurements of `bench`, are printed, one per line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Format {
+    /// Only the expression, or a readable summary of the measurement.
+    #[default]
+    Text,
+    /// An object per line with every field: the expression, its steps, the thread
+    /// and the time it was found, or the statistics of the measurement.
+    Json,
+    /// Like `json`, with a header line.
+    Csv,
+}
+
+impl Format {
+    /// Line printed before the solutions.
+    pub fn header(self) -> Option<&'static str> {
+        match self {
+            Format::Csv => Some("expression,steps,thread,elapsed_ms"),
+            Format::Text | Format::Json => None,
+        }
+    }
+
+    pub fn format(self, solution: &Solution) -> String {
+        let expression = solution.expression.to_string();
+        let steps = solution
+            .expression
+            .steps()
+            .unwrap_or_default()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let thread = solution.thread.as_deref().unwrap_or_default();
+        let elapsed_ms = solution.elapsed.as_secs_f64() * 1000.0;
+
+        match self {
+            Format::Text => expression,
+            Format::Json => format!(
+                "{{\"expression\":{},\"steps\":[{}],\"thread\":{},\"elapsed_ms\":{:.3}}}",
+                json_string(&expression),
+                steps.iter().map(|step| json_string(step)).collect::<Vec<_>>().join(","),
+                json_string(thread),
+                elapsed_ms
+            ),
+            Format::Csv => format!(
+                "{},{},{},{:.3}",
+                csv_field(&expression),
+                csv_field(&steps.join("; ")),
+                csv_field(thread),
+                elapsed_ms
+            ),
+        }
+    }
+}
+
+fn json_string(string: &str) -> String {
+    let mut json = String::from("\"");
+    for c in string.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// The field quoted if it contains a separator or a quote.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use lab3_1::{Expression, Operation, Solution};
+
+    use crate::output::{csv_field, json_string, Format};
+
+    #[test]
+    fn format_test() {
+        let solution = Solution {
+            expression: Expression::chain(&[3, 2, 2], &[Operation::Sum, Operation::Mul]),
+            thread: Some("worker 1".to_string()),
+            elapsed: Duration::from_micros(1500),
+        };
+
+        assert_eq!(Format::Text.format(&solution), "(3 + 2) * 2");
+        assert_eq!(
+            Format::Json.format(&solution),
+            r#"{"expression":"(3 + 2) * 2","steps":["3 + 2 = 5","5 * 2 = 10"],"thread":"worker 1","elapsed_ms":1.500}"#
+        );
+        assert_eq!(Format::Csv.format(&solution), "(3 + 2) * 2,3 + 2 = 5; 5 * 2 = 10,worker 1,1.500");
+    }
+
+    #[test]
+    fn escape_test() {
+        assert_eq!(json_string("a \"b\"\\\n"), r#""a \"b\"\\\u000a""#);
+        assert_eq!(csv_field("a, \"b\""), r#""a, ""b""""#);
+    }
+}
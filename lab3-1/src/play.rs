@@ -0,0 +1,171 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    ops::RangeInclusive,
+};
+
+use lab3_1::{value, Expression, Value, OPERATIONS};
+
+/// Numbers of the large deck, every one can be drawn once.
+pub const LARGE: [i32; 4] = [25, 50, 75, 100];
+
+/// SplitMix64 generator, the rounds only need to look random.
+pub struct Rng(u64);
+
+impl Rng {
+    /// Generator starting from `seed`, or from the random keys of the std hash maps.
+    pub fn new(seed: Option<u64>) -> Self {
+        Self(seed.unwrap_or_else(|| RandomState::new().build_hasher().finish()))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, the bias is negligible for the small `n` of the game.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Numbers and target of a round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Round {
+    pub numbers: Vec<i32>,
+    pub target: i32,
+}
+
+/// Draw `large` numbers of the large deck and the others from the small one,
+/// where every number of `small` appears twice, then a target in `targets`.
+pub fn draw(
+    rng: &mut Rng,
+    count: usize,
+    large: usize,
+    small: RangeInclusive<i32>,
+    targets: RangeInclusive<i32>,
+) -> Result<Round, String> {
+    if large > count || large > LARGE.len() {
+        return Err(format!("can't draw {} large numbers of {}", large, count.min(LARGE.len())));
+    }
+    let mut small_deck = small.flat_map(|num| [num, num]).collect::<Vec<_>>();
+    if count - large > small_deck.len() {
+        return Err(format!("can't draw {} small numbers of {}", count - large, small_deck.len()));
+    }
+    if targets.is_empty() {
+        return Err("the range of the targets is empty".to_string());
+    }
+
+    let mut large_deck = LARGE;
+    rng.shuffle(&mut large_deck);
+    rng.shuffle(&mut small_deck);
+    let numbers = large_deck[..large]
+        .iter()
+        .chain(&small_deck[..count - large])
+        .copied()
+        .collect();
+
+    let span = (*targets.end() as i64 - *targets.start() as i64 + 1) as usize;
+    let target = (*targets.start() as i64 + rng.below(span) as i64) as i32;
+    Ok(Round { numbers, target })
+}
+
+/// Value of an answer using only the numbers of the round, every one at most once,
+/// and the basic operations.
+pub fn check(expression: &Expression, numbers: &[i32]) -> Result<Value, String> {
+    let mut left = numbers.to_vec();
+    check_leaves(expression, &mut left)?;
+    expression
+        .value()
+        .ok_or_else(|| "the expression can't be evaluated".to_string())
+}
+
+fn check_leaves(expression: &Expression, left: &mut Vec<i32>) -> Result<(), String> {
+    match expression {
+        Expression::Number(num) => match left.iter().position(|left| left == num) {
+            Some(pos) => {
+                left.swap_remove(pos);
+                Ok(())
+            }
+            None => Err(format!("{} is not one of the numbers left", num)),
+        },
+        Expression::Binary(_, op, _) if !OPERATIONS.contains(op) => {
+            Err(format!("`{}` is not allowed, only + - * /", op))
+        }
+        Expression::Binary(lhs, _, rhs) => {
+            check_leaves(lhs, left)?;
+            check_leaves(rhs, left)
+        }
+    }
+}
+
+/// Points of an answer `distance` away from the target: 10 if exact, 7 within 5, 5 within 10.
+pub fn score(distance: Value) -> u32 {
+    match distance {
+        distance if distance == value(0) => 10,
+        distance if distance <= value(5) => 7,
+        distance if distance <= value(10) => 5,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::RangeInclusive;
+
+    use lab3_1::{value, Expression, Value};
+
+    use crate::play::{check, draw, score, Rng, LARGE};
+
+    #[test]
+    fn draw_test() {
+        let round = draw(&mut Rng::new(Some(42)), 6, 2, 1..=10, 101..=999).unwrap();
+        assert_eq!(round, draw(&mut Rng::new(Some(42)), 6, 2, 1..=10, 101..=999).unwrap());
+
+        assert_eq!(round.numbers.len(), 6);
+        assert!(round.numbers[..2].iter().all(|num| LARGE.contains(num)));
+        assert!(round.numbers[2..].iter().all(|num| (1..=10).contains(num)));
+        assert!((101..=999).contains(&round.target));
+
+        // every small number can be drawn twice
+        let round = draw(&mut Rng::new(None), 4, 0, 1..=2, 5..=5).unwrap();
+        assert_eq!(round.numbers.iter().filter(|&&num| num == 1).count(), 2);
+        assert_eq!(round.target, 5);
+
+        assert!(draw(&mut Rng::new(None), 6, 5, 1..=10, 101..=999).is_err());
+        assert!(draw(&mut Rng::new(None), 5, 0, 1..=2, 101..=999).is_err());
+        assert!(draw(&mut Rng::new(None), 6, 2, 1..=10, RangeInclusive::new(999, 101)).is_err());
+    }
+
+    #[test]
+    fn check_test() {
+        let numbers = [25, 3, 3, 7];
+        let check = |answer: &str| check(&answer.parse::<Expression>().unwrap(), &numbers);
+
+        assert_eq!(check("25 * 3 + 7"), Ok(value(82)));
+        assert_eq!(check("(25 + 3 + 3) * 7"), Ok(value(217)));
+        assert_eq!(check("25 / 3"), Ok(Value::new(25, 3)));
+        assert_eq!(check("7 * 7"), Err("7 is not one of the numbers left".to_string()));
+        assert_eq!(check("4"), Err("4 is not one of the numbers left".to_string()));
+        assert_eq!(check("3 ^ 3"), Err("`^` is not allowed, only + - * /".to_string()));
+        assert_eq!(check("7 / (3 - 3)"), Err("the expression can't be evaluated".to_string()));
+    }
+
+    #[test]
+    fn score_test() {
+        assert_eq!(score(value(0)), 10);
+        assert_eq!(score(Value::new(1, 2)), 7);
+        assert_eq!(score(value(5)), 7);
+        assert_eq!(score(value(10)), 5);
+        assert_eq!(score(value(11)), 0);
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Permutations of the numbers searched so far, shared with a `Solver` to follow its search.
+#[derive(Debug)]
+pub struct Progress {
+    total: AtomicUsize,
+    /// Permutations searched by every worker of the pool, summed when read.
+    workers: Vec<AtomicUsize>,
+    start: Mutex<Instant>,
+}
+
+impl Progress {
+    /// Progress counted by `workers` counters, one for each thread of the pool.
+    pub fn new(workers: usize) -> Self {
+        Self {
+            total: AtomicUsize::new(0),
+            workers: (0..workers.max(1)).map(|_| AtomicUsize::new(0)).collect(),
+            start: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Reset the counters at the start of a search of `total` permutations.
+    pub(crate) fn start(&self, total: usize) {
+        *self.start.lock().unwrap() = Instant::now();
+        for counter in &self.workers {
+            counter.store(0, Ordering::Relaxed);
+        }
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    /// A permutation was searched by `worker`.
+    pub(crate) fn add(&self, worker: usize) {
+        self.workers[worker % self.workers.len()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn done(&self) -> usize {
+        self.workers.iter().map(|counter| counter.load(Ordering::Relaxed)).sum()
+    }
+
+    pub fn total(&self) -> usize {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// Percent complete, permutations per second and estimated time left.
+    pub fn report(&self) -> String {
+        let (done, total) = (self.done(), self.total());
+        let elapsed = self.start.lock().unwrap().elapsed();
+        format_report(done, total, elapsed)
+    }
+}
+
+fn format_report(done: usize, total: usize, elapsed: Duration) -> String {
+    let percent = if total == 0 { 100.0 } else { done as f64 * 100.0 / total as f64 };
+    let rate = done as f64 / elapsed.as_secs_f64();
+
+    let eta = if done == 0 {
+        "unknown".to_string()
+    } else {
+        let left = Duration::from_secs_f64((total - done.min(total)) as f64 / rate);
+        format!("{:.0?}", left)
+    };
+    format!("{:5.1}% {:>10.0} permutations/s, ETA {}", percent, rate, eta)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::progress::{format_report, Progress};
+
+    #[test]
+    fn progress_test() {
+        let progress = Progress::new(2);
+        progress.start(10);
+        for worker in 0..5 {
+            progress.add(worker);
+        }
+        assert_eq!(progress.done(), 5);
+        assert_eq!(progress.total(), 10);
+
+        progress.start(4);
+        assert_eq!(progress.done(), 0);
+    }
+
+    #[test]
+    fn report_test() {
+        assert_eq!(
+            format_report(25, 100, Duration::from_secs(5)),
+            " 25.0%          5 permutations/s, ETA 15s"
+        );
+        assert!(format_report(0, 100, Duration::from_secs(1)).ends_with("ETA unknown"));
+    }
+}
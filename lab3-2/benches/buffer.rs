@@ -0,0 +1,78 @@
+//! Elements moved per second from a writer thread to a reader thread through the circular
+//! buffer, an element at a time and in batches, against a crossbeam channel of the same
+//! capacity: the reference of a lock-free buffer, which can be added to the group.
+//!
+//! Run with `cargo bench`.
+
+use std::thread;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use crossbeam::channel::bounded;
+
+// the buffer is a module of the binary, not a library
+#[path = "../src"]
+mod src {
+    #[allow(dead_code, unused_imports)]
+    pub mod shared;
+}
+
+use src::shared;
+use shared::new_buffer;
+
+const CAPACITY: usize = 1024;
+const ITEMS: u64 = 100_000;
+const BATCH: usize = 64;
+
+fn mutex(batch: usize) -> u64 {
+    let (mut reader, mut writer) = new_buffer(CAPACITY);
+    let items = (0..ITEMS).collect::<Vec<_>>();
+
+    thread::scope(|s| {
+        s.spawn(move || {
+            for mut chunk in items.chunks(batch) {
+                while !chunk.is_empty() {
+                    let mut written = writer.write_all(chunk);
+                    if written == 0 {
+                        writer.write_blocking(chunk[0]).unwrap();
+                        written = 1;
+                    }
+                    chunk = &chunk[written..];
+                }
+            }
+        });
+
+        let mut sum = 0;
+        while let Ok(data) = reader.read_blocking(batch) {
+            sum += data.into_iter().sum::<u64>();
+        }
+        sum
+    })
+}
+
+fn crossbeam() -> u64 {
+    let (sender, receiver) = bounded(CAPACITY);
+
+    thread::scope(|s| {
+        s.spawn(move || {
+            for item in 0..ITEMS {
+                sender.send(item).unwrap();
+            }
+        });
+
+        receiver.iter().sum()
+    })
+}
+
+fn buffer_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("buffer");
+    group.throughput(Throughput::Elements(ITEMS));
+
+    group.bench_function("mutex", |b| b.iter(|| mutex(1)));
+    group.bench_function("mutex batch", |b| b.iter(|| mutex(BATCH)));
+    group.bench_function("crossbeam", |b| b.iter(crossbeam));
+
+    group.finish();
+}
+
+criterion_group!(benches, buffer_throughput);
+criterion_main!(benches);
@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+
+use labs_common::{RunningStats, SensorData, SensorStats, SENSORS};
+
+use crate::shared::{BReader, BufferError, CircularBuffer};
+
+/// Statistics of every sensor, over all the samples and over the latest `window` ones.
+pub struct SensorAggregator {
+    window: usize,
+    running: [RunningStats; SENSORS],
+    recent: VecDeque<SensorData>,
+}
+
+impl SensorAggregator {
+    pub fn new(window: usize) -> Self {
+        Self { window, running: [RunningStats::new(); SENSORS], recent: VecDeque::with_capacity(window) }
+    }
+
+    pub fn push(&mut self, data: &SensorData) {
+        for (running, &value) in self.running.iter_mut().zip(data.values.iter()) {
+            running.push(value);
+        }
+
+        if self.recent.len() == self.window {
+            self.recent.pop_front();
+        }
+        if self.window > 0 {
+            self.recent.push_back(*data);
+        }
+    }
+
+    /// Wait for at least `min_items` samples from `reader` and add them,
+    /// returns how many were added.
+    pub fn consume(
+        &mut self,
+        reader: &mut CircularBuffer<SensorData, BReader>,
+        min_items: usize,
+    ) -> Result<usize, BufferError> {
+        let data = reader.read_blocking(min_items)?;
+        for sample in data.iter() {
+            self.push(sample);
+        }
+        Ok(data.len())
+    }
+
+    /// Statistics of `sensor` over all the samples, `None` before the first one.
+    pub fn total(&self, sensor: usize) -> Option<SensorStats> {
+        self.running[sensor].stats()
+    }
+
+    /// Statistics of `sensor` over the latest `window` samples.
+    pub fn window(&self, sensor: usize) -> Option<SensorStats> {
+        let mut running = RunningStats::new();
+        for data in self.recent.iter() {
+            running.push(data.values[sensor]);
+        }
+        running.stats()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use labs_common::SensorData;
+
+    use crate::aggregator::SensorAggregator;
+    use crate::shared::new_buffer;
+
+    fn sample(value: f32) -> SensorData {
+        SensorData { values: [value; 10], ..SensorData::default() }
+    }
+
+    #[test]
+    fn aggregator_test() {
+        let mut aggregator = SensorAggregator::new(2);
+        assert_eq!(aggregator.total(0), None);
+
+        for value in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            aggregator.push(&sample(value));
+        }
+        let total = aggregator.total(3).unwrap();
+        assert_eq!((total.count, total.min, total.max, total.mean), (8, 2.0, 9.0, 5.0));
+        assert!((total.stddev - 2.0).abs() < 1e-6);
+
+        let window = aggregator.window(3).unwrap();
+        assert_eq!((window.count, window.min, window.max, window.mean), (2, 7.0, 9.0, 8.0));
+        assert!((window.stddev - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn consume_test() {
+        let (mut reader, mut writer) = new_buffer(4);
+        writer.write_all(&[sample(1.0), sample(3.0)]);
+
+        let mut aggregator = SensorAggregator::new(4);
+        assert_eq!(aggregator.consume(&mut reader, 1), Ok(2));
+        assert_eq!(aggregator.total(9).unwrap().mean, 2.0);
+
+        drop(writer);
+        assert!(aggregator.consume(&mut reader, 1).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+//! Live statistics of the sensors: the samples read from the circular buffer set an input
+//! cell per sensor in a `Reactor`, whose compute cells keep the statistics of the latest
+//! samples and raise an alarm, printed by a callback, while their average is too high.
+
+// the dashboard only uses part of the buffer API
+#[allow(dead_code)]
+mod shared;
+
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+use labs_common::{RunningStats, SensorData, SensorStats, SENSORS};
+use react::{CellId, InputCellId, Reactor};
+use shared::{BReader, BWriter, CircularBuffer};
+
+/// Samples written by the producer before it stops.
+const SAMPLES: u32 = 60;
+/// Samples of the rolling statistics.
+const WINDOW: usize = 10;
+/// The alarm of a sensor is raised while the rolling average is above this.
+const THRESHOLD: f32 = 80.0;
+/// Sensor with a spike of `SPIKE` during the samples `SPIKE_SAMPLES`.
+const SPIKE_SENSOR: usize = 3;
+const SPIKE: f32 = 80.0;
+const SPIKE_SAMPLES: std::ops::Range<u32> = 20..35;
+
+/// Value of a cell of the dashboard, every cell of a reactor has the same type.
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    /// The latest `WINDOW` samples of a sensor.
+    Window(Vec<f32>),
+    /// Statistics of the window, `None` before the first sample.
+    Stats(Option<SensorStats>),
+    Alarm(bool),
+}
+
+fn window_stats(cell: &Cell) -> Cell {
+    let Cell::Window(window) = cell else { unreachable!("stats of a window") };
+    let mut running = RunningStats::new();
+    for &value in window {
+        running.push(value);
+    }
+    Cell::Stats(running.stats())
+}
+
+fn alarm(cell: &Cell) -> Cell {
+    let Cell::Stats(stats) = cell else { unreachable!("alarm of the statistics") };
+    Cell::Alarm(stats.is_some_and(|stats| stats.mean > THRESHOLD))
+}
+
+fn producer(mut writer: CircularBuffer<SensorData, BWriter>) {
+    for seq in 1..=SAMPLES {
+        thread::sleep(Duration::from_millis(50));
+        let mut values: [f32; SENSORS] = std::array::from_fn(|i| (5 * i) as f32 + ((seq as usize * 7 + i * 3) % 11) as f32);
+        if SPIKE_SAMPLES.contains(&seq) {
+            values[SPIKE_SENSOR] += SPIKE;
+        }
+        let data = SensorData { seq, values, timestamp: labs_common::unix_millis() };
+        if writer.write_blocking(data).is_err() {
+            break;
+        }
+    }
+    // dropping the writer lets the consumer know there is nothing else to read
+}
+
+/// Send the window of every sensor to the reactor after each sample read.
+fn consumer(mut reader: CircularBuffer<SensorData, BReader>, inputs: &[InputCellId], sender: Sender<(InputCellId, Cell)>) {
+    let mut windows = vec![VecDeque::with_capacity(WINDOW); SENSORS];
+    while let Ok(data) = reader.read_blocking(1) {
+        for sample in data {
+            for ((window, &input), value) in windows.iter_mut().zip(inputs).zip(sample.values) {
+                if window.len() == WINDOW {
+                    window.pop_front();
+                }
+                window.push_back(value);
+                sender.send((input, Cell::Window(window.iter().copied().collect()))).unwrap();
+            }
+        }
+    }
+}
+
+fn main() {
+    let mut reactor = Reactor::new();
+    let mut inputs = Vec::new();
+    let mut stats = Vec::new();
+    for sensor in 0..SENSORS {
+        let input = reactor.create_input(Cell::Window(Vec::new()));
+        let window = reactor.create_compute(&[CellId::Input(input)], |v| window_stats(&v[0])).unwrap();
+        let alarm = reactor.create_compute(&[CellId::Compute(window)], |v| alarm(&v[0])).unwrap();
+        reactor.add_callback(alarm, move |cell| match cell {
+            Cell::Alarm(true) => println!("sensor {:2}: ALARM, average above {}", sensor, THRESHOLD),
+            _ => println!("sensor {:2}: back to normal", sensor),
+        });
+        inputs.push(input);
+        stats.push(window);
+    }
+
+    let (reader, writer) = shared::new_buffer(WINDOW);
+    let (sender, receiver) = mpsc::channel();
+    let updates = thread::scope(|s| {
+        s.spawn(move || producer(writer));
+        s.spawn(|| consumer(reader, &inputs, sender));
+        // the reactor stays on this thread, the consumer drives it through the channel
+        reactor.feed(receiver)
+    });
+
+    println!("{} updates, statistics of the latest {} samples:", updates, WINDOW);
+    for (sensor, &cell) in stats.iter().enumerate() {
+        if let Some(Cell::Stats(Some(stats))) = reactor.value(CellId::Compute(cell)) {
+            println!("sensor {:2}: max {}; min {}; avg {}; stddev {:.2};",
+                sensor, stats.max, stats.min, stats.mean, stats.stddev
+            );
+        }
+    }
+}
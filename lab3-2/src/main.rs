@@ -1,29 +1,38 @@
+// the demo only uses part of the buffer API
+#[allow(dead_code)]
 mod shared;
+mod aggregator;
+use std::io::{self, BufReader};
+use std::net::TcpStream;
+use std::process;
 use std::time::Duration;
 
-use shared::{CircularBuffer, SensorData, BWriter, BReader};
+use aggregator::SensorAggregator;
+use labs_common::{read_frame, SensorData, SENSORS};
+use shared::{CircularBuffer, BWriter, BReader, FullPolicy};
 
-fn print_sensor(data: &Vec<shared::SensorData>) {
-    for i in 0..10 {
-        println!("sensor {:2}: max {}; min {}; avg {};",
-            i,
-            data.into_iter().map(|d| d.values[i]).reduce(f32::max).unwrap(),
-            data.into_iter().map(|d| d.values[i]).reduce(f32::min).unwrap(),
-            data.into_iter().map(|d| d.values[i]).sum::<f32>() / data.len() as f32
+/// Samples kept in the buffer, the consumer reads them all at once.
+const CAPACITY: usize = 10;
+
+fn print_sensor(aggregator: &SensorAggregator) {
+    for i in 0..SENSORS {
+        let (window, total) = match (aggregator.window(i), aggregator.total(i)) {
+            (Some(window), Some(total)) => (window, total),
+            _ => return,
+        };
+        println!("sensor {:2}: max {}; min {}; avg {}; stddev {:.2}; overall avg {};",
+            i, window.max, window.min, window.mean, window.stddev, total.mean
         );
     }
 }
 
 fn consumer(reader: &mut CircularBuffer<SensorData, BReader>) {
-    loop {
-        std::thread::sleep(Duration::from_secs(10));
-
-        let data = reader.read_data();
-        
-        if let Some(data) = data {
-            println!("{:#?}", data);
-            print_sensor(&data);
-        }
+    let mut aggregator = SensorAggregator::new(CAPACITY);
+    // woken up by the producer once the buffer is full, until the producer is gone
+    while aggregator.consume(reader, CAPACITY).is_ok() {
+        print_sensor(&aggregator);
+        let stats = reader.stats();
+        println!("read {} of {} samples, {} overwritten", stats.read, stats.written, stats.overwritten);
     }
 }
 
@@ -35,7 +44,7 @@ fn producer(writer: &mut CircularBuffer<SensorData, BWriter>) {
         let data = SensorData {
             seq: seq.next().unwrap(),
             values,
-            timestamp: 0,
+            timestamp: labs_common::unix_millis(),
         };
 
         if let Err(_) = writer.write_data(data) {
@@ -49,12 +58,74 @@ fn producer(writer: &mut CircularBuffer<SensorData, BWriter>) {
     }
 }
 
+/// Write the samples streamed by the producer listening on `addr`, until it disconnects.
+/// The writer is dropped then, so that the consumer ends after reading the last ones.
+fn receiver(addr: &str, mut writer: CircularBuffer<SensorData, BWriter>) -> io::Result<()> {
+    let mut stream = BufReader::new(TcpStream::connect(addr)?);
+    while let Some(data) = read_frame(&mut stream)? {
+        if writer.write_data(data).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
 
+/// Serve the counters of the buffer at `http://<addr>/metrics`.
+#[cfg(feature = "metrics")]
+fn serve_metrics(addr: &str, buffer: &CircularBuffer<SensorData, BWriter>) -> io::Result<()> {
+    let registry = labs_metrics::Registry::new();
+    buffer.register_metrics(&registry, "buffer");
+    println!("metrics on http://{}/metrics", registry.serve(addr)?);
+    Ok(())
+}
+
+#[cfg(not(feature = "metrics"))]
+fn serve_metrics(_addr: &str, _buffer: &CircularBuffer<SensorData, BWriter>) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "built without the metrics feature"))
+}
+
+/// Values of `--connect <addr>` and `--metrics <addr>`, `None` if the arguments are wrong.
+fn parse_args() -> Option<(Option<String>, Option<String>)> {
+    let (mut connect, mut metrics) = (None, None);
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args.next()?;
+        match flag.as_str() {
+            "--connect" => connect = Some(value),
+            "--metrics" => metrics = Some(value),
+            _ => return None,
+        }
+    }
+    Some((connect, metrics))
+}
 
 fn main() {
-    let (mut r,mut w) = shared::new_buffer();
-    std::thread::scope(|s| {
+    // `--connect <addr>` reads the samples of a lab2-1 `producer --listen <addr>`,
+    // possibly on another machine, instead of making them
+    let Some((connect, metrics)) = parse_args() else {
+        eprintln!("usage: lab3-2 [--connect <addr>] [--metrics <addr>]");
+        process::exit(2);
+    };
+
+    let (mut r,mut w) = shared::new_buffer(CAPACITY);
+    // keep the latest samples if the consumer is late
+    w.set_full_policy(FullPolicy::OverwriteOldest);
+    if let Some(Err(e)) = metrics.map(|addr| serve_metrics(&addr, &w)) {
+        eprintln!("metrics: {}", e);
+        process::exit(1);
+    }
+    let received = std::thread::scope(|s| {
         s.spawn(|| consumer(&mut r));
-        s.spawn(|| producer(&mut w));
+        match &connect {
+            Some(addr) => receiver(addr, w),
+            None => {
+                producer(&mut w);
+                Ok(())
+            }
+        }
     });
+    if let (Err(e), Some(addr)) = (received, connect) {
+        eprintln!("{}: {}", addr, e);
+        process::exit(1);
+    }
 }
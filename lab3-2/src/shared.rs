@@ -1,89 +1,892 @@
-use std::error::Error;
 use std::marker::PhantomData;
-use std::sync::{Arc, Mutex};
+use std::mem::MaybeUninit;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::task::Waker;
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone, Copy, Default)]
-pub struct SensorData {
-    pub seq: u32, // sequenza letture
-    pub values: [f32; 10],
-    pub timestamp: u32,
+use crossbeam::channel::{Receiver, Sender, TrySendError};
+
+pub use labs_error::BufferError;
+
+#[cfg(feature = "async")]
+pub mod stream;
+
+/// What `write_data` does when the buffer is full, that is when the slowest reader
+/// has `capacity` elements to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FullPolicy {
+    /// `write_data` fails with `BufferError::Full` and the new data is dropped.
+    #[default]
+    Reject,
+    /// The oldest data is replaced, the buffer keeps the latest `capacity` elements.
+    /// A lagging reader skips the elements it missed.
+    OverwriteOldest,
+}
+
+/// Counters of the buffer since it was created, the reads are summed over every reader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BufferStats {
+    pub written: usize,
+    pub read: usize,
+    /// Writes failed because the buffer was full, with `FullPolicy::Reject`.
+    pub rejected: usize,
+    /// Elements skipped by the lagging readers, with `FullPolicy::OverwriteOldest`.
+    pub overwritten: usize,
+    /// Most elements ever waiting for the slowest reader.
+    pub high_water: usize,
 }
 
 pub struct BReader {}
+/// Reader of `new_broadcast_buffer`, sharing the elements with the other readers.
+pub struct BBroadcast {}
 pub struct BWriter {}
-pub trait BufferMode {}
-impl BufferMode for BReader {}
-impl BufferMode for BWriter {}
-
-struct BufferHead<T>
-where T: Copy + Default {
-    len: usize,
-    index: usize,
+pub trait BufferMode {
+    /// Whether the handle is the writer, used when it is dropped.
+    const WRITER: bool;
+}
+impl BufferMode for BReader { const WRITER: bool = false; }
+impl BufferMode for BBroadcast { const WRITER: bool = false; }
+impl BufferMode for BWriter { const WRITER: bool = true; }
+
+/// Mode of the readers: how they return an element the other readers still have to read,
+/// the last one to read it moves it out of the buffer.
+pub trait ReadMode<T>: BufferMode {
+    fn share(data: &T) -> T;
+}
+
+/// The only reader is always the last one, nothing is cloned.
+impl<T> ReadMode<T> for BReader {
+    fn share(_: &T) -> T {
+        unreachable!("the only reader always moves the elements")
+    }
+}
+
+impl<T: Clone> ReadMode<T> for BBroadcast {
+    fn share(data: &T) -> T {
+        data.clone()
+    }
+}
+
+struct BufferHead<T> {
     capacity: usize,
-    data: [T; 10],
+    policy: FullPolicy,
+    /// Only the slots from `tail` to `written` hold an element.
+    data: Vec<MaybeUninit<T>>,
+    /// Elements ever written, the next one goes in `data[written % capacity]`.
+    written: usize,
+    /// Elements read by every reader and dropped, counted like `written`.
+    tail: usize,
+    /// Elements read by every reader, counted like `written`, `None` once it is dropped.
+    cursors: Vec<Option<usize>>,
+    /// Tasks of the readers polled while there was nothing to read, woken by the writer.
+    wakers: Vec<Option<Waker>>,
+    writer_alive: bool,
+    stats: BufferStats,
+}
+
+/// Head shared by the reader and the writer, with the conditions they wait on.
+struct Shared<T> {
+    head: Mutex<BufferHead<T>>,
+    /// Notified when data is written.
+    not_empty: Condvar,
+    /// Notified when data is read.
+    not_full: Condvar,
+    /// Channels of `notifications`, sent to with the conditions.
+    data_watchers: Mutex<Vec<Sender<()>>>,
+    space_watchers: Mutex<Vec<Sender<()>>>,
 }
 
-pub struct CircularBuffer<T, Mode: BufferMode>
-where T: Copy + Default {
-    head: Arc<Mutex<BufferHead<T>>>,
+impl<T> Shared<T> {
+    fn notify_data(&self) {
+        self.not_empty.notify_all();
+        notify_watchers(&self.data_watchers);
+    }
+
+    fn notify_space(&self) {
+        self.not_full.notify_all();
+        notify_watchers(&self.space_watchers);
+    }
+}
+
+fn notify_watchers(watchers: &Mutex<Vec<Sender<()>>>) {
+    // a full channel already has a notification pending, the dropped receivers are forgotten
+    watchers
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .retain(|watcher| !matches!(watcher.try_send(()), Err(TrySendError::Disconnected(_))));
+}
+
+pub struct CircularBuffer<T, Mode: BufferMode> {
+    shared: Arc<Shared<T>>,
+    /// Cursor of the reader in `BufferHead::cursors`, unused by the writer.
+    reader: usize,
     mode: PhantomData<Mode>
 }
 
-impl<T> BufferHead<T>
-where T: Copy + Default {
-    pub fn default() -> Self {
-        Self { len: 0, index: 0, capacity: 10, data: [T::default(); 10] }
+impl<T> BufferHead<T> {
+    fn with_capacity(capacity: usize, readers: usize) -> Self {
+        Self {
+            capacity,
+            policy: FullPolicy::default(),
+            data: (0..capacity).map(|_| MaybeUninit::uninit()).collect(),
+            written: 0,
+            tail: 0,
+            cursors: vec![Some(0); readers],
+            wakers: vec![None; readers],
+            writer_alive: true,
+            stats: BufferStats::default(),
+        }
+    }
+
+    /// Elements not read yet by `reader`.
+    fn len(&self, reader: usize) -> usize {
+        self.written - self.cursors[reader].unwrap()
+    }
+
+    /// Elements not read yet by the slowest reader, the ones that can't be overwritten.
+    fn max_len(&self) -> usize {
+        self.cursors.iter().flatten().map(|cursor| self.written - cursor).max().unwrap_or(0)
+    }
+
+    fn readers_alive(&self) -> bool {
+        self.cursors.iter().any(Option::is_some)
+    }
+
+    /// Cursor of the slowest reader, every element before it can be dropped.
+    fn min_cursor(&self) -> usize {
+        self.cursors.iter().flatten().copied().min().unwrap_or(self.written)
+    }
+
+    /// The element at `index`, it must be between `tail` and `written`.
+    fn get(&self, index: usize) -> &T {
+        debug_assert!((self.tail..self.written).contains(&index));
+        unsafe { self.data[index % self.capacity].assume_init_ref() }
+    }
+
+    /// Elements from `from` to `written` in the order they were written,
+    /// as one or two slices when they wrap around the end of the buffer.
+    fn as_slices(&self, from: usize) -> (&[T], &[T]) {
+        debug_assert!((self.tail..=self.written).contains(&from));
+        let start = from % self.capacity;
+        let end = start + (self.written - from);
+
+        let (first, second) = if end <= self.capacity {
+            (&self.data[start..end], &self.data[..0])
+        } else {
+            (&self.data[start..], &self.data[..end - self.capacity])
+        };
+        // the slots after `tail` are initialized
+        unsafe { (slice_assume_init(first), slice_assume_init(second)) }
+    }
+
+    /// Drop the elements read by every reader.
+    fn release(&mut self) {
+        let min_cursor = self.min_cursor();
+        while self.tail < min_cursor {
+            let pos = self.tail % self.capacity;
+            unsafe { self.data[pos].assume_init_drop() };
+            self.tail += 1;
+        }
+    }
+
+    /// Read the oldest element not read yet by `reader`, moved out of the buffer
+    /// if the other readers already read it, shared by `Mode` otherwise.
+    fn pop<Mode: ReadMode<T>>(&mut self, reader: usize) -> Option<T> {
+        let cursor = self.cursors[reader].unwrap();
+        if cursor == self.written {
+            return None;
+        }
+
+        self.cursors[reader] = Some(cursor + 1);
+        self.stats.read += 1;
+        if cursor == self.tail && self.min_cursor() > cursor {
+            self.tail += 1;
+            Some(unsafe { self.data[cursor % self.capacity].assume_init_read() })
+        } else {
+            Some(Mode::share(self.get(cursor)))
+        }
+    }
+
+    fn wake_readers(&mut self) {
+        self.wakers.iter_mut().filter_map(Option::take).for_each(Waker::wake);
+    }
+
+    fn take_all<Mode: ReadMode<T>>(&mut self, reader: usize) -> Result<Vec<T>, BufferError> {
+        let data = std::iter::from_fn(|| self.pop::<Mode>(reader)).collect::<Vec<_>>();
+
+        if data.is_empty() && !self.writer_alive {
+            return Err(BufferError::Disconnected);
+        }
+        Ok(data)
     }
 }
 
-pub fn new_buffer<T>() -> (CircularBuffer<T, BReader>, CircularBuffer<T, BWriter>)
-where T: Copy + Default {
-    let head = Arc::new(Mutex::new(BufferHead::default()));
-    (CircularBuffer::<T, BReader>::new(head.clone()), CircularBuffer::<T, BWriter>::new(head))
+impl<T> Drop for BufferHead<T> {
+    fn drop(&mut self) {
+        self.cursors.fill(None);
+        self.release();
+    }
 }
 
-impl<T> CircularBuffer<T, BReader>
-where T: Copy + Default {
-    fn new(head: Arc<Mutex<BufferHead<T>>>) -> Self {
-        Self { head, mode: PhantomData::<BReader> }
+/// Cast slots known to be initialized to their elements.
+unsafe fn slice_assume_init<T>(slots: &[MaybeUninit<T>]) -> &[T] {
+    &*(slots as *const [MaybeUninit<T>] as *const [T])
+}
+
+/// Reader and writer of a buffer of `capacity` elements.
+pub fn new_buffer<T>(capacity: usize) -> (CircularBuffer<T, BReader>, CircularBuffer<T, BWriter>) {
+    let (mut readers, writer) = with_readers(capacity, 1);
+    (readers.pop().unwrap(), writer)
+}
+
+/// Buffer of `capacity` elements where every one of the `n_readers` readers
+/// sees every element. The writer is stopped by the slowest reader, unless
+/// it is set to `FullPolicy::OverwriteOldest`, making the lagging readers skip.
+/// The elements are cloned for the readers that are not the last to read them.
+pub fn new_broadcast_buffer<T>(capacity: usize, n_readers: usize) -> (Vec<CircularBuffer<T, BBroadcast>>, CircularBuffer<T, BWriter>) {
+    with_readers(capacity, n_readers)
+}
+
+fn with_readers<T, Mode: BufferMode>(capacity: usize, n_readers: usize) -> (Vec<CircularBuffer<T, Mode>>, CircularBuffer<T, BWriter>) {
+    assert!(capacity > 0, "the buffer capacity must be at least 1");
+
+    let shared = Arc::new(Shared {
+        head: Mutex::new(BufferHead::with_capacity(capacity, n_readers)),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+        data_watchers: Mutex::new(Vec::new()),
+        space_watchers: Mutex::new(Vec::new()),
+    });
+    let readers = (0..n_readers)
+        .map(|reader| CircularBuffer::new(shared.clone(), reader))
+        .collect();
+    (readers, CircularBuffer::new(shared, 0))
+}
+
+/// Wait on `condvar` while `waiting` holds, at most until `deadline` if given.
+/// Returns the guard and `false` if the deadline was reached first.
+fn wait_while<'a, T, F>(
+    condvar: &Condvar,
+    mut head: MutexGuard<'a, BufferHead<T>>,
+    deadline: Option<Instant>,
+    mut waiting: F,
+) -> (MutexGuard<'a, BufferHead<T>>, bool)
+where F: FnMut(&BufferHead<T>) -> bool {
+    while waiting(&head) {
+        match deadline {
+            None => head = condvar.wait(head).unwrap(),
+            Some(deadline) => {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                if timeout.is_zero() {
+                    return (head, false);
+                }
+                head = condvar.wait_timeout(head, timeout).unwrap().0;
+            }
+        }
     }
+    (head, true)
+}
 
-    pub fn read_data(&mut self) -> Option<Vec<T>> {
-        let mut data = Vec::new();
+impl<T, Mode: BufferMode> CircularBuffer<T, Mode> {
+    fn new(shared: Arc<Shared<T>>, reader: usize) -> Self {
+        Self { shared, reader, mode: PhantomData }
+    }
 
-        let mut head = self.head.lock().unwrap();
+    /// Elements not read yet: by this reader, or by the slowest reader for the writer.
+    fn occupancy(&self, head: &BufferHead<T>) -> usize {
+        if Mode::WRITER { head.max_len() } else { head.len(self.reader) }
+    }
 
-        for index in 0..head.len {
-            let pos = (index + head.index) % head.capacity;
+    pub fn len(&self) -> usize {
+        let head = self.shared.head.lock().unwrap();
+        self.occupancy(&head)
+    }
 
-            data.push(head.data[pos].clone());
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the next write is rejected or overwrites the oldest element.
+    pub fn is_full(&self) -> bool {
+        let head = self.shared.head.lock().unwrap();
+        self.occupancy(&head) == head.capacity
+    }
+
+    /// Receiver of a message every time this handle may proceed: when data is written
+    /// for a reader, when data is read for the writer, and when the other side is dropped.
+    /// Many events may be merged into one message, to be used with `select!` together
+    /// with other channels.
+    pub fn notifications(&self) -> Receiver<()> {
+        let (watcher, notifications) = crossbeam::channel::bounded(1);
+
+        let head = self.shared.head.lock().unwrap();
+        // the condition may already hold, it would not be notified again
+        let ready = if Mode::WRITER { head.max_len() < head.capacity } else { head.len(self.reader) > 0 };
+        if ready {
+            watcher.send(()).unwrap();
         }
-        head.index = 0;
-        head.len = 0;
+        let watchers = if Mode::WRITER { &self.shared.space_watchers } else { &self.shared.data_watchers };
+        watchers.lock().unwrap().push(watcher);
 
-        Some(data)
+        notifications
+    }
+
+    pub fn stats(&self) -> BufferStats {
+        let head = self.shared.head.lock().unwrap();
+        BufferStats { written: head.written, ..head.stats }
+    }
+
+    /// The latest `n` elements not read yet, from the oldest of them, without reading them.
+    pub fn peek_latest(&self, n: usize) -> Vec<T>
+    where T: Clone {
+        let head = self.shared.head.lock().unwrap();
+        let count = n.min(self.occupancy(&head));
+
+        (head.written - count..head.written).map(|index| head.get(index).clone()).collect()
+    }
+
+    /// Register `stats`, the capacity and the elements waiting for the slowest reader with
+    /// `registry` as `<prefix>_*`, they are still read after the buffer is dropped.
+    #[cfg(feature = "metrics")]
+    pub fn register_metrics(&self, registry: &labs_metrics::Registry, prefix: &str)
+    where T: Send + 'static {
+        let metric = |name: &str| format!("{}_{}", prefix, name);
+        let stat = |field: fn(&BufferHead<T>) -> usize| {
+            let shared = self.shared.clone();
+            // a reader or a writer panicking leaves the counters consistent
+            move || field(&shared.head.lock().unwrap_or_else(PoisonError::into_inner))
+        };
+
+        let written = stat(|head| head.written);
+        registry.counter_fn(&metric("written_total"), "Elements written.", move || written() as u64);
+        let read = stat(|head| head.stats.read);
+        registry.counter_fn(&metric("read_total"), "Elements read, summed over every reader.", move || read() as u64);
+        let rejected = stat(|head| head.stats.rejected);
+        registry.counter_fn(&metric("rejected_total"), "Writes failed because the buffer was full.", move || rejected() as u64);
+        let overwritten = stat(|head| head.stats.overwritten);
+        registry.counter_fn(&metric("overwritten_total"), "Elements skipped by the lagging readers.", move || overwritten() as u64);
+        let len = stat(BufferHead::max_len);
+        registry.gauge_fn(&metric("len"), "Elements not read yet by the slowest reader.", move || len() as f64);
+        let high_water = stat(|head| head.stats.high_water);
+        registry.gauge_fn(&metric("high_water"), "Most elements ever waiting for the slowest reader.", move || high_water() as f64);
+        let capacity = stat(|head| head.capacity);
+        registry.gauge_fn(&metric("capacity"), "Elements the buffer holds.", move || capacity() as f64);
     }
 }
 
-impl<T> CircularBuffer<T, BWriter> 
-where T: Copy + Default {
-    fn new(head: Arc<Mutex<BufferHead<T>>>) -> Self {
-        Self { head, mode: PhantomData::<BWriter> }
+/// The elements are moved out of the buffer by the last reader to read them,
+/// the broadcast readers clone them for the others.
+impl<T, Mode: ReadMode<T>> CircularBuffer<T, Mode> {
+    /// Read every element, fails once the writer is dropped and nothing is left.
+    pub fn read_data(&mut self) -> Result<Vec<T>, BufferError> {
+        let data = self.shared.head.lock().unwrap().take_all::<Mode>(self.reader);
+        self.shared.notify_space();
+
+        data
+    }
+
+    /// Wait until there are at least `min_items` elements, or the buffer is full,
+    /// then read all of them. Once the writer is dropped the last elements are read
+    /// whatever their number.
+    pub fn read_blocking(&mut self, min_items: usize) -> Result<Vec<T>, BufferError> {
+        self.read_until(min_items, None).map(Option::unwrap)
+    }
+
+    /// Like `read_blocking`, `None` if `timeout` elapses first, the elements are left in the buffer.
+    pub fn read_timeout(&mut self, min_items: usize, timeout: Duration) -> Result<Option<Vec<T>>, BufferError> {
+        self.read_until(min_items, Some(Instant::now() + timeout))
+    }
+
+    fn read_until(&mut self, min_items: usize, deadline: Option<Instant>) -> Result<Option<Vec<T>>, BufferError> {
+        let head = self.shared.head.lock().unwrap();
+        let (mut head, ready) = wait_while(&self.shared.not_empty, head, deadline, |head| {
+            head.writer_alive && head.len(self.reader) < min_items.min(head.capacity)
+        });
+        if !ready {
+            return Ok(None);
+        }
+
+        let data = head.take_all::<Mode>(self.reader);
+        drop(head);
+        self.shared.notify_space();
+
+        data.map(Some)
+    }
+
+    /// Iterate over the elements while reading them, without collecting them.
+    /// The buffer stays locked until the iterator is dropped.
+    pub fn drain(&mut self) -> Drain<'_, T, Mode> {
+        Drain {
+            head: self.shared.head.lock().unwrap(),
+            reader: self.reader,
+            shared: &self.shared,
+            mode: PhantomData,
+        }
+    }
+
+    /// Access the elements in place instead of copying them, they are read
+    /// when the guard is dropped. The buffer stays locked until then.
+    pub fn read_slices(&mut self) -> ReadGuard<'_, T> {
+        ReadGuard {
+            head: self.shared.head.lock().unwrap(),
+            reader: self.reader,
+            shared: &self.shared,
+        }
+    }
+}
+
+impl<T> CircularBuffer<T, BWriter>  {
+    pub fn set_full_policy(&mut self, policy: FullPolicy) {
+        self.shared.head.lock().unwrap().policy = policy;
+    }
+
+    pub fn write_data(&mut self, data: T) -> Result<(), BufferError> {
+        let mut head = self.shared.head.lock().unwrap();
+        let written = Self::push(&mut head, data);
+        drop(head);
+        self.shared.notify_data();
+
+        written
+    }
+
+    /// Write as many elements of `data` as fit taking the lock once,
+    /// returns how many were written.
+    pub fn write_all(&mut self, data: &[T]) -> usize
+    where T: Clone {
+        let mut head = self.shared.head.lock().unwrap();
+        let written = data.iter().take_while(|data| Self::push(&mut head, (*data).clone()).is_ok()).count();
+        drop(head);
+        self.shared.notify_data();
+
+        written
+    }
+
+    /// Wait until there is space for `data`, whatever the full policy.
+    pub fn write_blocking(&mut self, data: T) -> Result<(), BufferError> {
+        self.write_until(data, None)
     }
 
-    pub fn write_data(&mut self, data: T) -> Result<(), Box<dyn Error>> {
-        let mut head = self.head.lock().unwrap();
+    /// Like `write_blocking`, fails if `timeout` elapses first and `data` is dropped.
+    pub fn write_timeout(&mut self, data: T, timeout: Duration) -> Result<(), BufferError> {
+        self.write_until(data, Some(Instant::now() + timeout))
+    }
+
+    fn write_until(&mut self, data: T, deadline: Option<Instant>) -> Result<(), BufferError> {
+        let head = self.shared.head.lock().unwrap();
+        let (mut head, ready) = wait_while(&self.shared.not_full, head, deadline, |head| {
+            head.max_len() == head.capacity
+        });
+        if !ready {
+            return Err(BufferError::Timeout);
+        }
+
+        let written = Self::push(&mut head, data);
+        drop(head);
+        self.shared.notify_data();
+
+        written
+    }
+
+    fn push(head: &mut BufferHead<T>, data: T) -> Result<(), BufferError> {
+        if !head.readers_alive() {
+            return Err(BufferError::Disconnected);
+        }
 
-        // if buffer is full don't write anything.
-        if head.len != head.capacity {
-            let pos = (head.index + head.len) % head.capacity;
+        // if buffer is full don't write anything, unless the oldest data can be replaced.
+        if head.max_len() == head.capacity {
+            if head.policy != FullPolicy::OverwriteOldest {
+                head.stats.rejected += 1;
+                return Err(BufferError::Full);
+            }
 
-            head.data[pos] = data;
-        } else { 
-            return Err("Buffer was full".into());
+            // the lagging readers lose the oldest element
+            let oldest = head.written + 1 - head.capacity;
+            for cursor in head.cursors.iter_mut().flatten() {
+                if *cursor < oldest {
+                    *cursor = oldest;
+                    head.stats.overwritten += 1;
+                }
+            }
+            head.release();
         }
-        head.len += 1;
+
+        let pos = head.written % head.capacity;
+        head.data[pos].write(data);
+        head.written += 1;
+        head.stats.high_water = head.stats.high_water.max(head.max_len());
+        head.wake_readers();
 
         Ok(())
     }
 }
+
+/// Iterator returned by `CircularBuffer::drain`.
+pub struct Drain<'a, T, Mode> {
+    head: MutexGuard<'a, BufferHead<T>>,
+    reader: usize,
+    shared: &'a Shared<T>,
+    mode: PhantomData<Mode>,
+}
+
+impl<T, Mode: ReadMode<T>> Iterator for Drain<'_, T, Mode> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        // every element is read as soon as it is returned
+        self.head.pop::<Mode>(self.reader)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.head.len(self.reader);
+        (len, Some(len))
+    }
+}
+
+impl<T, Mode> Drop for Drain<'_, T, Mode> {
+    fn drop(&mut self) {
+        // the writer is woken up once the lock is released
+        self.shared.notify_space();
+    }
+}
+
+/// Guard returned by `CircularBuffer::read_slices`.
+pub struct ReadGuard<'a, T> {
+    head: MutexGuard<'a, BufferHead<T>>,
+    reader: usize,
+    shared: &'a Shared<T>,
+}
+
+impl<T> ReadGuard<'_, T> {
+    /// The elements not read yet, from the oldest: the second slice is not empty
+    /// when they wrap around the end of the buffer.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        self.head.as_slices(self.head.cursors[self.reader].unwrap())
+    }
+
+    pub fn len(&self) -> usize {
+        self.head.len(self.reader)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for ReadGuard<'_, T> {
+    fn drop(&mut self) {
+        let written = self.head.written;
+        self.head.stats.read += self.head.len(self.reader);
+        self.head.cursors[self.reader] = Some(written);
+        self.head.release();
+        self.shared.notify_space();
+    }
+}
+
+impl<T, Mode: BufferMode> Drop for CircularBuffer<T, Mode> {
+    fn drop(&mut self) {
+        // a handle may be dropped while unwinding from a panic holding the lock
+        let mut head = self.shared.head.lock().unwrap_or_else(PoisonError::into_inner);
+        if Mode::WRITER {
+            head.writer_alive = false;
+            head.wake_readers();
+        } else {
+            head.cursors[self.reader] = None;
+            head.release();
+        }
+        drop(head);
+
+        // wake up the other side, it may be waiting for this handle
+        self.shared.notify_data();
+        self.shared.notify_space();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::shared::{new_broadcast_buffer, new_buffer, BufferError, BufferStats, FullPolicy};
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn metrics_test() {
+        let (mut reader, mut writer) = new_buffer::<u32>(4);
+        let registry = labs_metrics::Registry::new();
+        writer.register_metrics(&registry, "buffer");
+
+        writer.write_all(&[0, 1, 2, 3, 4]);
+        reader.read_data().unwrap();
+        writer.write_data(5).unwrap();
+        drop(writer);
+
+        let text = registry.render();
+        for line in ["buffer_written_total 5", "buffer_read_total 4", "buffer_rejected_total 1", "buffer_len 1", "buffer_capacity 4"] {
+            assert!(text.contains(&format!("{}\n", line)), "{} missing in\n{}", line, text);
+        }
+    }
+
+    #[test]
+    fn full_policy_test() {
+        let (mut reader, mut writer) = new_buffer::<u32>(10);
+
+        for n in 0..12 {
+            let _ = writer.write_data(n);
+        }
+        assert_eq!(writer.write_data(12), Err(BufferError::Full));
+        assert_eq!(reader.read_data().unwrap(), (0..10).collect::<Vec<_>>());
+
+        writer.set_full_policy(FullPolicy::OverwriteOldest);
+        for n in 0..12 {
+            writer.write_data(n).unwrap();
+        }
+        assert_eq!(reader.read_data().unwrap(), (2..12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn blocking_test() {
+        let (mut reader, mut writer) = new_buffer::<u32>(10);
+
+        assert!(reader.read_timeout(1, Duration::from_millis(10)).unwrap().is_none());
+        for n in 0..10 {
+            writer.write_blocking(n).unwrap();
+        }
+        assert_eq!(writer.write_timeout(10, Duration::from_millis(10)), Err(BufferError::Timeout));
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for n in 10..30 {
+                    writer.write_blocking(n).unwrap();
+                }
+            });
+
+            // the writer waits for every read, nothing is lost
+            let mut data = Vec::new();
+            while data.len() < 30 {
+                data.extend(reader.read_blocking(5).unwrap());
+            }
+            assert_eq!(data, (0..30).collect::<Vec<_>>());
+        });
+    }
+
+    #[test]
+    fn capacity_test() {
+        let (mut reader, mut writer) = new_buffer::<u32>(1);
+        writer.write_data(0).unwrap();
+        assert!(writer.write_data(1).is_err());
+        writer.set_full_policy(FullPolicy::OverwriteOldest);
+        writer.write_data(2).unwrap();
+        assert_eq!(reader.read_data().unwrap(), [2]);
+
+        let (mut reader, mut writer) = new_buffer::<u32>(100_000);
+        for n in 0..100_000 {
+            writer.write_data(n).unwrap();
+        }
+        assert!(writer.write_data(100_000).is_err());
+        assert_eq!(reader.read_blocking(100_000).unwrap().len(), 100_000);
+    }
+
+    #[test]
+    fn broadcast_test() {
+        let (mut readers, mut writer) = new_broadcast_buffer::<u32>(4, 2);
+
+        for n in 0..3 {
+            writer.write_data(n).unwrap();
+        }
+        assert_eq!(readers[0].read_data().unwrap(), [0, 1, 2]);
+        writer.write_data(3).unwrap();
+        // the second reader has not read anything yet, it stops the writer
+        assert!(writer.write_data(4).is_err());
+        assert_eq!(readers[0].read_data().unwrap(), [3]);
+        assert_eq!(readers[1].read_data().unwrap(), [0, 1, 2, 3]);
+
+        // the lagging reader skips the overwritten elements
+        writer.set_full_policy(FullPolicy::OverwriteOldest);
+        for n in 4..12 {
+            writer.write_data(n).unwrap();
+        }
+        assert_eq!(readers[0].read_data().unwrap(), [8, 9, 10, 11]);
+        assert_eq!(readers[1].read_data().unwrap(), [8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn disconnected_test() {
+        let (mut reader, mut writer) = new_buffer::<u32>(4);
+        writer.write_data(0).unwrap();
+        drop(writer);
+
+        // the elements written before are still read
+        assert_eq!(reader.read_blocking(4), Ok(vec![0]));
+        assert_eq!(reader.read_data(), Err(BufferError::Disconnected));
+        assert_eq!(reader.read_blocking(1), Err(BufferError::Disconnected));
+
+        let (mut readers, mut writer) = new_broadcast_buffer::<u32>(1, 2);
+        writer.write_data(0).unwrap();
+        // the dropped reader does not stop the writer anymore
+        drop(readers.remove(1));
+        assert_eq!(readers[0].read_data(), Ok(vec![0]));
+        writer.write_data(1).unwrap();
+        drop(readers);
+        assert_eq!(writer.write_data(2), Err(BufferError::Disconnected));
+
+        // a blocked reader is woken up by the writer drop
+        let (mut reader, writer) = new_buffer::<u32>(4);
+        std::thread::scope(|s| {
+            s.spawn(|| assert_eq!(reader.read_blocking(1), Err(BufferError::Disconnected)));
+            std::thread::sleep(Duration::from_millis(10));
+            drop(writer);
+        });
+    }
+
+    #[test]
+    fn write_all_drain_test() {
+        let (mut reader, mut writer) = new_buffer::<u32>(4);
+
+        assert_eq!(writer.write_all(&[0, 1, 2, 3, 4, 5]), 4);
+        let mut drain = reader.drain();
+        assert_eq!(drain.size_hint(), (4, Some(4)));
+        assert_eq!(drain.next(), Some(0));
+        drop(drain);
+
+        // the elements not iterated are still in the buffer
+        assert_eq!(writer.write_all(&[4, 5]), 1);
+        assert_eq!(reader.drain().collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert_eq!(reader.drain().next(), None);
+
+        writer.set_full_policy(FullPolicy::OverwriteOldest);
+        assert_eq!(writer.write_all(&[0, 1, 2, 3, 4, 5]), 6);
+        assert_eq!(reader.drain().sum::<u32>(), 2 + 3 + 4 + 5);
+    }
+
+    #[test]
+    fn read_slices_test() {
+        let (mut reader, mut writer) = new_buffer::<u32>(4);
+
+        assert_eq!(writer.write_all(&[0, 1, 2]), 3);
+        {
+            let guard = reader.read_slices();
+            assert_eq!(guard.as_slices(), (&[0, 1, 2][..], &[][..]));
+        }
+        assert!(reader.is_empty());
+
+        // the elements wrap around the end of the buffer
+        assert_eq!(writer.write_all(&[3, 4, 5]), 3);
+        let guard = reader.read_slices();
+        assert_eq!(guard.len(), 3);
+        assert_eq!(guard.as_slices(), (&[3][..], &[4, 5][..]));
+        drop(guard);
+        assert_eq!(writer.len(), 0);
+    }
+
+    #[test]
+    fn stats_test() {
+        let (mut readers, mut writer) = new_broadcast_buffer::<u32>(2, 2);
+
+        assert_eq!(writer.write_all(&[0, 1, 2]), 2);
+        readers[0].read_data().unwrap();
+        writer.set_full_policy(FullPolicy::OverwriteOldest);
+        writer.write_all(&[2, 3]);
+
+        let stats = BufferStats { written: 4, read: 2, rejected: 1, overwritten: 2, high_water: 2 };
+        assert_eq!(writer.stats(), stats);
+        assert_eq!(readers[1].stats(), stats);
+    }
+
+    #[test]
+    fn owned_elements_test() {
+        let (mut readers, mut writer) = new_broadcast_buffer::<String>(2, 2);
+        writer.set_full_policy(FullPolicy::OverwriteOldest);
+
+        for line in ["a", "b", "c"] {
+            writer.write_data(line.to_string()).unwrap();
+        }
+        assert_eq!(readers[0].read_data().unwrap(), ["b", "c"]);
+        writer.write_data("d".to_string()).unwrap();
+        assert_eq!(readers[1].read_data().unwrap(), ["c", "d"]);
+        assert_eq!(readers[0].peek_latest(2), ["d"]);
+
+        // every element is dropped once, whether read, overwritten or left in the buffer
+        let element = Arc::new(());
+        let (mut readers, mut writer) = new_broadcast_buffer(2, 2);
+        writer.set_full_policy(FullPolicy::OverwriteOldest);
+        for _ in 0..3 {
+            writer.write_data(element.clone()).unwrap();
+        }
+        assert_eq!(Arc::strong_count(&element), 3);
+        assert_eq!(readers[0].read_data().unwrap().len(), 2);
+        drop(readers.pop());
+        assert_eq!(Arc::strong_count(&element), 1);
+        writer.write_data(element.clone()).unwrap();
+        drop(writer);
+        drop(readers);
+        assert_eq!(Arc::strong_count(&element), 1);
+    }
+
+    #[test]
+    fn moved_elements_test() {
+        // not `Clone`, the only reader moves every element out of the buffer
+        #[derive(Debug, PartialEq)]
+        struct Line(String);
+
+        let (mut reader, mut writer) = new_buffer(2);
+        writer.set_full_policy(FullPolicy::OverwriteOldest);
+        for line in ["a", "b", "c"] {
+            writer.write_data(Line(line.to_string())).unwrap();
+        }
+        assert_eq!(reader.drain().next(), Some(Line("b".to_string())));
+        assert_eq!(reader.read_data().unwrap(), [Line("c".to_string())]);
+
+        writer.write_data(Line("d".to_string())).unwrap();
+        assert_eq!(reader.read_timeout(1, Duration::ZERO).unwrap(), Some(vec![Line("d".to_string())]));
+    }
+
+    #[test]
+    fn peek_test() {
+        let (mut readers, mut writer) = new_broadcast_buffer::<u32>(4, 2);
+        assert!(writer.is_empty() && readers[0].is_empty());
+
+        writer.write_all(&[0, 1, 2, 3]);
+        assert_eq!(readers[0].peek_latest(2), [2, 3]);
+        assert_eq!(readers[0].peek_latest(10), [0, 1, 2, 3]);
+        assert!(readers[0].is_full());
+        readers[0].read_data().unwrap();
+
+        // the writer is stopped by the slowest reader
+        assert_eq!((readers[0].len(), readers[1].len(), writer.len()), (0, 4, 4));
+        assert!(readers[0].peek_latest(2).is_empty());
+        assert!(writer.is_full());
+        assert_eq!(writer.peek_latest(1), [3]);
+        assert_eq!(readers[1].read_data().unwrap(), [0, 1, 2, 3]);
+        assert!(!writer.is_full());
+    }
+
+    #[test]
+    fn notifications_test() {
+        let (mut reader, mut writer) = new_buffer::<u32>(2);
+        let data = reader.notifications();
+        let space = writer.notifications();
+
+        // there is space from the start
+        assert!(data.try_recv().is_err());
+        assert!(space.try_recv().is_ok());
+
+        writer.write_all(&[0, 1]);
+        writer.write_data(2).unwrap_err();
+        // the writes are merged in a single message
+        assert_eq!(data.len(), 1);
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                crossbeam::select! {
+                    recv(data) -> _ => assert_eq!(reader.read_data().unwrap(), [0, 1]),
+                    recv(crossbeam::channel::after(Duration::from_secs(1))) -> _ => panic!("no data"),
+                }
+            });
+        });
+        space.recv_timeout(Duration::from_secs(1)).unwrap();
+
+        drop(reader);
+        space.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert!(writer.write_data(3).is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+
+use super::{BufferMode, CircularBuffer, ReadMode};
+
+impl<T, Mode: ReadMode<T>> CircularBuffer<T, Mode> {
+    /// Read the elements from async code, one at a time. The stream ends once the
+    /// writer is dropped and everything was read.
+    pub fn into_stream(self) -> BufferStream<T, Mode> {
+        BufferStream { reader: self }
+    }
+}
+
+/// Stream returned by `CircularBuffer::into_stream`.
+pub struct BufferStream<T, Mode: BufferMode> {
+    reader: CircularBuffer<T, Mode>,
+}
+
+impl<T, Mode: ReadMode<T>> Stream for BufferStream<T, Mode> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let reader = &self.reader;
+        let mut head = reader.shared.head.lock().unwrap();
+
+        if let Some(data) = head.pop::<Mode>(reader.reader) {
+            drop(head);
+            reader.shared.notify_space();
+            return Poll::Ready(Some(data));
+        }
+        if !head.writer_alive {
+            return Poll::Ready(None);
+        }
+
+        // the writer wakes the task up with the next element, or when it is dropped
+        head.wakers[reader.reader] = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::executor::block_on;
+    use futures::StreamExt;
+
+    use crate::shared::new_buffer;
+
+    #[test]
+    fn stream_test() {
+        let (reader, mut writer) = new_buffer::<u32>(4);
+        let mut stream = reader.into_stream();
+
+        writer.write_data(0).unwrap();
+        assert_eq!(block_on(stream.next()), Some(0));
+
+        // the stream waits for the writer, which is stopped by the buffer size
+        let writer = std::thread::spawn(move || {
+            for n in 1..10 {
+                writer.write_blocking(n).unwrap();
+            }
+        });
+        assert_eq!(block_on(stream.collect::<Vec<_>>()), (1..10).collect::<Vec<_>>());
+        writer.join().unwrap();
+    }
+}
@@ -0,0 +1,48 @@
+//! Searches on a generated tree of 100k nodes, a query at a time and all of them together,
+//! and lookups of its paths.
+//!
+//! Run with `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use lab3_3::FileSystem;
+use labs_testkit::FsFixture;
+
+const NODES: usize = 100_000;
+const DEPTH: usize = 12;
+const SEED: u64 = 42;
+
+const QUERIES: [&str; 5] = ["name:d4242", "content:sensor", "larger:150", "newer:1650000000", "older:0"];
+
+fn search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search");
+    let mut fs = FileSystem::generate(NODES, DEPTH, SEED);
+
+    for query in QUERIES {
+        group.bench_with_input(BenchmarkId::new("query", query), &query, |b, query| {
+            b.iter(|| fs.search(&[query]).unwrap().nodes().len())
+        });
+    }
+    group.bench_function(BenchmarkId::new("queries", QUERIES.len()), |b| {
+        b.iter(|| fs.search(&QUERIES).unwrap().nodes().len())
+    });
+
+    group.finish();
+}
+
+/// Every directory listed and every file got by its path, from the root down.
+fn lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lookup");
+    let FsFixture { mut fs, dirs, files } = FsFixture::random(NODES, DEPTH, SEED);
+
+    group.bench_function(BenchmarkId::new("list", dirs.len()), |b| {
+        b.iter(|| dirs.iter().map(|dir| fs.list(dir).unwrap().len()).sum::<usize>())
+    });
+    group.bench_function(BenchmarkId::new("get_file", files.len()), |b| {
+        b.iter(|| files.iter().filter(|file| fs.get_file(file).is_ok()).count())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, search, lookup);
+criterion_main!(benches);
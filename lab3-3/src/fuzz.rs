@@ -0,0 +1,52 @@
+//! Entry point for fuzzing the search queries, built with `RUSTFLAGS="--cfg fuzzing"`
+//! for a fuzzer linking the library, and by the tests.
+
+use crate::FileSystem;
+
+/// Bytes of the input parsed, the queries are short.
+pub const MAX_INPUT: usize = 4096;
+/// Queries searched at once, each one is matched against every node.
+const MAX_QUERIES: usize = 16;
+
+/// Search a small generated filesystem with the lines of `bytes` as the queries.
+/// Any input is a result or an `InvalidQuery`, without panicking.
+pub fn fuzz_search(bytes: &[u8]) {
+    let text = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_INPUT)]);
+    let queries = text.lines().take(MAX_QUERIES).collect::<Vec<_>>();
+
+    let mut fs = FileSystem::generate(50, 3, 1);
+    let _ = fs.search(&queries);
+}
+
+#[cfg(test)]
+mod test {
+    use labs_common::XorShift;
+
+    use crate::fuzz::fuzz_search;
+
+    #[test]
+    fn fuzz_search_test() {
+        let inputs: [&[u8]; 8] = [
+            b"",
+            b"name:",
+            b":",
+            b"name:f1.txt\nlarger:10",
+            b"larger:-1\nsmaller:99999999999",
+            b"content:\xff\xfe\nnewer:1600000000",
+            b"older:1:2\n\n::::",
+            "name:é\r\nöä:ü".as_bytes(),
+        ];
+        for input in inputs {
+            fuzz_search(input);
+        }
+
+        // the bytes of the queries, mixed up
+        let alphabet = b"name:contentlarger0123\n\xff";
+        let mut rng = XorShift::new(7);
+        for _ in 0..100 {
+            let len = rng.below(64);
+            let bytes = (0..len).map(|_| alphabet[rng.below(alphabet.len())]).collect::<Vec<_>>();
+            fuzz_search(&bytes);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::{cell::RefCell, rc::Rc};
+
+use labs_common::XorShift;
+
+use crate::{Dir, File, FileSystem, FileType, Node};
+
+/// Words of the content of the generated files.
+const WORDS: [&str; 8] = ["lorem", "ipsum", "dolor", "sit", "amet", "rust", "sensor", "buffer"];
+
+/// Creation times of the generated nodes, from 2020-09-13 on for about 3 years.
+const EPOCH: u64 = 1_600_000_000;
+const SPAN: u64 = 100_000_000;
+
+impl FileSystem {
+    /// Filesystem of `nodes` nodes under the root, at most `depth` directories deep,
+    /// the same for the same `seed`, to benchmark and test the searches on big trees.
+    ///
+    /// A node out of 5 is a directory, `d<n>`, the others are files, `f<n>.txt` with up to
+    /// 32 words of content; the parent of each one is any directory made before it that
+    /// is not `depth` deep. With `depth` 0 every node is a file of the root.
+    pub fn generate(nodes: usize, depth: usize, seed: u64) -> Self {
+        let mut rng = XorShift::new(seed);
+        let fs = FileSystem::new();
+        // the directories that can have children besides the root, with their depth
+        let mut parents: Vec<(Rc<RefCell<Node>>, usize)> = vec![];
+
+        for n in 0..nodes {
+            let creation_time = EPOCH + rng.next_u64() % SPAN;
+            let node = if depth > 0 && rng.below(5) == 0 {
+                Node::Dir(Dir {
+                    name: format!("d{}", n),
+                    creation_time,
+                    children: vec![],
+                })
+            } else {
+                let words = (0..rng.below(33)).map(|_| WORDS[rng.below(WORDS.len())]);
+                let content = words.collect::<Vec<_>>().join(" ");
+                let mut file = File::new(&format!("f{}.txt", n), content.as_bytes(), FileType::Text);
+                file.creation_time = creation_time;
+                Node::File(file)
+            };
+
+            let node = Rc::new(RefCell::new(node));
+            let level = match rng.below(parents.len() + 1) {
+                0 => {
+                    fs.root.borrow_mut().children.push(node.clone());
+                    1
+                }
+                parent => {
+                    let (parent, level) = &parents[parent - 1];
+                    match &mut *parent.borrow_mut() {
+                        Node::Dir(dir) => dir.children.push(node.clone()),
+                        Node::File(_) => unreachable!(),
+                    }
+                    level + 1
+                }
+            };
+            if node.borrow().is_dir() && level < depth {
+                parents.push((node, level));
+            }
+        }
+
+        fs
+    }
+}
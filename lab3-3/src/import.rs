@@ -0,0 +1,235 @@
+use std::{
+    cell::RefCell,
+    fs::{self, Metadata},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
+
+use labs_common::{Clock, SystemClock};
+
+use crate::{Dir, File, FileSystem, FileType, Node, MAX_CONTENT};
+
+impl FileSystem {
+    /// Filesystem with the tree on disk at `path` under its root, the symlinks followed.
+    /// The files keep their first 1000 bytes, they are `Text` if those are UTF-8 without
+    /// NULs; the nodes are created when they were on disk. The fifos, sockets and devices
+    /// are left out.
+    ///
+    /// A node that can't be read, a broken symlink or a symlink to a directory containing
+    /// it is an error, with its path.
+    pub fn from_dir(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_dir_with_clock(path, Arc::new(SystemClock))
+    }
+
+    /// Like `from_dir`, the nodes made after the import are created at the time of `clock`.
+    pub fn from_dir_with_clock(path: impl AsRef<Path>, clock: Arc<dyn Clock>) -> io::Result<Self> {
+        let mut root = Dir::import(path.as_ref(), &mut vec![])?;
+        root.name = String::new();
+
+        Ok(Self {
+            root: Rc::new(RefCell::new(root)),
+            clock,
+        })
+    }
+}
+
+impl Dir {
+    /// The tree at `path`, `ancestors` are the directories above it, canonicalized.
+    fn import(path: &Path, ancestors: &mut Vec<PathBuf>) -> io::Result<Self> {
+        let canonical = fs::canonicalize(path).map_err(|e| path_error(path, e))?;
+        if ancestors.contains(&canonical) {
+            let e = io::Error::new(io::ErrorKind::InvalidInput, "symlink to a directory containing it");
+            return Err(path_error(path, e));
+        }
+        let metadata = fs::metadata(path).map_err(|e| path_error(path, e))?;
+
+        let mut entries = fs::read_dir(path)
+            .and_then(|entries| entries.map(|entry| entry.map(|entry| entry.path())).collect::<io::Result<Vec<_>>>())
+            .map_err(|e| path_error(path, e))?;
+        // the order of `read_dir` depends on the filesystem
+        entries.sort();
+
+        ancestors.push(canonical);
+        let mut children = vec![];
+        for entry in entries {
+            let metadata = fs::metadata(&entry).map_err(|e| path_error(&entry, e))?;
+            let node = if metadata.is_dir() {
+                Node::Dir(Dir::import(&entry, ancestors)?)
+            } else if metadata.is_file() {
+                Node::File(File::import(&entry, &metadata)?)
+            } else {
+                continue;
+            };
+            children.push(Rc::new(RefCell::new(node)));
+        }
+        ancestors.pop();
+
+        Ok(Self {
+            name: file_name(path),
+            creation_time: disk_time(&metadata),
+            children,
+        })
+    }
+}
+
+impl File {
+    fn import(path: &Path, metadata: &Metadata) -> io::Result<Self> {
+        let mut content = Vec::new();
+        fs::File::open(path)
+            .and_then(|file| file.take(MAX_CONTENT as u64).read_to_end(&mut content))
+            .map_err(|e| path_error(path, e))?;
+
+        Ok(Self {
+            name: file_name(path),
+            type_: sniff_type(&content),
+            content,
+            creation_time: disk_time(metadata),
+        })
+    }
+}
+
+/// `e` with the path it is about, the errors of `std::fs` don't have it.
+fn path_error(path: &Path, e: io::Error) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", path.display(), e))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+/// Seconds since the epoch of the creation of a node on disk, of its last change on the
+/// filesystems not keeping the creation time.
+fn disk_time(metadata: &Metadata) -> u64 {
+    metadata
+        .created()
+        .or_else(|_| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |time| time.as_secs())
+}
+
+/// `Text` for UTF-8 without NULs, the last character may be cut by the truncation.
+fn sniff_type(content: &[u8]) -> FileType {
+    let utf8 = match std::str::from_utf8(content) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    match utf8 && !content.contains(&0) {
+        true => FileType::Text,
+        false => FileType::Binary,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::Deref;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::time::{Duration, UNIX_EPOCH};
+    use std::{env, fs, io, process};
+
+    use labs_common::ManualClock;
+
+    use crate::import::sniff_type;
+    use crate::{FileSystem, FileType, Node};
+
+    /// Empty directory for the test `name`, removed when dropped, also if the test fails.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = env::temp_dir().join(format!("lab3-3-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Deref for TempDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn from_dir_test() {
+        let dir = TempDir::new("from-dir");
+        fs::create_dir_all(dir.join("a/empty")).unwrap();
+        fs::write(dir.join("a/notes.txt"), "hello sensor\n").unwrap();
+        fs::write(dir.join("data.bin"), [0x7f, b'E', b'L', b'F', 0, 1]).unwrap();
+        fs::write(dir.join("long.txt"), "é".repeat(1000)).unwrap();
+
+        let clock = Arc::new(ManualClock::new(UNIX_EPOCH + Duration::from_secs(100)));
+        let mut tree = FileSystem::from_dir_with_clock(&*dir, clock).unwrap();
+
+        let names = |tree: &FileSystem, path| {
+            let nodes = tree.list(path).unwrap();
+            nodes.iter().map(|node| node.borrow().get_name().to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(names(&tree, "/"), ["a", "data.bin", "long.txt"]);
+        assert_eq!(names(&tree, "/a"), ["empty", "notes.txt"]);
+        assert!(names(&tree, "/a/empty").is_empty());
+
+        let file = |tree: &mut FileSystem, path| match &*tree.get_file(path).unwrap().borrow() {
+            Node::File(file) => (file.get_type().clone(), file.content.len()),
+            Node::Dir(_) => unreachable!(),
+        };
+        assert_eq!(file(&mut tree, "/a/notes.txt"), (FileType::Text, 13));
+        assert_eq!(file(&mut tree, "/data.bin"), (FileType::Binary, 6));
+        // truncated in the middle of an `é`
+        assert_eq!(file(&mut tree, "/long.txt"), (FileType::Text, 1000));
+
+        assert_eq!(tree.search(&["content:sensor"]).unwrap().nodes().len(), 1);
+        assert!(tree.list("/").unwrap().iter().all(|node| node.borrow().get_creation_time() > 100));
+
+        // the nodes made after the import are created at the time of the clock
+        tree.mk_dir("/b").unwrap();
+        let created = tree.search(&["older:150"]).unwrap();
+        assert_eq!(created.nodes().iter().map(|node| node.borrow().get_name().to_string()).collect::<Vec<_>>(), ["b"]);
+    }
+
+    #[test]
+    fn from_dir_error_test() {
+        let dir = TempDir::new("from-dir-error");
+        let missing = FileSystem::from_dir(dir.join("missing")).unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+        assert!(missing.to_string().contains("missing"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::symlink;
+
+            fs::create_dir(dir.join("a")).unwrap();
+            symlink(&*dir, dir.join("a/up")).unwrap();
+            let looping = FileSystem::from_dir(&*dir).unwrap_err();
+            assert_eq!(looping.kind(), io::ErrorKind::InvalidInput);
+            assert!(looping.to_string().contains("up"));
+
+            fs::remove_file(dir.join("a/up")).unwrap();
+            symlink(dir.join("gone"), dir.join("a/broken")).unwrap();
+            let broken = FileSystem::from_dir(&*dir).unwrap_err();
+            assert_eq!(broken.kind(), io::ErrorKind::NotFound);
+            assert!(broken.to_string().contains("broken"));
+        }
+    }
+
+    #[test]
+    fn sniff_type_test() {
+        assert_eq!(sniff_type(b""), FileType::Text);
+        assert_eq!(sniff_type("ciao à tutti\n".as_bytes()), FileType::Text);
+        assert_eq!(sniff_type(&"à".as_bytes()[..1]), FileType::Text);
+        assert_eq!(sniff_type(b"a\0b"), FileType::Binary);
+        assert_eq!(sniff_type(b"\xff\xfe"), FileType::Binary);
+    }
+}
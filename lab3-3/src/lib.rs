@@ -2,17 +2,31 @@ use std::{
     cell::{RefCell, RefMut},
     iter::Peekable,
     rc::Rc,
-    time::{SystemTime, UNIX_EPOCH},
+    sync::Arc,
+    time::UNIX_EPOCH,
 };
 
+use labs_common::{Clock, SystemClock};
+pub use labs_error::FsError;
+
+#[cfg(any(fuzzing, test))]
+pub mod fuzz;
+mod generate;
+mod import;
+
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FileType {
     Text,
     #[default]
     Binary,
 }
 
+/// Bytes kept of the content of a file.
+const MAX_CONTENT: usize = 1000;
+
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct File {
     name: String,
     content: Vec<u8>, // max 1000 bytes, rest of the file truncated
@@ -21,6 +35,7 @@ pub struct File {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Dir {
     name: String,
     creation_time: u64,
@@ -28,6 +43,7 @@ pub struct Dir {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Node {
     File(File),
     Dir(Dir),
@@ -36,6 +52,7 @@ pub enum Node {
 #[derive(Debug, Clone)]
 pub struct FileSystem {
     root: Rc<RefCell<Dir>>,
+    clock: Arc<dyn Clock>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -44,6 +61,17 @@ pub struct MatchResult<'a> {
     nodes: Vec<Rc<RefCell<Node>>>,
 }
 
+impl<'a> MatchResult<'a> {
+    /// Queries matched by at least a node.
+    pub fn queries(&self) -> &[&'a str] {
+        &self.queries
+    }
+
+    pub fn nodes(&self) -> &[Rc<RefCell<Node>>] {
+        &self.nodes
+    }
+}
+
 #[derive(Debug, Clone)]
 enum QueryParam {
     Name(String, usize),
@@ -55,6 +83,24 @@ enum QueryParam {
 }
 
 impl QueryParam {
+    /// The query `<param>:<value>` given at `index`, `None` if it isn't one.
+    fn parse(query: &str, index: usize) -> Option<Self> {
+        let (param, value) = query.split_once(':')?;
+        if value.contains(':') {
+            return None;
+        }
+
+        Some(match param {
+            "name" => Self::Name(value.to_string(), index),
+            "content" => Self::Content(value.to_string(), index),
+            "larger" => Self::Larger(value.parse().ok()?, index),
+            "smaller" => Self::Smaller(value.parse().ok()?, index),
+            "newer" => Self::Newer(value.parse().ok()?, index),
+            "older" => Self::Older(value.parse().ok()?, index),
+            _ => return None,
+        })
+    }
+
     fn match_value(&self, node: &Node) -> bool {
         match self {
             Self::Name(name, _) => node.get_name().contains(name),
@@ -104,28 +150,28 @@ impl QueryParam {
 }
 
 impl Node {
-    fn get_name(&self) -> &str {
+    pub fn get_name(&self) -> &str {
         match self {
             Self::Dir(d) => &d.name,
             Self::File(f) => &f.name,
         }
     }
 
-    fn get_content(&self) -> Option<&Vec<u8>> {
+    pub fn get_content(&self) -> Option<&Vec<u8>> {
         match self {
             Self::Dir(_) => None,
             Self::File(f) => Some(&f.content),
         }
     }
 
-    fn get_size(&self) -> Option<u32> {
+    pub fn get_size(&self) -> Option<u32> {
         match self {
             Self::Dir(_) => None,
             Self::File(f) => Some(f.content.len() as u32),
         }
     }
 
-    fn get_creation_time(&self) -> u64 {
+    pub fn get_creation_time(&self) -> u64 {
         match self {
             Self::Dir(d) => d.creation_time,
             Self::File(f) => f.creation_time,
@@ -152,14 +198,14 @@ impl Node {
         }
     }
 
-    fn is_file(&self) -> bool {
+    pub fn is_file(&self) -> bool {
         match self {
             Self::File(_) => true,
             _ => false,
         }
     }
 
-    fn is_dir(&self) -> bool {
+    pub fn is_dir(&self) -> bool {
         match self {
             Self::Dir(_) => true,
             _ => false,
@@ -181,18 +227,20 @@ impl Node {
     }
 }
 
-fn creation_time() -> u64 {
-    SystemTime::now()
+/// Seconds since the epoch of `clock`.
+fn creation_time(clock: &dyn Clock) -> u64 {
+    clock
+        .now()
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards")
         .as_secs()
 }
 
 impl Dir {
-    fn new(name: &str) -> Self {
+    fn new(name: &str, creation_time: u64) -> Self {
         Self {
             name: name.to_string(),
-            creation_time: creation_time(),
+            creation_time,
             children: vec![],
         }
     }
@@ -203,37 +251,45 @@ impl Dir {
             .map(|node| node.as_ref().borrow_mut())
     }
 
-    fn mk_dir<'a>(&mut self, path: &mut Peekable<impl Iterator<Item = &'a str>>) {
+    // `full` is the path given to the filesystem, the one of the errors
+    fn mk_dir<'a>(
+        &mut self,
+        path: &mut Peekable<impl Iterator<Item = &'a str>>,
+        full: &str,
+        creation_time: u64,
+    ) -> Result<(), FsError> {
         let next = match path.next() {
-            None => {
-                return;
-            }
+            // the root, or a directory ending with `/`
+            None | Some("") => return Err(FsError::AlreadyExists(full.to_string())),
             Some(val) => val,
         };
 
         // next is last path
         if path.peek().is_none() {
-            if self.contains_mut(next).is_none() {
-                self.children
-                    .push(Rc::new(RefCell::new(Node::Dir(Dir::new(next)))));
-                return;
+            if self.contains_mut(next).is_some() {
+                return Err(FsError::AlreadyExists(full.to_string()));
             }
-            return;
+            self.children
+                .push(Rc::new(RefCell::new(Node::Dir(Dir::new(next, creation_time)))));
+            return Ok(());
         }
 
-        if let Some(node) = self.contains_mut(next) {
-            let mut dir = node.as_ref().borrow_mut();
-            if let Node::Dir(ref mut next_dir) = *dir {
-                next_dir.mk_dir(path);
-            }
+        match self.contains_mut(next) {
+            None => Err(FsError::NotFound(full.to_string())),
+            Some(node) => match *node.as_ref().borrow_mut() {
+                Node::Dir(ref mut next_dir) => next_dir.mk_dir(path, full, creation_time),
+                Node::File(_) => Err(FsError::NotADirectory(full.to_string())),
+            },
         }
     }
 
-    fn rm_dir<'a>(&mut self, path: &mut Peekable<impl Iterator<Item = &'a str>>) {
+    fn rm_dir<'a>(
+        &mut self,
+        path: &mut Peekable<impl Iterator<Item = &'a str>>,
+        full: &str,
+    ) -> Result<(), FsError> {
         let next = match path.next() {
-            None => {
-                return;
-            }
+            None => return Err(FsError::NotFound(full.to_string())),
             Some(val) => val,
         };
 
@@ -246,27 +302,31 @@ impl Dir {
                     .position(|c| c.borrow().get_name() == next);
 
                 let index = match index_maybe {
-                    None => return,
+                    None => return Err(FsError::NotFound(full.to_string())),
                     Some(val) => val,
                 };
 
-                if let Node::Dir(ref dir_to_remove) = *self.children[index].borrow() {
-                    if dir_to_remove.children.len() != 0 {
-                        return;
+                match *self.children[index].borrow() {
+                    Node::Dir(ref dir_to_remove) if !dir_to_remove.children.is_empty() => {
+                        return Err(FsError::NotEmpty(full.to_string()));
                     }
+                    Node::Dir(_) => {}
+                    Node::File(_) => return Err(FsError::NotADirectory(full.to_string())),
                 }
 
                 index
             };
 
             self.children.remove(index);
-            return;
+            return Ok(());
         }
 
-        if let Some(node) = self.contains_mut(next) {
-            if let Node::Dir(ref mut next_dir) = *node.as_ref().borrow_mut() {
-                next_dir.rm_dir(path);
-            }
+        match self.contains_mut(next) {
+            None => Err(FsError::NotFound(full.to_string())),
+            Some(node) => match *node.as_ref().borrow_mut() {
+                Node::Dir(ref mut next_dir) => next_dir.rm_dir(path, full),
+                Node::File(_) => Err(FsError::NotADirectory(full.to_string())),
+            },
         }
     }
 
@@ -274,31 +334,32 @@ impl Dir {
         &mut self,
         path: &mut Peekable<impl Iterator<Item = &'a str>>,
         file: File,
-    ) -> bool {
+        full: &str,
+    ) -> Result<(), FsError> {
         let curr = match path.next() {
             Some(n) => n,
-            None => return false,
+            None => return Err(FsError::NotFound(full.to_string())),
         };
 
         if self.name != curr {
-            return false;
+            return Err(FsError::NotFound(full.to_string()));
         }
 
-        if path.peek().is_none() && self.contains_file(&file.name).is_none() {
+        if path.peek().is_none() {
+            if self.contains_mut(&file.name).is_some() {
+                return Err(FsError::AlreadyExists(full.to_string()));
+            }
             self.children.push(Rc::new(RefCell::new(Node::File(file))));
-            return true;
+            return Ok(());
         }
 
-        if let Some(dir) = self.contains_dir(path.peek().unwrap()) {
-            return dir
-                .as_ref()
-                .borrow_mut()
-                .as_dir()
-                .unwrap()
-                .new_file(path, file);
+        match self.contains_mut(path.peek().unwrap()) {
+            None => Err(FsError::NotFound(full.to_string())),
+            Some(node) => match *node.as_ref().borrow_mut() {
+                Node::Dir(ref mut dir) => dir.new_file(path, file, full),
+                Node::File(_) => Err(FsError::NotADirectory(full.to_string())),
+            },
         }
-
-        return false;
     }
 
     fn contains_mut(&mut self, name: &str) -> Option<Rc<RefCell<Node>>> {
@@ -312,32 +373,6 @@ impl Dir {
         res.map(|node| node.clone())
     }
 
-    fn contains_file(&mut self, name: &str) -> Option<Rc<RefCell<Node>>> {
-        self.children
-            .iter()
-            .find(|child| match *child.borrow() {
-                Node::File(ref f) => f.name == name,
-                _ => false,
-            })
-            .map_or(None, |file| match *file.as_ref().borrow() {
-                Node::File(_) => Some(file.clone()),
-                _ => None,
-            })
-    }
-
-    fn contains_dir(&mut self, name: &str) -> Option<Rc<RefCell<Node>>> {
-        self.children
-            .iter()
-            .find(|child| match *child.borrow() {
-                Node::Dir(ref d) => d.name == name,
-                _ => false,
-            })
-            .map_or(None, |dir| match *dir.as_ref().borrow() {
-                Node::Dir(_) => Some(dir.clone()),
-                _ => None,
-            })
-    }
-
     fn remove(&mut self, name: &str) {
         let pos = match self.children.iter().position(|c| match *c.borrow() {
             Node::File(ref f) => f.name == name,
@@ -390,6 +425,25 @@ impl Into<Node> for Dir {
 }
 
 impl File {
+    /// File created now, the content after the first 1000 bytes is truncated.
+    pub fn new(name: &str, content: &[u8], type_: FileType) -> Self {
+        Self::with_clock(name, content, type_, &SystemClock)
+    }
+
+    /// Like `new`, created at the time of `clock`, see `FileSystem::clock`.
+    pub fn with_clock(name: &str, content: &[u8], type_: FileType, clock: &dyn Clock) -> Self {
+        Self {
+            name: name.to_string(),
+            content: content[..content.len().min(MAX_CONTENT)].to_vec(),
+            creation_time: creation_time(clock),
+            type_,
+        }
+    }
+
+    pub fn get_type(&self) -> &FileType {
+        &self.type_
+    }
+
     fn match_queries(&mut self, queries: &mut Vec<(QueryParam, bool)>) -> bool {
         let mut query_matched = false;
 
@@ -404,63 +458,123 @@ impl File {
     }
 }
 
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FileSystem {
     pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    /// Empty filesystem whose nodes are created at the time of `clock`.
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
         Self {
-            root: Rc::new(RefCell::new(Dir::new(""))),
+            root: Rc::new(RefCell::new(Dir::new("", creation_time(&*clock)))),
+            clock,
         }
     }
 
-    pub fn from_dir(_path: &str) {}
-
-    pub fn mk_dir(&mut self, path: &str) {
-        let iter = &mut path.split("/").peekable();
+    /// Clock of the creation times, for the files added with `new_file`.
+    pub fn clock(&self) -> &dyn Clock {
+        &*self.clock
+    }
 
-        let mut root = self.root.as_ref().borrow_mut();
+    /// Clock of the nodes made from now on, the ones already made keep their time.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
 
-        if let Some(next) = iter.next() {
-            if next != root.name {
-                return;
-            }
+    /// Children of the directory at `path`.
+    pub fn list(&self, path: &str) -> Result<Vec<Rc<RefCell<Node>>>, FsError> {
+        if !path.starts_with('/') {
+            return Err(FsError::RelativePath(path.to_string()));
+        }
 
-            root.mk_dir(iter);
+        let mut children = self.root.borrow().children.clone();
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            let node = children
+                .iter()
+                .find(|child| child.borrow().get_name() == name)
+                .cloned()
+                .ok_or_else(|| FsError::NotFound(path.to_string()))?;
+            children = match *node.borrow() {
+                Node::Dir(ref dir) => dir.children.clone(),
+                Node::File(_) => return Err(FsError::NotADirectory(path.to_string())),
+            };
         }
+        Ok(children)
     }
 
-    pub fn rm_dir(&mut self, path: &str) {
+    /// Create the directory at `path`, its parent must exist.
+    pub fn mk_dir(&mut self, path: &str) -> Result<(), FsError> {
         let iter = &mut path.split("/").peekable();
 
         let mut root = self.root.as_ref().borrow_mut();
-        if let Some(next) = iter.next() {
-            if next != root.name {
-                return;
-            }
+        if iter.next() != Some(root.name.as_str()) {
+            return Err(FsError::RelativePath(path.to_string()));
+        }
+
+        root.mk_dir(iter, path, creation_time(&*self.clock))
+    }
 
-            root.rm_dir(iter);
+    /// Remove the directory at `path`, only if it's empty.
+    pub fn rm_dir(&mut self, path: &str) -> Result<(), FsError> {
+        let iter = &mut path.split("/").peekable();
+
+        let mut root = self.root.as_ref().borrow_mut();
+        if iter.next() != Some(root.name.as_str()) {
+            return Err(FsError::RelativePath(path.to_string()));
         }
+
+        root.rm_dir(iter, path)
     }
 
-    pub fn new_file(&mut self, path: &str, file: File) -> bool {
-        let mut dirs = path.trim().split_terminator("/").peekable();
-        self.root.as_ref().borrow_mut().new_file(&mut dirs, file)
+    /// Add `file` to the directory at `path`, the errors are about the path of the file.
+    pub fn new_file(&mut self, path: &str, file: File) -> Result<(), FsError> {
+        let path = path.trim();
+        let full = format!("{}/{}", path.trim_end_matches('/'), file.name);
+        if !path.starts_with('/') {
+            return Err(FsError::RelativePath(full));
+        }
+
+        let mut dirs = path.split_terminator("/").peekable();
+        self.root.as_ref().borrow_mut().new_file(&mut dirs, file, &full)
     }
 
-    pub fn get_file(&mut self, path: &str) -> Option<Rc<RefCell<Node>>> {
+    pub fn get_file(&mut self, path: &str) -> Result<Rc<RefCell<Node>>, FsError> {
+        let not_found = || FsError::NotFound(path.to_string());
+
         let mut split_path = path.split("/");
         if split_path.next() != Some("") {
-            return None;
+            return Err(FsError::RelativePath(path.to_string()));
         }
 
         // go through all the paths
         let split_path: Vec<&str> = split_path.collect();
 
+        // a file in the root
+        if let [name] = split_path[..] {
+            let node = self.root.as_ref().borrow_mut().contains_mut(name);
+            return match node {
+                Some(file) if file.borrow().is_file() => Ok(file),
+                Some(_) => Err(FsError::IsADirectory(path.to_string())),
+                None => Err(not_found()),
+            };
+        }
+
         let mut curr_dir = match self
             .root
             .as_ref()
             .borrow_mut()
-            .contains_dir(split_path.first().unwrap())
+            .contains_mut(split_path.first().unwrap())
         {
-            None => return None,
+            None => return Err(not_found()),
+            Some(dir) if dir.borrow().is_file() => {
+                return Err(FsError::NotADirectory(path.to_string()))
+            }
             Some(dir) => dir,
         };
 
@@ -472,10 +586,10 @@ impl FileSystem {
             {
                 match *node.borrow() {
                     Node::Dir(_) => node.clone(),
-                    Node::File(_) => return None,
+                    Node::File(_) => return Err(FsError::NotADirectory(path.to_string())),
                 }
             } else {
-                return None;
+                return Err(not_found());
             };
 
             curr_dir = new_dir;
@@ -488,16 +602,17 @@ impl FileSystem {
                 .and_then(|d| d.contains_mut(p))
             {
                 return match *file.borrow() {
-                    Node::File(_) => Some(file.clone()),
-                    _ => None,
+                    Node::File(_) => Ok(file.clone()),
+                    _ => Err(FsError::IsADirectory(path.to_string())),
                 };
             }
         }
 
-        return None;
+        return Err(not_found());
     }
 
-    pub fn search<'a>(&mut self, queries: &[&'a str]) -> Option<MatchResult<'a>> {
+    /// Nodes matching at least one of the queries, like `name:foo` or `larger:100`.
+    pub fn search<'a>(&mut self, queries: &[&'a str]) -> Result<MatchResult<'a>, FsError> {
         let mut result = MatchResult {
             queries: vec![],
             nodes: vec![],
@@ -505,48 +620,9 @@ impl FileSystem {
 
         let mut final_queries: Vec<(QueryParam, bool)> = vec![];
         // build vec of query
-        for (index, query) in queries
-            .iter()
-            .map(|q| q.split(":").collect::<Vec<&str>>())
-            .enumerate()
-        {
-            if query.len() != 2 {
-                return None;
-            }
-
-            let final_query = match query[0] {
-                "name" => QueryParam::Name(query[1].to_string(), index),
-                "content" => QueryParam::Content(query[1].to_string(), index),
-                "larger" => QueryParam::Larger(
-                    match query[1].to_string().parse::<u32>() {
-                        Ok(l) => l,
-                        Err(_) => return None,
-                    },
-                    index,
-                ),
-                "smaller" => QueryParam::Smaller(
-                    match query[1].to_string().parse::<u32>() {
-                        Ok(l) => l,
-                        Err(_) => return None,
-                    },
-                    index,
-                ),
-                "newer" => QueryParam::Newer(
-                    match query[1].to_string().parse::<u64>() {
-                        Ok(l) => l,
-                        Err(_) => return None,
-                    },
-                    index,
-                ),
-                "older" => QueryParam::Older(
-                    match query[1].to_string().parse::<u64>() {
-                        Ok(l) => l,
-                        Err(_) => return None,
-                    },
-                    index,
-                ),
-                _ => return None,
-            };
+        for (index, query) in queries.iter().enumerate() {
+            let final_query = QueryParam::parse(query, index)
+                .ok_or_else(|| FsError::InvalidQuery(query.to_string()))?;
 
             final_queries.push((final_query, false));
         }
@@ -560,14 +636,37 @@ impl FileSystem {
             .map(|fq| queries[fq.0.get_index()])
             .collect();
 
-        Some(result)
+        Ok(result)
+    }
+}
+
+/// Only the tree is saved, a restored filesystem has the `SystemClock`, see `set_clock`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for FileSystem {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.root.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for FileSystem {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self {
+            root: serde::Deserialize::deserialize(deserializer)?,
+            clock: Arc::new(SystemClock),
+        })
     }
 }
 
 #[cfg(test)]
 mod test {
 
-    use crate::{File, FileSystem, Node};
+    use std::sync::Arc;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use labs_common::ManualClock;
+
+    use crate::{File, FileSystem, FsError, MatchResult, Node};
 
     #[test]
     fn new_test() {
@@ -582,10 +681,10 @@ mod test {
     #[test]
     fn mk_dir_test() {
         let mut file = FileSystem::new();
-        file.mk_dir("/a");
-        file.mk_dir("/b");
-        file.mk_dir("/a/c");
-        file.mk_dir("/a/d");
+        file.mk_dir("/a").unwrap();
+        file.mk_dir("/b").unwrap();
+        file.mk_dir("/a/c").unwrap();
+        file.mk_dir("/a/d").unwrap();
 
         let children = &file.root.as_ref().borrow_mut().children;
         assert_eq!("a", children[0].as_ref().borrow().get_name());
@@ -610,12 +709,12 @@ mod test {
     #[test]
     fn rm_dir_test() {
         let mut file = FileSystem::new();
-        file.mk_dir("/a");
-        file.mk_dir("/b");
-        file.mk_dir("/a/c");
-        file.mk_dir("/a/d");
+        file.mk_dir("/a").unwrap();
+        file.mk_dir("/b").unwrap();
+        file.mk_dir("/a/c").unwrap();
+        file.mk_dir("/a/d").unwrap();
 
-        file.rm_dir("/a/c");
+        file.rm_dir("/a/c").unwrap();
         {
             let root = file.root.as_ref().borrow();
             assert_eq!(
@@ -627,7 +726,7 @@ mod test {
             );
         }
 
-        file.rm_dir("/a/f");
+        assert_eq!(file.rm_dir("/a/f"), Err(FsError::NotFound("/a/f".to_string())));
         {
             let root = file.root.as_ref().borrow();
             assert_eq!(
@@ -639,7 +738,7 @@ mod test {
             );
         }
 
-        file.rm_dir("/a/d");
+        file.rm_dir("/a/d").unwrap();
         {
             let root = file.root.as_ref().borrow();
             assert_eq!(
@@ -655,10 +754,10 @@ mod test {
     #[test]
     fn new_file_test() {
         let mut file = FileSystem::new();
-        file.mk_dir("/a");
-        file.mk_dir("/b");
-        file.mk_dir("/a/c");
-        file.mk_dir("/a/d");
+        file.mk_dir("/a").unwrap();
+        file.mk_dir("/b").unwrap();
+        file.mk_dir("/a/c").unwrap();
+        file.mk_dir("/a/d").unwrap();
 
         let new_file = File {
             name: "Sium".to_string(),
@@ -667,7 +766,7 @@ mod test {
             type_: crate::FileType::Binary,
         };
 
-        assert!(file.new_file("/", new_file.clone()));
+        assert_eq!(file.new_file("/", new_file.clone()), Ok(()));
         {
             let root = file.root.as_ref().borrow();
             assert_eq!(
@@ -676,7 +775,7 @@ mod test {
             );
         }
 
-        assert!(file.new_file("/a", new_file.clone()));
+        assert_eq!(file.new_file("/a", new_file.clone()), Ok(()));
         {
             let root = file.root.as_ref().borrow();
             assert_eq!(
@@ -702,18 +801,20 @@ mod test {
                 name: "a".into(),
                 ..Default::default()
             },
-        );
-        file.mk_dir("/b");
-        file.mk_dir("/b/c");
-        file.mk_dir("/b/d");
-        file.mk_dir("/b/c/a");
+        )
+        .unwrap();
+        file.mk_dir("/b").unwrap();
+        file.mk_dir("/b/c").unwrap();
+        file.mk_dir("/b/d").unwrap();
+        file.mk_dir("/b/c/a").unwrap();
         file.new_file(
             "/b/d",
             File {
                 name: "o".into(),
                 ..Default::default()
             },
-        );
+        )
+        .unwrap();
 
         let matches = file
             .search(&["name:a", "name:f", "name:o", "smaller:32"])
@@ -722,4 +823,116 @@ mod test {
         assert_eq!(matches.queries.len(), 3);
         assert_eq!(matches.nodes.len(), 3);
     }
+
+    #[test]
+    fn list_test() {
+        let mut file = FileSystem::new();
+        file.mk_dir("/a").unwrap();
+        file.mk_dir("/a/b").unwrap();
+        file.new_file("/a/b", File::new("f", b"hello", crate::FileType::Text)).unwrap();
+        file.new_file("/", File::new("g", &[0; 2000], crate::FileType::Binary)).unwrap();
+
+        let names = |path: &str| {
+            file.list(path)
+                .map(|nodes| nodes.iter().map(|n| n.borrow().get_name().to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(names("/"), Ok(vec!["a".to_string(), "g".to_string()]));
+        assert_eq!(names("/a/"), Ok(vec!["b".to_string()]));
+        assert_eq!(names("/a/b"), Ok(vec!["f".to_string()]));
+        assert_eq!(names("/a/b/f"), Err(FsError::NotADirectory("/a/b/f".to_string())));
+        assert_eq!(names("/c"), Err(FsError::NotFound("/c".to_string())));
+
+        let g = file.get_file("/g").unwrap();
+        assert_eq!(g.borrow().get_size(), Some(1000));
+        let f = file.get_file("/a/b/f").unwrap();
+        assert_eq!(f.borrow().get_content(), Some(&b"hello".to_vec()));
+    }
+
+    #[test]
+    fn errors_test() {
+        let mut file = FileSystem::new();
+        file.mk_dir("/a").unwrap();
+        file.mk_dir("/a/b").unwrap();
+        file.new_file("/a", File::new("f", b"", crate::FileType::Text)).unwrap();
+
+        let error = |kind: fn(String) -> FsError, path: &str| Err(kind(path.to_string()));
+        assert_eq!(file.mk_dir("a/c"), error(FsError::RelativePath, "a/c"));
+        assert_eq!(file.mk_dir("/a/b"), error(FsError::AlreadyExists, "/a/b"));
+        assert_eq!(file.mk_dir("/x/y"), error(FsError::NotFound, "/x/y"));
+        assert_eq!(file.mk_dir("/a/f/y"), error(FsError::NotADirectory, "/a/f/y"));
+        assert_eq!(file.rm_dir("/a"), error(FsError::NotEmpty, "/a"));
+        assert_eq!(file.rm_dir("/a/f"), error(FsError::NotADirectory, "/a/f"));
+
+        let f = File::new("f", b"", crate::FileType::Text);
+        assert_eq!(file.new_file("/a", f.clone()), error(FsError::AlreadyExists, "/a/f"));
+        assert_eq!(file.new_file("/x/", f.clone()), error(FsError::NotFound, "/x/f"));
+        assert_eq!(file.new_file("a", f), error(FsError::RelativePath, "a/f"));
+
+        assert_eq!(file.get_file("/a/b").unwrap_err(), FsError::IsADirectory("/a/b".to_string()));
+        assert_eq!(file.get_file("/a/f/g").unwrap_err(), FsError::NotADirectory("/a/f/g".to_string()));
+        assert_eq!(file.get_file("/a/g").unwrap_err(), FsError::NotFound("/a/g".to_string()));
+        assert_eq!(file.search(&["name:f", "size:3"]).unwrap_err(), FsError::InvalidQuery("size:3".to_string()));
+        assert_eq!(file.search(&["larger:x"]).unwrap_err(), FsError::InvalidQuery("larger:x".to_string()));
+    }
+
+    #[test]
+    fn clock_test() {
+        let clock = Arc::new(ManualClock::new(UNIX_EPOCH + Duration::from_secs(100)));
+        let mut file = FileSystem::with_clock(clock.clone());
+        file.mk_dir("/old").unwrap();
+        clock.advance(Duration::from_secs(100));
+        file.mk_dir("/new").unwrap();
+        file.new_file("/new", File::with_clock("f", b"", crate::FileType::Text, file.clock())).unwrap();
+
+        assert_eq!(file.root.borrow().creation_time, 100);
+        let names = |result: MatchResult| {
+            result.nodes().iter().map(|n| n.borrow().get_name().to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(names(file.search(&["older:150"]).unwrap()), ["old"]);
+        assert_eq!(names(file.search(&["newer:150"]).unwrap()), ["new", "f"]);
+        assert_eq!(names(file.search(&["newer:200"]).unwrap()), Vec::<String>::new());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_test() {
+        let mut file = FileSystem::generate(50, 3, 1);
+        let json = serde_json::to_string(&file).unwrap();
+        let mut restored: FileSystem = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.root, file.root);
+        let names = |fs: &mut FileSystem| fs.search(&["name:f1"]).unwrap().nodes().len();
+        assert_eq!(names(&mut restored), names(&mut file));
+        // the restored tree is not shared with the saved one
+        restored.mk_dir("/restored").unwrap();
+        assert!(file.list("/").unwrap().iter().all(|node| node.borrow().get_name() != "restored"));
+    }
+
+    #[test]
+    fn generate_test() {
+        let names = |fs: &mut FileSystem| {
+            let result = fs.search(&["name:"]).unwrap();
+            result.nodes().iter().map(|n| n.borrow().get_name().to_string()).collect::<Vec<_>>()
+        };
+
+        let mut file = FileSystem::generate(1000, 6, 7);
+        let generated = names(&mut file);
+        assert_eq!(1000, generated.len());
+        assert_eq!(generated, names(&mut FileSystem::generate(1000, 6, 7)));
+        assert_ne!(generated, names(&mut FileSystem::generate(1000, 6, 8)));
+
+        let dirs = generated.iter().filter(|name| name.starts_with('d')).count();
+        assert!(dirs > 100 && dirs < 300);
+        assert!(!file.search(&["content:sensor"]).unwrap().nodes().is_empty());
+        assert!(file.list("/").unwrap().len() < 1000);
+
+        let flat = FileSystem::generate(100, 0, 7);
+        assert_eq!(flat.list("/").unwrap().len(), 100);
+        let shallow = FileSystem::generate(1000, 1, 7);
+        for node in shallow.list("/").unwrap() {
+            if let Some(dir) = node.borrow_mut().as_dir() {
+                assert!(dir.children.iter().all(|child| child.borrow().is_file()));
+            }
+        }
+    }
 }
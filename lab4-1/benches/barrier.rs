@@ -0,0 +1,86 @@
+//! Time of a round of `wait`, every thread crossing the barrier once, for each barrier
+//! and number of threads.
+//!
+//! Run with `cargo bench`.
+
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// the barriers are a module of the binary, not a library
+#[path = "../src"]
+mod src {
+    #[allow(dead_code)]
+    pub mod barrier;
+}
+
+use src::barrier::{ChannelBarrier, ClassicBarrier, ThreadBarrier};
+
+const THREADS: [usize; 3] = [2, 4, 8];
+
+/// Run `rounds` rounds on `threads` threads, the calling one being the first of them.
+/// Only the rounds after the first are timed, once every thread is running.
+fn time_rounds<W: Send>(rounds: u64, waiters: Vec<W>, wait: fn(&W)) -> Duration {
+    let mut waiters = waiters.into_iter();
+    let first = waiters.next().unwrap();
+
+    thread::scope(|s| {
+        let others = waiters
+            .map(|waiter| {
+                s.spawn(move || {
+                    for _ in 0..=rounds {
+                        wait(&waiter);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        wait(&first);
+        let start = Instant::now();
+        for _ in 0..rounds {
+            wait(&first);
+        }
+        let elapsed = start.elapsed();
+
+        others.into_iter().for_each(|handle| handle.join().unwrap());
+        elapsed
+    })
+}
+
+fn barrier_wait(c: &mut Criterion) {
+    let mut group = c.benchmark_group("barrier wait");
+
+    for threads in THREADS {
+        group.bench_with_input(BenchmarkId::new("classic", threads), &threads, |b, &threads| {
+            b.iter_custom(|rounds| {
+                let barrier = Arc::new(ClassicBarrier::new(threads as u32));
+                time_rounds(rounds, vec![barrier; threads], |barrier| barrier.wait())
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("channel", threads), &threads, |b, &threads| {
+            b.iter_custom(|rounds| {
+                let mut barrier = ChannelBarrier::new(threads);
+                let waiters = (0..threads).map(|id| barrier.get_waiter(id)).collect();
+                time_rounds(rounds, waiters, |waiter| waiter.wait())
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("thread", threads), &threads, |b, &threads| {
+            b.iter_custom(|rounds| {
+                let mut barrier = ThreadBarrier::new(threads);
+                let waiters = (0..threads).map(|id| barrier.get_waiter(id)).collect();
+                let elapsed = time_rounds(rounds, waiters, |waiter| waiter.wait());
+                barrier.stop();
+                elapsed
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, barrier_wait);
+criterion_main!(benches);
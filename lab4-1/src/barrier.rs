@@ -1,13 +1,15 @@
 use std::{
     collections::HashMap,
     sync::{
-        mpsc::{channel, sync_channel, Receiver, Sender, SyncSender},
+        mpsc::{channel, Receiver, Sender},
         Condvar, Mutex,
     },
-    thread::{self, JoinHandle},
     vec,
 };
 
+use labs_common::{Actor, ActorBuilder};
+use tracing::{debug, trace};
+
 #[derive(Debug)]
 enum BarrierState {
     Closed,
@@ -46,7 +48,7 @@ impl ClassicBarrier {
 
         /* increase waiting count */
         let mut waiting = self.waiting.lock().unwrap();
-        *waiting = *waiting + 1;
+        *waiting += 1;
 
         /* block if not all thread are in wait() */
         if *waiting != self.nthread {
@@ -54,6 +56,7 @@ impl ClassicBarrier {
         }
 
         if *waiting == self.nthread {
+            trace!(nthread = self.nthread, "every thread arrived, closing");
             /* decrease waiting count */
             *waiting -= 1;
 
@@ -67,6 +70,7 @@ impl ClassicBarrier {
         }
 
         if *waiting == 0 {
+            trace!("every thread left, opening");
             let mut state = self.state.lock().unwrap();
             *state = BarrierState::Open;
             self.state_cv.notify_all();
@@ -129,20 +133,21 @@ impl ChannelWaiter {
         for _ in 0..self.nthread {
             self.receiver.recv().unwrap();
         }
+        trace!(id = self.id, "every thread arrived");
     }
 }
 
 pub struct ThreadBarrier {
     nthread: usize,
-    sender: SyncSender<usize>,
+    /// Receives the id of every thread arriving at the barrier.
+    coordinator: Actor<usize>,
     receiver: HashMap<usize, Receiver<usize>>,
-    handle: JoinHandle<()>,
     send_kill: Sender<()>,
 }
 
 pub struct ThreadWaiter {
     id: usize,
-    sender: SyncSender<usize>,
+    sender: crossbeam::channel::Sender<usize>,
     receiver: Receiver<usize>,
 }
 
@@ -151,8 +156,6 @@ impl ThreadBarrier {
         let mut rs_wait = HashMap::new();
         let mut ss_thread = vec![];
 
-        let (s_wait, r_thread) = sync_channel(0);
-
         for id in 0..nthread {
             let (s_thread, r_wait) = channel();
 
@@ -162,23 +165,30 @@ impl ThreadBarrier {
 
         let (s_kill, r_kill) = channel();
 
-        Self {
-            nthread,
-            sender: s_wait,
-            receiver: rs_wait,
-            handle: thread::spawn(move || loop {
+        // the waiters hand their id over to the coordinator, the mailbox holds none
+        let coordinator = ActorBuilder::new("barrier")
+            .capacity(0)
+            .spawn(move |r_thread: &crossbeam::channel::Receiver<usize>| loop {
                 for _ in 0..nthread {
                     r_thread.recv().unwrap();
                 }
 
-                if let Ok(_) = r_kill.try_recv() {
+                if r_kill.try_recv().is_ok() {
+                    debug!("stopped");
                     break;
                 }
 
+                trace!(nthread, "every thread arrived, releasing");
                 for (id, s_thread) in ss_thread.iter().enumerate() {
                     s_thread.send(id).unwrap();
                 }
-            }),
+            })
+            .expect("failed to spawn thread");
+
+        Self {
+            nthread,
+            coordinator,
+            receiver: rs_wait,
             send_kill: s_kill,
         }
     }
@@ -186,17 +196,20 @@ impl ThreadBarrier {
     pub fn get_waiter(&mut self, id: usize) -> ThreadWaiter {
         ThreadWaiter {
             id,
-            sender: self.sender.clone(),
+            sender: self.coordinator.mailbox(),
             receiver: self.receiver.remove(&id).unwrap(),
         }
     }
 
     pub fn stop(self) {
+        // the kill is queued before the round it ends, or the thread waits for another round
+        self.send_kill.send(()).unwrap();
         for id in 0..self.nthread {
-            self.sender.send(id).unwrap();
+            self.coordinator.send(id).unwrap();
+        }
+        if let Err(e) = self.coordinator.join() {
+            panic!("{}", e);
         }
-        self.send_kill.send(()).unwrap();
-        self.handle.join().unwrap();
     }
 }
 
@@ -1,23 +1,39 @@
-use std::{sync::Arc, thread};
+use std::{env, process, sync::Arc, thread};
 
 use barrier::ClassicBarrier;
+use tracing::{info, info_span};
 
 use crate::barrier::{ChannelBarrier, ThreadBarrier};
 
 mod barrier;
 
 fn main() {
+    // `--log-level <filter>` also prints the events of the barriers, e.g. `lab4_1=trace`
+    let mut args = env::args().skip(1);
+    let filter = match (args.next(), args.next()) {
+        (None, _) => "info".to_string(),
+        (Some(flag), Some(filter)) if flag == "--log-level" => filter,
+        _ => {
+            eprintln!("usage: lab4-1 [--log-level <filter>]");
+            process::exit(2);
+        }
+    };
+    if let Err(e) = labs_common::init_tracing(&filter) {
+        eprintln!("--log-level {}: {}", filter, e);
+        process::exit(2);
+    }
+
     let classic_barrier = Arc::new(ClassicBarrier::new(3));
 
-    println!("\nClassical\n");
     thread::scope(|s| {
         for i in 0..3 {
             let b = classic_barrier.clone();
 
             s.spawn(move || {
+                let _span = info_span!("waiter", barrier = "classic", id = i).entered();
                 for j in 0..10 {
                     b.wait();
-                    println!("after barrier {} {}", i, j);
+                    info!(round = j, "after barrier");
                 }
             });
         }
@@ -25,15 +41,15 @@ fn main() {
 
     let mut channel_barrier = ChannelBarrier::new(3);
 
-    println!("\nChannel Barrier\n");
     thread::scope(|s| {
         for i in 0..3 {
             let w = channel_barrier.get_waiter(i as usize);
 
             s.spawn(move || {
+                let _span = info_span!("waiter", barrier = "channel", id = i).entered();
                 for j in 0..10 {
                     w.wait();
-                    println!("after barrier {} {}", i, j);
+                    info!(round = j, "after barrier");
                 }
             });
         }
@@ -41,15 +57,15 @@ fn main() {
 
     let mut thread_barrier = ThreadBarrier::new(3);
 
-    println!("\nThread Barrier\n");
     thread::scope(|s| {
         for i in 0..3 {
             let w = thread_barrier.get_waiter(i as usize);
 
             s.spawn(move || {
+                let _span = info_span!("waiter", barrier = "thread", id = i).entered();
                 for j in 0..10 {
                     w.wait();
-                    println!("after barrier {} {}", i, j);
+                    info!(round = j, "after barrier");
                 }
             });
         }
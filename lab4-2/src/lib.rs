@@ -1,9 +1,12 @@
 use std::{
     collections::{HashMap, HashSet},
     hash::Hash,
+    sync::mpsc::Receiver,
     vec,
 };
 
+use tracing::{debug, debug_span, trace, warn};
+
 /// `InputCellId` is a unique identifier for an input cell.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct InputCellId(usize);
@@ -55,10 +58,11 @@ pub struct Reactor<'a, T> {
     next_id: usize,
 }
 
-// You are guaranteed that Reactor will only be tested against types that are Copy + PartialEq.
+// The values are cloned into the compute functions and the callbacks, they can be
+// windows of samples or structs, not only numbers.
 impl<'a, T> Reactor<'a, T>
 where
-    T: Copy + PartialEq,
+    T: Clone + PartialEq,
 {
     pub fn new() -> Self {
         Self {
@@ -125,7 +129,7 @@ where
         for dep in dependencies {
             let dep_computer = self.cell_map.get_mut(dep).unwrap();
             dep_computer.subscribers.push(cell);
-            values.push(dep_computer.value);
+            values.push(dep_computer.value.clone());
         }
 
         let value = compute_func(&values);
@@ -156,10 +160,6 @@ where
 
     fn notify(&mut self, id: CellId) {
         let computer = self.cell_map.get(&id).unwrap();
-        println!(
-            "id: {:?}, dep: {:#?}, sub: {:#?}",
-            id, computer.dependencies, computer.subscribers
-        );
 
         let mut values = vec![];
         for dep in &computer.dependencies {
@@ -168,8 +168,7 @@ where
             if comp.notify_resolved == false {
                 return;
             }
-            let value = comp.value;
-            values.push(value);
+            values.push(comp.value.clone());
         }
 
         let mut execute_callbacks = false;
@@ -185,10 +184,11 @@ where
 
         computer.notify_resolved = true;
 
+        trace!(cell = ?id, changed = execute_callbacks, "recomputed");
         if execute_callbacks {
             let computer = self.cell_map.get(&id).unwrap();
             let callbacks = computer.callbacks.clone();
-            let value = computer.value;
+            let value = computer.value.clone();
             self.execute_callbacks(value, callbacks.into_iter());
 
             let sub = self.cell_map.get(&id).unwrap().subscribers.clone();
@@ -198,8 +198,9 @@ where
 
     fn execute_callbacks(&mut self, value: T, callbacks: impl Iterator<Item = CallbackId>) {
         callbacks.for_each(|c_id| {
+            trace!(callback = c_id.0, "called");
             let callback = self.callback_map.get_mut(&c_id).unwrap();
-            callback(value);
+            callback(value.clone());
         })
     }
 
@@ -211,7 +212,7 @@ where
     // It turns out this introduces a significant amount of extra complexity to this exercise.
     // We chose not to cover this here, since this exercise is probably enough work as-is.
     pub fn value(&self, id: CellId) -> Option<T> {
-        self.cell_map.get(&id).and_then(|c| Some(c.value))
+        self.cell_map.get(&id).and_then(|c| Some(c.value.clone()))
     }
 
     // Sets the value of the specified input cell.
@@ -223,6 +224,7 @@ where
     //
     // As before, that turned out to add too much extra complexity.
     pub fn set_value(&mut self, id: InputCellId, new_value: T) -> bool {
+        let _span = debug_span!("set_value", input = id.0).entered();
         let comp = match self.cell_map.get_mut(&CellId::Input(id)) {
             None => return false,
             Some(c) => c,
@@ -238,6 +240,23 @@ where
         true
     }
 
+    // Sets the input cells with the values received from `updates`, one after the other, until
+    // every sender is dropped: the other threads drive the cells of a reactor they can't share.
+    //
+    // Returns the number of values set, the ones of nonexistent cells are skipped.
+    pub fn feed(&mut self, updates: Receiver<(InputCellId, T)>) -> usize {
+        let mut set = 0;
+        for (id, value) in updates {
+            if self.set_value(id, value) {
+                set += 1;
+            } else {
+                warn!(input = id.0, "update of a nonexistent cell skipped");
+            }
+        }
+        debug!(set, "every sender dropped");
+        set
+    }
+
     // Adds a callback to the specified compute cell.
     //
     // Returns the ID of the just-added callback, or None if the cell doesn't exist.
@@ -458,3 +458,50 @@ fn test_adder_with_boolean_values() {
         );
     }
 }
+
+#[test]
+#[ignore]
+fn compute_cells_of_values_that_are_not_copy() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let words = reactor.create_input(vec!["a".to_string()]);
+    let joined = reactor
+        .create_compute(&[CellId::Input(words)], |v| vec![v[0].join("-")])
+        .unwrap();
+    assert!(reactor
+        .add_callback(joined, |v: Vec<String>| cb.callback_called(v.len() as i32))
+        .is_some());
+
+    assert!(reactor.set_value(words, vec!["b".to_string(), "c".to_string()]));
+    cb.expect_to_have_been_called_with(1);
+    assert_eq!(
+        reactor.value(CellId::Compute(joined)),
+        Some(vec!["b-c".to_string()])
+    );
+}
+
+#[test]
+#[ignore]
+fn input_cells_are_fed_from_a_channel() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] * 10)
+        .unwrap();
+    // an input of another reactor, without a cell in this one
+    let mut other = Reactor::new();
+    other.create_input(0);
+    let missing = other.create_input(0);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let producer = std::thread::spawn(move || {
+        for value in 2..=4 {
+            sender.send((input, value)).unwrap();
+        }
+        sender.send((missing, 5)).unwrap();
+    });
+
+    assert_eq!(reactor.feed(receiver), 3);
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(40));
+    producer.join().unwrap();
+}
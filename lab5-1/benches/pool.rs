@@ -0,0 +1,42 @@
+//! Jobs run by a pool of 4 workers per second, for jobs of a few instructions, where the
+//! scheduling is most of the time, and for jobs of some microseconds of work.
+//!
+//! Run with `cargo bench`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lab5_1::{Job, ThreadPool};
+
+const WORKERS: u32 = 4;
+const JOBS: u64 = 1_000;
+
+fn tiny(n: u64) -> u64 {
+    black_box(n) + 1
+}
+
+fn chunky(n: u64) -> u64 {
+    (0..10_000).fold(n, |acc, i| black_box(acc.wrapping_mul(31).wrapping_add(i)))
+}
+
+fn pool_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pool");
+    group.throughput(Throughput::Elements(JOBS));
+
+    let pool = ThreadPool::<Job>::new(WORKERS);
+    for (name, job) in [("tiny", tiny as fn(u64) -> u64), ("chunky", chunky)] {
+        group.bench_function(BenchmarkId::new(name, WORKERS), |b| {
+            b.iter(|| {
+                let handles = (0..JOBS)
+                    .map(|n| pool.spawn(move || job(n)).unwrap())
+                    .collect::<Vec<_>>();
+                handles.into_iter().map(|handle| handle.join().unwrap()).fold(0, u64::wrapping_add)
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, pool_throughput);
+criterion_main!(benches);
@@ -0,0 +1,375 @@
+use std::{cell::Cell, collections::{VecDeque, HashMap}, panic::{self, AssertUnwindSafe}, sync::{atomic::{AtomicUsize, Ordering}, Arc}};
+
+use crossbeam::channel::{Sender, Receiver};
+use labs_common::{panic_message, Actor, ActorBuilder, Restart};
+use tracing::{debug, debug_span, trace};
+
+pub use labs_error::PoolError;
+
+#[derive(Debug)]
+enum WorkerState {
+    Ready,
+    Working,
+}
+
+thread_local! {
+    static WORKER_ID: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+/// Id of the worker running the current job, from 0 to the number of workers.
+pub fn current_worker() -> Option<u32> {
+    WORKER_ID.with(Cell::get)
+}
+
+/// Body of the worker `id`, restarted when a job panics: the job is done all the same.
+fn worker<F>(id: u32, finish_job: Sender<u32>) -> impl FnMut(&Receiver<F>) + Send
+where F: FnOnce() + Send + 'static {
+    let mut running = false;
+
+    move |f_recv| {
+        WORKER_ID.with(|worker| worker.set(Some(id)));
+        let _span = debug_span!("worker", id).entered();
+        if running {
+            running = false;
+            finish_job.send(id).unwrap();
+        }
+
+        // the scheduler drops the job channel when the pool is dropped
+        while let Ok(f) = f_recv.recv() {
+            trace!("job started");
+            running = true;
+            f();
+            running = false;
+            trace!("job done");
+
+            finish_job.send(id).unwrap();
+        }
+    }
+}
+
+/// Jobs counted since the pool started, each one is submitted, then started, then done.
+#[derive(Debug, Default)]
+struct Counters {
+    submitted: AtomicUsize,
+    started: AtomicUsize,
+    done: AtomicUsize,
+}
+
+impl Counters {
+    fn stats(&self) -> PoolStats {
+        // read from the last step, a job counted in a step is already counted in the previous ones
+        let done = self.done.load(Ordering::SeqCst);
+        let started = self.started.load(Ordering::SeqCst);
+        let submitted = self.submitted.load(Ordering::SeqCst);
+        PoolStats { submitted, queued: submitted - started, running: started - done, done }
+    }
+}
+
+/// Jobs of the pool since it was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PoolStats {
+    pub submitted: usize,
+    /// Jobs waiting for a worker.
+    pub queued: usize,
+    pub running: usize,
+    pub done: usize,
+}
+
+fn scheduler<F>(wake_channel: &Receiver<F>, pool: &mut Scheduler<F>)
+where F: FnOnce() + Send + 'static {
+    let _span = debug_span!("scheduler").entered();
+    let closed = crossbeam::channel::never();
+    let mut open = true;
+
+    loop {
+        // once the pool is dropped only the running jobs are waited for
+        let wake = if open { wake_channel } else { &closed };
+        crossbeam::select! {
+            recv(wake) -> res => match res {
+                Ok(job) => pool.ready_jobs.push_back(job),
+                Err(_) => {
+                    debug!(queued = pool.ready_jobs.len(), "pool dropped, running the jobs left");
+                    open = false;
+                }
+            },
+            recv(pool.job_finish_recv) -> id => {
+                let w = pool.workers.get_mut(&id.unwrap()).unwrap();
+                w.0 = WorkerState::Ready;
+                pool.counters.done.fetch_add(1, Ordering::SeqCst);
+            },
+        }
+
+        for (id, v) in pool.workers.iter_mut() {
+            if let WorkerState::Working = v.0 { continue; }
+
+            if let Some(f) = pool.ready_jobs.pop_front() {
+                trace!(worker = id, queued = pool.ready_jobs.len(), "job dispatched");
+                v.0 = WorkerState::Working;
+                pool.counters.started.fetch_add(1, Ordering::SeqCst);
+                v.1.send(f).unwrap();
+            }
+        }
+
+        let idle = pool.workers.values().all(|(state, _)| matches!(state, WorkerState::Ready));
+        if !open && idle && pool.ready_jobs.is_empty() {
+            break;
+        }
+    }
+
+    // closing the job channels stops the workers
+    for (_, (_, worker)) in pool.workers.drain() {
+        worker.join().unwrap();
+    }
+}
+
+struct Scheduler<F> {
+    ready_jobs: VecDeque<F>,
+    workers: HashMap<u32, (WorkerState, Actor<F>)>,
+    job_finish_recv: Receiver<u32>,
+    counters: Arc<Counters>,
+}
+
+/// Pool of workers running the jobs in the order they are submitted.
+/// Dropping the pool waits for every job submitted.
+pub struct ThreadPool<F>
+where F: FnOnce() + Send + 'static {
+    /// Receives the jobs submitted.
+    scheduler: Option<Actor<F>>,
+    counters: Arc<Counters>,
+}
+
+impl<F: FnOnce() + Send + 'static> ThreadPool<F> {
+    /// Pool of `n_workers` workers, panics if it can't be started, see `try_new`.
+    pub fn new(n_workers: u32) -> Self {
+        Self::try_new(n_workers).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(n_workers: u32) -> Result<Self, PoolError> {
+        if n_workers == 0 {
+            return Err(PoolError::NoWorkers);
+        }
+
+        let mut workers = HashMap::new();
+        let (worker_done_sx, worker_done_rx) = crossbeam::channel::bounded::<u32>(0);
+
+        for id in 0..n_workers {
+            // the workers already started stop once their job channel is dropped
+            let worker = ActorBuilder::new(&format!("worker {}", id))
+                .restart(Restart::Always)
+                .spawn(worker(id, worker_done_sx.clone()))?;
+
+            workers.insert(id, (WorkerState::Ready, worker));
+        }
+
+        let counters = Arc::new(Counters::default());
+        let mut sched = Scheduler {
+            ready_jobs: VecDeque::new(),
+            workers,
+            job_finish_recv: worker_done_rx,
+            counters: counters.clone(),
+        };
+
+        // a panic of the scheduler closes the pool, `execute` fails from then on
+        let scheduler = ActorBuilder::new("scheduler")
+            .spawn(move |wake_scheduler: &Receiver<F>| scheduler(wake_scheduler, &mut sched))?;
+
+        Ok(Self {
+            scheduler: Some(scheduler),
+            counters,
+        })
+    }
+
+    /// Submit `job`, fails only if the scheduler stopped because of a panic.
+    pub fn execute(&self, job: F) -> Result<(), PoolError> {
+        // counted before sending, the scheduler may start it right away
+        self.counters.submitted.fetch_add(1, Ordering::SeqCst);
+        self.scheduler.as_ref().unwrap().send(job).map_err(|_| {
+            self.counters.submitted.fetch_sub(1, Ordering::SeqCst);
+            PoolError::Closed
+        })
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.counters.stats()
+    }
+
+    /// Register the jobs of `stats` with `registry` as `<prefix>_jobs_*`, they are
+    /// still read after the pool is dropped.
+    #[cfg(feature = "metrics")]
+    pub fn register_metrics(&self, registry: &labs_metrics::Registry, prefix: &str) {
+        let metric = |name: &str| format!("{}_jobs_{}", prefix, name);
+        let counters = self.counters.clone();
+        registry.counter_fn(&metric("submitted_total"), "Jobs submitted to the pool.", move || counters.stats().submitted as u64);
+        let counters = self.counters.clone();
+        registry.gauge_fn(&metric("queued"), "Jobs waiting for a worker.", move || counters.stats().queued as f64);
+        let counters = self.counters.clone();
+        registry.gauge_fn(&metric("running"), "Jobs running on a worker.", move || counters.stats().running as f64);
+        let counters = self.counters.clone();
+        registry.counter_fn(&metric("done_total"), "Jobs finished, panicked ones included.", move || counters.stats().done as u64);
+    }
+}
+
+/// Job of a pool running closures of any kind, the ones given to `ThreadPool::spawn`.
+pub type Job = Box<dyn FnOnce() + Send>;
+
+/// Handle of a job submitted with `ThreadPool::spawn`, to wait for its result.
+pub struct JobHandle<T> {
+    result: Receiver<Result<T, PoolError>>,
+}
+
+impl<T> JobHandle<T> {
+    /// Wait for the job to finish, `PoolError::Panicked` if it panicked.
+    pub fn join(self) -> Result<T, PoolError> {
+        // the pool runs every job submitted before it is dropped, unless its scheduler panicked
+        self.result.recv().unwrap_or(Err(PoolError::Closed))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.result.is_empty()
+    }
+}
+
+impl ThreadPool<Job> {
+    /// Submit `f`, its result or its panic is kept for the handle: a panic doesn't stop the worker.
+    pub fn spawn<T, F>(&self, f: F) -> Result<JobHandle<T>, PoolError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (result_sx, result_rx) = crossbeam::channel::bounded(1);
+        self.execute(Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f)).map_err(|e| PoolError::Panicked(panic_message(e)));
+            // the handle may have been dropped, nobody wants the result then
+            let _ = result_sx.send(result);
+        }))?;
+        Ok(JobHandle { result: result_rx })
+    }
+}
+
+impl<F> Drop for ThreadPool<F>
+where F: FnOnce() + Send + 'static {
+    fn drop(&mut self) {
+        // the scheduler stops once the channel is closed and the jobs are done
+        if let Some(scheduler) = self.scheduler.take() {
+            scheduler.join().unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{atomic::{AtomicUsize, Ordering}, Arc};
+
+    use crate::{current_worker, Job, PoolError, PoolStats, ThreadPool};
+
+    #[test]
+    fn drop_test() {
+        let done = Arc::new(AtomicUsize::new(0));
+
+        let pool = ThreadPool::new(4);
+        for _ in 0..20 {
+            let done = done.clone();
+            pool.execute(move || {
+                done.fetch_add(1, Ordering::SeqCst);
+            }).unwrap();
+        }
+        drop(pool);
+
+        assert_eq!(done.load(Ordering::SeqCst), 20);
+    }
+
+    #[test]
+    fn execute_panic_test() {
+        let done = Arc::new(AtomicUsize::new(0));
+
+        let pool = ThreadPool::new(1);
+        pool.execute(Box::new(|| panic!("job panicked")) as Job).unwrap();
+        let after = done.clone();
+        pool.execute(Box::new(move || {
+            assert_eq!(current_worker(), Some(0));
+            after.fetch_add(1, Ordering::SeqCst);
+        })).unwrap();
+        // the worker is restarted, the panicked job is counted as done
+        while pool.stats().done < 2 {
+            std::thread::yield_now();
+        }
+        drop(pool);
+
+        assert_eq!(done.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn current_worker_test() {
+        let (sender, receiver) = crossbeam::channel::unbounded();
+
+        let pool = ThreadPool::new(2);
+        for _ in 0..10 {
+            let sender = sender.clone();
+            pool.execute(move || sender.send(current_worker()).unwrap()).unwrap();
+        }
+        drop(pool);
+        drop(sender);
+
+        assert!(receiver.iter().all(|id| matches!(id, Some(0 | 1))));
+        assert_eq!(current_worker(), None);
+    }
+
+    #[test]
+    fn spawn_test() {
+        let pool = ThreadPool::<Job>::new(2);
+        let handles = (0..10).map(|x| pool.spawn(move || x * x).unwrap()).collect::<Vec<_>>();
+        let panicked = pool.spawn(|| panic!("job {} panicked", 10)).unwrap();
+        let after = pool.spawn(current_worker).unwrap();
+
+        let squares = handles.into_iter().map(|handle| handle.join().unwrap()).collect::<Vec<_>>();
+        assert_eq!(squares, (0..10).map(|x| x * x).collect::<Vec<_>>());
+        assert!(matches!(panicked.join(), Err(PoolError::Panicked(message)) if message == "job 10 panicked"));
+        // the worker of the panicked job is still running
+        assert!(matches!(after.join().unwrap(), Some(0 | 1)));
+    }
+
+    #[test]
+    fn stats_test() {
+        let pool = ThreadPool::<Job>::new(1);
+        let (release, wait) = crossbeam::channel::bounded::<()>(0);
+        let blocked = pool.spawn(move || wait.recv().unwrap()).unwrap();
+        let queued = pool.spawn(|| ()).unwrap();
+
+        while pool.stats().running == 0 {
+            std::thread::yield_now();
+        }
+        // the only worker waits on the channel, the second job waits for it
+        assert_eq!(pool.stats(), PoolStats { submitted: 2, queued: 1, running: 1, done: 0 });
+
+        release.send(()).unwrap();
+        blocked.join().unwrap();
+        queued.join().unwrap();
+        // the scheduler counts the job done after its result is sent
+        while pool.stats().running > 0 {
+            std::thread::yield_now();
+        }
+        assert_eq!(pool.stats(), PoolStats { submitted: 2, queued: 0, running: 0, done: 2 });
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn metrics_test() {
+        let registry = labs_metrics::Registry::new();
+        let pool = ThreadPool::<Job>::new(2);
+        pool.register_metrics(&registry, "pool");
+        for _ in 0..3 {
+            pool.spawn(|| ()).unwrap();
+        }
+        drop(pool);
+
+        let text = registry.render();
+        assert!(text.contains("pool_jobs_submitted_total 3\n"));
+        assert!(text.contains("pool_jobs_running 0\n"));
+        assert!(text.contains("pool_jobs_done_total 3\n"));
+    }
+
+    #[test]
+    fn no_workers_test() {
+        assert!(matches!(ThreadPool::<Job>::try_new(0), Err(PoolError::NoWorkers)));
+    }
+}
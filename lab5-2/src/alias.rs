@@ -0,0 +1,129 @@
+use std::collections::{BTreeMap, HashSet};
+
+use labs_error::ShellError;
+use serde::{Deserialize, Serialize};
+
+/// Argument of the `alias` builtin.
+#[derive(Debug, PartialEq, Eq)]
+pub enum AliasArg {
+    /// `name=value`
+    Define(String, String),
+    /// `name`, print the alias definition
+    Show(String),
+}
+
+/// Aliases defined with the `alias` builtin, expanded before a command is run.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Aliases {
+    aliases: BTreeMap<String, String>,
+}
+
+impl Aliases {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn define(&mut self, name: &str, value: &str) {
+        self.aliases.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.aliases.remove(name).is_some()
+    }
+
+    /// Replace the first word of `line` with its alias, until it is not an alias anymore.
+    /// Each alias is expanded at most once, so `alias ls='ls -F'` does not loop forever.
+    pub fn expand(&self, line: &str) -> String {
+        let mut line = line.to_string();
+        let mut expanded = HashSet::new();
+
+        loop {
+            let rest = line.trim_start();
+            let word = rest.split(char::is_whitespace).next().unwrap_or_default();
+
+            match self.aliases.get(word) {
+                Some(value) if expanded.insert(word.to_string()) => {
+                    line = format!("{}{}", value, &rest[word.len()..]);
+                }
+                _ => return line,
+            }
+        }
+    }
+}
+
+/// Parse the arguments of `alias`, e.g. `ll='ls -la' la="ls -A" l`.
+pub fn parse_alias_args(args: &str) -> Result<Vec<AliasArg>, ShellError> {
+    let mut parsed = vec![];
+    let mut rest = args.trim_start();
+
+    while !rest.is_empty() {
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let name = &rest[..end];
+        if name.is_empty() {
+            return Err(ShellError::AliasName(args.trim().to_string()));
+        }
+        rest = &rest[end..];
+
+        match rest.strip_prefix('=') {
+            None => parsed.push(AliasArg::Show(name.to_string())),
+            Some(value) => {
+                let (value, remaining) = match value.chars().next() {
+                    Some(quote @ ('\'' | '"')) => value[1..]
+                        .split_once(quote)
+                        .ok_or_else(|| ShellError::UnterminatedQuote(args.trim().to_string()))?,
+                    _ => value.split_once(char::is_whitespace).unwrap_or((value, "")),
+                };
+                parsed.push(AliasArg::Define(name.to_string(), value.to_string()));
+                rest = remaining;
+            }
+        }
+
+        rest = rest.trim_start();
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod test {
+    use labs_error::ShellError;
+
+    use crate::alias::{parse_alias_args, AliasArg, Aliases};
+
+    #[test]
+    fn parse_alias_args_test() {
+        assert_eq!(
+            parse_alias_args("ll='ls -la' la=\"ls -A\" l=ls x").unwrap(),
+            vec![
+                AliasArg::Define("ll".into(), "ls -la".into()),
+                AliasArg::Define("la".into(), "ls -A".into()),
+                AliasArg::Define("l".into(), "ls".into()),
+                AliasArg::Show("x".into()),
+            ]
+        );
+        assert!(matches!(parse_alias_args("ll='ls -la"), Err(ShellError::UnterminatedQuote(_))));
+        assert!(matches!(parse_alias_args("=ls"), Err(ShellError::AliasName(_))));
+    }
+
+    #[test]
+    fn expand_test() {
+        let mut aliases = Aliases::default();
+        aliases.define("ll", "ls -la");
+        aliases.define("l", "ll");
+        aliases.define("ls", "ls --color");
+
+        assert_eq!(aliases.expand("l /tmp\n"), "ls --color -la /tmp\n");
+        assert_eq!(aliases.expand("echo ll\n"), "echo ll\n");
+
+        aliases.define("a", "b");
+        aliases.define("b", "a");
+        assert_eq!(aliases.expand("a 1"), "a 1");
+    }
+}
@@ -0,0 +1,123 @@
+use labs_error::ShellError;
+
+/// Operator between two commands of a list, deciding if the second one is run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connector {
+    /// First command or `;`, always run
+    Then,
+    /// `&&`, run if the previous command succeeded
+    And,
+    /// `||`, run if the previous command failed
+    Or,
+}
+
+impl Connector {
+    /// Whether the command after the operator runs, given the status of the previous one.
+    pub fn should_run(self, last_status: i32) -> bool {
+        match self {
+            Connector::Then => true,
+            Connector::And => last_status == 0,
+            Connector::Or => last_status != 0,
+        }
+    }
+}
+
+/// Split a line like `make && ./app || echo failed; ls` into its commands,
+/// each one preceded by the operator joining it to the previous command.
+/// The operators between single or double quotes are part of the command.
+pub fn parse_command_list(line: &str) -> Result<Vec<(Connector, String)>, ShellError> {
+    let mut list = vec![];
+    // operator before the command being parsed
+    let mut prev = (";", Connector::Then);
+    let mut rest = line;
+
+    loop {
+        let next = find_operator(rest);
+        let (command, after) = match next {
+            Some((i, op, _)) => (&rest[..i], &rest[i + op.len()..]),
+            None => (rest, ""),
+        };
+
+        let command = command.trim();
+        if !command.is_empty() {
+            list.push((prev.1, format!("{}\n", command)));
+        } else if prev.1 != Connector::Then {
+            // `a;` and `a; ;` are fine, the commands around `&&` and `||` are not optional
+            return Err(ShellError::Syntax(prev.0.to_string()));
+        } else if let Some((_, op @ ("&&" | "||"), _)) = next {
+            return Err(ShellError::Syntax(op.to_string()));
+        }
+
+        match next {
+            Some((_, op, connector)) => prev = (op, connector),
+            None => return Ok(list),
+        }
+        rest = after;
+    }
+}
+
+/// First operator of `line` outside the quotes, with its position.
+fn find_operator(line: &str) -> Option<(usize, &'static str, Connector)> {
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None => {
+                let operator = [
+                    ("&&", Connector::And),
+                    ("||", Connector::Or),
+                    (";", Connector::Then),
+                ]
+                .into_iter()
+                .find(|(op, _)| line[i..].starts_with(op));
+                if let Some((op, connector)) = operator {
+                    return Some((i, op, connector));
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use labs_error::ShellError;
+
+    use crate::chain::{parse_command_list, Connector};
+
+    #[test]
+    fn parse_command_list_test() {
+        assert_eq!(
+            parse_command_list("cargo build && ./app || echo failed; ls\n").unwrap(),
+            vec![
+                (Connector::Then, "cargo build\n".to_string()),
+                (Connector::And, "./app\n".to_string()),
+                (Connector::Or, "echo failed\n".to_string()),
+                (Connector::Then, "ls\n".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_command_list("ls;\n").unwrap(),
+            vec![(Connector::Then, "ls\n".to_string())]
+        );
+        assert!(matches!(parse_command_list("&& ls\n"), Err(ShellError::Syntax(op)) if op == "&&"));
+        assert!(matches!(parse_command_list("ls ||\n"), Err(ShellError::Syntax(op)) if op == "||"));
+        assert!(parse_command_list("ls && ; ls\n").is_err());
+    }
+
+    #[test]
+    fn quoted_operators_test() {
+        assert_eq!(
+            parse_command_list("alias ll='ls -la; pwd' && echo \"a && b || c\"; ls\n").unwrap(),
+            vec![
+                (Connector::Then, "alias ll='ls -la; pwd'\n".to_string()),
+                (Connector::And, "echo \"a && b || c\"\n".to_string()),
+                (Connector::Then, "ls\n".to_string()),
+            ]
+        );
+        // a double quote between single quotes doesn't open a quote
+        assert_eq!(parse_command_list("echo '\"'; ls\n").unwrap().len(), 2);
+    }
+}
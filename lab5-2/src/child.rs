@@ -0,0 +1,297 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    process::{Child, ChildStdin, Command, ExitStatus, Stdio},
+    sync::Arc,
+};
+
+use crossbeam::channel::{Receiver, Sender};
+use lab5_1::{Job, JobHandle, ThreadPool};
+use labs_error::ShellError;
+use labs_metrics::Registry;
+use tracing::{debug, debug_span, warn};
+
+use crate::{expand::expand_globs, pty::Pty};
+
+/// Identifier of a launched program, used to tag its events.
+pub type JobId = usize;
+
+/// Message sent from the event loop to a child supervisor.
+#[derive(Debug)]
+pub enum ChildInput {
+    Line(String),
+    /// The console reached EOF, close the child stdin.
+    Eof,
+    Kill,
+    /// The shell terminal was resized, only meaningful for programs on a pseudo-terminal.
+    Resize,
+}
+
+/// Message sent from a child supervisor to the event loop.
+#[derive(Debug)]
+pub enum ChildEvent {
+    /// The program was launched, sent before any other event.
+    Started,
+    Stdout(String),
+    Stderr(String),
+    Exited(ExitStatus),
+    /// The program could not be started.
+    SpawnFailed(ShellError),
+}
+
+/// Request to launch `prog`, its console input is read from `input_rx`.
+#[derive(Debug)]
+pub struct Launch {
+    pub id: JobId,
+    pub prog: String,
+    /// Variables added to the environment of the program, the ones set with `export`.
+    pub env: BTreeMap<String, String>,
+    pub input_rx: Receiver<ChildInput>,
+}
+
+/// Forward every line of `reader` to the event loop, wrapped by `event`.
+fn pump_output<R: Read>(
+    reader: R,
+    id: JobId,
+    child_sx: &Sender<(JobId, ChildEvent)>,
+    event: fn(String) -> ChildEvent,
+) {
+    let mut reader = BufReader::new(reader);
+    loop {
+        let mut output = String::new();
+        match reader.read_line(&mut output) {
+            // EOF reached or pipe broken
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                // the event loop is gone, the shell is exiting
+                if child_sx.send((id, event(output))).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Forward the output of a pseudo-terminal as soon as it is read,
+/// interactive programs print their prompts without a newline.
+fn pump_chunks<R: Read>(mut reader: R, id: JobId, child_sx: &Sender<(JobId, ChildEvent)>) {
+    let mut buf = [0u8; 4096];
+    loop {
+        match reader.read(&mut buf) {
+            // EOF reached, or EIO once the program closed the terminal
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let output = String::from_utf8_lossy(&buf[..n]).into_owned();
+                // the event loop is gone, the shell is exiting
+                if child_sx.send((id, ChildEvent::Stdout(output))).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Jobs of the pool a program takes at most: its supervisor and the pumps of stdout and stderr.
+const JOBS_PER_CHILD: u32 = 3;
+
+/// Body of the child handler actor: launch every program requested on its mailbox `prog_rx`,
+/// supervised by a job of a pool sized for `children` programs running at once: the next ones
+/// are launched as soon as one of them exits.
+/// With `pty` the programs run on a pseudo-terminal instead of pipes.
+/// The jobs of the pool are registered with `registry` as `shell_pool_jobs_*`.
+pub fn handle_child(
+    prog_rx: &Receiver<Launch>,
+    child_sx: Sender<(JobId, ChildEvent)>,
+    pty: bool,
+    children: u32,
+    registry: &Registry,
+) {
+    let pool = Arc::new(ThreadPool::<Job>::new(children * JOBS_PER_CHILD));
+    pool.register_metrics(registry, "shell_pool");
+    // a slot is taken by every running program, a pump never waits for a worker then
+    let (slot_sx, slot_rx) = crossbeam::channel::bounded::<()>(children as usize);
+    let mut supervisors: Vec<JobHandle<()>> = Vec::new();
+
+    // the event loop drops its sender when the shell exits
+    while let Ok(launch) = prog_rx.recv() {
+        // `slot_rx` is held here, the send only waits for a free slot
+        let _ = slot_sx.send(());
+        supervisors.retain(|handle| !handle.is_finished());
+
+        let id = launch.id;
+        let (events, slot, jobs) = (child_sx.clone(), slot_rx.clone(), pool.clone());
+        let supervisor = pool.spawn(move || {
+            supervise_child(launch, events, pty, &jobs);
+            drop(jobs);
+            // the slot of this program was sent before, it is still in the channel
+            let _ = slot.recv();
+        });
+        match supervisor {
+            Ok(supervisor) => supervisors.push(supervisor),
+            // the launch is answered anyway, the event loop waits for it
+            Err(e) => {
+                warn!(job = id, error = %e, "cannot supervise the program");
+                let _ = slot_rx.recv();
+                let _ = child_sx.send((id, ChildEvent::SpawnFailed(e.into())));
+            }
+        }
+    }
+
+    // the pool is dropped here, after the supervisors dropped their references to it
+    for handle in supervisors {
+        if let Err(e) = handle.join() {
+            panic!("{}", e);
+        }
+    }
+}
+
+/// Standard input of a running program.
+enum ChildStdio {
+    Pipe(Option<ChildStdin>),
+    Pty(Pty),
+}
+
+impl ChildStdio {
+    fn write_line(&mut self, line: &str) {
+        // the child may have closed its stdin, stop forwarding in that case
+        let written = match self {
+            ChildStdio::Pipe(stdin) => stdin
+                .as_mut()
+                .map(|stdin| stdin.write_all(line.as_bytes()).and_then(|_| stdin.flush())),
+            ChildStdio::Pty(pty) => Some(pty.write_all(line.as_bytes())),
+        };
+        if let (ChildStdio::Pipe(stdin), Some(Err(_))) = (self, written) {
+            *stdin = None;
+        }
+    }
+
+    fn close(&mut self) {
+        match self {
+            ChildStdio::Pipe(stdin) => *stdin = None,
+            ChildStdio::Pty(pty) => {
+                let _ = pty.send_eof();
+            }
+        }
+    }
+
+    fn resize(&self) {
+        if let ChildStdio::Pty(pty) = self {
+            // a failure leaves the program with the previous size, nothing to do about it
+            let _ = pty.resize();
+        }
+    }
+}
+
+fn spawn_piped(name: &str, args: &[String], env: &BTreeMap<String, String>) -> io::Result<Child> {
+    Command::new(name)
+        .args(args)
+        .envs(env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+}
+
+fn supervise_child(
+    launch: Launch,
+    child_sx: Sender<(JobId, ChildEvent)>,
+    pty: bool,
+    pool: &ThreadPool<Job>,
+) {
+    let Launch { id, prog, env, input_rx } = launch;
+    let _span = debug_span!("child", job = id).entered();
+
+    let progs = expand_globs(prog.split_ascii_whitespace());
+    let name = progs.first().cloned().unwrap_or_default();
+    let args = progs.get(1..).unwrap_or_default();
+
+    let spawned = if pty {
+        Pty::spawn(&name, args, &env).and_then(|(pty, child)| {
+            let reader = pty.reader()?;
+            Ok((Some(reader), ChildStdio::Pty(pty), child))
+        })
+    } else {
+        spawn_piped(&name, args, &env)
+            .map(|mut child| (None, ChildStdio::Pipe(child.stdin.take()), child))
+    };
+    let (pty_reader, mut child_stdin, mut child) = match spawned {
+        Ok(spawned) => spawned,
+        Err(e) => {
+            debug!(program = %name, error = %e, "spawn failed");
+            // the event loop is gone, the shell is exiting
+            let _ = child_sx.send((id, ChildEvent::SpawnFailed(ShellError::spawn(&name, e))));
+            return;
+        }
+    };
+
+    debug!(program = %name, pid = child.id(), "spawned");
+    // the shell exited while the program was started, nobody would read its output
+    if child_sx.send((id, ChildEvent::Started)).is_err() {
+        let _ = child.kill();
+        let _ = child.wait();
+        return;
+    }
+
+    // Every pump holds a sender: the channel disconnects once all the outputs are closed.
+    let (done_sx, done_rx) = crossbeam::channel::bounded::<()>(0);
+
+    let mut pumps = Vec::new();
+    if let Some(reader) = pty_reader {
+        let (pty_done, pty_sx) = (done_sx.clone(), child_sx.clone());
+        pumps.push(pool.spawn(move || {
+            pump_chunks(reader, id, &pty_sx);
+            drop(pty_done);
+        }));
+    }
+    if let Some(child_stdout) = child.stdout.take() {
+        let (stdout_done, stdout_sx) = (done_sx.clone(), child_sx.clone());
+        pumps.push(pool.spawn(move || {
+            pump_output(child_stdout, id, &stdout_sx, ChildEvent::Stdout);
+            drop(stdout_done);
+        }));
+    }
+    if let Some(child_stderr) = child.stderr.take() {
+        let (stderr_done, stderr_sx) = (done_sx.clone(), child_sx.clone());
+        pumps.push(pool.spawn(move || {
+            pump_output(child_stderr, id, &stderr_sx, ChildEvent::Stderr);
+            drop(stderr_done);
+        }));
+    }
+    drop(done_sx);
+
+    // a pump that didn't start leaves an output unread, the program can't go on without it
+    if let Some(Err(e)) = pumps.iter().find(|pump| pump.is_err()) {
+        warn!(error = %e, "cannot read the output, killing the program");
+        let _ = child.kill();
+    }
+
+    // once the event loop forgets the job, nothing more is received
+    let mut input_rx = input_rx;
+    loop {
+        crossbeam::select! {
+            recv(input_rx) -> input => match input {
+                Ok(ChildInput::Line(line)) => child_stdin.write_line(&line),
+                Ok(ChildInput::Eof) => child_stdin.close(),
+                Ok(ChildInput::Kill) => {
+                    debug!("killed");
+                    // the child may already be gone, nothing left to do then
+                    let _ = child.kill();
+                }
+                Ok(ChildInput::Resize) => child_stdin.resize(),
+                Err(_) => input_rx = crossbeam::channel::never(),
+            },
+            recv(done_rx) -> _ => break,
+        }
+    }
+    for pump in pumps.into_iter().flatten() {
+        if let Err(e) = pump.join() {
+            panic!("{}", e);
+        }
+    }
+
+    drop(child_stdin);
+    let status = child.wait().unwrap();
+    debug!(%status, "exited");
+    // background jobs may outlive the event loop, nobody is waiting for them then
+    let _ = child_sx.send((id, ChildEvent::Exited(status)));
+}
@@ -0,0 +1,360 @@
+use std::{
+    env, fs,
+    io::{self, stdin, stdout, Read, Write},
+    mem,
+    path::{self, Path, MAIN_SEPARATOR},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    history::History,
+    platform::{Current, Platform},
+};
+
+const CTRL_C: u8 = 0x03;
+const CTRL_D: u8 = 0x04;
+const BACKSPACE: u8 = 0x08;
+const TAB: u8 = b'\t';
+const ESC: u8 = 0x1b;
+const DEL: u8 = 0x7f;
+
+#[derive(Debug)]
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Tab,
+    Interrupt,
+    Eof,
+    Unknown,
+}
+
+/// Line editor for interactive terminals, with history recall and tab completion.
+pub struct LineEditor {
+    history: Arc<Mutex<History>>,
+    buffer: Vec<char>,
+    cursor: usize,
+    // cursor position on screen, relative to the start of the edited text
+    drawn_cursor: usize,
+}
+
+impl LineEditor {
+    pub fn new(history: Arc<Mutex<History>>) -> Self {
+        Self {
+            history,
+            buffer: vec![],
+            cursor: 0,
+            drawn_cursor: 0,
+        }
+    }
+
+    /// Same contract as `BufRead::read_line`: the line is appended to `buf`
+    /// together with its `\n`, and `Ok(0)` is returned on EOF.
+    pub fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        let _raw = Current::enable_raw_mode()?;
+
+        self.buffer.clear();
+        self.cursor = 0;
+        self.drawn_cursor = 0;
+        // index in the history while browsing it, together with the line being edited before
+        let mut browsing: Option<(usize, Vec<char>)> = None;
+
+        loop {
+            match self.read_key()? {
+                Key::Char(c) => {
+                    self.buffer.insert(self.cursor, c);
+                    self.cursor += 1;
+                }
+                Key::Enter => {
+                    print!("\r\n");
+                    stdout().flush()?;
+                    let line = self.buffer.iter().chain(['\n'].iter()).collect::<String>();
+                    buf.push_str(&line);
+                    return Ok(line.len());
+                }
+                Key::Backspace if self.cursor > 0 => {
+                    self.cursor -= 1;
+                    self.buffer.remove(self.cursor);
+                }
+                Key::Delete if self.cursor < self.buffer.len() => {
+                    self.buffer.remove(self.cursor);
+                }
+                Key::Left if self.cursor > 0 => self.cursor -= 1,
+                Key::Right if self.cursor < self.buffer.len() => self.cursor += 1,
+                Key::Home => self.cursor = 0,
+                Key::End => self.cursor = self.buffer.len(),
+                Key::Up => {
+                    let len = self.history.lock().unwrap().entries().len();
+                    let index = match browsing {
+                        None if len > 0 => {
+                            browsing = Some((len, mem::take(&mut self.buffer)));
+                            len - 1
+                        }
+                        Some((index, _)) if index > 0 => index - 1,
+                        _ => continue,
+                    };
+                    if let Some((current, _)) = browsing.as_mut() {
+                        *current = index;
+                    }
+                    self.recall(index);
+                }
+                Key::Down => match browsing.take() {
+                    Some((index, draft))
+                        if index + 1 >= self.history.lock().unwrap().entries().len() =>
+                    {
+                        self.replace_buffer(draft)
+                    }
+                    Some((index, draft)) => {
+                        self.recall(index + 1);
+                        browsing = Some((index + 1, draft));
+                    }
+                    None => continue,
+                },
+                Key::Tab => self.complete()?,
+                Key::Interrupt => {
+                    print!("^C\r\n");
+                    stdout().flush()?;
+                    buf.push('\n');
+                    return Ok(1);
+                }
+                Key::Eof if self.buffer.is_empty() => return Ok(0),
+                Key::Eof if self.cursor < self.buffer.len() => {
+                    self.buffer.remove(self.cursor);
+                }
+                _ => continue,
+            }
+
+            self.redraw()?;
+        }
+    }
+
+    fn read_byte(&self) -> io::Result<Option<u8>> {
+        let mut byte = [0u8];
+        match stdin().lock().read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    fn read_key(&self) -> io::Result<Key> {
+        let byte = match self.read_byte()? {
+            None => return Ok(Key::Eof),
+            Some(b) => b,
+        };
+
+        let key = match byte {
+            b'\r' | b'\n' => Key::Enter,
+            BACKSPACE | DEL => Key::Backspace,
+            TAB => Key::Tab,
+            CTRL_C => Key::Interrupt,
+            CTRL_D => Key::Eof,
+            ESC => self.read_escape()?,
+            b if b.is_ascii_control() => Key::Unknown,
+            b if b.is_ascii() => Key::Char(b as char),
+            b => self.read_utf8(b)?,
+        };
+
+        Ok(key)
+    }
+
+    /// Decode the `ESC [ x` sequences sent by the arrow and editing keys.
+    fn read_escape(&self) -> io::Result<Key> {
+        if self.read_byte()? != Some(b'[') {
+            return Ok(Key::Unknown);
+        }
+
+        let key = match self.read_byte()? {
+            Some(b'A') => Key::Up,
+            Some(b'B') => Key::Down,
+            Some(b'C') => Key::Right,
+            Some(b'D') => Key::Left,
+            Some(b'H') => Key::Home,
+            Some(b'F') => Key::End,
+            Some(b'3') if self.read_byte()? == Some(b'~') => Key::Delete,
+            _ => Key::Unknown,
+        };
+
+        Ok(key)
+    }
+
+    fn read_utf8(&self, first: u8) -> io::Result<Key> {
+        let len = match first.leading_ones() {
+            2 => 2,
+            3 => 3,
+            4 => 4,
+            _ => return Ok(Key::Unknown),
+        };
+
+        let mut bytes = vec![first];
+        for _ in 1..len {
+            match self.read_byte()? {
+                Some(b) => bytes.push(b),
+                None => return Ok(Key::Eof),
+            }
+        }
+
+        Ok(String::from_utf8(bytes)
+            .ok()
+            .and_then(|s| s.chars().next())
+            .map_or(Key::Unknown, Key::Char))
+    }
+
+    /// Replace the edited text with the history entry at `index`.
+    fn recall(&mut self, index: usize) {
+        let entry = self.history.lock().unwrap().entries()[index]
+            .chars()
+            .collect();
+        self.replace_buffer(entry);
+    }
+
+    fn replace_buffer(&mut self, buffer: Vec<char>) {
+        self.buffer = buffer;
+        self.cursor = self.buffer.len();
+    }
+
+    /// Redraw the edited text in place, the prompt before it is left untouched.
+    fn redraw(&mut self) -> io::Result<()> {
+        let mut out = stdout();
+        if self.drawn_cursor > 0 {
+            write!(out, "\x1b[{}D", self.drawn_cursor)?;
+        }
+        write!(out, "\x1b[K{}", self.buffer.iter().collect::<String>())?;
+
+        let back = self.buffer.len() - self.cursor;
+        if back > 0 {
+            write!(out, "\x1b[{}D", back)?;
+        }
+        self.drawn_cursor = self.cursor;
+
+        out.flush()
+    }
+
+    /// Complete the word under the cursor: the first word of the line is
+    /// completed with the executables in `$PATH`, the others with file names.
+    fn complete(&mut self) -> io::Result<()> {
+        let start = self.buffer[..self.cursor]
+            .iter()
+            .rposition(|c| c.is_whitespace())
+            .map_or(0, |i| i + 1);
+        let word = self.buffer[start..self.cursor].iter().collect::<String>();
+
+        let candidates = if start == 0 && !word.contains(path::is_separator) {
+            complete_command(&word)
+        } else {
+            complete_file(&word)
+        };
+
+        let completion = match candidates.as_slice() {
+            [] => return Ok(()),
+            [single] if single.ends_with(path::is_separator) => single.clone(),
+            [single] => format!("{} ", single),
+            _ => common_prefix(&candidates),
+        };
+
+        if completion.len() > word.len() {
+            let rest = completion[word.len()..].chars().collect::<Vec<_>>();
+            let len = rest.len();
+            self.buffer.splice(self.cursor..self.cursor, rest);
+            self.cursor += len;
+        } else {
+            // nothing left to insert, show the alternatives below the line
+            print!("\r\n{}\r\n", candidates.join("  "));
+            self.drawn_cursor = 0;
+        }
+
+        Ok(())
+    }
+}
+
+/// Executables in `$PATH` whose name starts with `prefix`.
+fn complete_command(prefix: &str) -> Vec<String> {
+    let path = env::var_os("PATH").unwrap_or_default();
+
+    let mut candidates = env::split_paths(&path)
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .metadata()
+                .is_ok_and(|m| Current::is_executable(&entry.path(), &m))
+        })
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.starts_with(prefix))
+        .collect::<Vec<_>>();
+
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+/// Paths starting with `word`, directories are returned with a trailing separator.
+fn complete_file(word: &str) -> Vec<String> {
+    let (dir, prefix) = match word.rfind(path::is_separator) {
+        Some(i) => (&word[..=i], &word[i + 1..]),
+        None => ("", word),
+    };
+    let read_dir = if dir.is_empty() {
+        Path::new(".")
+    } else {
+        Path::new(dir)
+    };
+
+    let mut candidates = fs::read_dir(read_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let slash = if entry.path().is_dir() {
+                MAIN_SEPARATOR.to_string()
+            } else {
+                String::new()
+            };
+            Some(format!("{}{}{}", dir, name, slash))
+        })
+        .collect::<Vec<_>>();
+
+    candidates.sort();
+    candidates
+}
+
+fn common_prefix(words: &[String]) -> String {
+    let mut prefix = words.first().cloned().unwrap_or_default();
+    for word in words {
+        while !word.starts_with(&prefix) {
+            prefix.pop();
+        }
+    }
+    prefix
+}
+
+#[cfg(test)]
+mod test {
+    use crate::editor::{common_prefix, complete_file};
+
+    #[test]
+    fn common_prefix_test() {
+        let words = ["cargo".to_string(), "cat".into(), "cal".into()];
+        assert_eq!(common_prefix(&words), "ca");
+        assert_eq!(common_prefix(&words[..1]), "cargo");
+        assert_eq!(common_prefix(&[]), "");
+    }
+
+    #[test]
+    fn complete_file_test() {
+        assert_eq!(complete_file("sr"), ["src/"]);
+        assert_eq!(complete_file("src/ma"), ["src/main.rs"]);
+        assert!(complete_file("does-not-exist/").is_empty());
+    }
+}
@@ -0,0 +1,54 @@
+use glob::{glob_with, MatchOptions};
+
+/// Expand the `*`, `?` and `[...]` patterns in `words` with the matching paths,
+/// a pattern matching nothing is kept as it is.
+pub fn expand_globs<'a>(words: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let options = MatchOptions {
+        // like a shell, `*` does not match hidden files
+        require_literal_leading_dot: true,
+        ..MatchOptions::new()
+    };
+
+    let mut expanded = vec![];
+    for word in words {
+        if !word.contains(['*', '?', '[']) {
+            expanded.push(word.to_string());
+            continue;
+        }
+
+        let mut matches = glob_with(word, options)
+            .map(|paths| {
+                paths
+                    .filter_map(Result::ok)
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        if matches.is_empty() {
+            expanded.push(word.to_string());
+        } else {
+            matches.sort();
+            expanded.append(&mut matches);
+        }
+    }
+
+    expanded
+}
+
+#[cfg(test)]
+mod test {
+    use crate::expand::expand_globs;
+
+    #[test]
+    fn expand_globs_test() {
+        let expanded = expand_globs(["wc", "-l", "src/ma?n.rs", "Cargo.tom[lx]"]);
+        assert_eq!(expanded, ["wc", "-l", "src/main.rs", "Cargo.toml"]);
+
+        let expanded = expand_globs(["src/*.rs"]);
+        assert!(expanded.contains(&"src/main.rs".to_string()));
+        assert!(expanded.iter().all(|path| path.ends_with(".rs")));
+
+        assert_eq!(expand_globs(["*.nothing", "[a"]), ["*.nothing", "[a"]);
+    }
+}
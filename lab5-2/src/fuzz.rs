@@ -0,0 +1,84 @@
+//! Entry point for fuzzing the command lines, built with `RUSTFLAGS="--cfg fuzzing"`
+//! and by the tests. The shell is only a binary, a fuzzer can't link it yet: for now
+//! the tests run it on fixed and random command lines.
+
+use crate::alias::{parse_alias_args, AliasArg, Aliases};
+use crate::chain::parse_command_list;
+use crate::{split_command, split_timeout};
+
+/// Bytes of the input parsed, the lines of the shell are short.
+pub const MAX_INPUT: usize = 4096;
+/// Aliases defined at most, an alias copies its value in the line every time it is expanded.
+const MAX_ALIASES: usize = 16;
+
+/// Parse the lines of `bytes` as the shell does before running them: the lists of commands,
+/// the aliases, the `alias` builtin, the `:timeout` prefix and the words of the program.
+/// The programs and the other builtins are not run. Any input is parsed or is a
+/// `ShellError`, without panicking.
+pub fn fuzz_parse(bytes: &[u8]) {
+    let text = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_INPUT)]);
+    let mut aliases = Aliases::default();
+
+    for line in text.lines() {
+        let Ok(list) = parse_command_list(line) else {
+            continue;
+        };
+        for (_, prog) in list {
+            let prog = aliases.expand(&prog);
+            match split_command(&prog) {
+                ("alias", args) => define_aliases(&mut aliases, args),
+                ("unalias", args) => args.split_whitespace().for_each(|name| {
+                    aliases.remove(name);
+                }),
+                _ => {
+                    if let Ok((_, prog)) = split_timeout(&prog) {
+                        prog.split_ascii_whitespace().for_each(drop);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn define_aliases(aliases: &mut Aliases, args: &str) {
+    for arg in parse_alias_args(args).unwrap_or_default() {
+        if let AliasArg::Define(name, value) = arg {
+            if aliases.iter().count() < MAX_ALIASES || aliases.get(&name).is_some() {
+                aliases.define(&name, &value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use labs_common::XorShift;
+
+    use crate::fuzz::fuzz_parse;
+
+    #[test]
+    fn fuzz_parse_test() {
+        let inputs: [&[u8]; 8] = [
+            b"",
+            b"&&\n||\n;;;",
+            b"alias a='a a' b=\"a\" c\na && b || c",
+            b"alias x='\nalias =y\nalias 'z\nunalias x y",
+            b":timeout\n:timeout 1e400 ls\n:timeout -1 ls\n:timeout NaN ls",
+            b"ls \xff\xfe && :timeout 0.5 \xc3",
+            "alias é='ö; ü' && é\u{a0}x".as_bytes(),
+            b"alias a=b b=c c=a\na\nb;c",
+        ];
+        for input in inputs {
+            fuzz_parse(input);
+        }
+
+        // the bytes of the command lines, mixed up
+        let alphabet = b"alias =':timeout 1 &|;\n\"\xff";
+        let mut rng = XorShift::new(7);
+        for _ in 0..200 {
+            let len = rng.below(64);
+            let bytes = (0..len).map(|_| alphabet[rng.below(alphabet.len())]).collect::<Vec<_>>();
+            fuzz_parse(&bytes);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use labs_error::ShellError;
+
+use crate::platform::{Current, Platform};
+
+/// Name of the history file, placed in the user home directory.
+const HISTORY_FILE: &str = ".lab5_history";
+
+/// Lines entered at the prompt, optionally mirrored to a file.
+#[derive(Debug, Default)]
+pub struct History {
+    entries: Vec<String>,
+    file: Option<PathBuf>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// History kept in memory only, starting from `entries`.
+    pub fn with_entries(entries: Vec<String>) -> Self {
+        Self {
+            entries,
+            file: None,
+        }
+    }
+
+    /// History persisted in `~/.lab5_history`, or in memory only if the home is not known.
+    pub fn from_home() -> Self {
+        match Current::home_dir() {
+            Some(home) => Self::from_file(home.join(HISTORY_FILE)),
+            None => Self::new(),
+        }
+    }
+
+    /// Load the entries already stored in `path`, new entries are appended to it.
+    pub fn from_file(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .map(|content| content.lines().map(String::from).collect())
+            .unwrap_or_default();
+
+        Self {
+            entries,
+            file: Some(path),
+        }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    pub fn last(&self) -> Option<&str> {
+        self.entries.last().map(String::as_str)
+    }
+
+    /// Entry number `n`, numbered from 1 as shown by the `history` builtin.
+    pub fn get(&self, n: usize) -> Option<&str> {
+        n.checked_sub(1)
+            .and_then(|i| self.entries.get(i))
+            .map(String::as_str)
+    }
+
+    pub fn push(&mut self, line: &str) -> io::Result<()> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(());
+        }
+        self.entries.push(line.to_string());
+
+        if let Some(path) = &self.file {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", line)?;
+        }
+
+        Ok(())
+    }
+
+    /// Replace every `!!` with the last entry and every `!n` with entry number `n`.
+    pub fn expand(&self, line: &str) -> Result<String, ShellError> {
+        let mut expanded = String::new();
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '!' {
+                expanded.push(c);
+                continue;
+            }
+
+            match chars.peek() {
+                Some('!') => {
+                    chars.next();
+                    expanded.push_str(self.last().ok_or_else(|| ShellError::EventNotFound("!!".to_string()))?);
+                }
+                Some(d) if d.is_ascii_digit() => {
+                    let mut number = String::new();
+                    while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                        number.push(d);
+                    }
+                    let entry = number
+                        .parse()
+                        .ok()
+                        .and_then(|n| self.get(n))
+                        .ok_or_else(|| ShellError::EventNotFound(format!("!{}", number)))?;
+                    expanded.push_str(entry);
+                }
+                _ => expanded.push(c),
+            }
+        }
+
+        Ok(expanded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::history::History;
+
+    fn history(lines: &[&str]) -> History {
+        let mut history = History::new();
+        for line in lines {
+            history.push(line).unwrap();
+        }
+        history
+    }
+
+    #[test]
+    fn push_skips_blank_lines() {
+        let history = history(&["ls -la\n", "   \n", "echo hi"]);
+        assert_eq!(history.entries(), ["ls -la", "echo hi"]);
+    }
+
+    #[test]
+    fn expand_test() {
+        let history = history(&["ls -la", "echo hi"]);
+
+        assert_eq!(history.expand("!!").unwrap(), "echo hi");
+        assert_eq!(history.expand("!1 /tmp").unwrap(), "ls -la /tmp");
+        assert_eq!(history.expand("echo !").unwrap(), "echo !");
+        assert!(history.expand("!3").is_err());
+        assert!(History::new().expand("!!").is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crossbeam::channel::Receiver;
+
+/// Where a line of the session transcript comes from.
+#[derive(Debug, Clone, Copy)]
+pub enum LogSource {
+    /// Command line entered at the prompt or read from a script.
+    Prompt,
+    /// Console line forwarded to the running child.
+    Stdin,
+    Stdout,
+    Stderr,
+    /// Exit code of a command.
+    Exit,
+}
+
+#[derive(Debug)]
+pub struct LogRecord {
+    pub time: SystemTime,
+    pub source: LogSource,
+    pub text: String,
+}
+
+impl LogRecord {
+    pub fn new(source: LogSource, text: &str) -> Self {
+        Self {
+            time: SystemTime::now(),
+            source,
+            text: text.trim_end_matches('\n').to_string(),
+        }
+    }
+}
+
+impl fmt::Display for LogSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match self {
+            Self::Prompt => "prompt",
+            Self::Stdin => "stdin",
+            Self::Stdout => "stdout",
+            Self::Stderr => "stderr",
+            Self::Exit => "exit",
+        };
+        // pad so that the text of every record starts on the same column
+        f.pad(source)
+    }
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = self.time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        write!(
+            f,
+            "{}.{:03} {:6} {}",
+            time.as_secs(),
+            time.subsec_millis(),
+            self.source,
+            self.text
+        )
+    }
+}
+
+/// Write every record to `file` until all the senders are dropped.
+pub fn logger(mut file: File, log_rx: Receiver<LogRecord>) -> io::Result<()> {
+    for record in log_rx {
+        writeln!(file, "{}", record)?;
+    }
+    file.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::logger::{LogRecord, LogSource};
+
+    #[test]
+    fn display_test() {
+        let mut record = LogRecord::new(LogSource::Exit, "0\n");
+        record.time = UNIX_EPOCH + Duration::from_millis(1_500);
+        assert_eq!(record.to_string(), "1.500 exit   0");
+    }
+}
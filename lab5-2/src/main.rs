@@ -1,75 +1,354 @@
 use std::{
-    io::{stdin, stdout, BufRead, BufReader, Write},
-    process::{Command, Stdio},
+    collections::BTreeMap,
+    env,
+    fs::File,
+    io::{stdin, stdout, BufRead, BufReader, IsTerminal, Write},
+    mem,
+    net::SocketAddr,
+    num::NonZeroU32,
+    path::PathBuf,
+    process,
+    sync::{Arc, Mutex},
     thread,
+    time::{Duration, Instant},
 };
 
+use alias::{parse_alias_args, AliasArg, Aliases};
+use chain::parse_command_list;
+use child::{handle_child, ChildEvent, ChildInput, JobId, Launch};
+use clap::Parser;
 use crossbeam::channel::{Receiver, Sender};
+use editor::LineEditor;
+use history::History;
+use lab3_3::FileSystem;
+use labs_common::{Actor, ActorBuilder, Clock, SystemClock};
+use labs_error::ShellError;
+use labs_metrics::{Counter, Gauge, Registry};
+use logger::{logger, LogRecord, LogSource};
+use platform::{Current, Platform};
+use prompt::{render_prompt, DEFAULT_PROMPT};
+use script::Script;
+use session::Session;
+use tracing::{debug, debug_span, trace};
+use vfs::{vfs_builtin, VFS_COMMAND};
 
+mod alias;
+mod chain;
+mod child;
+mod editor;
+mod expand;
+#[cfg(any(fuzzing, test))]
+#[cfg_attr(fuzzing, allow(dead_code))]
+pub mod fuzz;
+mod history;
+mod logger;
+mod platform;
+mod prompt;
+#[cfg_attr(not(unix), path = "pty_unsupported.rs")]
+mod pty;
+mod script;
+mod session;
+mod vfs;
+
+#[derive(Debug, Parser)]
+struct Args {
+    /// Run the commands in this file instead of prompting for them
+    #[arg()]
+    script: Option<PathBuf>,
+
+    /// Run the given commands instead of prompting for them
+    #[arg(short, conflicts_with = "script")]
+    command: Option<String>,
+
+    /// Kill every command running longer than this many seconds
+    #[arg(long, value_parser = parse_seconds)]
+    default_timeout: Option<Duration>,
+
+    /// Record the commands, their output and exit status in this file
+    #[arg(long)]
+    log: Option<PathBuf>,
+
+    /// Run the programs on a pseudo-terminal, for programs that behave differently without one
+    #[arg(long)]
+    pty: bool,
+
+    /// Programs running at once, the next ones are launched once one of them exits
+    #[arg(long, default_value = "32")]
+    max_jobs: NonZeroU32,
+
+    /// Prompt template, `{cwd}`, `{status}` and `{branch}` are replaced by the current
+    /// directory, the exit code of the last command and the git branch
+    #[arg(long, default_value = DEFAULT_PROMPT)]
+    prompt: String,
+
+    /// Print the events of the shell on stderr: a level like `debug`, or directives like
+    /// `warn,lab5_2=trace` to pick the modules
+    #[arg(long, default_value = "warn")]
+    log_level: String,
+
+    /// Serve the metrics of the jobs and of the pool running them at http://<addr>/metrics
+    #[arg(long)]
+    metrics: Option<SocketAddr>,
+
+    /// Restore the directory, the variables, the aliases, the history and the `vfs` files
+    /// saved in this file, and save them there on exit. The history is kept in the session
+    /// instead of `~/.lab5_history`
+    #[arg(long)]
+    session: Option<PathBuf>,
+}
+
+/// Console line that kills the running child instead of being forwarded to it.
+const KILL_COMMAND: &str = ":kill";
+/// Prefix limiting the running time of a command, e.g. `:timeout 5 sleep 10`.
+const TIMEOUT_COMMAND: &str = ":timeout";
+/// Exit code of a command killed because of its timeout, same as coreutils `timeout`.
+const TIMEOUT_STATUS: i32 = 124;
+/// Launch a command in the background, e.g. `:spawn ping localhost`.
+const SPAWN_COMMAND: &str = ":spawn";
+/// Send the console input to a background command, `:switch` alone goes back to the shell.
+const SWITCH_COMMAND: &str = ":switch";
+/// List the running commands.
+const JOBS_COMMAND: &str = ":jobs";
+
+/// State of the shell kept between commands, saved by `--session`.
+#[derive(Debug, Default)]
+struct ShellState {
+    aliases: Aliases,
+    /// Variables set with `export`, added to the environment of the programs.
+    env: BTreeMap<String, String>,
+    /// Filesystem of the `vfs` builtin, empty when the shell starts.
+    vfs: FileSystem,
+}
+
+impl ShellState {
+    /// State of a new shell, the `vfs` files take their times from `clock`.
+    fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            vfs: FileSystem::with_clock(clock),
+            ..Self::default()
+        }
+    }
+}
+
+/// Command launched by the shell and not exited yet.
 #[derive(Debug)]
-enum ChildState {
-    Working,
-    Killed,
+struct Job {
+    prog: String,
+    input_sx: Sender<ChildInput>,
+}
+
+/// Metrics of the job table, updated by the event loop.
+#[derive(Debug)]
+struct JobMetrics {
+    running: Gauge,
+    launched: Counter,
+}
+
+impl JobMetrics {
+    fn new(registry: &Registry) -> Self {
+        Self {
+            running: registry.gauge("shell_jobs_running", "Commands launched and not exited yet."),
+            launched: registry.counter("shell_jobs_launched_total", "Commands launched."),
+        }
+    }
 }
 
 struct EventLoop {
     console_rx: Receiver<String>,
-    child_rx: Receiver<(ChildState, String)>,
-    prog_sx: Sender<String>,
-    child_sx: Sender<String>,
+    child_rx: Receiver<(JobId, ChildEvent)>,
+    /// Mailbox of the child handler.
+    prog_sx: Sender<Launch>,
+    jobs: BTreeMap<JobId, Job>,
+    next_job: JobId,
+    /// Background job receiving the console input after `:switch`.
+    focus: Option<JobId>,
+    /// Resizes of the shell terminal, never ready without `--pty`.
+    resize_rx: Receiver<()>,
+    prompt: String,
+    last_status: i32,
+    history: Arc<Mutex<History>>,
+    script: Option<Script>,
+    default_timeout: Option<Duration>,
+    /// Clock of the timeouts and of the `vfs` creation times.
+    clock: Arc<dyn Clock>,
+    shell: ShellState,
+    log_sx: Option<Sender<LogRecord>>,
+    metrics: JobMetrics,
 }
 
-fn input_reader(console_sx: Sender<String>) {
-    let mut stdin = BufReader::new(stdin());
-    loop {
-        let mut output = String::new();
-        stdin.read_line(&mut output).unwrap();
-        console_sx.send(output).unwrap();
+impl EventLoop {
+    fn log(&self, source: LogSource, text: &str) {
+        if let Some(log_sx) = &self.log_sx {
+            log_sx.send(LogRecord::new(source, text)).unwrap();
+        }
     }
-}
 
-fn handle_child(
-    prog_rx: Receiver<String>,
-    child_console_rx: Receiver<String>,
-    child_sx: Sender<(ChildState, String)>,
-) {
-    loop {
-        let prog = prog_rx.recv().unwrap();
+    /// Ask the child handler to start `prog`, returning the id tagging its events.
+    fn launch(&mut self, prog: &str) -> JobId {
+        let id = self.next_job;
+        self.next_job += 1;
+
+        let (input_sx, input_rx) = crossbeam::channel::unbounded();
+        let job = Job {
+            prog: prog.trim().to_string(),
+            input_sx,
+        };
+        self.jobs.insert(id, job);
+        self.metrics.running.set(self.jobs.len() as i64);
+        self.metrics.launched.inc();
+
+        let launch = Launch {
+            id,
+            prog: prog.to_string(),
+            env: self.shell.env.clone(),
+            input_rx,
+        };
+        self.prog_sx.send(launch).unwrap();
+        debug!(job = id, running = self.jobs.len(), "launched");
+
+        id
+    }
+
+    fn send_input(&self, id: JobId, input: ChildInput) {
+        if let Some(job) = self.jobs.get(&id) {
+            // the job may have just exited, its exit event is still in the channel
+            let _ = job.input_sx.send(input);
+        }
+    }
 
-        let mut progs = prog.split_ascii_whitespace().collect::<Vec<_>>();
-        println!("child: {:?}", progs);
+    fn remove_job(&mut self, id: JobId) {
+        self.jobs.remove(&id);
+        self.metrics.running.set(self.jobs.len() as i64);
+        debug!(job = id, running = self.jobs.len(), "removed");
+        if self.focus == Some(id) {
+            self.focus = None;
+        }
+    }
 
-        let mut child = Command::new(progs[0])
-            .args(&mut progs[1..])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()
-            .unwrap();
+    fn print_prompt(&self) {
+        match self.focus {
+            Some(id) => print!("[{}]> ", id),
+            None => print!("{}", render_prompt(&self.prompt, self.last_status)),
+        }
+        stdout().flush().unwrap();
+    }
 
-        let child_stdout = child.stdout.take().unwrap();
-        let mut child_stdin = child.stdin.take().unwrap();
-        let mut child_reader = BufReader::new(child_stdout);
+    /// Print the output of a background job, each line prefixed by the job id.
+    fn background_event(&mut self, id: JobId, child_event: ChildEvent) {
+        match child_event {
+            ChildEvent::Started => (),
+            ChildEvent::Stdout(line) => {
+                let line = format!("[{}] {}", id, line);
+                self.log(LogSource::Stdout, &line);
+                stdout().write_all(line.as_bytes()).unwrap();
+            }
+            ChildEvent::Stderr(line) => {
+                let line = format!("[{}] {}", id, line);
+                self.log(LogSource::Stderr, &line);
+                eprint!("{}", line);
+            }
+            ChildEvent::Exited(status) => {
+                println!("[{}] exited: {}", id, status);
+                self.log(
+                    LogSource::Exit,
+                    &format!("[{}] {}", id, Current::exit_code(status)),
+                );
+                self.remove_job(id);
+            }
+            ChildEvent::SpawnFailed(e) => {
+                eprintln!("[{}] {}", id, e);
+                let exit = e.status();
+                self.log(LogSource::Exit, &format!("[{}] {}", id, exit));
+                self.remove_job(id);
+            }
+        }
+        stdout().flush().unwrap();
+    }
 
+    /// Wait for a console line, printing the background output meanwhile.
+    /// Returns `None` once the console reaches EOF.
+    fn next_console_line(&mut self) -> Option<String> {
         loop {
-            let console = child_console_rx.try_recv().unwrap_or(String::new());
-            if let Some(1) = console.as_bytes().first() {
-                child.kill().unwrap();
-                break;
+            crossbeam::select! {
+                recv(self.console_rx) -> line => return line.ok(),
+                recv(self.child_rx) -> child_event => {
+                    let (id, child_event) = child_event.unwrap();
+                    self.background_event(id, child_event);
+                    self.print_prompt();
+                },
+                recv(self.resize_rx) -> _ => self.resize_jobs(),
             }
+        }
+    }
 
-            println!("Child: {:?}", console);
+    fn resize_jobs(&self) {
+        trace!(jobs = self.jobs.len(), "terminal resized");
+        for id in self.jobs.keys() {
+            self.send_input(*id, ChildInput::Resize);
+        }
+    }
+
+    /// Kill the background jobs still running and wait for them to exit.
+    fn kill_jobs(&mut self) {
+        debug!(jobs = self.jobs.len(), "killing the jobs left");
+        for id in self.jobs.keys() {
+            self.send_input(*id, ChildInput::Kill);
+        }
+        while !self.jobs.is_empty() {
+            let (id, child_event) = self.child_rx.recv().unwrap();
+            self.background_event(id, child_event);
+        }
+    }
+}
+
+fn parse_seconds(secs: &str) -> Result<Duration, ShellError> {
+    secs.parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| ShellError::InvalidSeconds(secs.to_string()))
+}
+
+/// Split the `:timeout <secs>` prefix from a command line.
+fn split_timeout(prog: &str) -> Result<(Option<Duration>, &str), ShellError> {
+    let rest = match prog.trim_start().strip_prefix(TIMEOUT_COMMAND) {
+        Some(rest) if rest.starts_with(char::is_whitespace) => rest.trim_start(),
+        _ => return Ok((None, prog)),
+    };
+
+    let (secs, command) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    if command.trim().is_empty() {
+        return Err(ShellError::Usage(format!("{} <secs> <command>", TIMEOUT_COMMAND)));
+    }
 
-            child_stdin.write_all(console.as_bytes()).unwrap();
-            let mut output = String::new();
-            let bytes = child_reader.read_line(&mut output).unwrap();
+    Ok((Some(parse_seconds(secs)?), command))
+}
 
-            // EOF reached
-            if bytes == 0 {
-                child_sx.send((ChildState::Killed, output)).unwrap();
+fn input_reader(console_sx: Sender<String>, history: Arc<Mutex<History>>) {
+    // the line editor needs a terminal, plain lines are read from pipes and files
+    let mut editor = LineEditor::new(history);
+    let mut stdin = BufReader::new(stdin());
+    let interactive = stdin.get_ref().is_terminal();
+    loop {
+        let mut output = String::new();
+        let read = if interactive {
+            editor.read_line(&mut output)
+        } else {
+            stdin.read_line(&mut output)
+        };
+
+        // on EOF the sender is dropped, letting the event loop know there is no more input
+        match read {
+            Ok(0) => break,
+            Err(e) => {
+                eprintln!("stdin: {}", e);
                 break;
             }
+            Ok(_) => (),
+        }
 
-            child_sx.send((ChildState::Working, output)).unwrap();
+        // the event loop is gone, the shell is exiting
+        if console_sx.send(output).is_err() {
+            break;
         }
     }
 }
@@ -80,59 +359,563 @@ enum LoopState {
     ProgRunning,
 }
 
-#[derive(Debug)]
-enum LineFrom {
-    Console,
-    Child,
+fn print_history(history: &History) {
+    for (n, line) in history.entries().iter().enumerate() {
+        println!("{:5}  {}", n + 1, line);
+    }
 }
 
-fn main_event_loop(event: EventLoop) {
+fn main_event_loop(event: &mut EventLoop) -> i32 {
     let mut state = LoopState::Prompting;
     loop {
+        if let Some(script) = event.script.as_mut() {
+            let (line_no, prog) = match script.next_command() {
+                Some(command) => command,
+                None => return event.last_status,
+            };
+
+            event.last_status = run_list(event, &prog, &mut state);
+            if event.last_status != 0 {
+                let script = event.script.as_ref().unwrap();
+                eprintln!(
+                    "{}: line {}: exited with status {}",
+                    script.name(),
+                    line_no,
+                    event.last_status
+                );
+                return event.last_status;
+            }
+            continue;
+        }
+
         if let LoopState::Prompting = state {
-            print!("> ");
-            stdout().flush().unwrap();
+            event.print_prompt();
         }
 
-        let prog = event.console_rx.recv().unwrap();
-        event.prog_sx.send(prog).unwrap();
-        state = LoopState::ProgRunning;
+        let line = match event.next_console_line() {
+            Some(line) => line,
+            // Ctrl+D or end of the piped input
+            None => {
+                println!();
+                return event.last_status;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
 
-        while let LoopState::ProgRunning = state {
-            let (from, output) = crossbeam::select! {
-                recv(event.child_rx) -> line => (LineFrom::Child, line.unwrap()),
-                recv(event.console_rx) -> line => (LineFrom::Console, (ChildState::Working, line.unwrap())),
-            };
+        // after `:switch n` the console belongs to the job, only the `:` commands reach the shell
+        if let Some(id) = event.focus.filter(|_| !line.trim_start().starts_with(':')) {
+            event.log(LogSource::Stdin, &line);
+            event.send_input(id, ChildInput::Line(line));
+            continue;
+        }
 
-            if let ChildState::Killed = output.0 {
-                state = LoopState::Prompting;
+        let mut history = event.history.lock().unwrap();
+        let prog = match history.expand(&line) {
+            Ok(prog) => prog,
+            Err(e) => {
+                println!("{}", e);
+                continue;
             }
+        };
+        if prog != line {
+            print!("{}", prog);
+        }
+        if let Err(e) = history.push(&prog) {
+            println!("history: {}", e);
+        }
+
+        drop(history);
 
-            match from {
-                LineFrom::Console => event.child_sx.send(output.1).unwrap(),
-                LineFrom::Child => stdout().write_all(output.1.as_bytes()).unwrap(),
+        event.last_status = run_list(event, &prog, &mut state);
+    }
+}
+
+fn alias_builtin(aliases: &mut Aliases, args: &str) -> i32 {
+    let args = match parse_alias_args(args) {
+        Ok(args) if args.is_empty() => {
+            for (name, value) in aliases.iter() {
+                println!("alias {}='{}'", name, value);
             }
-            stdout().flush().unwrap();
+            return 0;
+        }
+        Ok(args) => args,
+        Err(e) => {
+            println!("{}", e);
+            return e.status();
+        }
+    };
+
+    let mut status = 0;
+    for arg in args {
+        match arg {
+            AliasArg::Define(name, value) => aliases.define(&name, &value),
+            AliasArg::Show(name) => match aliases.get(&name) {
+                Some(value) => println!("alias {}='{}'", name, value),
+                None => {
+                    println!("alias: {}: not found", name);
+                    status = 1;
+                }
+            },
         }
     }
+    status
+}
+
+fn unalias_builtin(aliases: &mut Aliases, args: &str) -> i32 {
+    let mut status = 0;
+    for name in args.split_whitespace() {
+        if !aliases.remove(name) {
+            println!("unalias: {}: not found", name);
+            status = 1;
+        }
+    }
+    status
+}
+
+/// Change the directory of the shell and of the programs launched after it, the home without `dir`.
+fn cd_builtin(dir: &str) -> i32 {
+    let dir = match (dir, Current::home_dir()) {
+        ("", Some(home)) => home,
+        ("", None) => {
+            println!("cd: home directory not known");
+            return 1;
+        }
+        (dir, _) => PathBuf::from(dir),
+    };
+    match env::set_current_dir(&dir) {
+        Ok(()) => 0,
+        Err(e) => {
+            println!("cd: {}: {}", dir.display(), e);
+            1
+        }
+    }
+}
+
+fn is_var_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Set the variables of `NAME=value` arguments for the programs launched after,
+/// print all of them without arguments.
+fn export_builtin(env: &mut BTreeMap<String, String>, args: &str) -> i32 {
+    if args.is_empty() {
+        for (name, value) in env.iter() {
+            println!("export {}={}", name, value);
+        }
+        return 0;
+    }
+
+    let mut status = 0;
+    for arg in args.split_whitespace() {
+        match arg.split_once('=') {
+            Some((name, value)) if is_var_name(name) => {
+                env.insert(name.to_string(), value.to_string());
+            }
+            _ => {
+                println!("export: `{}`: not a valid identifier", arg);
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+fn parse_job(event: &EventLoop, builtin: &str, arg: &str) -> Option<JobId> {
+    match arg.parse() {
+        Ok(id) if event.jobs.contains_key(&id) => Some(id),
+        _ => {
+            println!("{}: {}: no such job", builtin, arg);
+            None
+        }
+    }
+}
+
+fn spawn_builtin(event: &mut EventLoop, args: &str) -> i32 {
+    if args.is_empty() {
+        println!("usage: {} <command>", SPAWN_COMMAND);
+        return 2;
+    }
+    let id = event.launch(&format!("{}\n", args));
+    println!("[{}] {}", id, args);
+    0
+}
+
+fn switch_builtin(event: &mut EventLoop, args: &str) -> i32 {
+    if args.is_empty() {
+        event.focus = None;
+        return 0;
+    }
+    match parse_job(event, SWITCH_COMMAND, args) {
+        Some(id) => {
+            event.focus = Some(id);
+            0
+        }
+        None => 1,
+    }
+}
+
+fn kill_builtin(event: &mut EventLoop, args: &str) -> i32 {
+    let id = match (args, event.focus) {
+        ("", Some(id)) => id,
+        ("", None) => {
+            println!("no program running");
+            return 1;
+        }
+        (args, _) => match parse_job(event, KILL_COMMAND, args) {
+            Some(id) => id,
+            None => return 1,
+        },
+    };
+    event.send_input(id, ChildInput::Kill);
+    0
+}
+
+fn print_jobs(event: &EventLoop) {
+    for (id, job) in &event.jobs {
+        let focus = if event.focus == Some(*id) { "*" } else { " " };
+        println!("[{}]{} {}", id, focus, job.prog);
+    }
+}
+
+/// Run the commands of a list like `make && ./app || echo failed`, returning the exit code
+/// of the last one run.
+fn run_list(event: &mut EventLoop, line: &str, state: &mut LoopState) -> i32 {
+    let list = match parse_command_list(line) {
+        Ok(list) => list,
+        Err(e) => {
+            println!("{}", e);
+            return e.status();
+        }
+    };
+
+    let mut status = 0;
+    for (connector, prog) in list {
+        if connector.should_run(status) {
+            status = run_command(event, prog, state);
+        }
+    }
+    status
+}
+
+/// Split the first word of a command line, the builtin or the program, from its arguments.
+fn split_command(prog: &str) -> (&str, &str) {
+    let prog = prog.trim();
+    let (command, args) = prog.split_once(char::is_whitespace).unwrap_or((prog, ""));
+    (command, args.trim())
+}
+
+/// Run a builtin or launch a program, returning the exit code.
+fn run_command(event: &mut EventLoop, prog: String, state: &mut LoopState) -> i32 {
+    let _span = debug_span!("command", line = prog.trim()).entered();
+    event.log(LogSource::Prompt, &prog);
+    let prog = event.shell.aliases.expand(&prog);
+    let (command, args) = split_command(&prog);
+
+    match command {
+        "" => 0,
+        KILL_COMMAND => kill_builtin(event, args),
+        SPAWN_COMMAND => spawn_builtin(event, args),
+        SWITCH_COMMAND => switch_builtin(event, args),
+        JOBS_COMMAND => {
+            print_jobs(event);
+            0
+        }
+        "history" => {
+            print_history(&event.history.lock().unwrap());
+            0
+        }
+        "alias" => alias_builtin(&mut event.shell.aliases, args),
+        "unalias" => unalias_builtin(&mut event.shell.aliases, args),
+        "cd" => cd_builtin(args),
+        "export" => export_builtin(&mut event.shell.env, args),
+        VFS_COMMAND => vfs_builtin(&mut event.shell.vfs, args, &mut stdout()),
+        _ => run_prog(event, prog, state),
+    }
+}
+
+/// Channel ready once `timeout` elapsed on `clock`, never ready without a timeout.
+fn timer(clock: &Arc<dyn Clock>, timeout: Option<Duration>) -> Receiver<Instant> {
+    match timeout {
+        Some(timeout) => clock.after(timeout),
+        None => crossbeam::channel::never(),
+    }
+}
+
+/// Launch `prog` and forward the console to it until it exits, returning its exit code.
+/// The output of the background jobs is still printed meanwhile.
+fn run_prog(event: &mut EventLoop, prog: String, state: &mut LoopState) -> i32 {
+    let (timeout, prog) = match split_timeout(&prog) {
+        Ok((timeout, prog)) => (timeout.or(event.default_timeout), prog.to_string()),
+        Err(e) => {
+            println!("{}", e);
+            return e.status();
+        }
+    };
+    let mut timer = timer(&event.clock, timeout);
+    let mut timed_out = false;
+    // replaced by a never ready channel once the console reaches EOF
+    let mut console_rx = event.console_rx.clone();
+
+    let mut exit = 0;
+    let fg = event.launch(&prog);
+    *state = LoopState::ProgRunning;
+
+    // wait for the child to be running before forwarding the console to it
+    loop {
+        match event.child_rx.recv().unwrap() {
+            (id, ChildEvent::Started) if id == fg => break,
+            (id, ChildEvent::SpawnFailed(e)) if id == fg => {
+                event.remove_job(fg);
+                eprintln!("{}", e);
+                let exit = e.status();
+                event.log(LogSource::Exit, &exit.to_string());
+                *state = LoopState::Prompting;
+                return exit;
+            }
+            (id, child_event) => event.background_event(id, child_event),
+        }
+    }
+
+    while let LoopState::ProgRunning = state {
+        crossbeam::select! {
+            recv(event.child_rx) -> child_event => match child_event.unwrap() {
+                (id, child_event) if id != fg => event.background_event(id, child_event),
+                (_, ChildEvent::Stdout(line)) => {
+                    event.log(LogSource::Stdout, &line);
+                    stdout().write_all(line.as_bytes()).unwrap();
+                }
+                (_, ChildEvent::Stderr(line)) => {
+                    event.log(LogSource::Stderr, &line);
+                    eprint!("{}", line);
+                }
+                (_, ChildEvent::Exited(status)) => {
+                    if event.script.is_none() {
+                        println!("exited: {}", status);
+                    }
+                    exit = if timed_out { TIMEOUT_STATUS } else { Current::exit_code(status) };
+                    event.log(LogSource::Exit, &exit.to_string());
+                    event.remove_job(fg);
+                    *state = LoopState::Prompting;
+                }
+                (_, ChildEvent::Started | ChildEvent::SpawnFailed(..)) => unreachable!(),
+            },
+            recv(console_rx) -> line => {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => {
+                        event.send_input(fg, ChildInput::Eof);
+                        console_rx = crossbeam::channel::never();
+                        continue;
+                    }
+                };
+                event.log(LogSource::Stdin, &line);
+                let input = if line.trim() == KILL_COMMAND {
+                    ChildInput::Kill
+                } else {
+                    ChildInput::Line(line)
+                };
+                event.send_input(fg, input);
+            },
+            recv(timer) -> _ => {
+                eprintln!("timeout: killed after {:?}", timeout.unwrap());
+                event.send_input(fg, ChildInput::Kill);
+                timed_out = true;
+                timer = crossbeam::channel::never();
+            },
+            recv(event.resize_rx) -> _ => event.resize_jobs(),
+        }
+        stdout().flush().unwrap();
+    }
+
+    exit
+}
+
+/// Restore the console mode saved when the shell started, then exit with `status`.
+fn exit(console: Option<<Current as Platform>::RawMode>, status: i32) -> ! {
+    drop(console);
+    process::exit(status);
 }
 
 fn main() {
+    let args = Args::parse();
+    // the line editor may still be reading with the console in raw mode when the shell exits
+    let console = stdin().is_terminal().then(Current::save_console).and_then(Result::ok);
+    if let Err(e) = labs_common::init_tracing(&args.log_level) {
+        eprintln!("--log-level {}: {}", args.log_level, e);
+        exit(console, 2);
+    }
+    let script = match (args.command, args.script) {
+        (Some(command), _) => Some(Script::from_command(&command)),
+        (None, Some(path)) => match Script::from_file(&path) {
+            Ok(script) => Some(script),
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                exit(console, 127);
+            }
+        },
+        (None, None) => None,
+    };
+
+    // before spawning any thread, they must all ignore the resize signal
+    let resize_rx = match args.pty.then(pty::resize_events) {
+        Some(Ok(resize_rx)) => resize_rx,
+        Some(Err(e)) => {
+            eprintln!("pty: {}", e);
+            exit(console, 1);
+        }
+        None => crossbeam::channel::never(),
+    };
+
     let (child_sx, child_rx) = crossbeam::channel::unbounded();
-    let (father_sx, father_rx) = crossbeam::channel::unbounded();
     let (console_sx, console_rx) = crossbeam::channel::unbounded();
-    let (prog_sx, prog_rx) = crossbeam::channel::unbounded();
 
-    let event = EventLoop {
+    let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+    let (shell, history) = match args.session.as_deref().map(Session::load) {
+        Some(Ok(Some(session))) => session.restore(clock.clone()),
+        Some(Ok(None)) => (ShellState::new(clock.clone()), History::new()),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            exit(console, e.status());
+        }
+        None => (ShellState::new(clock.clone()), History::from_home()),
+    };
+    let history = Arc::new(Mutex::new(history));
+
+    let (log_sx, log_handle) = match args.log.map(|path| (File::create(&path), path)) {
+        Some((Ok(file), _)) => {
+            let (log_sx, log_rx) = crossbeam::channel::unbounded();
+            (
+                Some(log_sx),
+                Some(thread::spawn(move || logger(file, log_rx))),
+            )
+        }
+        Some((Err(e), path)) => {
+            eprintln!("{}: {}", path.display(), e);
+            exit(console, 127);
+        }
+        None => (None, None),
+    };
+
+    // the metrics are kept without `--metrics` too, nobody reads them then
+    let registry = Registry::new();
+    if let Some(addr) = args.metrics {
+        if let Err(e) = registry.serve(addr) {
+            eprintln!("metrics: {}: {}", addr, e);
+            exit(console, 1);
+        }
+    }
+
+    let metrics = JobMetrics::new(&registry);
+    let (pty, max_jobs) = (args.pty, args.max_jobs.get());
+    let child_handler: Actor<Launch> = match ActorBuilder::new("child handler")
+        .spawn(move |prog_rx| handle_child(prog_rx, child_sx.clone(), pty, max_jobs, &registry))
+    {
+        Ok(child_handler) => child_handler,
+        Err(e) => {
+            eprintln!("child handler: {}", e);
+            exit(console, 1);
+        }
+    };
+
+    let mut event = EventLoop {
         child_rx,
-        child_sx: father_sx,
         console_rx,
-        prog_sx,
+        prog_sx: child_handler.mailbox(),
+        jobs: BTreeMap::new(),
+        next_job: 1,
+        focus: None,
+        resize_rx,
+        prompt: args.prompt,
+        last_status: 0,
+        history: history.clone(),
+        script,
+        default_timeout: args.default_timeout,
+        shell,
+        clock,
+        log_sx,
+        metrics,
     };
 
-    thread::scope(|s| {
-        s.spawn(move || main_event_loop(event));
-        s.spawn(move || input_reader(console_sx));
-        s.spawn(move || handle_child(prog_rx, father_rx, child_sx));
-    });
+    // a script reads no console, the programs it runs get an empty input
+    if event.script.is_none() {
+        thread::spawn(move || input_reader(console_sx, history));
+    } else {
+        drop(console_sx);
+    }
+
+    let status = main_event_loop(&mut event);
+    event.kill_jobs();
+    if let Some(path) = &args.session {
+        let history = event.history.lock().unwrap();
+        let saved = Session::capture(mem::take(&mut event.shell), &history)
+            .and_then(|session| session.save(path));
+        if let Err(e) = saved {
+            eprintln!("{}: {}", path.display(), e);
+        }
+    }
+    drop(event);
+
+    // the jobs are all gone, the child handler stops once its mailbox is closed
+    if let Err(e) = child_handler.join() {
+        eprintln!("{}", e);
+    }
+
+    // the event loop dropped its sender, wait for the logger to write everything
+    if let Some(Err(e)) = log_handle.map(|handle| handle.join().unwrap()) {
+        eprintln!("log: {}", e);
+    }
+    exit(console, status);
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use labs_common::{Clock, ManualClock};
+    use labs_error::ShellError;
+
+    use crate::{export_builtin, split_timeout, timer};
+
+    #[test]
+    fn timer_test() {
+        let clock = Arc::new(ManualClock::default());
+        let timer_rx = timer(&(clock.clone() as Arc<dyn Clock>), Some(Duration::from_secs(5)));
+
+        clock.advance(Duration::from_secs(4));
+        assert!(timer_rx.recv_timeout(Duration::from_millis(20)).is_err());
+        clock.advance(Duration::from_secs(1));
+        assert!(timer_rx.recv().is_ok());
+
+        assert!(timer(&(clock as Arc<dyn Clock>), None).recv_timeout(Duration::from_millis(1)).is_err());
+    }
+
+    #[test]
+    fn export_test() {
+        let mut env = BTreeMap::new();
+        assert_eq!(export_builtin(&mut env, "EDITOR=vi _A1=a=b EMPTY="), 0);
+        assert_eq!(export_builtin(&mut env, "1A=x NAME A-B=c EDITOR=nano"), 1);
+
+        let expected = [("EDITOR", "nano"), ("EMPTY", ""), ("_A1", "a=b")];
+        assert!(env.iter().map(|(k, v)| (k.as_str(), v.as_str())).eq(expected));
+    }
+
+    #[test]
+    fn split_timeout_test() {
+        assert_eq!(split_timeout("ls -la\n").unwrap(), (None, "ls -la\n"));
+        assert_eq!(
+            split_timeout(":timeout 1.5 sleep 10\n").unwrap(),
+            (Some(Duration::from_millis(1500)), "sleep 10\n")
+        );
+        assert_eq!(split_timeout(":timeouts\n").unwrap(), (None, ":timeouts\n"));
+        assert!(matches!(split_timeout(":timeout 5\n"), Err(ShellError::Usage(_))));
+        assert!(matches!(
+            split_timeout(":timeout five sleep 10\n"),
+            Err(ShellError::InvalidSeconds(secs)) if secs == "five"
+        ));
+    }
 }
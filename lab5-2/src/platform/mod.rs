@@ -0,0 +1,40 @@
+use std::{
+    fs::Metadata,
+    io,
+    path::{Path, PathBuf},
+    process::ExitStatus,
+};
+
+#[cfg(unix)]
+mod unix;
+#[cfg(windows)]
+mod windows;
+
+/// Operating system the shell is built for.
+#[cfg(unix)]
+pub type Current = unix::Unix;
+#[cfg(windows)]
+pub type Current = windows::Windows;
+
+/// Operations that depend on the operating system.
+pub trait Platform {
+    /// Console put in raw mode, the previous mode is restored on drop.
+    type RawMode;
+
+    /// Deliver every key to the line editor as soon as it is typed, without echo,
+    /// `Ctrl+C` included.
+    fn enable_raw_mode() -> io::Result<Self::RawMode>;
+
+    /// Mode of the console as it is now, restored on drop: the shell may exit while the
+    /// line editor has the console in raw mode.
+    fn save_console() -> io::Result<Self::RawMode>;
+
+    /// Whether the file at `path` can be launched.
+    fn is_executable(path: &Path, metadata: &Metadata) -> bool;
+
+    /// Shell exit code for a terminated program.
+    fn exit_code(status: ExitStatus) -> i32;
+
+    /// Home directory of the user, where the history is stored.
+    fn home_dir() -> Option<PathBuf>;
+}
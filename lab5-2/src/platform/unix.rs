@@ -0,0 +1,69 @@
+use std::{
+    env,
+    fs::Metadata,
+    io, mem,
+    os::unix::{fs::PermissionsExt, process::ExitStatusExt},
+    path::{Path, PathBuf},
+    process::ExitStatus,
+};
+
+use super::Platform;
+
+pub struct Unix;
+
+/// Terminal put in non-canonical mode without echo, restored on drop.
+pub struct RawMode {
+    original: libc::termios,
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &self.original) };
+    }
+}
+
+impl Platform for Unix {
+    type RawMode = RawMode;
+
+    fn enable_raw_mode() -> io::Result<RawMode> {
+        let mut termios = unsafe { mem::zeroed::<libc::termios>() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut termios) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let original = termios;
+
+        termios.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+        termios.c_iflag &= !(libc::IXON | libc::ICRNL);
+        termios.c_cc[libc::VMIN] = 1;
+        termios.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &termios) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(RawMode { original })
+    }
+
+    fn save_console() -> io::Result<RawMode> {
+        let mut original = unsafe { mem::zeroed::<libc::termios>() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(RawMode { original })
+    }
+
+    fn is_executable(_path: &Path, metadata: &Metadata) -> bool {
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    }
+
+    /// The exit code, or 128 + the signal that killed the program.
+    fn exit_code(status: ExitStatus) -> i32 {
+        status
+            .code()
+            .or_else(|| status.signal().map(|signal| 128 + signal))
+            .unwrap_or(1)
+    }
+
+    fn home_dir() -> Option<PathBuf> {
+        env::var_os("HOME").map(PathBuf::from)
+    }
+}
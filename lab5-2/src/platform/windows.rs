@@ -0,0 +1,81 @@
+use std::{
+    env,
+    ffi::OsStr,
+    fs::Metadata,
+    io,
+    path::{Path, PathBuf},
+    process::ExitStatus,
+};
+
+use windows_sys::Win32::System::Console::{
+    GetConsoleMode, GetStdHandle, SetConsoleMode, CONSOLE_MODE, ENABLE_ECHO_INPUT,
+    ENABLE_LINE_INPUT, ENABLE_PROCESSED_INPUT, ENABLE_VIRTUAL_TERMINAL_INPUT, STD_INPUT_HANDLE,
+};
+
+use super::Platform;
+
+/// Extensions launched without being typed, when `%PATHEXT%` is not set.
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+pub struct Windows;
+
+/// Console input without line buffering nor echo, restored on drop.
+/// The arrow keys are reported with the same escape sequences of a Unix terminal.
+pub struct RawMode {
+    original: CONSOLE_MODE,
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe { SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), self.original) };
+    }
+}
+
+impl Platform for Windows {
+    type RawMode = RawMode;
+
+    fn enable_raw_mode() -> io::Result<RawMode> {
+        let handle = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+        let mut original = 0;
+        if unsafe { GetConsoleMode(handle, &mut original) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mode = (original & !(ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT | ENABLE_PROCESSED_INPUT))
+            | ENABLE_VIRTUAL_TERMINAL_INPUT;
+        if unsafe { SetConsoleMode(handle, mode) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(RawMode { original })
+    }
+
+    fn save_console() -> io::Result<RawMode> {
+        let mut original = 0;
+        if unsafe { GetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), &mut original) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(RawMode { original })
+    }
+
+    /// Windows has no executable permission, the extension tells what can be launched.
+    fn is_executable(path: &Path, metadata: &Metadata) -> bool {
+        let pathext = env::var("PATHEXT").unwrap_or_else(|_| DEFAULT_PATHEXT.to_string());
+        let extension = path.extension().and_then(OsStr::to_str).unwrap_or_default();
+
+        metadata.is_file()
+            && pathext
+                .split(';')
+                .filter(|ext| !ext.is_empty())
+                .any(|ext| ext.trim_start_matches('.').eq_ignore_ascii_case(extension))
+    }
+
+    /// There are no signals, a killed program exits with the code given to `TerminateProcess`.
+    fn exit_code(status: ExitStatus) -> i32 {
+        status.code().unwrap_or(1)
+    }
+
+    fn home_dir() -> Option<PathBuf> {
+        env::var_os("USERPROFILE").map(PathBuf::from)
+    }
+}
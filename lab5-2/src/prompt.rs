@@ -0,0 +1,84 @@
+use std::{env, fs, path::Path};
+
+use crate::platform::{Current, Platform};
+
+/// Prompt printed when the template is not given.
+pub const DEFAULT_PROMPT: &str = "> ";
+
+/// Replace the placeholders of the prompt `template`:
+/// `{cwd}` the current directory, `{status}` the last exit code, `{branch}` the git branch.
+pub fn render_prompt(template: &str, status: i32) -> String {
+    let cwd = env::current_dir().unwrap_or_default();
+    // reading the repository is only worth it if the branch is shown
+    let branch = if template.contains("{branch}") {
+        git_branch(&cwd)
+    } else {
+        None
+    };
+
+    render(template, &display_cwd(&cwd), status, branch.as_deref())
+}
+
+fn render(template: &str, cwd: &str, status: i32, branch: Option<&str>) -> String {
+    template
+        .replace("{cwd}", cwd)
+        .replace("{status}", &status.to_string())
+        .replace("{branch}", branch.unwrap_or_default())
+}
+
+/// Current directory with the home directory abbreviated to `~`.
+fn display_cwd(cwd: &Path) -> String {
+    match Current::home_dir().and_then(|home| cwd.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => Path::new("~").join(rest).display().to_string(),
+        None => cwd.display().to_string(),
+    }
+}
+
+/// Branch checked out in the git repository containing `dir`, if any.
+fn git_branch(dir: &Path) -> Option<String> {
+    let head = dir
+        .ancestors()
+        .map(|dir| dir.join(".git").join("HEAD"))
+        .find_map(|head| fs::read_to_string(head).ok())?;
+    parse_head(&head)
+}
+
+/// Branch name of a `.git/HEAD` file, the abbreviated commit when the HEAD is detached.
+fn parse_head(head: &str) -> Option<String> {
+    let head = head.trim();
+    match head.strip_prefix("ref: ") {
+        Some(reference) => Some(
+            reference
+                .strip_prefix("refs/heads/")
+                .unwrap_or(reference)
+                .to_string(),
+        ),
+        None if head.len() >= 7 => Some(head[..7].to_string()),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prompt::{parse_head, render};
+
+    #[test]
+    fn render_test() {
+        assert_eq!(
+            render("{cwd} ({branch}) [{status}] > ", "~/lab", 127, Some("main")),
+            "~/lab (main) [127] > "
+        );
+        assert_eq!(render("{branch}{x}> ", "/", 0, None), "{x}> ");
+    }
+
+    #[test]
+    fn parse_head_test() {
+        assert_eq!(
+            parse_head("ref: refs/heads/feature/x\n"),
+            Some("feature/x".to_string())
+        );
+        assert_eq!(parse_head("0b3fba7c6d1e2f\n"), Some("0b3fba7".to_string()));
+        assert_eq!(parse_head(""), None);
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Write},
+    mem,
+    os::{
+        fd::{AsRawFd, OwnedFd},
+        unix::process::CommandExt,
+    },
+    process::{Child, Command, Stdio},
+    thread,
+};
+
+use crossbeam::channel::Receiver;
+use nix::{
+    pty::{openpty, Winsize},
+    sys::{
+        signal::{SigSet, Signal},
+        termios::{tcgetattr, tcsetattr, LocalFlags, SetArg},
+    },
+    unistd::setsid,
+};
+
+/// End of file character of the pseudo-terminal, the `Ctrl+D` typed on a terminal.
+const VEOF: u8 = 0x04;
+
+/// Master side of the pseudo-terminal a program is running on.
+#[derive(Debug)]
+pub struct Pty {
+    master: File,
+}
+
+impl Pty {
+    /// Launch `name` with `args` on a new pseudo-terminal, the size of the shell terminal.
+    /// The terminal echo is disabled, the shell already shows the typed lines.
+    pub fn spawn<S: AsRef<str>>(
+        name: &str,
+        args: &[S],
+        env: &BTreeMap<String, String>,
+    ) -> io::Result<(Self, Child)> {
+        let pty = openpty(window_size().as_ref(), None)?;
+
+        let mut termios = tcgetattr(&pty.slave)?;
+        termios.local_flags &= !LocalFlags::ECHO;
+        tcsetattr(&pty.slave, SetArg::TCSANOW, &termios)?;
+
+        let child = spawn_on(name, args, env, &pty.slave)?;
+        // only the child must keep the slave open, reading the master fails once it exits
+        drop(pty.slave);
+
+        let master = File::from(pty.master);
+        Ok((Self { master }, child))
+    }
+
+    /// Reader of everything the program writes, both its stdout and its stderr.
+    pub fn reader(&self) -> io::Result<File> {
+        self.master.try_clone()
+    }
+
+    pub fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.master.write_all(buf)?;
+        self.master.flush()
+    }
+
+    /// Signal the end of the input, closing the master would hang up the program instead.
+    pub fn send_eof(&mut self) -> io::Result<()> {
+        self.write_all(&[VEOF])
+    }
+
+    /// Propagate the size of the shell terminal, the program receives a `SIGWINCH`.
+    pub fn resize(&self) -> io::Result<()> {
+        let size = match window_size() {
+            Some(size) => size,
+            None => return Ok(()),
+        };
+        if unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ, &size) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+fn spawn_on<S: AsRef<str>>(
+    name: &str,
+    args: &[S],
+    env: &BTreeMap<String, String>,
+    slave: &OwnedFd,
+) -> io::Result<Child> {
+    let mut command = Command::new(name);
+    command
+        .args(args.iter().map(AsRef::as_ref))
+        .envs(env)
+        .stdin(Stdio::from(slave.try_clone()?))
+        .stdout(Stdio::from(slave.try_clone()?))
+        .stderr(Stdio::from(slave.try_clone()?));
+
+    // a new session with the pseudo-terminal as the controlling terminal,
+    // so that the program gets the job control signals and `/dev/tty` works
+    unsafe {
+        command.pre_exec(|| {
+            setsid()?;
+            if libc::ioctl(libc::STDIN_FILENO, libc::TIOCSCTTY, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    command.spawn()
+}
+
+/// Size of the terminal the shell is running on, `None` if stdin is not a terminal.
+fn window_size() -> Option<Winsize> {
+    let mut size = unsafe { mem::zeroed::<Winsize>() };
+    match unsafe { libc::ioctl(libc::STDIN_FILENO, libc::TIOCGWINSZ, &mut size) } {
+        0 => Some(size),
+        _ => None,
+    }
+}
+
+/// Receive a message every time the shell terminal is resized.
+///
+/// `SIGWINCH` is blocked and waited for by a dedicated thread, this must be called
+/// before any other thread is spawned, so that they all inherit the signal mask.
+pub fn resize_events() -> io::Result<Receiver<()>> {
+    let mut signals = SigSet::empty();
+    signals.add(Signal::SIGWINCH);
+    signals.thread_block()?;
+
+    let (resize_sx, resize_rx) = crossbeam::channel::unbounded();
+    thread::spawn(move || {
+        while signals.wait().is_ok() {
+            // the event loop is gone, the shell is exiting
+            if resize_sx.send(()).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(resize_rx)
+}
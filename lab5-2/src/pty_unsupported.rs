@@ -0,0 +1,45 @@
+//! `--pty` is only available on Unix, every operation fails elsewhere.
+
+use std::{collections::BTreeMap, fs::File, io, process::Child};
+
+use crossbeam::channel::Receiver;
+
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "pseudo-terminals are not supported on this platform",
+    )
+}
+
+#[derive(Debug)]
+pub struct Pty;
+
+impl Pty {
+    pub fn spawn<S: AsRef<str>>(
+        _name: &str,
+        _args: &[S],
+        _env: &BTreeMap<String, String>,
+    ) -> io::Result<(Self, Child)> {
+        Err(unsupported())
+    }
+
+    pub fn reader(&self) -> io::Result<File> {
+        Err(unsupported())
+    }
+
+    pub fn write_all(&mut self, _buf: &[u8]) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn send_eof(&mut self) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn resize(&self) -> io::Result<()> {
+        Err(unsupported())
+    }
+}
+
+pub fn resize_events() -> io::Result<Receiver<()>> {
+    Err(unsupported())
+}
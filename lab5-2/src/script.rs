@@ -0,0 +1,61 @@
+use std::{fs, io, path::Path};
+
+/// Commands run by the shell in non-interactive mode.
+#[derive(Debug)]
+pub struct Script {
+    name: String,
+    lines: Vec<String>,
+    next: usize,
+}
+
+impl Script {
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(Self::new(path.display().to_string(), &content))
+    }
+
+    /// Script made of the commands passed with `-c`.
+    pub fn from_command(command: &str) -> Self {
+        Self::new("-c".to_string(), command)
+    }
+
+    fn new(name: String, content: &str) -> Self {
+        Self {
+            name,
+            lines: content.lines().map(String::from).collect(),
+            next: 0,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Next command together with its line number, blank lines and comments are skipped.
+    pub fn next_command(&mut self) -> Option<(usize, String)> {
+        while let Some(line) = self.lines.get(self.next) {
+            self.next += 1;
+
+            let command = line.trim();
+            if !command.is_empty() && !command.starts_with('#') {
+                return Some((self.next, format!("{}\n", command)));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::script::Script;
+
+    #[test]
+    fn next_command_test() {
+        let mut script = Script::from_command("#!/bin/lab5-2\n\necho a\n  # comment\n  ls -la  \n");
+
+        assert_eq!(script.next_command(), Some((3, "echo a\n".to_string())));
+        assert_eq!(script.next_command(), Some((5, "ls -la\n".to_string())));
+        assert_eq!(script.next_command(), None);
+    }
+}
@@ -0,0 +1,130 @@
+//! State of the shell saved in the file of `--session` when it exits, and restored from it
+//! when it starts again.
+
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use lab3_3::FileSystem;
+use labs_common::Clock;
+use labs_error::ShellError;
+use serde::{Deserialize, Serialize};
+
+use crate::{alias::Aliases, history::History, ShellState};
+
+/// Everything restored by `--session`, saved as JSON.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Session {
+    /// Current directory of the shell, where the programs are launched.
+    cwd: PathBuf,
+    /// Variables set with `export`.
+    env: BTreeMap<String, String>,
+    aliases: Aliases,
+    history: Vec<String>,
+    /// Filesystem of the `vfs` builtin.
+    vfs: FileSystem,
+}
+
+impl Session {
+    /// Session of the shell exiting, in the current directory.
+    pub fn capture(shell: ShellState, history: &History) -> io::Result<Self> {
+        Ok(Self {
+            cwd: env::current_dir()?,
+            env: shell.env,
+            aliases: shell.aliases,
+            history: history.entries().to_vec(),
+            vfs: shell.vfs,
+        })
+    }
+
+    /// Go back to the directory of the session, returning the rest of its state.
+    /// The `vfs` files created from now on take their times from `clock`.
+    pub fn restore(self, clock: Arc<dyn Clock>) -> (ShellState, History) {
+        // the directory may be gone, the shell stays where it was started then
+        if let Err(e) = env::set_current_dir(&self.cwd) {
+            eprintln!("cd: {}: {}", self.cwd.display(), e);
+        }
+
+        let mut vfs = self.vfs;
+        vfs.set_clock(clock);
+        let shell = ShellState {
+            aliases: self.aliases,
+            env: self.env,
+            vfs,
+        };
+        (shell, History::with_entries(self.history))
+    }
+
+    /// The session saved in `path`, `None` if nothing was saved there yet.
+    pub fn load(path: &Path) -> Result<Option<Self>, ShellError> {
+        let invalid = |message: String| ShellError::Session {
+            path: path.display().to_string(),
+            message,
+        };
+
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(invalid(e.to_string())),
+        };
+        serde_json::from_str(&text).map(Some).map_err(|e| invalid(e.to_string()))
+    }
+
+    /// Write the session to a file next to `path` and rename it over `path`,
+    /// a shell killed while saving leaves the previous session in place.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(&tmp, path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process, sync::Arc};
+
+    use labs_common::{Clock, SystemClock};
+    use labs_error::ShellError;
+
+    use crate::{history::History, session::Session, vfs::vfs_builtin, ShellState};
+
+    #[test]
+    fn save_load_test() {
+        let path = env::temp_dir().join(format!("lab5-2-session-{}", process::id()));
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+        assert!(Session::load(&path).unwrap().is_none());
+
+        let mut shell = ShellState::default();
+        shell.aliases.define("ll", "ls -la");
+        shell.env.insert("EDITOR".to_string(), "vi".to_string());
+        vfs_builtin(&mut shell.vfs, "write /notes hello", &mut Vec::new());
+        let mut history = History::new();
+        history.push("ll /tmp").unwrap();
+
+        Session::capture(shell, &history).unwrap().save(&path).unwrap();
+        let (mut shell, history) = Session::load(&path).unwrap().unwrap().restore(clock);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(shell.aliases.get("ll"), Some("ls -la"));
+        assert_eq!(shell.env["EDITOR"], "vi");
+        assert_eq!(history.entries(), ["ll /tmp"]);
+        let mut out = Vec::new();
+        assert_eq!(vfs_builtin(&mut shell.vfs, "cat /notes", &mut out), 0);
+        assert_eq!(String::from_utf8(out).unwrap(), "hello\n");
+    }
+
+    #[test]
+    fn invalid_session_test() {
+        let path = env::temp_dir().join(format!("lab5-2-invalid-session-{}", process::id()));
+        fs::write(&path, "{\"cwd\": ").unwrap();
+        let loaded = Session::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(loaded, Err(ShellError::Session { path: p, .. }) if p == path.display().to_string()));
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+use lab3_3::{File, FileSystem, FileType, Node};
+use labs_error::ShellError;
+
+/// Drive the in-memory filesystem of lab3-3, e.g. `vfs ls /a`.
+pub const VFS_COMMAND: &str = "vfs";
+
+const USAGE: &str = "vfs ls [dir] | cat <file> | find <query>... | mkdir <dir> | rmdir <dir> | write <file> [text]";
+const FIND_USAGE: &str = "vfs find <query>..., like name:foo, content:, larger:, smaller:, newer: or older:";
+const WRITE_USAGE: &str = "vfs write </path/of/file> [text]";
+
+/// Split `/a/b/file` into the directory `/a/b` and the name `file`.
+fn split_path(path: &str) -> Option<(&str, &str)> {
+    let (dir, name) = path.strip_prefix('/')?.rsplit_once('/').unwrap_or(("", path.strip_prefix('/')?));
+    if name.is_empty() {
+        return None;
+    }
+    Some((if dir.is_empty() { "/" } else { &path[..dir.len() + 1] }, name))
+}
+
+/// Names of `nodes`, a line each, the directories ending with `/`.
+fn print_nodes(nodes: &[Rc<RefCell<Node>>], out: &mut dyn Write) -> io::Result<()> {
+    for node in nodes {
+        let node = node.borrow();
+        let suffix = if node.is_dir() { "/" } else { "" };
+        writeln!(out, "{}{}", node.get_name(), suffix)?;
+    }
+    Ok(())
+}
+
+/// Run the `vfs` command `args` on `fs`, writing its output to `out`, returning the exit code.
+pub fn vfs_builtin(fs: &mut FileSystem, args: &str, out: &mut dyn Write) -> i32 {
+    match run(fs, args, out) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("vfs: {}", e);
+            e.status()
+        }
+    }
+}
+
+fn run(fs: &mut FileSystem, args: &str, out: &mut dyn Write) -> Result<(), ShellError> {
+    let (command, args) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+    let args = args.trim();
+
+    match command {
+        "ls" => {
+            let dir = if args.is_empty() { "/" } else { args };
+            print_nodes(&fs.list(dir)?, out)?;
+        }
+        "cat" => {
+            let content = fs.get_file(args)?.borrow().get_content().cloned().unwrap_or_default();
+            out.write_all(&content)?;
+            if content.last().is_some_and(|&last| last != b'\n') {
+                writeln!(out)?;
+            }
+        }
+        "find" => {
+            let queries = args.split_whitespace().collect::<Vec<_>>();
+            if queries.is_empty() {
+                return Err(ShellError::Usage(FIND_USAGE.to_string()));
+            }
+            print_nodes(fs.search(&queries)?.nodes(), out)?;
+        }
+        "mkdir" | "rmdir" if !args.is_empty() => {
+            let dir = args.trim_end_matches('/');
+            if command == "mkdir" {
+                fs.mk_dir(dir)?;
+            } else {
+                fs.rm_dir(dir)?;
+            }
+        }
+        "write" => {
+            let (path, text) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+            let (dir, name) = split_path(path).ok_or_else(|| ShellError::Usage(WRITE_USAGE.to_string()))?;
+            let content = format!("{}\n", text.trim());
+            let file = File::with_clock(name, content.as_bytes(), FileType::Text, fs.clock());
+            fs.new_file(dir, file)?;
+        }
+        _ => return Err(ShellError::Usage(USAGE.to_string())),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use lab3_3::FileSystem;
+
+    use crate::vfs::{split_path, vfs_builtin};
+
+    fn run(fs: &mut FileSystem, args: &str) -> (i32, String) {
+        let mut out = Vec::new();
+        let status = vfs_builtin(fs, args, &mut out);
+        (status, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn split_path_test() {
+        assert_eq!(split_path("/a/b/file"), Some(("/a/b", "file")));
+        assert_eq!(split_path("/file"), Some(("/", "file")));
+        assert_eq!(split_path("/a/"), None);
+        assert_eq!(split_path("file"), None);
+    }
+
+    #[test]
+    fn vfs_test() {
+        let mut fs = FileSystem::new();
+        assert_eq!(run(&mut fs, "mkdir /a"), (0, String::new()));
+        assert_eq!(run(&mut fs, "mkdir /a/b/"), (0, String::new()));
+        assert_eq!(run(&mut fs, "write /a/notes hello world"), (0, String::new()));
+        assert_eq!(run(&mut fs, "write /top"), (0, String::new()));
+
+        assert_eq!(run(&mut fs, "ls"), (0, "a/\ntop\n".to_string()));
+        assert_eq!(run(&mut fs, "ls /a"), (0, "b/\nnotes\n".to_string()));
+        assert_eq!(run(&mut fs, "cat /a/notes"), (0, "hello world\n".to_string()));
+        assert_eq!(run(&mut fs, "find name:note"), (0, "notes\n".to_string()));
+        assert_eq!(run(&mut fs, "find content:world name:b"), (0, "b/\nnotes\n".to_string()));
+
+        assert_eq!(run(&mut fs, "rmdir /a/b").0, 0);
+        assert_eq!(run(&mut fs, "ls /a"), (0, "notes\n".to_string()));
+
+        assert_eq!(run(&mut fs, "ls /x").0, 1);
+        assert_eq!(run(&mut fs, "cat /a/missing").0, 1);
+        assert_eq!(run(&mut fs, "write /x/file text").0, 1);
+        assert_eq!(run(&mut fs, "write /a/notes again").0, 1);
+        assert_eq!(run(&mut fs, "rmdir /a").0, 1);
+        assert_eq!(run(&mut fs, "find size:3").0, 2);
+        assert_eq!(run(&mut fs, "find").0, 2);
+        assert_eq!(run(&mut fs, "write notes").0, 2);
+        assert_eq!(run(&mut fs, "mv /a /b").0, 2);
+    }
+}
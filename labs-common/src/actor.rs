@@ -0,0 +1,233 @@
+//! Threads receiving their work from a mailbox, restarted when they panic: the workers
+//! and the scheduler of the lab5-1 pool, the coordinator of the lab4-1 `ThreadBarrier`
+//! and the child handler of the lab5-2 shell.
+
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crossbeam::channel::{Receiver, SendError, Sender};
+use tracing::{debug, warn};
+
+/// What an actor does when its body panics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Restart {
+    /// Stop at the first panic, the messages left in the mailbox are dropped.
+    #[default]
+    Never,
+    /// Run the body again after a panic, at most this many times.
+    UpTo(u32),
+    Always,
+}
+
+impl Restart {
+    fn allows(self, restarts: u32) -> bool {
+        match self {
+            Restart::Never => false,
+            Restart::UpTo(max) => restarts < max,
+            Restart::Always => true,
+        }
+    }
+}
+
+/// The body of an actor panicked more times than its `Restart` allows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActorPanic {
+    pub actor: String,
+    pub message: String,
+}
+
+impl fmt::Display for ActorPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} panicked: {}", self.actor, self.message)
+    }
+}
+
+impl Error for ActorPanic {}
+
+/// Message of a panic, the payload of `panic!` is a `&str` or a `String`.
+pub fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload.downcast_ref::<&str>().map_or("Box<dyn Any>", |message| message).to_string(),
+    }
+}
+
+/// Name, restart policy and mailbox of an actor not started yet.
+#[derive(Debug, Clone)]
+pub struct ActorBuilder {
+    name: String,
+    restart: Restart,
+    capacity: Option<usize>,
+}
+
+impl ActorBuilder {
+    /// Actor stopping at its first panic, with an unbounded mailbox.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            restart: Restart::Never,
+            capacity: None,
+        }
+    }
+
+    pub fn restart(mut self, restart: Restart) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    /// Messages queued at most, a sender waits for room: with 0 each message is handed
+    /// over to the body.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Start a thread named after the actor running `body` with its mailbox, the actor stops
+    /// when `body` returns. After a panic `body` runs again as the `Restart` allows, with
+    /// the messages still in the mailbox and the state it captured.
+    pub fn spawn<M, F>(self, mut body: F) -> io::Result<Actor<M>>
+    where
+        M: Send + 'static,
+        F: FnMut(&Receiver<M>) + Send + 'static,
+    {
+        let (mailbox, inbox) = match self.capacity {
+            Some(capacity) => crossbeam::channel::bounded(capacity),
+            None => crossbeam::channel::unbounded(),
+        };
+        let restarts = Arc::new(AtomicU32::new(0));
+
+        let (name, restart, count) = (self.name.clone(), self.restart, restarts.clone());
+        let handle = thread::Builder::new().name(self.name.clone()).spawn(move || loop {
+            let message = match panic::catch_unwind(AssertUnwindSafe(|| body(&inbox))) {
+                Ok(()) => {
+                    debug!(actor = %name, "stopped");
+                    return Ok(());
+                }
+                Err(payload) => panic_message(payload),
+            };
+
+            let restarts = count.load(Ordering::Relaxed);
+            if !restart.allows(restarts) {
+                warn!(actor = %name, restarts, "panicked, stopping: {}", message);
+                return Err(ActorPanic { actor: name, message });
+            }
+            warn!(actor = %name, restarts, "panicked, restarting: {}", message);
+            count.fetch_add(1, Ordering::Relaxed);
+        })?;
+
+        Ok(Actor {
+            name: self.name,
+            mailbox,
+            handle,
+            restarts,
+        })
+    }
+}
+
+/// Thread started by `ActorBuilder::spawn`, dropping the handle detaches it.
+#[derive(Debug)]
+pub struct Actor<M> {
+    name: String,
+    mailbox: Sender<M>,
+    handle: JoinHandle<Result<(), ActorPanic>>,
+    restarts: Arc<AtomicU32>,
+}
+
+impl<M> Actor<M> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Fails once the actor stopped, giving the message back.
+    pub fn send(&self, message: M) -> Result<(), SendError<M>> {
+        self.mailbox.send(message)
+    }
+
+    /// Another sender to the mailbox, for the threads talking to the actor.
+    pub fn mailbox(&self) -> Sender<M> {
+        self.mailbox.clone()
+    }
+
+    /// Times the body ran again after a panic.
+    pub fn restarts(&self) -> u32 {
+        self.restarts.load(Ordering::Relaxed)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Drop the sender of this handle and wait for the body to return, the mailbox is
+    /// disconnected once the senders given by `mailbox` are dropped too.
+    pub fn join(self) -> Result<(), ActorPanic> {
+        drop(self.mailbox);
+        let name = self.name;
+        // only dropping the body can panic outside of `catch_unwind`
+        self.handle.join().unwrap_or_else(|payload| {
+            Err(ActorPanic {
+                actor: name,
+                message: panic_message(payload),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crossbeam::channel::Receiver;
+
+    use crate::actor::{ActorBuilder, ActorPanic, Restart};
+
+    #[test]
+    fn restart_test() {
+        let (done_sx, done_rx) = crossbeam::channel::unbounded();
+        let mut bodies = 0;
+        let actor = ActorBuilder::new("squares")
+            .restart(Restart::Always)
+            .spawn(move |numbers: &Receiver<i32>| {
+                bodies += 1;
+                for n in numbers {
+                    assert!(n >= 0, "negative number {}", n);
+                    done_sx.send((bodies, n * n)).unwrap();
+                }
+            })
+            .unwrap();
+
+        for n in [2, -1, 3, -2, 4] {
+            actor.send(n).unwrap();
+        }
+        // the state and the mailbox are kept after the panics
+        assert_eq!(done_rx.iter().take(3).collect::<Vec<_>>(), [(1, 4), (2, 9), (3, 16)]);
+        assert_eq!(actor.restarts(), 2);
+        assert_eq!(actor.name(), "squares");
+        assert_eq!(actor.join(), Ok(()));
+    }
+
+    #[test]
+    fn panic_test() {
+        let actor = ActorBuilder::new("parser")
+            .restart(Restart::UpTo(1))
+            .capacity(0)
+            .spawn(|lines: &Receiver<&str>| {
+                let line = lines.recv().unwrap();
+                panic!("invalid line `{}`", line);
+            })
+            .unwrap();
+
+        actor.send("a").unwrap();
+        actor.send("b").unwrap();
+        let mailbox = actor.mailbox();
+        assert_eq!(
+            actor.join(),
+            Err(ActorPanic { actor: "parser".to_string(), message: "invalid line `b`".to_string() })
+        );
+        // the mailbox is dropped with the actor
+        assert!(mailbox.send("c").is_err());
+    }
+}
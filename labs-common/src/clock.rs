@@ -0,0 +1,192 @@
+use std::fmt;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crossbeam::channel::{Receiver, Sender};
+
+/// Source of the current time, shared as an `Arc<dyn Clock>`: the `SystemClock` by default,
+/// a `ManualClock` in the tests, which only moves when told to.
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> SystemTime;
+
+    /// Block the calling thread until the clock moved `duration` forward.
+    fn sleep(&self, duration: Duration);
+
+    /// Channel receiving once the clock moved `duration` forward, to `select!` on.
+    /// No thread waits for it, dropping the receiver cancels it.
+    fn after(&self, duration: Duration) -> Receiver<Instant>;
+
+    /// `now` in milliseconds since the epoch, 0 before it.
+    fn unix_millis(&self) -> u64 {
+        let now = self.now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        now.as_millis() as u64
+    }
+}
+
+/// The wall clock of the system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+
+    fn after(&self, duration: Duration) -> Receiver<Instant> {
+        crossbeam::channel::after(duration)
+    }
+}
+
+/// Clock moved by hand with `set` and `advance`, waking the threads sleeping on it
+/// once their time has come.
+#[derive(Debug)]
+pub struct ManualClock {
+    state: Mutex<ManualState>,
+    moved: Condvar,
+}
+
+#[derive(Debug)]
+struct ManualState {
+    now: SystemTime,
+    sleepers: usize,
+    /// Channels of `after` not ready yet, with the time they are ready at.
+    timers: Vec<(SystemTime, Sender<Instant>)>,
+}
+
+impl ManualState {
+    fn fire_timers(&mut self) {
+        let now = self.now;
+        self.timers.retain(|(wake, timer)| {
+            if *wake > now {
+                return true;
+            }
+            // the receiver may be dropped, nobody is waiting then
+            let _ = timer.try_send(Instant::now());
+            false
+        });
+    }
+}
+
+impl ManualClock {
+    pub fn new(now: SystemTime) -> Self {
+        Self {
+            state: Mutex::new(ManualState { now, sleepers: 0, timers: vec![] }),
+            moved: Condvar::new(),
+        }
+    }
+
+    /// Move the clock to `now`, backwards too, like an adjusted wall clock.
+    pub fn set(&self, now: SystemTime) {
+        let mut state = self.state.lock().unwrap();
+        state.now = now;
+        state.fire_timers();
+        self.moved.notify_all();
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.now += duration;
+        state.fire_timers();
+        self.moved.notify_all();
+    }
+
+    /// Threads sleeping on the clock, to move it only once they started.
+    pub fn sleepers(&self) -> usize {
+        self.state.lock().unwrap().sleepers
+    }
+}
+
+impl Default for ManualClock {
+    /// Stopped at the epoch.
+    fn default() -> Self {
+        Self::new(UNIX_EPOCH)
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        self.state.lock().unwrap().now
+    }
+
+    fn sleep(&self, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        let wake = state.now + duration;
+        state.sleepers += 1;
+        let mut state = self.moved.wait_while(state, |state| state.now < wake).unwrap();
+        state.sleepers -= 1;
+    }
+
+    fn after(&self, duration: Duration) -> Receiver<Instant> {
+        let (timer_sx, timer_rx) = crossbeam::channel::bounded(1);
+        let mut state = self.state.lock().unwrap();
+        let wake = state.now + duration;
+        state.timers.push((wake, timer_sx));
+        state.fire_timers();
+        timer_rx
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{Clock, ManualClock, SystemClock};
+
+    #[test]
+    fn manual_clock_test() {
+        let clock = ManualClock::default();
+        assert_eq!(clock.unix_millis(), 0);
+        clock.advance(Duration::from_millis(1_500));
+        assert_eq!(clock.unix_millis(), 1_500);
+        clock.set(UNIX_EPOCH + Duration::from_secs(10));
+        assert_eq!(clock.now(), UNIX_EPOCH + Duration::from_secs(10));
+        clock.sleep(Duration::ZERO);
+
+        assert!(SystemClock.unix_millis() > 1_600_000_000_000);
+    }
+
+    #[test]
+    fn sleep_test() {
+        let clock = Arc::new(ManualClock::default());
+        let sleeper = {
+            let clock = clock.clone();
+            thread::spawn(move || clock.sleep(Duration::from_secs(60)))
+        };
+
+        while clock.sleepers() == 0 {
+            thread::yield_now();
+        }
+        clock.advance(Duration::from_secs(30));
+        thread::sleep(Duration::from_millis(20));
+        assert!(!sleeper.is_finished());
+        assert_eq!(clock.sleepers(), 1);
+
+        clock.advance(Duration::from_secs(30));
+        sleeper.join().unwrap();
+        assert_eq!(clock.sleepers(), 0);
+    }
+
+    #[test]
+    fn after_test() {
+        let clock = ManualClock::default();
+        let timer = clock.after(Duration::from_secs(5));
+        let dropped = clock.after(Duration::from_secs(5));
+        drop(dropped);
+        assert!(clock.after(Duration::ZERO).try_recv().is_ok());
+
+        clock.advance(Duration::from_secs(4));
+        assert!(timer.try_recv().is_err());
+        clock.advance(Duration::from_secs(1));
+        assert!(timer.try_recv().is_ok());
+        assert!(clock.state.lock().unwrap().timers.is_empty());
+
+        assert!(SystemClock.after(Duration::ZERO).recv_timeout(Duration::from_secs(1)).is_ok());
+    }
+}
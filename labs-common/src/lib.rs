@@ -0,0 +1,106 @@
+//! The sensor readings shared by the labs: the producers of lab2-1 and lab3-2
+//! write the same `SensorData`, in the same bytes, read by either consumer.
+//! With them the `Clock` of their timestamps, the frames sending them over TCP
+//! and the `XorShift` of the generated inputs. The `Actor` threads of the labs
+//! supervising their panics. With the `tracing` feature, `init_tracing` for the
+//! `--log-level` of the binaries.
+
+use std::error::Error;
+use std::fmt;
+
+pub use actor::{panic_message, Actor, ActorBuilder, ActorPanic, Restart};
+pub use clock::{Clock, ManualClock, SystemClock};
+#[cfg(feature = "tracing")]
+pub use log::init_tracing;
+pub use net::{read_frame, write_frame};
+pub use rng::XorShift;
+pub use stats::{sampling_jitter, RunningStats, SensorStats};
+
+mod actor;
+mod clock;
+#[cfg(feature = "tracing")]
+mod log;
+mod net;
+mod rng;
+mod stats;
+
+/// Number of values in every `SensorData`.
+pub const SENSORS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SensorData {
+    pub seq: u32, // sequenza letture
+    pub values: [f32; SENSORS],
+    /// Time of the reading in milliseconds since the epoch, see `unix_millis`.
+    pub timestamp: u64,
+}
+
+/// Bytes that aren't a `SensorData`, of the wrong length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeError {
+    pub len: usize,
+}
+
+impl fmt::Display for SizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a SensorData is {} bytes, not {}", SensorData::SIZE, self.len)
+    }
+}
+
+impl Error for SizeError {}
+
+impl SensorData {
+    /// `seq` and the 10 `values`, 4 bytes each, and the 8 bytes of `timestamp`.
+    pub const SIZE: usize = 52;
+
+    /// The fields one after the other in little endian, on every platform.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[..4].copy_from_slice(&self.seq.to_le_bytes());
+        for (chunk, value) in bytes[4..44].chunks_exact_mut(4).zip(self.values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        bytes[44..].copy_from_slice(&self.timestamp.to_le_bytes());
+        bytes
+    }
+
+    /// The reading written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SizeError> {
+        let bytes: &[u8; Self::SIZE] = bytes.try_into().map_err(|_| SizeError { len: bytes.len() })?;
+        let word = |i: usize| bytes[i * 4..(i + 1) * 4].try_into().unwrap();
+
+        Ok(Self {
+            seq: u32::from_le_bytes(word(0)),
+            values: std::array::from_fn(|i| f32::from_le_bytes(word(i + 1))),
+            timestamp: u64::from_le_bytes(bytes[44..].try_into().unwrap()),
+        })
+    }
+}
+
+/// Current time in milliseconds since the epoch. The wall clock can be adjusted
+/// while running, two following times may go backwards.
+pub fn unix_millis() -> u64 {
+    SystemClock.unix_millis()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{SensorData, SizeError};
+
+    #[test]
+    fn bytes_test() {
+        let data = SensorData {
+            seq: 0x01020304,
+            values: [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, -0.5],
+            timestamp: 0x0a0b0c0d_00000007,
+        };
+        let bytes = data.to_bytes();
+        assert_eq!(bytes[..4], [0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(bytes[40..44], [0x00, 0x00, 0x00, 0xbf]);
+        assert_eq!(bytes[44..], [0x07, 0x00, 0x00, 0x00, 0x0d, 0x0c, 0x0b, 0x0a]);
+        assert_eq!(SensorData::from_bytes(&bytes), Ok(data));
+
+        assert_eq!(SensorData::from_bytes(&bytes[1..]), Err(SizeError { len: 51 }));
+        assert_eq!(SensorData::from_bytes(&[]).unwrap_err().to_string(), "a SensorData is 52 bytes, not 0");
+    }
+}
@@ -0,0 +1,28 @@
+use std::io::{self, IsTerminal};
+
+use tracing_subscriber::filter::{EnvFilter, ParseError};
+
+/// Print the events enabled by `filter` on stderr: a level like `debug`, or directives
+/// like `warn,lab5_1=trace` to pick the modules, see `EnvFilter`. Every line has the time,
+/// the level, the thread, the spans with their fields, then the fields of the event.
+pub fn init_tracing(filter: &str) -> Result<(), ParseError> {
+    let filter = EnvFilter::try_new(filter)?;
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_thread_names(true)
+        // the colors would be escapes in the files and the pipes reading the lines
+        .with_ansi(io::stderr().is_terminal())
+        .with_writer(io::stderr)
+        .init();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::init_tracing;
+
+    #[test]
+    fn init_tracing_test() {
+        assert!(init_tracing("warn,lab5_1=loud").is_err());
+    }
+}
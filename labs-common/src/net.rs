@@ -0,0 +1,60 @@
+use std::io::{self, ErrorKind, Read, Write};
+
+use crate::{SensorData, SizeError};
+
+/// Send `data` as a frame: its length as a u32 in little endian, then its bytes.
+pub fn write_frame(output: &mut impl Write, data: &SensorData) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(4 + SensorData::SIZE);
+    frame.extend_from_slice(&(SensorData::SIZE as u32).to_le_bytes());
+    frame.extend_from_slice(&data.to_bytes());
+    output.write_all(&frame)
+}
+
+/// Read the next frame written by `write_frame`, `None` if the stream ended between two frames.
+pub fn read_frame(input: &mut impl Read) -> io::Result<Option<SensorData>> {
+    let mut len = [0u8; 4];
+    match input.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    // the length is checked before reading, a wrong one would be read as a huge frame
+    let len = u32::from_le_bytes(len) as usize;
+    if len != SensorData::SIZE {
+        return Err(io::Error::new(ErrorKind::InvalidData, SizeError { len }));
+    }
+    let mut bytes = [0u8; SensorData::SIZE];
+    input.read_exact(&mut bytes)?;
+    Ok(Some(SensorData::from_bytes(&bytes).unwrap()))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::ErrorKind;
+
+    use crate::{read_frame, write_frame, SensorData};
+
+    #[test]
+    fn frame_test() {
+        let first = SensorData { seq: 1, values: [1.5; 10], timestamp: 7 };
+        let second = SensorData { seq: 2, ..first };
+        let mut stream = vec![];
+        write_frame(&mut stream, &first).unwrap();
+        write_frame(&mut stream, &second).unwrap();
+        assert_eq!(stream.len(), 2 * (4 + SensorData::SIZE));
+        assert_eq!(stream[..4], [52, 0, 0, 0]);
+
+        let mut input = stream.as_slice();
+        assert_eq!(read_frame(&mut input).unwrap(), Some(first));
+        assert_eq!(read_frame(&mut input).unwrap(), Some(second));
+        assert_eq!(read_frame(&mut input).unwrap(), None);
+
+        let truncated = read_frame(&mut &stream[..30]).unwrap_err();
+        assert_eq!(truncated.kind(), ErrorKind::UnexpectedEof);
+
+        let wrong = read_frame(&mut &[8, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8][..]).unwrap_err();
+        assert_eq!(wrong.kind(), ErrorKind::InvalidData);
+        assert_eq!(wrong.to_string(), "a SensorData is 52 bytes, not 8");
+    }
+}
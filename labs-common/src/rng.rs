@@ -0,0 +1,49 @@
+/// xorshift64, enough for inputs that are the same on every run, not for anything secret.
+#[derive(Debug, Clone)]
+pub struct XorShift(u64);
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        // xorshift never leaves 0
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Number in `0..n`, `n` must not be 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Number in `0.0..1.0`.
+    pub fn unit(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::XorShift;
+
+    #[test]
+    fn xorshift_test() {
+        let numbers = |seed| {
+            let mut rng = XorShift::new(seed);
+            (0..100).map(|_| rng.next_u64()).collect::<Vec<_>>()
+        };
+        assert_eq!(numbers(1), numbers(1));
+        assert_ne!(numbers(1), numbers(2));
+        assert!(numbers(0).iter().all(|&n| n != 0));
+
+        let mut rng = XorShift::new(3);
+        for _ in 0..1000 {
+            assert!(rng.below(7) < 7);
+            assert!((0.0..1.0).contains(&rng.unit()));
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::SensorData;
+
+/// Statistics of the values of a sensor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SensorStats {
+    pub count: usize,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    /// Population standard deviation.
+    pub stddev: f32,
+}
+
+impl SensorStats {
+    /// Statistics of the values of `sensor` in `data`, `None` if it's empty.
+    pub fn of(data: &[SensorData], sensor: usize) -> Option<Self> {
+        let mut running = RunningStats::new();
+        for data in data {
+            running.push(data.values[sensor]);
+        }
+        running.stats()
+    }
+}
+
+/// Statistics updated one value at a time, without keeping the values.
+#[derive(Debug, Clone, Copy)]
+pub struct RunningStats {
+    count: usize,
+    min: f32,
+    max: f32,
+    mean: f64,
+    /// Sum of the squared distances from the mean, Welford's algorithm.
+    m2: f64,
+}
+
+impl Default for RunningStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunningStats {
+    pub fn new() -> Self {
+        Self { count: 0, min: f32::INFINITY, max: f32::NEG_INFINITY, mean: 0.0, m2: 0.0 }
+    }
+
+    pub fn push(&mut self, value: f32) {
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+
+        let delta = value as f64 - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value as f64 - self.mean);
+    }
+
+    /// The statistics of the values pushed, `None` before the first one.
+    pub fn stats(&self) -> Option<SensorStats> {
+        if self.count == 0 {
+            return None;
+        }
+
+        Some(SensorStats {
+            count: self.count,
+            min: self.min,
+            max: self.max,
+            mean: self.mean as f32,
+            stddev: (self.m2 / self.count as f64).sqrt() as f32,
+        })
+    }
+}
+
+/// Mean interval between two readings and its jitter, the standard deviation, in milliseconds.
+/// The intervals where the clock went backwards are adjustments of the producer clock,
+/// not real intervals, they are left out.
+pub fn sampling_jitter(data: &[SensorData]) -> Option<(f64, f64)> {
+    let intervals = data
+        .windows(2)
+        .filter_map(|pair| pair[1].timestamp.checked_sub(pair[0].timestamp))
+        .map(|interval| interval as f64)
+        .collect::<Vec<_>>();
+    if intervals.is_empty() {
+        return None;
+    }
+
+    let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+    let variance =
+        intervals.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / intervals.len() as f64;
+    Some((mean, variance.sqrt()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{sampling_jitter, RunningStats, SensorData, SensorStats};
+
+    #[test]
+    fn running_test() {
+        let mut running = RunningStats::new();
+        assert_eq!(running.stats(), None);
+        for value in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            running.push(value);
+        }
+        let stats = running.stats().unwrap();
+        assert_eq!((stats.count, stats.min, stats.max, stats.mean), (8, 2.0, 9.0, 5.0));
+        assert!((stats.stddev - 2.0).abs() < 1e-6);
+
+        let data = [1.0, 3.0].map(|value| SensorData { values: [value; 10], ..SensorData::default() });
+        assert_eq!(SensorStats::of(&data, 9).unwrap().mean, 2.0);
+        assert_eq!(SensorStats::of(&[], 0), None);
+    }
+
+    #[test]
+    fn sampling_jitter_test() {
+        let data = |timestamps: &[u64]| {
+            timestamps
+                .iter()
+                .map(|&timestamp| SensorData { timestamp, ..SensorData::default() })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(sampling_jitter(&data(&[1000])), None);
+        assert_eq!(sampling_jitter(&data(&[1000, 2000, 3000])), Some((1000.0, 0.0)));
+        assert_eq!(sampling_jitter(&data(&[1000, 1900, 3000])), Some((1000.0, 100.0)));
+        // the clock went back between 2000 and 1500
+        assert_eq!(sampling_jitter(&data(&[1000, 2000, 1500, 2500])), Some((1000.0, 0.0)));
+    }
+}
@@ -0,0 +1,41 @@
+use std::io;
+
+use thiserror::Error;
+
+/// Failure of a read or a write of the circular buffer of lab3-2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum BufferError {
+    /// The buffer had no space and the data was dropped.
+    #[error("the buffer is full")]
+    Full,
+    /// The deadline passed before there was space for the data.
+    #[error("timed out waiting for space in the buffer")]
+    Timeout,
+    /// The other side of the buffer was dropped: the writer for a reader that read
+    /// everything, all the readers for the writer.
+    #[error("the other side of the buffer was dropped")]
+    Disconnected,
+}
+
+/// Failure of the buffer of lab2-1, shared by the processes through a file, with the path
+/// of the file.
+#[derive(Debug, Error)]
+pub enum FileBufferError {
+    /// The file doesn't hold a buffer, or an archive, of the expected capacity and element
+    /// size, or it is corrupted.
+    #[error("{path}: {reason}")]
+    Invalid { path: String, reason: String },
+    /// A resize to fewer slots than the records not read yet.
+    #[error("{path}: can't resize to {capacity} slots, {unread} records are not read yet")]
+    Resize { path: String, capacity: u32, unread: u32 },
+    /// A producer is still writing a slot, the operation can be retried later.
+    #[error("{0}: a producer is writing, retry later")]
+    Busy(String),
+    #[error("{0}: no archive configured")]
+    NoArchive(String),
+    /// A capacity or a policy of the command line that can't be parsed.
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
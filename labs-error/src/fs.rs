@@ -0,0 +1,22 @@
+use thiserror::Error;
+
+/// Failure of the in-memory filesystem of lab3-3, with the path given to it.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FsError {
+    #[error("{0}: the path doesn't start with /")]
+    RelativePath(String),
+    #[error("{0}: no such file or directory")]
+    NotFound(String),
+    #[error("{0}: not a directory")]
+    NotADirectory(String),
+    #[error("{0}: is a directory")]
+    IsADirectory(String),
+    #[error("{0}: already exists")]
+    AlreadyExists(String),
+    #[error("{0}: directory not empty")]
+    NotEmpty(String),
+    /// A search query that isn't `name:`, `content:`, `larger:`, `smaller:`, `newer:` or `older:`
+    /// followed by its value.
+    #[error("{0}: invalid query")]
+    InvalidQuery(String),
+}
@@ -0,0 +1,12 @@
+//! The errors of the labs, one type for every module so that the callers can match
+//! on the cause of a failure instead of reading its message.
+
+pub use buffer::{BufferError, FileBufferError};
+pub use fs::FsError;
+pub use pool::PoolError;
+pub use shell::ShellError;
+
+mod buffer;
+mod fs;
+mod pool;
+mod shell;
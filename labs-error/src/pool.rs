@@ -0,0 +1,18 @@
+use std::io;
+
+use thiserror::Error;
+
+/// Failure of the thread pool of lab5-1 or of one of its jobs.
+#[derive(Debug, Error)]
+pub enum PoolError {
+    #[error("a pool needs at least a worker")]
+    NoWorkers,
+    #[error("cannot start a worker: {0}")]
+    Spawn(#[from] io::Error),
+    /// The scheduler of the pool stopped, no job is run anymore.
+    #[error("the pool is closed")]
+    Closed,
+    /// The job panicked, with the message of the panic.
+    #[error("the job panicked: {0}")]
+    Panicked(String),
+}
@@ -0,0 +1,94 @@
+use std::io;
+
+use thiserror::Error;
+
+use crate::{FsError, PoolError};
+
+/// Failure of a command line of the shell of lab5-2.
+#[derive(Debug, Error)]
+pub enum ShellError {
+    /// An operator of a command list without a command, like `&&` in `ls && || pwd`.
+    #[error("syntax error near `{0}`")]
+    Syntax(String),
+    /// Wrong arguments of a builtin, with its usage.
+    #[error("usage: {0}")]
+    Usage(String),
+    #[error("invalid number of seconds: {0}")]
+    InvalidSeconds(String),
+    #[error("alias: invalid alias name in `{0}`")]
+    AliasName(String),
+    #[error("alias: unterminated quote in `{0}`")]
+    UnterminatedQuote(String),
+    /// A history expansion, `!!` or `!n`, without its entry.
+    #[error("{0}: event not found")]
+    EventNotFound(String),
+    #[error("{0}: command not found")]
+    CommandNotFound(String),
+    #[error("{program}: {source}")]
+    Spawn { program: String, source: io::Error },
+    /// A session file of `--session` that can't be restored.
+    #[error("{path}: invalid session: {message}")]
+    Session { path: String, message: String },
+    /// The pool running the programs stopped, the program wasn't started.
+    #[error("cannot run the program: {0}")]
+    Pool(#[from] PoolError),
+    #[error(transparent)]
+    Fs(#[from] FsError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl ShellError {
+    /// The program couldn't be started, `CommandNotFound` if it doesn't exist.
+    pub fn spawn(program: &str, source: io::Error) -> Self {
+        match source.kind() {
+            io::ErrorKind::NotFound => Self::CommandNotFound(program.to_string()),
+            _ => Self::Spawn { program: program.to_string(), source },
+        }
+    }
+
+    /// Exit code of the command that failed, the ones of bash: 2 for a misuse of the syntax
+    /// or of a builtin, 127 for a missing program and 126 for one that can't be run.
+    pub fn status(&self) -> i32 {
+        match self {
+            Self::Syntax(_) | Self::Usage(_) | Self::InvalidSeconds(_) => 2,
+            Self::AliasName(_) | Self::UnterminatedQuote(_) => 2,
+            Self::Fs(FsError::InvalidQuery(_)) => 2,
+            Self::CommandNotFound(_) => 127,
+            Self::Spawn { .. } => 126,
+            Self::EventNotFound(_) | Self::Session { .. } | Self::Pool(_) | Self::Fs(_) | Self::Io(_) => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use crate::{FsError, ShellError};
+
+    #[test]
+    fn spawn_test() {
+        let missing = ShellError::spawn("nope", io::Error::from(io::ErrorKind::NotFound));
+        assert!(matches!(missing, ShellError::CommandNotFound(ref program) if program == "nope"));
+        assert_eq!(missing.to_string(), "nope: command not found");
+        assert_eq!(missing.status(), 127);
+
+        let denied = ShellError::spawn("./script", io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(matches!(denied, ShellError::Spawn { .. }));
+        assert_eq!(denied.status(), 126);
+    }
+
+    #[test]
+    fn status_test() {
+        assert_eq!(ShellError::Syntax("&&".to_string()).status(), 2);
+        assert_eq!(ShellError::from(FsError::InvalidQuery("size:3".to_string())).status(), 2);
+        let missing = ShellError::from(FsError::NotFound("/a".to_string()));
+        assert_eq!(missing.status(), 1);
+        assert_eq!(missing.to_string(), "/a: no such file or directory");
+
+        let session = ShellError::Session { path: "s.json".to_string(), message: "EOF".to_string() };
+        assert_eq!(session.to_string(), "s.json: invalid session: EOF");
+        assert_eq!(session.status(), 1);
+    }
+}
@@ -0,0 +1,87 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+
+use crate::Registry;
+
+/// A client not sending its request for this long is dropped, the requests are served one at a time.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+impl Registry {
+    /// Serve `GET /metrics` on `addr` from a thread running until the process exits.
+    /// Returns the address bound, the port given may be 0.
+    pub fn serve(&self, addr: impl ToSocketAddrs) -> io::Result<SocketAddr> {
+        let listener = TcpListener::bind(addr)?;
+        let local = listener.local_addr()?;
+
+        let registry = self.clone();
+        thread::Builder::new()
+            .name("metrics".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    // a client gone before its answer only loses its answer
+                    let _ = stream.and_then(|stream| respond(&registry, stream));
+                }
+            })?;
+        Ok(local)
+    }
+}
+
+fn respond(registry: &Registry, stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut request = BufReader::new(&stream);
+
+    let mut line = String::new();
+    request.read_line(&mut line)?;
+    let found = matches!(line.split(' ').collect::<Vec<_>>()[..], ["GET", "/metrics", _]);
+    // the headers are not used, they are read so that the client isn't reset before the answer
+    while line.trim_end() != "" {
+        line.clear();
+        if request.read_line(&mut line)? == 0 {
+            break;
+        }
+    }
+
+    let (status, body) = match found {
+        true => ("200 OK", registry.render()),
+        false => ("404 Not Found", "only /metrics is served\n".to_string()),
+    };
+    write!(
+        &stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    use crate::Registry;
+
+    fn get(registry: &Registry, path: &str) -> String {
+        let addr = registry.serve("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serve_test() {
+        let registry = Registry::new();
+        registry.counter("jobs_total", "Jobs run.").add(2);
+
+        let response = get(&registry, "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\n# HELP jobs_total Jobs run.\n# TYPE jobs_total counter\njobs_total 2\n"));
+
+        assert!(get(&registry, "/").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
@@ -0,0 +1,175 @@
+//! Counters and gauges of the long-running labs, the thread pool, the circular
+//! buffers and the jobs of the shell, rendered in the Prometheus text format and
+//! served over HTTP by `Registry::serve`.
+
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+mod http;
+
+/// Counter only going up, cloned handles count together.
+#[derive(Debug, Clone, Default)]
+pub struct Counter(Arc<AtomicU64>);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Value going up and down, cloned handles share it.
+#[derive(Debug, Clone, Default)]
+pub struct Gauge(Arc<AtomicI64>);
+
+impl Gauge {
+    pub fn set(&self, value: i64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dec(&self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Counter,
+    Gauge,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Counter => "counter",
+            Kind::Gauge => "gauge",
+        })
+    }
+}
+
+type ReadValue = Box<dyn Fn() -> f64 + Send + Sync>;
+
+struct Metric {
+    name: String,
+    help: String,
+    kind: Kind,
+    /// Current value, read every time the metrics are rendered.
+    read: ReadValue,
+}
+
+/// Metrics registered by the components, cloned handles share them.
+#[derive(Clone, Default)]
+pub struct Registry {
+    metrics: Arc<Mutex<Vec<Metric>>>,
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let metrics = self.metrics.lock().unwrap();
+        f.debug_list().entries(metrics.iter().map(|metric| &metric.name)).finish()
+    }
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Panics if `name` is already registered, the same name would be rendered twice.
+    fn register(&self, name: &str, help: &str, kind: Kind, read: ReadValue) {
+        let mut metrics = self.metrics.lock().unwrap();
+        assert!(metrics.iter().all(|metric| metric.name != name), "metric {} registered twice", name);
+        metrics.push(Metric { name: name.to_string(), help: help.to_string(), kind, read });
+    }
+
+    pub fn counter(&self, name: &str, help: &str) -> Counter {
+        let counter = Counter::default();
+        let read = counter.clone();
+        self.register(name, help, Kind::Counter, Box::new(move || read.get() as f64));
+        counter
+    }
+
+    pub fn gauge(&self, name: &str, help: &str) -> Gauge {
+        let gauge = Gauge::default();
+        let read = gauge.clone();
+        self.register(name, help, Kind::Gauge, Box::new(move || read.get() as f64));
+        gauge
+    }
+
+    /// Counter kept by the component itself, `read` is called every time the metrics are rendered.
+    pub fn counter_fn(&self, name: &str, help: &str, read: impl Fn() -> u64 + Send + Sync + 'static) {
+        self.register(name, help, Kind::Counter, Box::new(move || read() as f64));
+    }
+
+    /// Gauge kept by the component itself, `read` is called every time the metrics are rendered.
+    pub fn gauge_fn(&self, name: &str, help: &str, read: impl Fn() -> f64 + Send + Sync + 'static) {
+        self.register(name, help, Kind::Gauge, Box::new(read));
+    }
+
+    /// Every metric in the Prometheus text format, in the order they were registered.
+    pub fn render(&self) -> String {
+        let metrics = self.metrics.lock().unwrap();
+        let mut text = String::new();
+        for metric in metrics.iter() {
+            writeln!(text, "# HELP {} {}", metric.name, metric.help).unwrap();
+            writeln!(text, "# TYPE {} {}", metric.name, metric.kind).unwrap();
+            writeln!(text, "{} {}", metric.name, (metric.read)()).unwrap();
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Registry;
+
+    #[test]
+    fn render_test() {
+        let registry = Registry::new();
+        let jobs = registry.counter("jobs_total", "Jobs run.");
+        let running = registry.gauge("jobs_running", "Jobs running.");
+        registry.gauge_fn("load", "Load of the workers.", || 0.5);
+
+        jobs.add(3);
+        jobs.clone().inc();
+        running.inc();
+        running.inc();
+        running.dec();
+
+        assert_eq!(registry.render(), "\
+# HELP jobs_total Jobs run.
+# TYPE jobs_total counter
+jobs_total 4
+# HELP jobs_running Jobs running.
+# TYPE jobs_running gauge
+jobs_running 1
+# HELP load Load of the workers.
+# TYPE load gauge
+load 0.5
+");
+    }
+
+    #[test]
+    #[should_panic(expected = "metric jobs_total registered twice")]
+    fn duplicate_test() {
+        let registry = Registry::new();
+        registry.counter("jobs_total", "Jobs run.");
+        registry.clone().counter_fn("jobs_total", "Jobs run.", || 0);
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::VecDeque;
+
+use lab3_3::FileSystem;
+
+/// Generated filesystem with the paths of its nodes, to pick the existing ones.
+#[derive(Debug, Clone)]
+pub struct FsFixture {
+    pub fs: FileSystem,
+    /// Absolute paths of the directories, the parents before their children.
+    pub dirs: Vec<String>,
+    /// Absolute paths of the files, the ones of the parents before the children.
+    pub files: Vec<String>,
+}
+
+impl FsFixture {
+    /// Tree of `nodes` nodes at most `depth` directories deep, see `FileSystem::generate`.
+    pub fn random(nodes: usize, depth: usize, seed: u64) -> Self {
+        let mut fixture = Self {
+            fs: FileSystem::generate(nodes, depth, seed),
+            dirs: vec![],
+            files: vec![],
+        };
+
+        let mut queue = VecDeque::from([String::new()]);
+        while let Some(dir) = queue.pop_front() {
+            let list = if dir.is_empty() { "/" } else { &dir };
+            for node in fixture.fs.list(list).unwrap() {
+                let node = node.borrow();
+                let path = format!("{}/{}", dir, node.get_name());
+                if node.is_dir() {
+                    fixture.dirs.push(path.clone());
+                    queue.push_back(path);
+                } else {
+                    fixture.files.push(path);
+                }
+            }
+        }
+
+        fixture
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::FsFixture;
+
+    #[test]
+    fn random_test() {
+        let mut fixture = FsFixture::random(2000, 3, 11);
+        assert_eq!(fixture.dirs.len() + fixture.files.len(), 2000);
+        assert!(fixture.dirs.iter().all(|dir| dir.matches('/').count() <= 3));
+
+        let same = FsFixture::random(2000, 3, 11);
+        assert_eq!((&fixture.dirs, &fixture.files), (&same.dirs, &same.files));
+
+        for dir in fixture.dirs.clone() {
+            assert!(fixture.fs.list(&dir).is_ok());
+        }
+        for file in fixture.files.clone() {
+            assert!(fixture.fs.get_file(&file).is_ok());
+        }
+    }
+}
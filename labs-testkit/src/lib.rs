@@ -0,0 +1,10 @@
+//! Large inputs that are the same on every run, for the tests and the benchmarks of the labs
+//! instead of small trees and streams written by hand: the same seed gives the same input.
+
+#[cfg(feature = "fs")]
+pub use fs::FsFixture;
+pub use sensor::SensorStream;
+
+#[cfg(feature = "fs")]
+mod fs;
+mod sensor;
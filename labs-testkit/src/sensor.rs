@@ -0,0 +1,72 @@
+use labs_common::{SensorData, XorShift, SENSORS};
+
+/// Time of the first reading, in milliseconds since the epoch: 2020-09-13.
+const START: u64 = 1_600_000_000_000;
+
+/// Endless readings of the sensors, each one drifting around its own level,
+/// `rate` a second from the same start.
+#[derive(Debug, Clone)]
+pub struct SensorStream {
+    rng: XorShift,
+    rate: u32,
+    /// Readings generated, the sequence number wraps around but the time keeps going.
+    count: u64,
+    values: [f32; SENSORS],
+}
+
+impl SensorStream {
+    /// Stream of `rate` readings a second, the same for the same `seed`, `rate` must not be 0.
+    pub fn synthetic(rate: u32, seed: u64) -> Self {
+        assert!(rate > 0, "a stream of 0 readings a second");
+        Self {
+            rng: XorShift::new(seed),
+            rate,
+            count: 0,
+            values: std::array::from_fn(|i| 20.0 + 5.0 * i as f32),
+        }
+    }
+}
+
+impl Iterator for SensorStream {
+    type Item = SensorData;
+
+    fn next(&mut self) -> Option<SensorData> {
+        for value in &mut self.values {
+            *value += self.rng.unit() - 0.5;
+        }
+        let data = SensorData {
+            seq: self.count as u32,
+            values: self.values,
+            timestamp: START + self.count * 1000 / self.rate as u64,
+        };
+        self.count += 1;
+
+        Some(data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SensorStream;
+
+    #[test]
+    fn synthetic_test() {
+        let data = SensorStream::synthetic(4, 5).take(100).collect::<Vec<_>>();
+        assert_eq!(data, SensorStream::synthetic(4, 5).take(100).collect::<Vec<_>>());
+        assert_ne!(data, SensorStream::synthetic(4, 6).take(100).collect::<Vec<_>>());
+
+        assert!(data.iter().enumerate().all(|(i, d)| d.seq == i as u32));
+        assert_eq!(data[1].timestamp - data[0].timestamp, 250);
+        assert_eq!(data[99].timestamp - data[0].timestamp, 24_750);
+        assert!(data.iter().all(|d| d.values.iter().zip(&data[0].values).all(|(v, first)| (v - first).abs() < 50.0)));
+    }
+
+    #[test]
+    fn seq_wrap_test() {
+        let stream = SensorStream { count: u32::MAX as u64, ..SensorStream::synthetic(1, 5) };
+        let data = stream.take(2).collect::<Vec<_>>();
+
+        assert_eq!((data[0].seq, data[1].seq), (u32::MAX, 0));
+        assert_eq!(data[1].timestamp - data[0].timestamp, 1000);
+    }
+}